@@ -429,6 +429,9 @@ CONFIGURATION:
   - Verify wallet addresses (auto-generated during install)
   - Adjust minimum payout thresholds
 
+  Check the config and wallet/pool connectivity:
+    ${PAYMENTS_DIR}/bin/solopool-payments -c ${PAYMENTS_DIR}/config/config.toml check-config
+
 API AUTHENTICATION:
   The API is protected by bearer token authentication.
   Token file: ${BASE_DIR}/.payments_api_token
@@ -440,12 +443,13 @@ API AUTHENTICATION:
   The WebUI proxies authenticated requests to this API automatically.
 
 API ENDPOINTS:
-  GET /api/health                    - Health check (no auth required)
+  GET /api/health                    - Per-coin health report, 503 when down (no auth required)
   GET /api/stats                     - All payment stats
   GET /api/stats/:coin               - Stats for specific coin
   GET /api/miner/:coin/:address      - Miner balance and history
   GET /api/payments/:coin            - Recent payments
   GET /api/payments/:coin/:address   - Miner payment history
+  GET /metrics                       - Prometheus metrics
 
 POOL WALLETS (AUTO-GENERATED):
   All pool wallets are automatically generated during installation.
@@ -466,6 +470,9 @@ log "  Config:   ${PAYMENTS_DIR}/config/config.toml"
 log "  Database: ${PAYMENTS_DIR}/data/"
 log "  Logs:     ${PAYMENTS_DIR}/logs/"
 log ""
+log "  Check the configuration:"
+log "    ${PAYMENTS_DIR}/bin/solopool-payments -c ${PAYMENTS_DIR}/config/config.toml check-config"
+log ""
 log "  Start the service:"
 log "    sudo systemctl start solopool-payments"
 log ""
@@ -0,0 +1,587 @@
+//! Accounting export
+//!
+//! Turns a coin's payment history into double-entry transactions for
+//! bookkeeping: block rewards received, miner credits, payouts, cold sweeps
+//! and network fees. The postings of every transaction add up to zero.
+//!
+//! Rendered as CSV (one row per posting), JSON Lines (one transaction per
+//! line) or a Beancount ledger. CSV and JSON carry amounts in both atomic
+//! units and whole coins; Beancount posts whole coins with the atomic amount
+//! as posting metadata.
+//!
+//! Network fees are only known for consolidations and for payouts held for
+//! approval (the fee of the prepared transaction); other payout fees are not
+//! stored, so the hot wallet account overstates the wallet by those fees.
+
+use crate::db::{ApprovalStatus, Coin, ConsolidationStatus, Database, LedgerEntryType, RewardStatus};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// Output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Csv,
+    Jsonl,
+    Beancount,
+}
+
+impl Format {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Csv => "text/csv; charset=utf-8",
+            Format::Jsonl => "application/x-ndjson",
+            Format::Beancount => "text/plain; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+            Format::Beancount => "beancount",
+        }
+    }
+}
+
+/// Books kept per coin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Account {
+    /// Pool wallet
+    HotWallet,
+    /// Cold storage fed by hot wallet sweeps
+    ColdWallet,
+    /// Block rewards received by the pool wallet
+    BlockRewards,
+    /// Block rewards credited to miners
+    MinerRewards,
+    /// Transaction fees paid by the pool wallet
+    NetworkFees,
+    /// Pending miner balances
+    Miners,
+    /// Fractional atomic units held over to miners' next rewards
+    MinerCarry,
+    /// Dormant balances moved out of pending
+    Abandoned,
+    /// Balances carried over from before ledger tracking
+    OpeningBalances,
+}
+
+impl Account {
+    fn name(&self, coin: Coin) -> String {
+        let (root, leaf) = match self {
+            Account::HotWallet => ("Assets", "HotWallet"),
+            Account::ColdWallet => ("Assets", "ColdWallet"),
+            Account::BlockRewards => ("Income", "BlockRewards"),
+            Account::MinerRewards => ("Expenses", "MinerRewards"),
+            Account::NetworkFees => ("Expenses", "NetworkFees"),
+            Account::Miners => ("Liabilities", "Miners"),
+            Account::MinerCarry => ("Liabilities", "MinerCarry"),
+            Account::Abandoned => ("Liabilities", "Abandoned"),
+            Account::OpeningBalances => ("Equity", "OpeningBalances"),
+        };
+        format!("{}:Pool:{}:{}", root, commodity(coin), leaf)
+    }
+}
+
+/// Beancount commodity (and CSV/JSON unit) of a coin
+fn commodity(coin: Coin) -> String {
+    coin.to_string().to_uppercase()
+}
+
+/// One side of a transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct Posting {
+    pub account: String,
+    pub amount_atomic: Decimal,
+    /// Whole coins
+    pub amount: Decimal,
+    pub commodity: String,
+}
+
+/// A balanced transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct Transaction {
+    pub date: DateTime<Utc>,
+    pub coin: Coin,
+    /// Source record, e.g. `block-12`, `ledger-345`, `payment-<id>`
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_height: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub postings: Vec<Posting>,
+}
+
+impl Transaction {
+    /// `amount` moved from `credit` to `debit`
+    #[allow(clippy::too_many_arguments)]
+    fn transfer(
+        coin: Coin,
+        date: DateTime<Utc>,
+        id: String,
+        kind: &'static str,
+        description: String,
+        debit: Account,
+        credit: Account,
+        amount: Decimal,
+    ) -> Self {
+        let posting = |account: Account, amount: Decimal| Posting {
+            account: account.name(coin),
+            amount_atomic: amount.normalize(),
+            amount: coin.human_amount(amount),
+            commodity: commodity(coin),
+        };
+
+        Transaction {
+            date,
+            coin,
+            id,
+            kind,
+            description,
+            address: None,
+            block_height: None,
+            block_hash: None,
+            payment_id: None,
+            tx_hash: None,
+            memo: None,
+            postings: vec![posting(debit, amount), posting(credit, -amount)],
+        }
+    }
+}
+
+/// `[from, to)` covering the given days; `to` is inclusive, open ends cover everything
+pub fn date_range(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> anyhow::Result<(DateTime<Utc>, DateTime<Utc>)> {
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            anyhow::bail!("from ({}) is after to ({})", from, to);
+        }
+    }
+
+    let start = from
+        .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight").and_utc())
+        .unwrap_or(DateTime::UNIX_EPOCH);
+    let end = match to {
+        Some(d) => d
+            .succ_opt()
+            .ok_or_else(|| anyhow::anyhow!("to ({}) is out of range", d))?
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight")
+            .and_utc(),
+        None => Utc::now(),
+    };
+    Ok((start, end))
+}
+
+/// Transactions of the given coins dated in `[from, to)`, oldest first
+pub async fn transactions(
+    db: &Database,
+    coins: &[Coin],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<Transaction>> {
+    let mut transactions = Vec::new();
+    for coin in coins {
+        transactions.extend(coin_transactions(db, *coin, from, to).await?);
+    }
+    transactions.sort_by_key(|t| t.date);
+    Ok(transactions)
+}
+
+async fn coin_transactions(
+    db: &Database,
+    coin: Coin,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<Transaction>> {
+    let in_range = |date: DateTime<Utc>| date >= from && date < to;
+    let mut transactions = Vec::new();
+
+    // Block rewards received; awaiting or missing coinbases are not pool funds
+    let blocks = db.get_blocks(coin).await?;
+    for block in &blocks {
+        let received = matches!(block.reward_status, RewardStatus::Reported | RewardStatus::Verified);
+        if !received || !in_range(block.timestamp) || block.reward.is_zero() {
+            continue;
+        }
+        let mut transaction = Transaction::transfer(
+            coin,
+            block.timestamp,
+            format!("block-{}", block.id),
+            "block_reward",
+            format!("Block {} reward", block.block_height),
+            Account::HotWallet,
+            Account::BlockRewards,
+            block.reward,
+        );
+        transaction.address = Some(block.finder_wallet.clone());
+        transaction.block_height = Some(block.block_height);
+        transaction.block_hash = Some(block.block_hash.clone());
+        transaction.tx_hash = block.coinbase_tx.clone();
+        transactions.push(transaction);
+    }
+
+    // Miner credits and pool wallet movements from the ledger
+    let blocks: HashMap<i64, _> = blocks.iter().map(|b| (b.id, b)).collect();
+    for entry in db.get_ledger_entries_between(coin, from, to).await? {
+        if entry.amount.is_zero() {
+            continue;
+        }
+        let (kind, debit, credit) = match entry.entry_type {
+            LedgerEntryType::OpeningBalance => ("opening_balance", Account::OpeningBalances, Account::Miners),
+            LedgerEntryType::BlockReward => ("miner_credit", Account::MinerRewards, Account::Miners),
+            LedgerEntryType::BlockReversal => ("miner_reversal", Account::MinerRewards, Account::Miners),
+            LedgerEntryType::RoundingCarry => ("rounding_carry", Account::MinerRewards, Account::MinerCarry),
+            LedgerEntryType::Abandoned => ("abandoned", Account::Abandoned, Account::Miners),
+            LedgerEntryType::ColdSweep => ("cold_sweep", Account::ColdWallet, Account::HotWallet),
+        };
+        let block = entry.block_id.and_then(|id| blocks.get(&id));
+        let description = match (entry.entry_type, block) {
+            (LedgerEntryType::ColdSweep, _) => "Sweep to cold wallet".to_string(),
+            (_, Some(block)) => format!("Block {} {}", block.block_height, kind.replace('_', " ")),
+            (_, None) => kind.replace('_', " "),
+        };
+
+        let mut transaction = Transaction::transfer(
+            coin,
+            entry.created_at,
+            format!("ledger-{}", entry.id),
+            kind,
+            description,
+            debit,
+            credit,
+            entry.amount,
+        );
+        transaction.address = Some(entry.wallet_address);
+        transaction.block_height = block.map(|b| b.block_height);
+        transaction.block_hash = block.map(|b| b.block_hash.clone());
+        transaction.payment_id = entry.payment_id;
+        // Cold sweeps keep their transaction in the memo
+        match entry.memo.as_deref().and_then(|m| m.strip_prefix("tx ")) {
+            Some(tx_hash) if entry.entry_type == LedgerEntryType::ColdSweep => {
+                transaction.tx_hash = Some(tx_hash.to_string())
+            }
+            _ => transaction.memo = entry.memo,
+        }
+        transactions.push(transaction);
+    }
+
+    // Payouts leave the wallet once confirmed
+    for payment in db.get_confirmed_payments_between(coin, from, to).await? {
+        let mut transaction = Transaction::transfer(
+            coin,
+            payment.confirmed_at.unwrap_or(payment.created_at),
+            format!("payment-{}", payment.id),
+            "payout",
+            "Payout".to_string(),
+            Account::Miners,
+            Account::HotWallet,
+            payment.amount,
+        );
+        transaction.address = Some(payment.wallet_address);
+        transaction.payment_id = Some(payment.id);
+        transaction.tx_hash = payment.tx_hash;
+        transactions.push(transaction);
+    }
+
+    // Network fees the wallet reported
+    for consolidation in db
+        .list_consolidations(coin, Some(ConsolidationStatus::Confirmed), i64::MAX)
+        .await?
+    {
+        let date = consolidation.confirmed_at.unwrap_or(consolidation.created_at);
+        if !in_range(date) || consolidation.fee.is_zero() {
+            continue;
+        }
+        let mut transaction = Transaction::transfer(
+            coin,
+            date,
+            format!("consolidation-{}", consolidation.id),
+            "network_fee",
+            "Consolidation fee".to_string(),
+            Account::NetworkFees,
+            Account::HotWallet,
+            consolidation.fee,
+        );
+        transaction.tx_hash = Some(consolidation.tx_hashes.join(","));
+        transactions.push(transaction);
+    }
+
+    for approval in db
+        .list_payout_approvals(coin, Some(ApprovalStatus::Relayed), i64::MAX)
+        .await?
+    {
+        let date = approval.decided_at.unwrap_or(approval.created_at);
+        let Some(fee) = approval.fee.filter(|f| !f.is_zero()) else {
+            continue;
+        };
+        if !in_range(date) {
+            continue;
+        }
+        let mut transaction = Transaction::transfer(
+            coin,
+            date,
+            format!("approval-{}", approval.id),
+            "network_fee",
+            "Payout fee".to_string(),
+            Account::NetworkFees,
+            Account::HotWallet,
+            fee,
+        );
+        transaction.tx_hash = approval.tx_hash;
+        transactions.push(transaction);
+    }
+
+    Ok(transactions)
+}
+
+/// Render transactions in the given format
+pub fn render(format: Format, transactions: &[Transaction]) -> String {
+    match format {
+        Format::Csv => render_csv(transactions),
+        Format::Jsonl => render_jsonl(transactions),
+        Format::Beancount => render_beancount(transactions),
+    }
+}
+
+fn render_csv(transactions: &[Transaction]) -> String {
+    let mut out = String::from(
+        "date,coin,id,type,account,amount_atomic,amount,commodity,address,block_height,block_hash,payment_id,tx_hash,description,memo\n",
+    );
+    for t in transactions {
+        for posting in &t.postings {
+            let fields = [
+                t.date.to_rfc3339(),
+                t.coin.to_string(),
+                t.id.clone(),
+                t.kind.to_string(),
+                posting.account.clone(),
+                posting.amount_atomic.to_string(),
+                posting.amount.to_string(),
+                posting.commodity.clone(),
+                t.address.clone().unwrap_or_default(),
+                t.block_height.map(|h| h.to_string()).unwrap_or_default(),
+                t.block_hash.clone().unwrap_or_default(),
+                t.payment_id.clone().unwrap_or_default(),
+                t.tx_hash.clone().unwrap_or_default(),
+                t.description.clone(),
+                t.memo.clone().unwrap_or_default(),
+            ];
+            let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Quote a CSV field if it needs it
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_jsonl(transactions: &[Transaction]) -> String {
+    let mut out = String::new();
+    for t in transactions {
+        // Plain structs and strings always serialize
+        out.push_str(&serde_json::to_string(t).expect("transaction serializes"));
+        out.push('\n');
+    }
+    out
+}
+
+fn render_beancount(transactions: &[Transaction]) -> String {
+    let mut out = String::from("; Solo pool accounting export\n\n");
+
+    // Open every account used, on the day of the first transaction
+    if let Some(first) = transactions.first() {
+        let date = first.date.format("%Y-%m-%d");
+        let commodities: BTreeSet<&str> = transactions
+            .iter()
+            .flat_map(|t| t.postings.iter().map(|p| p.commodity.as_str()))
+            .collect();
+        for commodity in &commodities {
+            let _ = writeln!(out, "{} commodity {}", date, commodity);
+        }
+        let accounts: BTreeSet<(&str, &str)> = transactions
+            .iter()
+            .flat_map(|t| t.postings.iter().map(|p| (p.account.as_str(), p.commodity.as_str())))
+            .collect();
+        for (account, commodity) in accounts {
+            let _ = writeln!(out, "{} open {} {}", date, account, commodity);
+        }
+        out.push('\n');
+    }
+
+    for t in transactions {
+        let _ = writeln!(
+            out,
+            "{} * \"{}\"",
+            t.date.format("%Y-%m-%d"),
+            beancount_string(&t.description)
+        );
+        let _ = writeln!(out, "  id: \"{}\"", beancount_string(&t.id));
+        let _ = writeln!(out, "  type: \"{}\"", t.kind);
+        let _ = writeln!(out, "  time: \"{}\"", t.date.to_rfc3339());
+        let strings = [
+            ("address", &t.address),
+            ("block_hash", &t.block_hash),
+            ("payment_id", &t.payment_id),
+            ("tx_hash", &t.tx_hash),
+            ("memo", &t.memo),
+        ];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                let _ = writeln!(out, "  {}: \"{}\"", key, beancount_string(value));
+            }
+        }
+        if let Some(height) = t.block_height {
+            let _ = writeln!(out, "  block_height: {}", height);
+        }
+        for posting in &t.postings {
+            let _ = writeln!(out, "  {}  {} {}", posting.account, posting.amount, posting.commodity);
+            let _ = writeln!(out, "    atomic: {}", posting.amount_atomic);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Escape a Beancount string literal
+fn beancount_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{BlockCredit, PaymentStatus, TempDatabase};
+
+    /// A block shared by two miners, one payout and a cold sweep
+    async fn history(db: &Database) {
+        db.record_block(
+            Coin::Xmr,
+            3_100_000,
+            "blockhash",
+            Decimal::new(600_000_000_000, 0),
+            "miner1",
+            "rig",
+            RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        let block = db.get_blocks(Coin::Xmr).await.unwrap().remove(0);
+        let credit = |address: &str, amount: i64| BlockCredit {
+            wallet_address: address.to_string(),
+            amount: Decimal::new(amount, 0),
+            carry: Decimal::ZERO,
+            memo: None,
+        };
+        db.apply_block_distribution(&block, &[credit("miner1", 400_000_000_000), credit("miner2", 200_000_000_000)])
+            .await
+            .unwrap();
+
+        let payment = db
+            .create_payment(Coin::Xmr, "miner1", Decimal::new(400_000_000_000, 0))
+            .await
+            .unwrap();
+        db.update_payment_status(&payment, PaymentStatus::Confirmed, Some("payouttx"), None)
+            .await
+            .unwrap();
+
+        db.record_cold_sweep(Coin::Xmr, "coldaddr", Decimal::new(100_000_000_000, 0), "sweeptx")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn transactions_balance_and_follow_the_money() {
+        let db = TempDatabase::new().await;
+        history(&db).await;
+        let (from, to) = date_range(None, None).unwrap();
+        let transactions = transactions(&db, &[Coin::Xmr], from, to).await.unwrap();
+
+        let kinds: Vec<&str> = transactions.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, ["block_reward", "miner_credit", "miner_credit", "payout", "cold_sweep"]);
+        for t in &transactions {
+            let total: Decimal = t.postings.iter().map(|p| p.amount_atomic).sum();
+            assert!(total.is_zero(), "{} does not balance", t.id);
+        }
+
+        // Net position per account
+        let mut accounts: HashMap<&str, Decimal> = HashMap::new();
+        for posting in transactions.iter().flat_map(|t| &t.postings) {
+            *accounts.entry(posting.account.as_str()).or_default() += posting.amount;
+        }
+        assert_eq!(accounts["Assets:Pool:XMR:HotWallet"], Decimal::new(1, 1));
+        assert_eq!(accounts["Assets:Pool:XMR:ColdWallet"], Decimal::new(1, 1));
+        assert_eq!(accounts["Liabilities:Pool:XMR:Miners"], Decimal::new(-2, 1));
+        assert_eq!(accounts["Income:Pool:XMR:BlockRewards"], Decimal::new(-6, 1));
+
+        let payout = &transactions[3];
+        assert_eq!(payout.tx_hash.as_deref(), Some("payouttx"));
+        assert_eq!(transactions[4].tx_hash.as_deref(), Some("sweeptx"));
+    }
+
+    #[tokio::test]
+    async fn date_range_excludes_other_days() {
+        let db = TempDatabase::new().await;
+        history(&db).await;
+        let yesterday = Utc::now().date_naive().pred_opt().unwrap();
+        let (from, to) = date_range(None, Some(yesterday)).unwrap();
+        assert!(transactions(&db, &[Coin::Xmr], from, to).await.unwrap().is_empty());
+
+        assert!(date_range(Some(Utc::now().date_naive()), Some(yesterday)).is_err());
+    }
+
+    #[tokio::test]
+    async fn renders_every_format() {
+        let db = TempDatabase::new().await;
+        history(&db).await;
+        let (from, to) = date_range(None, None).unwrap();
+        let transactions = transactions(&db, &[Coin::Xmr], from, to).await.unwrap();
+
+        let csv = render(Format::Csv, &transactions);
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("date,coin,id,type,account,amount_atomic,amount"));
+        let row: Vec<&str> = lines.next().unwrap().split(',').collect();
+        assert_eq!(&row[3..8], ["block_reward", "Assets:Pool:XMR:HotWallet", "600000000000", "0.6", "XMR"]);
+        assert_eq!(csv.lines().count(), 1 + 2 * transactions.len());
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+
+        let jsonl = render(Format::Jsonl, &transactions);
+        let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(first["type"], "block_reward");
+        assert_eq!(first["coin"], "xmr");
+        assert_eq!(first["block_height"], 3_100_000);
+        assert_eq!(first["postings"][1]["amount"], "-0.6");
+
+        let beancount = render(Format::Beancount, &transactions);
+        assert!(beancount.contains(" open Liabilities:Pool:XMR:Miners XMR\n"));
+        assert!(beancount.contains(" * \"Block 3100000 reward\"\n"));
+        assert!(beancount.contains("  Income:Pool:XMR:BlockRewards  -0.6 XMR\n    atomic: -600000000000\n"));
+        assert!(beancount.contains("  tx_hash: \"payouttx\"\n"));
+    }
+}
@@ -1,24 +1,60 @@
 //! REST API for payment processor
 //!
 //! Endpoints:
-//! - GET /api/health - Health check (no auth required)
+//! - GET /api/health - Per-coin health report (no auth required; balances only with a token)
 //! - GET /api/stats - Overall payment processor stats
 //! - GET /api/stats/:coin - Stats for a specific coin
 //! - GET /api/miner/:coin/:address - Miner balance and history
 //! - GET /api/payments/:coin - Recent payments for a coin
 //! - GET /api/payments/:coin/:address - Payment history for a miner
+//! - GET /api/consolidations/:coin - Pool wallet consolidations
+//! - GET /api/dormant/:coin - Liabilities to miners that stopped mining
+//! - GET /api/teams/:coin - Teams and their member splits
+//! - GET /api/approvals/:coin - Payouts held for approval
+//! - POST /api/approvals/:id/approve - Approve a held payout
+//! - POST /api/approvals/:id/reject - Reject a held payout
+//! - GET /api/proof/:payment_id - Payment proof for a miner to verify a payment
+//! - POST /api/pause[/:coin] - Pause payouts for all coins or one coin
+//! - POST /api/resume[/:coin] - Lift a pause
+//! - POST /api/notify/:coin - Process new blocks now (block notify hook)
+//! - GET /api/jobs - Scheduled jobs and their latest runs
+//! - GET /api/jobs/runs - Job run history
+//! - POST /api/jobs/:coin/:job/run - Run a job now
+//! - GET /api/events - Server-sent event stream of blocks, rewards and payments
+//! - GET /api/export - Accounting records as CSV, JSON Lines or Beancount
+//! - GET /api/solvency[/:coin] - Liabilities to miners against pool wallet balances
+//! - GET /metrics - Prometheus metrics
 
-use crate::db::{Coin, Database, MinerBalance, Payment};
+use crate::accounting::{self, Format};
+use crate::db::{
+    ApprovalStatus, Coin, Consolidation, Database, JobRun, MinerBalance, Payment, PayoutApproval,
+    PayoutPause, RewardStatus,
+};
+use crate::distribution::PoolFee;
+use crate::events::EventBus;
+use crate::health::{Health, HealthReport, HealthStatus};
+use crate::metrics::Metrics;
+use crate::notify::BlockNotify;
+use crate::scheduler::{JobInfo, Scheduler};
+use crate::solvency::{self, SolvencyReport};
+use crate::wallets::Wallet;
 use axum::{
     body::Body,
     extract::{Path, Query, State},
-    http::{Request, StatusCode},
+    http::{header, HeaderMap, Request, StatusCode},
     middleware::{self, Next},
-    response::{IntoResponse, Response},
-    routing::get,
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
     Json, Router,
 };
+use chrono::NaiveDate;
+use futures::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::Arc;
 
 /// Shared state for API handlers
@@ -26,6 +62,19 @@ use std::sync::Arc;
 pub struct ApiState {
     pub db: Database,
     pub api_token: String,
+    pub metrics: Metrics,
+    /// Wallets that can sign payment proofs on demand, by coin
+    pub proof_wallets: HashMap<Coin, Arc<dyn Wallet>>,
+    /// Wakes a coin's processor when a block notify hook fires
+    pub block_notify: BlockNotify,
+    /// Per-coin cycle timestamps and probes for the health report
+    pub health: Health,
+    /// Processing and payment jobs, for run history and manual runs
+    pub scheduler: Scheduler,
+    /// Blocks, rewards and payments for the event stream
+    pub events: EventBus,
+    /// Enabled pool fees, by coin
+    pub pool_fees: HashMap<Coin, PoolFee>,
 }
 
 /// Create the API router
@@ -39,6 +88,26 @@ pub fn create_router(state: ApiState) -> Router {
         .route("/api/miner/:coin/:address", get(get_miner_info))
         .route("/api/payments/:coin", get(get_coin_payments))
         .route("/api/payments/:coin/:address", get(get_miner_payments))
+        .route("/api/consolidations/:coin", get(get_consolidations))
+        .route("/api/dormant/:coin", get(get_dormant))
+        .route("/api/teams/:coin", get(get_teams))
+        .route("/api/approvals/:coin", get(get_approvals))
+        .route("/api/approvals/:id/approve", post(approve_payout))
+        .route("/api/approvals/:id/reject", post(reject_payout))
+        .route("/api/proof/:payment_id", get(get_payment_proof))
+        .route("/api/pause", post(pause_all))
+        .route("/api/pause/:coin", post(pause_coin))
+        .route("/api/resume", post(resume_all))
+        .route("/api/resume/:coin", post(resume_coin))
+        .route("/api/notify/:coin", post(notify_block))
+        .route("/api/jobs", get(get_jobs))
+        .route("/api/jobs/runs", get(get_job_runs))
+        .route("/api/jobs/:coin/:job/run", post(run_job))
+        .route("/api/events", get(stream_events))
+        .route("/api/export", get(export_accounting))
+        .route("/api/solvency", get(get_all_solvency))
+        .route("/api/solvency/:coin", get(get_coin_solvency))
+        .route("/metrics", get(get_metrics))
         .layer(middleware::from_fn(move |req: Request<Body>, next: Next| {
             let token = api_token.clone();
             async move { require_auth(token, req, next).await }
@@ -65,35 +134,82 @@ async fn require_auth(
     }
 
     // Check for valid Authorization header
-    if let Some(auth_header) = request.headers().get("Authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..];
-                if token == api_token {
-                    return next.run(request).await;
-                }
-            }
-        }
+    if has_bearer_token(request.headers(), &api_token) {
+        return next.run(request).await;
     }
 
     // Not authenticated
     (StatusCode::UNAUTHORIZED, "Invalid or missing API token").into_response()
 }
 
-/// Health check endpoint
-async fn health_check() -> impl IntoResponse {
-    Json(serde_json::json!({
-        "status": "ok",
-        "service": "solopool-payments"
-    }))
+/// Whether the request carries `Authorization: Bearer <api_token>`
+fn has_bearer_token(headers: &HeaderMap, api_token: &str) -> bool {
+    headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token == api_token)
+}
+
+/// Query params for the health report
+#[derive(Deserialize)]
+struct HealthQuery {
+    /// Also answer 503 when degraded
+    #[serde(default)]
+    strict: bool,
+}
+
+/// Health report: 200 when ok or degraded, 503 when down (or degraded with ?strict=true)
+async fn health_check(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<HealthQuery>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<HealthReport>) {
+    let authorized = state.api_token.is_empty() || has_bearer_token(&headers, &state.api_token);
+    let report = state.health.report(&state.db, authorized).await;
+
+    let failing = match report.status {
+        HealthStatus::Ok => false,
+        HealthStatus::Degraded => query.strict,
+        HealthStatus::Down => true,
+    };
+    let code = if failing {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    (code, Json(report))
+}
+
+/// Prometheus metrics endpoint
+async fn get_metrics(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Response, (StatusCode, String)> {
+    let body = state
+        .metrics
+        .render(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+        .into_response())
 }
 
 /// Overall stats response
 #[derive(Serialize)]
 struct AllStatsResponse {
+    /// Global payout pause
+    payouts_paused: Option<PauseResponse>,
     xmr: Option<CoinStatsResponse>,
     xtm: Option<CoinStatsResponse>,
     aleo: Option<CoinStatsResponse>,
+    btc: Option<CoinStatsResponse>,
+    bch: Option<CoinStatsResponse>,
+    dgb: Option<CoinStatsResponse>,
 }
 
 /// Stats for a single coin
@@ -104,17 +220,68 @@ struct CoinStatsResponse {
     total_pending: String,
     total_paid: String,
     pending_payments: u32,
+    blocks_awaiting_coinbase: u32,
+    blocks_missing_coinbase: u32,
+    /// Percentage of each block reward taken as pool fee
+    pool_fee_percent: String,
+    /// Fixed pool fee per block, in atomic units
+    pool_fee_donation: String,
+    /// Address credited with the pool fee
+    pool_fee_address: Option<String>,
+    /// Pause stopping this coin's payouts (its own or the global one)
+    payouts_paused: Option<PauseResponse>,
+}
+
+/// An active payout pause
+#[derive(Serialize)]
+struct PauseResponse {
+    /// "all" for the global pause
+    scope: String,
+    reason: String,
+    source: String,
+    paused_at: String,
+}
+
+impl From<PayoutPause> for PauseResponse {
+    fn from(p: PayoutPause) -> Self {
+        Self {
+            scope: p.coin.map(|c| c.to_string()).unwrap_or_else(|| "all".to_string()),
+            reason: p.reason,
+            source: p.source,
+            paused_at: p.paused_at.to_rfc3339(),
+        }
+    }
 }
 
 /// Get stats for all coins
 async fn get_all_stats(
     State(state): State<Arc<ApiState>>,
 ) -> Result<Json<AllStatsResponse>, (StatusCode, String)> {
-    let xmr = get_coin_stats_internal(&state.db, Coin::Xmr).await.ok();
-    let xtm = get_coin_stats_internal(&state.db, Coin::Xtm).await.ok();
-    let aleo = get_coin_stats_internal(&state.db, Coin::Aleo).await.ok();
+    let xmr = get_coin_stats_internal(&state, Coin::Xmr).await.ok();
+    let xtm = get_coin_stats_internal(&state, Coin::Xtm).await.ok();
+    let aleo = get_coin_stats_internal(&state, Coin::Aleo).await.ok();
+    let btc = get_coin_stats_internal(&state, Coin::Btc).await.ok();
+    let bch = get_coin_stats_internal(&state, Coin::Bch).await.ok();
+    let dgb = get_coin_stats_internal(&state, Coin::Dgb).await.ok();
+
+    let payouts_paused = state
+        .db
+        .list_payout_pauses()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .find(|p| p.coin.is_none())
+        .map(PauseResponse::from);
 
-    Ok(Json(AllStatsResponse { xmr, xtm, aleo }))
+    Ok(Json(AllStatsResponse {
+        payouts_paused,
+        xmr,
+        xtm,
+        aleo,
+        btc,
+        bch,
+        dgb,
+    }))
 }
 
 /// Get stats for a specific coin
@@ -126,14 +293,17 @@ async fn get_coin_stats(
         .parse()
         .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
 
-    let stats = get_coin_stats_internal(&state.db, coin)
+    let stats = get_coin_stats_internal(&state, coin)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     Ok(Json(stats))
 }
 
-async fn get_coin_stats_internal(db: &Database, coin: Coin) -> anyhow::Result<CoinStatsResponse> {
+async fn get_coin_stats_internal(state: &ApiState, coin: Coin) -> anyhow::Result<CoinStatsResponse> {
+    let db = &state.db;
+    let fee = state.pool_fees.get(&coin);
+
     // Get payable balances (min = 0 to get all)
     let balances = db
         .get_payable_balances(coin, rust_decimal::Decimal::from(0))
@@ -144,12 +314,27 @@ async fn get_coin_stats_internal(db: &Database, coin: Coin) -> anyhow::Result<Co
 
     let pending_payments = db.get_pending_payments(coin).await?.len();
 
+    let block_counts = db.count_blocks_by_reward_status(coin).await?;
+    let blocks_with = |status: RewardStatus| -> u32 {
+        block_counts
+            .iter()
+            .filter(|(s, _)| *s == status)
+            .map(|(_, c)| *c as u32)
+            .sum()
+    };
+
     Ok(CoinStatsResponse {
         coin: coin.to_string(),
         total_miners: balances.len() as u32,
         total_pending: total_pending.to_string(),
         total_paid: total_paid.to_string(),
         pending_payments: pending_payments as u32,
+        blocks_awaiting_coinbase: blocks_with(RewardStatus::AwaitingCoinbase),
+        blocks_missing_coinbase: blocks_with(RewardStatus::Missing),
+        pool_fee_percent: fee.map(|f| f.percent.normalize()).unwrap_or_default().to_string(),
+        pool_fee_donation: fee.map(|f| f.donation.normalize()).unwrap_or_default().to_string(),
+        pool_fee_address: fee.map(|f| f.address.clone()),
+        payouts_paused: db.get_payout_pause(coin).await?.map(PauseResponse::from),
     })
 }
 
@@ -160,6 +345,8 @@ struct MinerInfoResponse {
     coin: String,
     pending_balance: String,
     total_paid: String,
+    /// Set aside after a long absence; returns to pending once the miner mines again
+    abandoned_balance: String,
     total_shares: i64,
     last_share: Option<String>,
     last_payment: Option<String>,
@@ -188,6 +375,8 @@ async fn get_miner_info(
             total_shares: 0,
             last_share: None,
             last_payment: None,
+            carry_balance: rust_decimal::Decimal::from(0),
+            abandoned_balance: rust_decimal::Decimal::from(0),
         });
 
     let payments = state
@@ -201,6 +390,7 @@ async fn get_miner_info(
         coin: coin.to_string(),
         pending_balance: balance.pending_balance.to_string(),
         total_paid: balance.total_paid.to_string(),
+        abandoned_balance: balance.abandoned_balance.to_string(),
         total_shares: balance.total_shares,
         last_share: balance.last_share.map(|d| d.to_rfc3339()),
         last_payment: balance.last_payment.map(|d| d.to_rfc3339()),
@@ -229,7 +419,7 @@ impl From<Payment> for PaymentResponse {
             wallet_address: p.wallet_address,
             amount: p.amount.to_string(),
             tx_hash: p.tx_hash,
-            status: format!("{:?}", p.status).to_lowercase(),
+            status: p.status.to_string(),
             created_at: p.created_at.to_rfc3339(),
             confirmed_at: p.confirmed_at.map(|d| d.to_rfc3339()),
         }
@@ -293,3 +483,889 @@ async fn get_miner_payments(
         payments.into_iter().map(PaymentResponse::from).collect(),
     ))
 }
+
+/// Dormant liabilities report
+#[derive(Serialize)]
+struct DormantResponse {
+    coin: String,
+    days: u32,
+    total_pending: String,
+    total_abandoned: String,
+    balances: Vec<DormantBalanceResponse>,
+}
+
+/// A balance owed to a miner that stopped mining
+#[derive(Serialize)]
+struct DormantBalanceResponse {
+    wallet_address: String,
+    pending_balance: String,
+    abandoned_balance: String,
+    last_share: Option<String>,
+    last_payment: Option<String>,
+}
+
+/// Query params for the dormant report
+#[derive(Deserialize)]
+struct DormantQuery {
+    #[serde(default = "default_dormant_days")]
+    days: u32,
+    #[serde(default = "default_limit")]
+    limit: i32,
+}
+
+fn default_dormant_days() -> u32 {
+    30
+}
+
+/// Get balances owed to miners without shares for `days`
+async fn get_dormant(
+    State(state): State<Arc<ApiState>>,
+    Path(coin): Path<String>,
+    Query(query): Query<DormantQuery>,
+) -> Result<Json<DormantResponse>, (StatusCode, String)> {
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+
+    let since = chrono::Utc::now() - chrono::Duration::days(query.days.into());
+    let balances = state
+        .db
+        .get_dormant_balances(coin, since)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let total_pending: rust_decimal::Decimal = balances.iter().map(|b| b.pending_balance).sum();
+    let total_abandoned: rust_decimal::Decimal = balances.iter().map(|b| b.abandoned_balance).sum();
+
+    Ok(Json(DormantResponse {
+        coin: coin.to_string(),
+        days: query.days,
+        total_pending: total_pending.to_string(),
+        total_abandoned: total_abandoned.to_string(),
+        balances: balances
+            .into_iter()
+            .take(query.limit as usize)
+            .map(|b| DormantBalanceResponse {
+                wallet_address: b.wallet_address,
+                pending_balance: b.pending_balance.to_string(),
+                abandoned_balance: b.abandoned_balance.to_string(),
+                last_share: b.last_share.map(|d| d.to_rfc3339()),
+                last_payment: b.last_payment.map(|d| d.to_rfc3339()),
+            })
+            .collect(),
+    }))
+}
+
+/// A team and the miners redirected to it
+#[derive(Serialize)]
+struct TeamResponse {
+    id: String,
+    members: Vec<TeamMemberResponse>,
+    redirected: Vec<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+/// A team member's share of the team's rewards
+#[derive(Serialize)]
+struct TeamMemberResponse {
+    wallet_address: String,
+    percent: String,
+}
+
+/// Get a coin's teams
+async fn get_teams(
+    State(state): State<Arc<ApiState>>,
+    Path(coin): Path<String>,
+) -> Result<Json<Vec<TeamResponse>>, (StatusCode, String)> {
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+
+    let teams = state
+        .db
+        .get_teams(coin)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let redirects = state
+        .db
+        .get_team_redirects(coin)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(
+        teams
+            .into_iter()
+            .map(|team| {
+                let mut redirected: Vec<String> = redirects
+                    .iter()
+                    .filter(|(_, id)| **id == team.id)
+                    .map(|(address, _)| address.clone())
+                    .collect();
+                redirected.sort();
+
+                TeamResponse {
+                    id: team.id,
+                    members: team
+                        .members
+                        .into_iter()
+                        .map(|m| TeamMemberResponse {
+                            wallet_address: m.wallet_address,
+                            percent: m.percent.normalize().to_string(),
+                        })
+                        .collect(),
+                    redirected,
+                    created_at: team.created_at.to_rfc3339(),
+                    updated_at: team.updated_at.to_rfc3339(),
+                }
+            })
+            .collect(),
+    ))
+}
+
+/// Everything a miner needs to verify a payment
+#[derive(Serialize)]
+struct PaymentProofResponse {
+    payment_id: String,
+    coin: String,
+    wallet_address: String,
+    amount: String,
+    status: String,
+    tx_hash: Option<String>,
+    /// Monero tx key or Tari kernel signature
+    tx_key: Option<String>,
+    /// Signed proof from the pool wallet (XMR, with `generate=true`)
+    tx_proof: Option<String>,
+    /// Message the proof was signed with
+    message: Option<String>,
+    confirmed_at: Option<String>,
+    /// How to check the payment
+    verify: String,
+}
+
+/// Query params for payment proofs
+#[derive(Deserialize)]
+struct ProofQuery {
+    /// Ask the pool wallet to sign a proof
+    #[serde(default)]
+    generate: bool,
+    message: Option<String>,
+}
+
+/// Get the proof for a payment, optionally signing one with the pool wallet
+async fn get_payment_proof(
+    State(state): State<Arc<ApiState>>,
+    Path(payment_id): Path<String>,
+    Query(query): Query<ProofQuery>,
+) -> Result<Json<PaymentProofResponse>, (StatusCode, String)> {
+    let payment = state
+        .db
+        .get_payment(&payment_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Payment not found".to_string()))?;
+
+    let mut tx_proof = None;
+    if query.generate {
+        let tx_hash = payment
+            .tx_hash
+            .as_deref()
+            .ok_or_else(|| (StatusCode::CONFLICT, "Payment has not been sent".to_string()))?;
+        let wallet = state.proof_wallets.get(&payment.coin).ok_or_else(|| {
+            (
+                StatusCode::NOT_IMPLEMENTED,
+                format!("Proof generation is not supported for {}", payment.coin),
+            )
+        })?;
+
+        tx_proof = wallet
+            .get_tx_proof(tx_hash, &payment.wallet_address, query.message.as_deref())
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+    }
+
+    let verify = match payment.coin {
+        Coin::Xmr if tx_proof.is_some() => {
+            "monero-wallet-cli: check_tx_proof <tx_hash> <wallet_address> <tx_proof> [message]"
+        }
+        Coin::Xmr => {
+            "monero-wallet-cli: check_tx_key <tx_hash> <tx_key> <wallet_address>, \
+             or enter tx_hash, wallet_address and tx_key in a block explorer's prove-sending form"
+        }
+        Coin::Xtm => "Search a Tari block explorer for the kernel signature in tx_key",
+        Coin::Aleo => {
+            "Look up tx_hash on an Aleo explorer; the credits.aleo/transfer_public inputs show the recipient and amount"
+        }
+        Coin::Btc | Coin::Bch | Coin::Dgb => {
+            "Look up tx_hash on a block explorer; the output to wallet_address shows the amount"
+        }
+    };
+
+    Ok(Json(PaymentProofResponse {
+        payment_id: payment.id,
+        coin: payment.coin.to_string(),
+        wallet_address: payment.wallet_address,
+        amount: payment.amount.to_string(),
+        status: payment.status.to_string(),
+        tx_hash: payment.tx_hash,
+        tx_key: payment.tx_key,
+        message: tx_proof.as_ref().and(query.message),
+        tx_proof,
+        confirmed_at: payment.confirmed_at.map(|d| d.to_rfc3339()),
+        verify: verify.to_string(),
+    }))
+}
+
+/// Consolidations response
+#[derive(Serialize)]
+struct ConsolidationsResponse {
+    coin: String,
+    /// Payouts are held while a consolidation is unconfirmed
+    payouts_held: bool,
+    consolidations: Vec<ConsolidationResponse>,
+}
+
+/// A pool wallet consolidation
+#[derive(Serialize)]
+struct ConsolidationResponse {
+    id: String,
+    tx_hashes: Vec<String>,
+    outputs: i64,
+    amount: String,
+    fee: String,
+    status: String,
+    created_at: String,
+    confirmed_at: Option<String>,
+    error_message: Option<String>,
+}
+
+impl From<Consolidation> for ConsolidationResponse {
+    fn from(c: Consolidation) -> Self {
+        Self {
+            id: c.id,
+            tx_hashes: c.tx_hashes,
+            outputs: c.outputs,
+            amount: c.amount.to_string(),
+            fee: c.fee.to_string(),
+            status: c.status.to_string(),
+            created_at: c.created_at.to_rfc3339(),
+            confirmed_at: c.confirmed_at.map(|d| d.to_rfc3339()),
+            error_message: c.error_message,
+        }
+    }
+}
+
+/// Get recent consolidations for a coin
+async fn get_consolidations(
+    State(state): State<Arc<ApiState>>,
+    Path(coin): Path<String>,
+    Query(query): Query<PaymentsQuery>,
+) -> Result<Json<ConsolidationsResponse>, (StatusCode, String)> {
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+
+    let consolidations = state
+        .db
+        .list_consolidations(coin, None, query.limit as i64)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let payouts_held = state
+        .db
+        .has_pending_consolidation(coin)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(ConsolidationsResponse {
+        coin: coin.to_string(),
+        payouts_held,
+        consolidations: consolidations
+            .into_iter()
+            .map(ConsolidationResponse::from)
+            .collect(),
+    }))
+}
+
+/// A payout held for approval
+#[derive(Serialize)]
+struct ApprovalResponse {
+    id: String,
+    coin: String,
+    status: String,
+    total: String,
+    fee: Option<String>,
+    tx_hash: Option<String>,
+    /// False when only the intent is queued and the transaction is built on approval
+    prepared: bool,
+    recipients: Vec<PaymentResponse>,
+    created_at: String,
+    decided_at: Option<String>,
+    note: Option<String>,
+}
+
+impl From<PayoutApproval> for ApprovalResponse {
+    fn from(a: PayoutApproval) -> Self {
+        Self {
+            id: a.id,
+            coin: a.coin.to_string(),
+            status: a.status.to_string(),
+            total: a.total.to_string(),
+            fee: a.fee.map(|f| f.to_string()),
+            tx_hash: a.tx_hash,
+            prepared: a.tx_metadata.is_some(),
+            recipients: a.payments.into_iter().map(PaymentResponse::from).collect(),
+            created_at: a.created_at.to_rfc3339(),
+            decided_at: a.decided_at.map(|d| d.to_rfc3339()),
+            note: a.note,
+        }
+    }
+}
+
+/// Query params for the approvals list
+#[derive(Deserialize)]
+struct ApprovalsQuery {
+    status: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: i32,
+}
+
+/// Get payouts held for approval
+async fn get_approvals(
+    State(state): State<Arc<ApiState>>,
+    Path(coin): Path<String>,
+    Query(query): Query<ApprovalsQuery>,
+) -> Result<Json<Vec<ApprovalResponse>>, (StatusCode, String)> {
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+    let status: Option<ApprovalStatus> = query
+        .status
+        .map(|s| s.parse())
+        .transpose()
+        .map_err(|e: anyhow::Error| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let approvals = state
+        .db
+        .list_payout_approvals(coin, status, query.limit as i64)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(
+        approvals.into_iter().map(ApprovalResponse::from).collect(),
+    ))
+}
+
+/// Optional body for approve/reject
+#[derive(Deserialize, Default)]
+struct DecisionRequest {
+    note: Option<String>,
+}
+
+/// Approve a held payout; it is relayed on the next payment cycle
+async fn approve_payout(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<String>,
+    body: Option<Json<DecisionRequest>>,
+) -> Result<Json<ApprovalResponse>, (StatusCode, String)> {
+    decide_payout(&state, &id, true, body.map(|b| b.0).unwrap_or_default()).await
+}
+
+/// Reject a held payout, releasing the reserved balances
+async fn reject_payout(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<String>,
+    body: Option<Json<DecisionRequest>>,
+) -> Result<Json<ApprovalResponse>, (StatusCode, String)> {
+    decide_payout(&state, &id, false, body.map(|b| b.0).unwrap_or_default()).await
+}
+
+async fn decide_payout(
+    state: &ApiState,
+    id: &str,
+    approve: bool,
+    request: DecisionRequest,
+) -> Result<Json<ApprovalResponse>, (StatusCode, String)> {
+    // Without a token anyone who can reach the API could release funds
+    if state.api_token.is_empty() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Payout approval requires api.token to be set".to_string(),
+        ));
+    }
+
+    let decided = state
+        .db
+        .decide_payout_approval(id, approve, request.note.as_deref())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let approval = state
+        .db
+        .get_payout_approval(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Approval not found".to_string()))?;
+
+    if !decided {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Payout is already {}", approval.status),
+        ));
+    }
+
+    Ok(Json(ApprovalResponse::from(approval)))
+}
+
+/// Body for pause
+#[derive(Deserialize)]
+struct PauseRequest {
+    reason: String,
+}
+
+/// Pause payouts for all coins
+async fn pause_all(
+    State(state): State<Arc<ApiState>>,
+    Json(request): Json<PauseRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    set_pause(&state, None, Some(request.reason)).await
+}
+
+/// Pause payouts for one coin
+async fn pause_coin(
+    State(state): State<Arc<ApiState>>,
+    Path(coin): Path<String>,
+    Json(request): Json<PauseRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+    set_pause(&state, Some(coin), Some(request.reason)).await
+}
+
+/// Lift the global pause
+async fn resume_all(State(state): State<Arc<ApiState>>) -> Result<StatusCode, (StatusCode, String)> {
+    set_pause(&state, None, None).await
+}
+
+/// Lift a coin's pause (the global pause, if any, still applies)
+async fn resume_coin(
+    State(state): State<Arc<ApiState>>,
+    Path(coin): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+    set_pause(&state, Some(coin), None).await
+}
+
+/// Pause with a reason, or resume with None
+async fn set_pause(
+    state: &ApiState,
+    coin: Option<Coin>,
+    reason: Option<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if state.api_token.is_empty() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Pausing payouts requires api.token to be set".to_string(),
+        ));
+    }
+
+    match reason {
+        Some(reason) => {
+            if reason.trim().is_empty() {
+                return Err((StatusCode::BAD_REQUEST, "reason must not be empty".to_string()));
+            }
+            state
+                .db
+                .set_payout_pause(coin, &reason, "api")
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            Ok(StatusCode::NO_CONTENT)
+        }
+        None => {
+            let cleared = state
+                .db
+                .clear_payout_pause(coin)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            if cleared {
+                Ok(StatusCode::NO_CONTENT)
+            } else {
+                Err((StatusCode::NOT_FOUND, "Payouts are not paused".to_string()))
+            }
+        }
+    }
+}
+
+/// Block notify hook: process a coin's new blocks now instead of at the next interval
+async fn notify_block(
+    State(state): State<Arc<ApiState>>,
+    Path(coin): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+
+    state.block_notify.notify(coin);
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Get every scheduled job with its next and latest run
+async fn get_jobs(State(state): State<Arc<ApiState>>) -> Json<Vec<JobInfo>> {
+    Json(state.scheduler.jobs())
+}
+
+#[derive(Deserialize)]
+struct JobRunsQuery {
+    coin: Option<String>,
+    job: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: i32,
+}
+
+/// Get job run history, newest first
+async fn get_job_runs(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<JobRunsQuery>,
+) -> Result<Json<Vec<JobRun>>, (StatusCode, String)> {
+    let coin: Option<Coin> = query
+        .coin
+        .map(|c| c.parse())
+        .transpose()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+
+    let runs = state
+        .db
+        .list_job_runs(coin, query.job.as_deref(), query.limit as i64)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(runs))
+}
+
+/// Manual run request result
+#[derive(Serialize)]
+struct RunJobResponse {
+    /// The job was running; the requested run starts when it finishes
+    queued: bool,
+}
+
+/// Run a job now instead of at its next interval
+async fn run_job(
+    State(state): State<Arc<ApiState>>,
+    Path((coin, job)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<RunJobResponse>), (StatusCode, String)> {
+    // The payment job sends funds
+    if state.api_token.is_empty() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Running jobs requires api.token to be set".to_string(),
+        ));
+    }
+
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+
+    match state.scheduler.trigger(coin, &job) {
+        Some(queued) => Ok((StatusCode::ACCEPTED, Json(RunJobResponse { queued }))),
+        None => Err((StatusCode::NOT_FOUND, format!("No {} job for {}", job, coin))),
+    }
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    coin: Option<String>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    #[serde(default)]
+    format: Format,
+}
+
+/// Double-entry accounting records for a date range, as a download
+async fn export_accounting(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let coins: Vec<Coin> = match query.coin {
+        Some(coin) => vec![coin
+            .parse()
+            .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?],
+        None => Coin::ALL.to_vec(),
+    };
+    let (from, to) = accounting::date_range(query.from, query.to)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let transactions = accounting::transactions(&state.db, &coins, from, to)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let filename = match coins.as_slice() {
+        [coin] => format!("solopool-{}.{}", coin, query.format.extension()),
+        _ => format!("solopool.{}", query.format.extension()),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, query.format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        accounting::render(query.format, &transactions),
+    )
+        .into_response())
+}
+
+/// Solvency of every coin with a running processor
+async fn get_all_solvency(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<Vec<SolvencyReport>>, (StatusCode, String)> {
+    let mut reports = Vec::new();
+    for coin in Coin::ALL {
+        let Some(wallet) = state.health.wallet(coin) else {
+            continue;
+        };
+        let report = solvency::report(&state.db, coin, Some(wallet.as_ref()))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        reports.push(report);
+    }
+
+    Ok(Json(reports))
+}
+
+/// Liabilities to miners against the pool wallet of one coin
+async fn get_coin_solvency(
+    State(state): State<Arc<ApiState>>,
+    Path(coin): Path<String>,
+) -> Result<Json<SolvencyReport>, (StatusCode, String)> {
+    let coin: Coin = coin
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+
+    let wallet = state.health.wallet(coin);
+    let report = solvency::report(&state.db, coin, wallet.as_deref())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(report))
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    coin: Option<String>,
+    /// Replay position for clients that can't send the Last-Event-ID header
+    last_event_id: Option<i64>,
+}
+
+/// Stream events as they happen
+///
+/// Each SSE event is named after its `type` and carries the event id, so a
+/// reconnecting `EventSource` resumes from the `Last-Event-ID` it last saw.
+async fn stream_events(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<EventsQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, (StatusCode, String)> {
+    let coin: Option<Coin> = query
+        .coin
+        .map(|c| c.parse())
+        .transpose()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid coin".to_string()))?;
+
+    let last_event_id = match headers.get("last-event-id") {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid Last-Event-ID".to_string()))?,
+        ),
+        None => query.last_event_id,
+    };
+
+    let stream = state.events.subscribe(coin, last_event_id).map(|event| {
+        Ok(SseEvent::default()
+            .id(event.id.to_string())
+            .event(event.kind.name())
+            .json_data(&event)
+            .unwrap_or_else(|_| SseEvent::default().comment("unserializable event")))
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TempDatabase;
+    use crate::pools::monero_pool::MoneroPoolApi;
+    use crate::wallets::monero::MoneroWallet;
+    use rust_decimal::Decimal;
+    use serde_json::json;
+
+    const TOKEN: &str = "secret";
+
+    /// monero-pool API and wallet RPC on one mock server
+    async fn backend() -> String {
+        let app = Router::new()
+            .route("/stats", get(|| async { Json(json!({})) }))
+            .route(
+                "/json_rpc",
+                post(|Json(request): Json<serde_json::Value>| async move {
+                    let params = &request["params"];
+                    let result = match request["method"].as_str() {
+                        Some("get_tx_proof") => json!({
+                            "signature": format!(
+                                "OutProofV2-{}-{}-{}",
+                                params["txid"].as_str().unwrap(),
+                                params["address"].as_str().unwrap(),
+                                params["message"].as_str().unwrap()
+                            )
+                        }),
+                        _ => json!({"balance": 2_000, "unlocked_balance": 1_500}),
+                    };
+                    Json(json!({"jsonrpc": "2.0", "id": "0", "result": result}))
+                }),
+            );
+        serve(app).await
+    }
+
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    /// The API for `db`, with XMR's pool and wallet at `backend_url`
+    async fn api(db: &Database, health: Health, backend_url: &str) -> String {
+        let wallet: Arc<dyn Wallet> = Arc::new(MoneroWallet::new(backend_url, 15));
+        health.register(
+            Coin::Xmr,
+            Arc::new(MoneroPoolApi::new(backend_url)),
+            wallet.clone(),
+        );
+        let state = ApiState {
+            db: db.clone(),
+            api_token: TOKEN.to_string(),
+            metrics: Metrics::new(),
+            proof_wallets: HashMap::from([(Coin::Xmr, wallet)]),
+            block_notify: BlockNotify::new(),
+            health,
+            scheduler: Scheduler::new(db.clone(), 0),
+            events: EventBus::new(db.clone()),
+            pool_fees: HashMap::new(),
+        };
+        serve(create_router(state)).await
+    }
+
+    async fn fetch(url: String, token: Option<&str>) -> (u16, serde_json::Value) {
+        let mut request = reqwest::Client::new().get(url);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.unwrap();
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn health_is_public_but_balances_need_the_token() {
+        let db = TempDatabase::new().await;
+        let url = api(&db, Health::new(60, 600), &backend().await).await;
+
+        let (status, report) = fetch(format!("{}/api/health", url), None).await;
+        assert_eq!(status, 200);
+        assert_eq!(report["status"], "ok");
+        assert!(report["coins"]["xmr"].get("wallet_balance").is_none());
+
+        let (_, report) = fetch(format!("{}/api/health", url), Some(TOKEN)).await;
+        assert_eq!(report["coins"]["xmr"]["wallet_balance"], "1500");
+
+        let (status, _) = fetch(format!("{}/api/stats", url), None).await;
+        assert_eq!(status, 401);
+    }
+
+    #[tokio::test]
+    async fn health_is_503_when_down_or_degraded_in_strict_mode() {
+        let db = TempDatabase::new().await;
+
+        // Zero intervals make every stage stale at once
+        let url = api(&db, Health::new(0, 0), &backend().await).await;
+        let (status, report) = fetch(format!("{}/api/health", url), None).await;
+        assert_eq!((status, report["status"].as_str()), (200, Some("degraded")));
+        let (status, _) = fetch(format!("{}/api/health?strict=true", url), None).await;
+        assert_eq!(status, 503);
+
+        let url = api(&db, Health::new(60, 600), "http://127.0.0.1:1").await;
+        let (status, report) = fetch(format!("{}/api/health", url), None).await;
+        assert_eq!((status, report["status"].as_str()), (503, Some("down")));
+    }
+
+    /// A payment of 500 to alice, sent as "tx1" with key "key1" if `sent`
+    async fn payment(db: &Database, coin: Coin, sent: bool) -> String {
+        let id = db
+            .create_payment(coin, "alice", Decimal::from(500))
+            .await
+            .unwrap();
+        if sent {
+            db.mark_payment_sent(&id, "tx1", Some("key1"))
+                .await
+                .unwrap();
+        }
+        id
+    }
+
+    #[tokio::test]
+    async fn proof_returns_the_key_stored_at_send_time() {
+        let db = TempDatabase::new().await;
+        let url = api(&db, Health::new(60, 600), &backend().await).await;
+        let id = payment(&db, Coin::Xmr, true).await;
+
+        let (status, _) = fetch(format!("{}/api/proof/{}", url, id), None).await;
+        assert_eq!(status, 401);
+
+        let (status, proof) = fetch(format!("{}/api/proof/{}", url, id), Some(TOKEN)).await;
+        assert_eq!(status, 200);
+        assert_eq!(proof["tx_hash"], "tx1");
+        assert_eq!(proof["tx_key"], "key1");
+        assert_eq!(proof["tx_proof"], serde_json::Value::Null);
+        assert!(proof["verify"].as_str().unwrap().contains("check_tx_key"));
+    }
+
+    #[tokio::test]
+    async fn proof_is_signed_by_the_pool_wallet_on_request() {
+        let db = TempDatabase::new().await;
+        let url = api(&db, Health::new(60, 600), &backend().await).await;
+        let id = payment(&db, Coin::Xmr, true).await;
+
+        let (status, proof) = fetch(
+            format!("{}/api/proof/{}?generate=true&message=hello", url, id),
+            Some(TOKEN),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(proof["tx_proof"], "OutProofV2-tx1-alice-hello");
+        assert_eq!(proof["message"], "hello");
+        assert!(proof["verify"].as_str().unwrap().contains("check_tx_proof"));
+    }
+
+    #[tokio::test]
+    async fn proof_generation_needs_a_sent_payment_and_a_proof_wallet() {
+        let db = TempDatabase::new().await;
+        let url = api(&db, Health::new(60, 600), &backend().await).await;
+        let proof = |id: &str| format!("{}/api/proof/{}?generate=true", url, id);
+
+        let unsent = payment(&db, Coin::Xmr, false).await;
+        assert_eq!(fetch(proof(&unsent), Some(TOKEN)).await.0, 409);
+
+        let tari = payment(&db, Coin::Xtm, true).await;
+        assert_eq!(fetch(proof(&tari), Some(TOKEN)).await.0, 501);
+
+        assert_eq!(fetch(proof("missing"), Some(TOKEN)).await.0, 404);
+    }
+}
@@ -0,0 +1,221 @@
+//! `check-config` subcommand
+//!
+//! Runs `Config::validate`, then probes every enabled coin:
+//! - the wallet RPC answers and reports the configured pool wallet address
+//!   (ALEO has no wallet RPC to ask, so its private key is reported as not
+//!   verified)
+//! - the pool API answers `is_online`
+//!
+//! With `[xmr.wallet_manager]` enabled, the XMR wallet's sync height is
+//! compared with monerod's (without opening or refreshing the wallet).
+//!
+//! For BTC / BCH / DGB the wallet must own the pool address and CKPool's
+//! `pool.status` must have been written recently.
+//!
+//! Prints a report grouped by config section and exits non-zero if any
+//! error was found.
+
+use super::{Report, Status};
+use crate::config::{
+    Config, PoolType, Severity, DEFAULT_ALEO_POOL_API_URL, DEFAULT_XMR_POOL_API_URL,
+    DEFAULT_XTM_MERGE_PROXY_API_URL, DEFAULT_XTM_MINOTARI_API_URL,
+};
+use crate::db::Coin;
+use crate::pools::{
+    aleo::AleoPoolApi, ckpool::CkPoolApi, minotari::MinotariMinerApi, monero_pool::MoneroPoolApi,
+    tari::TariMergeProxyApi, PoolApi,
+};
+use crate::wallets::{
+    aleo::AleoWallet, bitcoind::BitcoindWallet, monero::MoneroWallet, tari::TariWallet, Wallet,
+};
+use crate::wallet_manager::{WalletManager, WalletReadiness};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// How long to wait for a single wallet or pool probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run the check and print the report; returns the exit code
+pub async fn run(config: &Config, offline: bool) -> anyhow::Result<i32> {
+    let mut report = Report::default();
+
+    for issue in config.validate() {
+        let status = match issue.severity {
+            Severity::Error => Status::Error,
+            Severity::Warning => Status::Warning,
+        };
+        report.add(issue.section, status, issue.message);
+    }
+
+    if !offline {
+        probe_coins(config, &mut report).await;
+    }
+
+    Ok(report.print("Config"))
+}
+
+/// Probe the wallet RPC and pool API of every enabled coin
+async fn probe_coins(config: &Config, report: &mut Report) {
+    if let Some(xmr) = config.xmr.as_ref().filter(|c| c.enabled) {
+        let wallet = MoneroWallet::new(&xmr.wallet_rpc_url, xmr.mixin);
+        probe_wallet(report, "xmr", &wallet, &xmr.wallet_rpc_url, &xmr.pool_wallet_address).await;
+
+        if let Some(manager) = xmr.wallet_manager.clone().filter(|m| m.enabled) {
+            let wallet = MoneroWallet::new(&xmr.wallet_rpc_url, xmr.mixin);
+            let manager = WalletManager::new(Coin::Xmr, wallet, &xmr.pool_wallet_address, manager);
+            match timeout(PROBE_TIMEOUT, manager.inspect()).await {
+                Err(_) => report.add(
+                    "xmr",
+                    Status::Error,
+                    format!("wallet sync check did not finish within {}s", PROBE_TIMEOUT.as_secs()),
+                ),
+                Ok(Err(e)) => report.add("xmr", Status::Error, format!("wallet sync check failed: {}", e)),
+                Ok(Ok(WalletReadiness::Ready)) => {
+                    report.add("xmr", Status::Ok, "pool wallet is open and synced with monerod")
+                }
+                Ok(Ok(not_ready)) => report.add(
+                    "xmr",
+                    Status::Warning,
+                    format!("{}; payouts will wait until the wallet is ready", not_ready),
+                ),
+            }
+        }
+
+        let url = xmr.pool_api_url.as_deref().unwrap_or(DEFAULT_XMR_POOL_API_URL);
+        let pool_api: Option<Box<dyn PoolApi>> = match xmr.pool_type {
+            PoolType::MoneroPool => Some(Box::new(MoneroPoolApi::new(url))),
+            PoolType::MergeProxy => Some(Box::new(TariMergeProxyApi::new(url))),
+            PoolType::MinotariMiner | PoolType::Ckpool => None,
+        };
+        if let Some(pool_api) = pool_api {
+            probe_pool(report, "xmr", pool_api.as_ref(), url, xmr.pool_type.as_str()).await;
+        }
+    } else {
+        report.add("xmr", Status::Ok, "disabled");
+    }
+
+    if let Some(xtm) = config.xtm.as_ref().filter(|c| c.enabled) {
+        let wallet = TariWallet::new(&xtm.wallet_rpc_url);
+        probe_wallet(report, "xtm", &wallet, &xtm.wallet_rpc_url, &xtm.pool_wallet_address).await;
+
+        let pool_api: Option<(Box<dyn PoolApi>, &str)> = match xtm.pool_type {
+            PoolType::MergeProxy => {
+                let url = xtm.pool_api_url.as_deref().unwrap_or(DEFAULT_XTM_MERGE_PROXY_API_URL);
+                Some((Box::new(TariMergeProxyApi::new(url)), url))
+            }
+            PoolType::MinotariMiner => {
+                let url = xtm.pool_api_url.as_deref().unwrap_or(DEFAULT_XTM_MINOTARI_API_URL);
+                Some((Box::new(MinotariMinerApi::new(url)), url))
+            }
+            PoolType::MoneroPool | PoolType::Ckpool => None,
+        };
+        if let Some((pool_api, url)) = pool_api {
+            probe_pool(report, "xtm", pool_api.as_ref(), url, xtm.pool_type.as_str()).await;
+        }
+    } else {
+        report.add("xtm", Status::Ok, "disabled");
+    }
+
+    if let Some(aleo) = config.aleo.as_ref().filter(|c| c.enabled) {
+        let wallet = AleoWallet::new(
+            &aleo.node_rpc_url,
+            &aleo.pool_wallet_address,
+            &aleo.pool_private_key,
+        );
+        probe_wallet(report, "aleo", &wallet, &aleo.node_rpc_url, &aleo.pool_wallet_address).await;
+
+        let url = aleo.pool_api_url.as_deref().unwrap_or(DEFAULT_ALEO_POOL_API_URL);
+        probe_pool(report, "aleo", &AleoPoolApi::new(url), url, "aleo pool server").await;
+    } else {
+        report.add("aleo", Status::Ok, "disabled");
+    }
+
+    for (coin, section, coin_config) in [
+        (Coin::Btc, "btc", &config.btc),
+        (Coin::Bch, "bch", &config.bch),
+        (Coin::Dgb, "dgb", &config.dgb),
+    ] {
+        let Some(coin_config) = coin_config.as_ref().filter(|c| c.enabled) else {
+            report.add(section, Status::Ok, "disabled");
+            continue;
+        };
+
+        let wallet = BitcoindWallet::from_config(coin, coin_config);
+        probe_wallet(
+            report,
+            section,
+            &wallet,
+            &coin_config.wallet_rpc_url,
+            &coin_config.pool_wallet_address,
+        )
+        .await;
+
+        let log_dir = coin_config.pool_data_path.display().to_string();
+        let pool_api = CkPoolApi::new(&coin_config.pool_data_path, wallet);
+        probe_pool(report, section, &pool_api, &log_dir, "ckpool logs").await;
+    }
+}
+
+/// Check the wallet RPC is reachable and holds the configured pool wallet
+async fn probe_wallet(
+    report: &mut Report,
+    section: &'static str,
+    wallet: &dyn Wallet,
+    rpc_url: &str,
+    expected_address: &str,
+) {
+    match timeout(PROBE_TIMEOUT, wallet.get_address()).await {
+        Err(_) => report.add(
+            section,
+            Status::Error,
+            format!("wallet RPC at {} did not respond within {}s", rpc_url, PROBE_TIMEOUT.as_secs()),
+        ),
+        Ok(Err(e)) => report.add(section, Status::Error, format!("wallet RPC at {} failed: {}", rpc_url, e)),
+        Ok(Ok(Some(address))) if address == expected_address => report.add(
+            section,
+            Status::Ok,
+            format!("wallet RPC reachable at {}, address matches pool_wallet_address", rpc_url),
+        ),
+        Ok(Ok(Some(address))) => report.add(
+            section,
+            Status::Error,
+            format!(
+                "wallet RPC at {} holds {} but pool_wallet_address is {}",
+                rpc_url, address, expected_address
+            ),
+        ),
+        // The ALEO node has no wallet, so the address derived from
+        // pool_private_key can't be compared with pool_wallet_address
+        Ok(Ok(None)) => report.add(
+            section,
+            Status::Warning,
+            format!(
+                "node RPC reachable at {}; pool_private_key not verified against pool_wallet_address",
+                rpc_url
+            ),
+        ),
+    }
+}
+
+/// Check the pool API answers
+async fn probe_pool(
+    report: &mut Report,
+    section: &'static str,
+    pool_api: &dyn PoolApi,
+    url: &str,
+    kind: &str,
+) {
+    match timeout(PROBE_TIMEOUT, pool_api.is_online()).await {
+        Ok(true) => report.add(section, Status::Ok, format!("pool API online at {} ({})", url, kind)),
+        Ok(false) => report.add(
+            section,
+            Status::Error,
+            format!("pool API at {} ({}) is not responding", url, kind),
+        ),
+        Err(_) => report.add(
+            section,
+            Status::Error,
+            format!("pool API at {} ({}) did not respond within {}s", url, kind, PROBE_TIMEOUT.as_secs()),
+        ),
+    }
+}
@@ -0,0 +1,1305 @@
+//! Operator commands that work directly on the payments database
+//!
+//! Read commands print tables to stdout. Commands that write (redistribute,
+//! mark-payment, reclaim, pause, resume) print what will change and ask for confirmation
+//! unless `--yes` is given. Writes go through the same `Database` methods as the
+//! service and are safe to run while it is running.
+
+use super::{confirm, Report, Status};
+use crate::accounting::{self, Format};
+use crate::db::{Coin, Database, LedgerEntryType, MinerBalance, PaymentStatus, RewardStatus, TeamMember};
+use crate::distribution;
+use crate::solvency::{self, SolvencyStatus};
+use crate::wallets::Wallet;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Differences smaller than one atomic unit are rounding, not drift
+const INTEGRITY_TOLERANCE: Decimal = Decimal::ONE;
+
+/// Payments left pending/processing longer than this are reported as stuck
+const STUCK_PAYMENT_SECS: i64 = 24 * 3600;
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// `balances`
+pub async fn balances(
+    db: &Database,
+    coins: Vec<Coin>,
+    address: Option<&str>,
+    min: Option<Decimal>,
+    limit: usize,
+) -> anyhow::Result<i32> {
+    for coin in coins {
+        let mut balances: Vec<_> = db
+            .get_all_balances(coin)
+            .await?
+            .into_iter()
+            .filter(|b| address.is_none_or(|a| b.wallet_address == a))
+            .filter(|b| min.is_none_or(|m| b.pending_balance >= m))
+            .collect();
+        balances.sort_by_key(|b| std::cmp::Reverse(b.pending_balance));
+
+        let pending: Decimal = balances.iter().map(|b| b.pending_balance).sum();
+        println!(
+            "{}: {} balance(s), {} pending (atomic units)",
+            coin,
+            balances.len(),
+            pending.normalize()
+        );
+
+        if balances.is_empty() {
+            println!();
+            continue;
+        }
+
+        println!(
+            "  {:>22} {:>22} {:>8}  {:<19}  {:<19}  ADDRESS",
+            "PENDING", "TOTAL PAID", "SHARES", "LAST SHARE", "LAST PAYMENT"
+        );
+        for balance in balances.iter().take(limit) {
+            println!(
+                "  {:>22} {:>22} {:>8}  {:<19}  {:<19}  {}",
+                balance.pending_balance.normalize(),
+                balance.total_paid.normalize(),
+                balance.total_shares,
+                format_time(balance.last_share),
+                format_time(balance.last_payment),
+                balance.wallet_address,
+            );
+        }
+        if balances.len() > limit {
+            println!("  ... {} more (use --limit)", balances.len() - limit);
+        }
+        println!();
+    }
+
+    Ok(0)
+}
+
+/// `payments`
+pub async fn payments(
+    db: &Database,
+    coins: Vec<Coin>,
+    status: Option<PaymentStatus>,
+    address: Option<&str>,
+    limit: i64,
+) -> anyhow::Result<i32> {
+    for coin in coins {
+        let payments = db.list_payments(coin, status, address, limit).await?;
+
+        println!("{}: {} payment(s)", coin, payments.len());
+        if payments.is_empty() {
+            println!();
+            continue;
+        }
+
+        println!(
+            "  {:<36}  {:<24} {:>22}  {:<10}  {:<19}  TX HASH / ERROR",
+            "ID", "ADDRESS", "AMOUNT", "STATUS", "CREATED"
+        );
+        for payment in payments {
+            let detail = match (&payment.tx_hash, &payment.error_message) {
+                (Some(tx_hash), _) => tx_hash.clone(),
+                (None, Some(error)) => error.clone(),
+                (None, None) => "-".to_string(),
+            };
+            println!(
+                "  {:<36}  {:<24} {:>22}  {:<10}  {:<19}  {}",
+                payment.id,
+                short_address(&payment.wallet_address),
+                payment.amount.normalize(),
+                payment.status,
+                format_time(Some(payment.created_at)),
+                detail,
+            );
+        }
+        println!();
+    }
+
+    Ok(0)
+}
+
+/// `block`
+pub async fn block(db: &Database, coin: Coin, height: i64) -> anyhow::Result<i32> {
+    let blocks = db.get_blocks_at_height(coin, height).await?;
+    if blocks.is_empty() {
+        eprintln!("No {} block recorded at height {}", coin, height);
+        return Ok(1);
+    }
+
+    for block in blocks {
+        println!("Block {} at height {} (id {})", coin, block.block_height, block.id);
+        println!("  Hash:        {}", block.block_hash);
+        println!("  Reward:      {}", block.reward.normalize());
+        println!("  Finder:      {} ({})", block.finder_wallet, block.finder_worker);
+        println!("  Found:       {}", format_time(Some(block.timestamp)));
+        println!("  Distributed: {}", if block.distributed { "yes" } else { "no" });
+        println!("  Coinbase:    {}", block.reward_status);
+        if let Some(reported) = block.reported_reward {
+            println!("  Pool reward: {}", reported.normalize());
+        }
+        if let Some(coinbase_tx) = &block.coinbase_tx {
+            println!("  Coinbase tx: {}", coinbase_tx);
+        }
+
+        let entries = db.get_block_ledger(&block).await?;
+        if entries.is_empty() {
+            if block.distributed {
+                println!("  No ledger entries (distributed before ledger tracking)");
+            }
+            println!();
+            continue;
+        }
+
+        println!();
+        println!(
+            "  {:<19}  {:<15} {:>26}  {:<24}  MEMO",
+            "TIME", "TYPE", "AMOUNT", "ADDRESS"
+        );
+        for entry in &entries {
+            println!(
+                "  {:<19}  {:<15} {:>26}  {:<24}  {}",
+                format_time(Some(entry.created_at)),
+                entry.entry_type,
+                entry.amount.normalize(),
+                short_address(&entry.wallet_address),
+                entry.memo.as_deref().unwrap_or(""),
+            );
+        }
+
+        let net: Decimal = entries.iter().map(|e| e.amount).sum();
+        println!();
+        println!("  Net credited: {} of {}", net.normalize(), block.reward.normalize());
+        println!();
+    }
+
+    Ok(0)
+}
+
+/// `redistribute`
+pub async fn redistribute(
+    db: &Database,
+    coin: Coin,
+    params: &distribution::Params,
+    height: i64,
+    id: Option<i64>,
+    yes: bool,
+) -> anyhow::Result<i32> {
+    let blocks = db.get_blocks_at_height(coin, height).await?;
+    let block = match (blocks.len(), id) {
+        (0, _) => {
+            eprintln!("No {} block recorded at height {}", coin, height);
+            return Ok(1);
+        }
+        (_, Some(id)) => match blocks.into_iter().find(|b| b.id == id) {
+            Some(block) => block,
+            None => {
+                eprintln!("No {} block with id {} at height {}", coin, id, height);
+                return Ok(1);
+            }
+        },
+        (1, None) => blocks.into_iter().next().expect("one block"),
+        (_, None) => {
+            let ids: Vec<String> = blocks.iter().map(|b| b.id.to_string()).collect();
+            eprintln!(
+                "{} blocks recorded at height {} (ids {}); choose one with --id",
+                blocks.len(),
+                height,
+                ids.join(", ")
+            );
+            return Ok(1);
+        }
+    };
+
+    let existing = db.get_block_ledger(&block).await?;
+    if block.distributed && existing.is_empty() {
+        eprintln!(
+            "Block {} was distributed before ledger tracking, so its credits cannot be reversed. \
+             Adjust the affected balances manually.",
+            block.block_height
+        );
+        return Ok(1);
+    }
+
+    let mut current: BTreeMap<&str, Decimal> = BTreeMap::new();
+    for entry in &existing {
+        *current.entry(entry.wallet_address.as_str()).or_default() += entry.amount;
+    }
+
+    let credits = distribution::calculate_with(db, &block, params).await?;
+    let new = distribution::per_wallet(&credits);
+
+    println!(
+        "Redistributing {} block {} (id {}), reward {}",
+        coin,
+        block.block_height,
+        block.id,
+        block.reward.normalize()
+    );
+    println!();
+    println!("  {:>26} {:>26} {:>26}  ADDRESS", "CURRENT", "NEW", "CHANGE");
+
+    let mut wallets: Vec<&str> = current.keys().copied().collect();
+    for credit in &new {
+        if !wallets.contains(&credit.wallet_address.as_str()) {
+            wallets.push(&credit.wallet_address);
+        }
+    }
+
+    let mut changed = false;
+    for wallet in wallets {
+        let before = current.get(wallet).copied().unwrap_or_default();
+        let after = new
+            .iter()
+            .find(|c| c.wallet_address == wallet)
+            .map(|c| c.amount + c.carry)
+            .unwrap_or_default();
+        changed |= before != after;
+
+        println!(
+            "  {:>26} {:>26} {:>26}  {}",
+            before.normalize(),
+            after.normalize(),
+            (after - before).normalize(),
+            wallet
+        );
+    }
+    println!();
+
+    if !changed && block.distributed {
+        println!("Distribution is unchanged, nothing to do");
+        return Ok(0);
+    }
+
+    if !confirm("Apply this distribution?", yes)? {
+        println!("Aborted");
+        return Ok(1);
+    }
+
+    db.redistribute_block(&block, &new, "manual redistribution").await?;
+    println!("Block {} redistributed", block.block_height);
+    Ok(0)
+}
+
+/// `recompute`
+pub async fn recompute(
+    db: &Database,
+    db_path: &Path,
+    coins: Vec<(Coin, distribution::Params)>,
+    scratch: Option<PathBuf>,
+    limit: usize,
+) -> anyhow::Result<i32> {
+    // A temporary copy next to the live database unless asked to keep one
+    let keep = scratch.is_some();
+    let scratch_path = scratch.unwrap_or_else(|| {
+        let mut name = db_path.as_os_str().to_owned();
+        name.push(format!(".recompute-{}", uuid::Uuid::new_v4()));
+        PathBuf::from(name)
+    });
+    if scratch_path.exists() {
+        eprintln!("{} already exists; choose another --scratch path", scratch_path.display());
+        return Ok(1);
+    }
+
+    db.copy_to(&scratch_path).await?;
+    let scratch = Database::new(&scratch_path).await?;
+    let result = compare_recomputed(db, &scratch, coins, limit).await;
+
+    if keep {
+        println!("Recomputed database kept at {}", scratch_path.display());
+    } else {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = scratch_path.as_os_str().to_owned();
+            path.push(suffix);
+            let _ = std::fs::remove_file(PathBuf::from(path));
+        }
+    }
+
+    result
+}
+
+/// Replay each coin into `scratch` and print the balances that change
+async fn compare_recomputed(
+    db: &Database,
+    scratch: &Database,
+    coins: Vec<(Coin, distribution::Params)>,
+    limit: usize,
+) -> anyhow::Result<i32> {
+    for (coin, params) in coins {
+        let replayed = distribution::replay(scratch, coin, &params).await?;
+        if replayed.blocks == 0 {
+            continue;
+        }
+
+        // Pending plus carry: what each miner is owed to the fraction of a unit
+        let owed = |balances: Vec<MinerBalance>| -> BTreeMap<String, Decimal> {
+            balances
+                .into_iter()
+                .map(|b| (b.wallet_address, b.pending_balance + b.carry_balance))
+                .collect()
+        };
+        let current = owed(db.get_all_balances(coin).await?);
+        let recomputed = owed(scratch.get_all_balances(coin).await?);
+
+        let mut changes: Vec<(&str, Decimal, Decimal)> = current
+            .keys()
+            .chain(recomputed.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|wallet| {
+                let before = current.get(wallet).copied().unwrap_or_default();
+                let after = recomputed.get(wallet).copied().unwrap_or_default();
+                (wallet.as_str(), before, after)
+            })
+            .filter(|(_, before, after)| before != after)
+            .collect();
+        changes.sort_by_key(|(_, before, after)| std::cmp::Reverse((after - before).abs()));
+
+        let net: Decimal = changes.iter().map(|(_, before, after)| after - before).sum();
+        let overpaid = changes.iter().filter(|(_, _, after)| after.is_sign_negative()).count();
+        println!(
+            "{}: {} block(s) replayed with a {}s window, teams {}, {}; {} balance(s) change, net {} (atomic units)",
+            coin,
+            replayed.blocks,
+            params.window_secs,
+            if params.teams { "on" } else { "off" },
+            params.fee.as_ref().map(|f| f.to_string()).unwrap_or_else(|| "no pool fee".to_string()),
+            changes.len(),
+            net.normalize()
+        );
+        if replayed.untracked > 0 {
+            println!(
+                "  {} block(s) distributed before ledger tracking are left in opening balances",
+                replayed.untracked
+            );
+        }
+        if overpaid > 0 {
+            println!("  {} miner(s) have already been paid more than they would have earned", overpaid);
+        }
+
+        if changes.is_empty() {
+            println!();
+            continue;
+        }
+
+        println!("  {:>26} {:>26} {:>26}  ADDRESS", "CURRENT", "RECOMPUTED", "CHANGE");
+        for (wallet, before, after) in changes.iter().take(limit) {
+            println!(
+                "  {:>26} {:>26} {:>26}  {}",
+                before.normalize(),
+                after.normalize(),
+                (after - before).normalize(),
+                wallet
+            );
+        }
+        if changes.len() > limit {
+            println!("  ... {} more (use --limit)", changes.len() - limit);
+        }
+        println!();
+    }
+
+    Ok(0)
+}
+
+/// `mark-payment`
+pub async fn mark_payment(
+    db: &Database,
+    payment_id: &str,
+    status: PaymentStatus,
+    tx_hash: Option<&str>,
+    note: Option<&str>,
+    yes: bool,
+) -> anyhow::Result<i32> {
+    let Some(payment) = db.get_payment(payment_id).await? else {
+        eprintln!("Payment {} not found", payment_id);
+        return Ok(1);
+    };
+
+    if payment.status == status {
+        println!("Payment {} is already {}", payment.id, status);
+        return Ok(0);
+    }
+
+    println!("Payment {}", payment.id);
+    println!("  Coin:    {}", payment.coin);
+    println!("  Address: {}", payment.wallet_address);
+    println!("  Amount:  {}", payment.amount.normalize());
+    println!("  TX hash: {}", payment.tx_hash.as_deref().or(tx_hash).unwrap_or("-"));
+    println!("  Status:  {} -> {}", payment.status, status);
+
+    if status == PaymentStatus::Confirmed {
+        println!(
+            "  Balance: {} moves from pending to total paid",
+            payment.amount.normalize()
+        );
+    } else if payment.status == PaymentStatus::Confirmed {
+        println!(
+            "  Balance: {} moves from total paid back to pending (will be paid again)",
+            payment.amount.normalize()
+        );
+    }
+    println!();
+
+    if !confirm("Update this payment?", yes)? {
+        println!("Aborted");
+        return Ok(1);
+    }
+
+    db.override_payment_status(&payment, status, tx_hash, note)
+        .await?;
+    println!("Payment {} marked {}", payment.id, status);
+    Ok(0)
+}
+
+/// `export`
+pub async fn export(
+    db: &Database,
+    coins: Vec<Coin>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    format: Format,
+    output: Option<&Path>,
+) -> anyhow::Result<i32> {
+    let (start, end) = accounting::date_range(from, to)?;
+
+    let transactions = accounting::transactions(db, &coins, start, end).await?;
+
+    let rendered = accounting::render(format, &transactions);
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)?;
+            eprintln!("Wrote {} transaction(s) to {}", transactions.len(), path.display());
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(0)
+}
+
+/// `dormant`
+pub async fn dormant(db: &Database, coins: Vec<(Coin, u32)>, limit: usize) -> anyhow::Result<i32> {
+    for (coin, days) in coins {
+        let since = Utc::now() - chrono::Duration::days(days.into());
+        let balances = db.get_dormant_balances(coin, since).await?;
+
+        let pending: Decimal = balances.iter().map(|b| b.pending_balance).sum();
+        let abandoned: Decimal = balances.iter().map(|b| b.abandoned_balance).sum();
+        println!(
+            "{}: {} miner(s) without shares for {} day(s): {} pending, {} abandoned (atomic units)",
+            coin,
+            balances.len(),
+            days,
+            pending.normalize(),
+            abandoned.normalize()
+        );
+
+        if balances.is_empty() {
+            println!();
+            continue;
+        }
+
+        println!(
+            "  {:>22} {:>22}  {:<19}  {:<19}  ADDRESS",
+            "PENDING", "ABANDONED", "LAST SHARE", "LAST PAYMENT"
+        );
+        for balance in balances.iter().take(limit) {
+            println!(
+                "  {:>22} {:>22}  {:<19}  {:<19}  {}",
+                balance.pending_balance.normalize(),
+                balance.abandoned_balance.normalize(),
+                format_time(balance.last_share),
+                format_time(balance.last_payment),
+                balance.wallet_address,
+            );
+        }
+        if balances.len() > limit {
+            println!("  ... {} more (use --limit)", balances.len() - limit);
+        }
+        println!();
+    }
+
+    Ok(0)
+}
+
+/// `reclaim`
+pub async fn reclaim(db: &Database, coin: Coin, address: &str, yes: bool) -> anyhow::Result<i32> {
+    let balance = db
+        .get_miner_balance(coin, address)
+        .await?
+        .filter(|b| b.abandoned_balance > Decimal::ZERO);
+    let Some(balance) = balance else {
+        eprintln!("{} has no abandoned {} balance", address, coin);
+        return Ok(1);
+    };
+
+    println!("Balance {} ({})", balance.wallet_address, coin);
+    println!(
+        "  Pending: {} -> {}",
+        balance.pending_balance.normalize(),
+        (balance.pending_balance + balance.abandoned_balance).normalize()
+    );
+    println!("  Abandoned: {} -> 0", balance.abandoned_balance.normalize());
+    println!();
+
+    if !confirm("Reclaim this balance?", yes)? {
+        println!("Aborted");
+        return Ok(1);
+    }
+
+    let amount = db.reclaim_abandoned(coin, address, "reclaimed by operator").await?;
+    println!("Reclaimed {} for {}", amount.normalize(), address);
+    Ok(0)
+}
+
+/// `pause`
+pub async fn pause(db: &Database, coin: Option<Coin>, reason: &str, yes: bool) -> anyhow::Result<i32> {
+    if reason.trim().is_empty() {
+        eprintln!("--reason must not be empty");
+        return Ok(1);
+    }
+
+    let scope = coin.map(|c| c.to_string()).unwrap_or_else(|| "all".to_string());
+    println!("Pause ({})", scope);
+    println!("  Reason: {}", reason);
+    println!();
+
+    if !confirm("Pause payouts?", yes)? {
+        println!("Aborted");
+        return Ok(1);
+    }
+
+    db.set_payout_pause(coin, reason, "cli").await?;
+    print_pauses(db).await?;
+    Ok(0)
+}
+
+/// `resume`
+pub async fn resume(db: &Database, coin: Option<Coin>, yes: bool) -> anyhow::Result<i32> {
+    let scope = coin.map(|c| c.to_string()).unwrap_or_else(|| "all".to_string());
+    let Some(pause) = db
+        .list_payout_pauses()
+        .await?
+        .into_iter()
+        .find(|p| p.coin == coin)
+    else {
+        eprintln!("Payouts are not paused for {}", scope);
+        return Ok(1);
+    };
+
+    println!("Pause ({})", scope);
+    println!("  Reason: {}", pause.reason);
+    println!("  Source: {}", pause.source);
+    println!("  Since:  {}", format_time(Some(pause.paused_at)));
+    println!();
+
+    if !confirm("Resume payouts?", yes)? {
+        println!("Aborted");
+        return Ok(1);
+    }
+
+    db.clear_payout_pause(coin).await?;
+    print_pauses(db).await?;
+    Ok(0)
+}
+
+async fn print_pauses(db: &Database) -> anyhow::Result<()> {
+    let pauses = db.list_payout_pauses().await?;
+    if pauses.is_empty() {
+        println!("Payouts are not paused");
+        return Ok(());
+    }
+
+    println!("{:<6} {:<8} {:<19}  REASON", "SCOPE", "SOURCE", "SINCE");
+    for pause in pauses {
+        println!(
+            "{:<6} {:<8} {:<19}  {}",
+            pause.coin.map(|c| c.to_string()).unwrap_or_else(|| "all".to_string()),
+            pause.source,
+            format_time(Some(pause.paused_at)),
+            pause.reason
+        );
+    }
+    Ok(())
+}
+
+/// `teams`
+pub async fn teams(db: &Database, coins: Vec<Coin>) -> anyhow::Result<i32> {
+    for coin in coins {
+        let teams = db.get_teams(coin).await?;
+        let redirects = db.get_team_redirects(coin).await?;
+        if teams.is_empty() {
+            continue;
+        }
+
+        println!("{}", coin);
+        for team in &teams {
+            println!("  {} (updated {})", team.id, format_time(Some(team.updated_at)));
+            for member in &team.members {
+                println!("    {:>7}%  {}", member.percent.normalize(), member.wallet_address);
+            }
+
+            let mut redirected: Vec<&String> = redirects
+                .iter()
+                .filter(|(_, team_id)| **team_id == team.id)
+                .map(|(address, _)| address)
+                .collect();
+            redirected.sort();
+            for address in redirected {
+                println!("    <- {}", address);
+            }
+        }
+        println!();
+    }
+    Ok(0)
+}
+
+/// `set-team`
+pub async fn set_team(
+    db: &Database,
+    coin: Coin,
+    id: &str,
+    members: &[TeamMember],
+    yes: bool,
+) -> anyhow::Result<i32> {
+    if let Err(e) = distribution::check_team_id(id).and_then(|_| distribution::check_team_members(members)) {
+        eprintln!("{}", e);
+        return Ok(1);
+    }
+
+    let teams = db.get_teams(coin).await?;
+    if let Some(member) = members.iter().find(|m| teams.iter().any(|t| t.id == m.wallet_address)) {
+        eprintln!("{} is a team; teams cannot be members of other teams", member.wallet_address);
+        return Ok(1);
+    }
+
+    let current = teams.iter().find(|t| t.id == id);
+    println!("Team {} ({})", id, coin);
+    for member in members {
+        let before = current
+            .and_then(|t| t.members.iter().find(|m| m.wallet_address == member.wallet_address))
+            .map(|m| format!("{}%", m.percent.normalize()))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "  {:>7} -> {:>7}  {}",
+            before,
+            format!("{}%", member.percent.normalize()),
+            member.wallet_address
+        );
+    }
+    for member in current
+        .iter()
+        .flat_map(|t| &t.members)
+        .filter(|m| !members.iter().any(|n| n.wallet_address == m.wallet_address))
+    {
+        println!(
+            "  {:>7} -> {:>7}  {}",
+            format!("{}%", member.percent.normalize()),
+            "-",
+            member.wallet_address
+        );
+    }
+    println!();
+
+    let question = if current.is_some() { "Replace the team's members?" } else { "Create this team?" };
+    if !confirm(question, yes)? {
+        println!("Aborted");
+        return Ok(1);
+    }
+
+    db.set_team(coin, id, members).await?;
+    println!("Team {} saved; blocks distributed from now on use these percentages", id);
+    Ok(0)
+}
+
+/// `delete-team`
+pub async fn delete_team(db: &Database, coin: Coin, id: &str, yes: bool) -> anyhow::Result<i32> {
+    let Some(team) = db.get_teams(coin).await?.into_iter().find(|t| t.id == id) else {
+        eprintln!("No {} team '{}'", coin, id);
+        return Ok(1);
+    };
+    let redirects = db
+        .get_team_redirects(coin)
+        .await?
+        .into_values()
+        .filter(|team_id| *team_id == team.id)
+        .count();
+
+    println!("Team {} ({}): {} members, {} redirected miners", team.id, coin, team.members.len(), redirects);
+    println!("Shares mined with the team id as login will be credited to that id itself.");
+    println!();
+
+    if !confirm("Delete this team?", yes)? {
+        println!("Aborted");
+        return Ok(1);
+    }
+
+    db.delete_team(coin, id).await?;
+    println!("Deleted team {}", id);
+    Ok(0)
+}
+
+/// `redirect`
+pub async fn redirect(
+    db: &Database,
+    coin: Coin,
+    address: &str,
+    team: Option<&str>,
+    yes: bool,
+) -> anyhow::Result<i32> {
+    let teams = db.get_teams(coin).await?;
+    let current = db.get_team_redirects(coin).await?.remove(address);
+
+    let Some(team) = team else {
+        let Some(current) = current else {
+            eprintln!("{} is not redirected", address);
+            return Ok(1);
+        };
+        println!("{} ({}): {} -> own balance", address, coin, current);
+        if !confirm("Remove the redirect?", yes)? {
+            println!("Aborted");
+            return Ok(1);
+        }
+        db.clear_team_redirect(coin, address).await?;
+        println!("Removed redirect for {}", address);
+        return Ok(0);
+    };
+
+    if teams.iter().any(|t| t.id == address) {
+        eprintln!("{} is a team and cannot be redirected", address);
+        return Ok(1);
+    }
+    if !teams.iter().any(|t| t.id == team) {
+        eprintln!("No {} team '{}'", coin, team);
+        return Ok(1);
+    }
+
+    println!(
+        "{} ({}): {} -> {}",
+        address,
+        coin,
+        current.as_deref().unwrap_or("own balance"),
+        team
+    );
+    if !confirm("Redirect this miner's rewards?", yes)? {
+        println!("Aborted");
+        return Ok(1);
+    }
+
+    db.set_team_redirect(coin, address, team).await?;
+    println!("Redirected {} to team {}", address, team);
+    Ok(0)
+}
+
+/// `check-integrity`
+///
+/// For every balance row the ledger credits must equal pending + total paid,
+/// rounding carries must equal the carry balance, abandoned entries must
+/// equal the abandoned balance, and total paid must equal
+/// the sum of confirmed payments. Distributed blocks must have credits
+/// (including carries) that add up to their reward.
+pub async fn check_integrity(db: &Database, coins: Vec<Coin>) -> anyhow::Result<i32> {
+    let mut report = Report::default();
+
+    for coin in coins {
+        let section = coin.to_string();
+        let errors_before = report.count(Status::Error);
+        let warnings_before = report.count(Status::Warning);
+
+        let balances = db.get_all_balances(coin).await?;
+        let ledger = db.get_ledger_entries(coin).await?;
+        let payments = db.list_payments(coin, None, None, i64::MAX).await?;
+        let blocks = db.get_blocks(coin).await?;
+
+        let mut credited: BTreeMap<&str, Decimal> = BTreeMap::new();
+        let mut carried: BTreeMap<&str, Decimal> = BTreeMap::new();
+        let mut abandoned: BTreeMap<&str, Decimal> = BTreeMap::new();
+        let mut block_credits: BTreeMap<i64, Decimal> = BTreeMap::new();
+        for entry in &ledger {
+            // Moves between pending and abandoned: counts for both
+            if entry.entry_type == LedgerEntryType::Abandoned {
+                *abandoned.entry(entry.wallet_address.as_str()).or_default() -= entry.amount;
+            }
+
+            let totals = match entry.entry_type {
+                // Pool funds, not owed to anyone
+                LedgerEntryType::ColdSweep => continue,
+                LedgerEntryType::RoundingCarry => &mut carried,
+                _ => &mut credited,
+            };
+            *totals.entry(entry.wallet_address.as_str()).or_default() += entry.amount;
+
+            if let Some(block_id) = entry.block_id {
+                if matches!(
+                    entry.entry_type,
+                    LedgerEntryType::BlockReward
+                        | LedgerEntryType::BlockReversal
+                        | LedgerEntryType::RoundingCarry
+                ) {
+                    *block_credits.entry(block_id).or_default() += entry.amount;
+                }
+            }
+        }
+
+        let mut confirmed: BTreeMap<&str, Decimal> = BTreeMap::new();
+        for payment in payments.iter().filter(|p| p.status == PaymentStatus::Confirmed) {
+            *confirmed.entry(payment.wallet_address.as_str()).or_default() += payment.amount;
+        }
+
+        // Balances against ledger and payment history
+        for balance in &balances {
+            let address = balance.wallet_address.as_str();
+            let credits = credited.get(address).copied().unwrap_or_default();
+            let earned = balance.pending_balance + balance.total_paid;
+
+            if (earned - credits).abs() >= INTEGRITY_TOLERANCE {
+                report.add(
+                    &section,
+                    Status::Error,
+                    format!(
+                        "{}: pending {} + paid {} = {}, but ledger credits total {} (off by {})",
+                        address,
+                        balance.pending_balance.normalize(),
+                        balance.total_paid.normalize(),
+                        earned.normalize(),
+                        credits.normalize(),
+                        (earned - credits).normalize()
+                    ),
+                );
+            }
+
+            let carry = carried.get(address).copied().unwrap_or_default();
+            if balance.carry_balance != carry {
+                report.add(
+                    &section,
+                    Status::Error,
+                    format!(
+                        "{}: carry balance {} but rounding carries total {}",
+                        address,
+                        balance.carry_balance.normalize(),
+                        carry.normalize()
+                    ),
+                );
+            }
+
+            let set_aside = abandoned.get(address).copied().unwrap_or_default();
+            if balance.abandoned_balance != set_aside {
+                report.add(
+                    &section,
+                    Status::Error,
+                    format!(
+                        "{}: abandoned balance {} but abandoned entries total {}",
+                        address,
+                        balance.abandoned_balance.normalize(),
+                        set_aside.normalize()
+                    ),
+                );
+            }
+
+            let paid = confirmed.get(address).copied().unwrap_or_default();
+            if (balance.total_paid - paid).abs() >= INTEGRITY_TOLERANCE {
+                report.add(
+                    &section,
+                    Status::Error,
+                    format!(
+                        "{}: total paid {} but confirmed payments total {}",
+                        address,
+                        balance.total_paid.normalize(),
+                        paid.normalize()
+                    ),
+                );
+            }
+
+            if balance.pending_balance.is_sign_negative() && !balance.pending_balance.is_zero() {
+                report.add(
+                    &section,
+                    Status::Warning,
+                    format!(
+                        "{}: negative pending balance {} (will be offset by future rewards)",
+                        address,
+                        balance.pending_balance.normalize()
+                    ),
+                );
+            }
+        }
+
+        // Credits for wallets with no balance row
+        for (address, credits) in &credited {
+            if !credits.is_zero() && !balances.iter().any(|b| b.wallet_address == *address) {
+                report.add(
+                    &section,
+                    Status::Error,
+                    format!("{}: ledger credits {} but no balance row", address, credits.normalize()),
+                );
+            }
+        }
+
+        // Block credits against block rewards
+        let mut untracked = 0;
+        for block in &blocks {
+            match (block.distributed, block_credits.get(&block.id)) {
+                (true, Some(total)) if (*total - block.reward).abs() >= INTEGRITY_TOLERANCE => {
+                    report.add(
+                        &section,
+                        Status::Error,
+                        format!(
+                            "block {} (id {}): credits total {} but reward is {}",
+                            block.block_height,
+                            block.id,
+                            total.normalize(),
+                            block.reward.normalize()
+                        ),
+                    );
+                }
+                (true, None) => untracked += 1,
+                (false, Some(total)) if !total.is_zero() => {
+                    report.add(
+                        &section,
+                        Status::Error,
+                        format!(
+                            "block {} (id {}): not marked distributed but has credits totalling {}",
+                            block.block_height,
+                            block.id,
+                            total.normalize()
+                        ),
+                    );
+                }
+                _ => {}
+            }
+        }
+        if untracked > 0 {
+            report.add(
+                &section,
+                Status::Warning,
+                format!("{} distributed block(s) predate ledger tracking and cannot be checked", untracked),
+            );
+        }
+
+        // Blocks whose coinbase never reached the pool wallet
+        for block in blocks.iter().filter(|b| b.reward_status == RewardStatus::Missing) {
+            report.add(
+                &section,
+                Status::Warning,
+                format!(
+                    "block {} (id {}): coinbase not found in pool wallet, reward of {} withheld",
+                    block.block_height,
+                    block.id,
+                    block.reward.normalize()
+                ),
+            );
+        }
+
+        // Payments that never completed
+        let now = Utc::now();
+        for payment in &payments {
+            let age = (now - payment.created_at).num_seconds();
+            let in_flight = matches!(
+                payment.status,
+                PaymentStatus::PendingApproval | PaymentStatus::Pending | PaymentStatus::Processing
+            );
+
+            if in_flight && age > STUCK_PAYMENT_SECS {
+                report.add(
+                    &section,
+                    Status::Warning,
+                    format!(
+                        "payment {} to {} has been {} since {} (tx {})",
+                        payment.id,
+                        payment.wallet_address,
+                        payment.status,
+                        format_time(Some(payment.created_at)),
+                        payment.tx_hash.as_deref().unwrap_or("none")
+                    ),
+                );
+            }
+        }
+
+        if report.count(Status::Error) == errors_before && report.count(Status::Warning) == warnings_before {
+            report.add(
+                &section,
+                Status::Ok,
+                format!(
+                    "{} balance(s), {} ledger entries, {} payment(s), {} block(s) consistent",
+                    balances.len(),
+                    ledger.len(),
+                    payments.len(),
+                    blocks.len()
+                ),
+            );
+        }
+    }
+
+    Ok(report.print("Database"))
+}
+
+/// `solvency`
+pub async fn solvency(db: &Database, coins: Vec<(Coin, Option<Box<dyn Wallet>>)>) -> anyhow::Result<i32> {
+    let mut summary = Report::default();
+
+    for (coin, wallet) in coins {
+        let report = solvency::report(db, coin, wallet.as_deref()).await?;
+        let amount = |value: Option<Decimal>| match value {
+            Some(value) => value.normalize().to_string(),
+            None => "unknown".to_string(),
+        };
+
+        println!("{} (atomic units):", coin);
+        println!("  {:<26}{:>26}", "Owed to miners", amount(Some(report.owed_to_miners)));
+        println!("  {:<26}{:>26}", "  awaiting payment", amount(Some(report.payments_pending)));
+        println!("  {:<26}{:>26}", "  sent, unconfirmed", amount(Some(report.payments_unconfirmed)));
+        println!("  {:<26}{:>26}", "Undistributed rewards", amount(Some(report.immature_rewards)));
+        println!("  {:<26}{:>26}", "Liabilities", amount(Some(report.liabilities)));
+        println!("  {:<26}{:>26}", "Wallet unlocked", amount(report.wallet_unlocked));
+        println!("  {:<26}{:>26}", "Wallet total", amount(report.wallet_total));
+        println!("  {:<26}{:>26}", "Surplus", amount(report.surplus));
+        println!();
+
+        let section = coin.to_string();
+        match report.status {
+            SolvencyStatus::Ok => summary.add(
+                &section,
+                Status::Ok,
+                format!("unlocked balance covers liabilities by {}", amount(report.unlocked_surplus)),
+            ),
+            SolvencyStatus::Warning => summary.add(
+                &section,
+                Status::Warning,
+                format!(
+                    "liabilities exceed the unlocked balance by {}; covered once locked funds mature",
+                    amount(report.unlocked_surplus.map(|s| -s))
+                ),
+            ),
+            SolvencyStatus::Deficit => summary.add(
+                &section,
+                Status::Error,
+                format!(
+                    "liabilities exceed the wallet balance by {}",
+                    amount(report.surplus.map(|s| -s))
+                ),
+            ),
+            SolvencyStatus::Unknown => summary.add(
+                &section,
+                Status::Error,
+                format!(
+                    "wallet balance unavailable: {}",
+                    report.wallet_error.as_deref().unwrap_or("unknown error")
+                ),
+            ),
+        }
+    }
+
+    Ok(summary.print("Solvency"))
+}
+
+/// Shorten an address for table output
+fn short_address(address: &str) -> String {
+    if address.chars().count() <= 24 {
+        return address.to_string();
+    }
+
+    let head: String = address.chars().take(12).collect();
+    let tail: String = address
+        .chars()
+        .rev()
+        .take(9)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("{}...{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{BlockCredit, BlockFound, TempDatabase};
+
+    /// A reported XMR block at height 100, with one share each from `miners`
+    async fn found_block(db: &Database, reward: i64, miners: &[&str]) -> BlockFound {
+        for miner in miners {
+            db.record_share(Coin::Xmr, miner, "rig", Decimal::ONE, None, false)
+                .await
+                .unwrap();
+        }
+        db.record_block(
+            Coin::Xmr,
+            100,
+            "a",
+            Decimal::from(reward),
+            miners[0],
+            "rig",
+            RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        db.get_undistributed_blocks(Coin::Xmr)
+            .await
+            .unwrap()
+            .remove(0)
+    }
+
+    async fn distribute(db: &Database, block: &BlockFound, credits: &[(&str, i64)]) {
+        let credits: Vec<BlockCredit> = credits
+            .iter()
+            .map(|(address, amount)| BlockCredit {
+                wallet_address: address.to_string(),
+                amount: Decimal::from(*amount),
+                carry: Decimal::ZERO,
+                memo: None,
+            })
+            .collect();
+        db.apply_block_distribution(block, &credits).await.unwrap();
+    }
+
+    /// (pending, total paid) of a wallet
+    async fn balance(db: &Database, address: &str) -> (Decimal, Decimal) {
+        let balance = db
+            .get_miner_balance(Coin::Xmr, address)
+            .await
+            .unwrap()
+            .unwrap();
+        (balance.pending_balance, balance.total_paid)
+    }
+
+    async fn confirmed_payment(db: &Database, address: &str, amount: i64) -> String {
+        let id = db
+            .create_payment(Coin::Xmr, address, Decimal::from(amount))
+            .await
+            .unwrap();
+        let status = mark_payment(db, &id, PaymentStatus::Confirmed, Some("tx"), None, true)
+            .await
+            .unwrap();
+        assert_eq!(status, 0);
+        id
+    }
+
+    #[tokio::test]
+    async fn redistribute_moves_balances_to_the_new_split() {
+        let db = TempDatabase::new().await;
+        let block = found_block(&db, 1000, &["alice", "bob"]).await;
+        distribute(&db, &block, &[("alice", 1000)]).await;
+        confirmed_payment(&db, "alice", 300).await;
+        assert_eq!(check_integrity(&db, vec![Coin::Xmr]).await.unwrap(), 0);
+
+        let status = redistribute(
+            &db,
+            Coin::Xmr,
+            &distribution::Params::default(),
+            100,
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, 0);
+
+        // Alice keeps what she was already paid; only her pending balance drops
+        assert_eq!(
+            balance(&db, "alice").await,
+            (Decimal::from(200), Decimal::from(300))
+        );
+        assert_eq!(
+            balance(&db, "bob").await,
+            (Decimal::from(500), Decimal::ZERO)
+        );
+        assert_eq!(check_integrity(&db, vec![Coin::Xmr]).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn redistribute_can_leave_an_overpaid_wallet_negative() {
+        let db = TempDatabase::new().await;
+        let block = found_block(&db, 1000, &["alice", "bob"]).await;
+        distribute(&db, &block, &[("alice", 1000)]).await;
+        confirmed_payment(&db, "alice", 800).await;
+
+        let status = redistribute(
+            &db,
+            Coin::Xmr,
+            &distribution::Params::default(),
+            100,
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, 0);
+
+        assert_eq!(
+            balance(&db, "alice").await,
+            (Decimal::from(-300), Decimal::from(800))
+        );
+        assert_eq!(
+            balance(&db, "bob").await,
+            (Decimal::from(500), Decimal::ZERO)
+        );
+        assert_eq!(check_integrity(&db, vec![Coin::Xmr]).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn mark_payment_moves_the_amount_between_pending_and_paid() {
+        let db = TempDatabase::new().await;
+        let block = found_block(&db, 1000, &["alice"]).await;
+        distribute(&db, &block, &[("alice", 1000)]).await;
+
+        let id = confirmed_payment(&db, "alice", 400).await;
+        assert_eq!(
+            balance(&db, "alice").await,
+            (Decimal::from(600), Decimal::from(400))
+        );
+        assert_eq!(check_integrity(&db, vec![Coin::Xmr]).await.unwrap(), 0);
+
+        let status = mark_payment(
+            &db,
+            &id,
+            PaymentStatus::Failed,
+            None,
+            Some("never arrived"),
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, 0);
+        assert_eq!(
+            balance(&db, "alice").await,
+            (Decimal::from(1000), Decimal::ZERO)
+        );
+        assert_eq!(
+            db.get_payment(&id).await.unwrap().unwrap().status,
+            PaymentStatus::Failed
+        );
+        assert_eq!(check_integrity(&db, vec![Coin::Xmr]).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn check_integrity_flags_credits_that_do_not_match_the_reward() {
+        let db = TempDatabase::new().await;
+        let block = found_block(&db, 1000, &["alice"]).await;
+        distribute(&db, &block, &[("alice", 1000)]).await;
+        assert_eq!(check_integrity(&db, vec![Coin::Xmr]).await.unwrap(), 0);
+
+        // A second block whose credits fall 100 short of its reward
+        db.record_block(
+            Coin::Xmr,
+            101,
+            "b",
+            Decimal::from(1000),
+            "alice",
+            "rig",
+            RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        let short = db
+            .get_undistributed_blocks(Coin::Xmr)
+            .await
+            .unwrap()
+            .remove(0);
+        distribute(&db, &short, &[("alice", 900)]).await;
+        assert_eq!(check_integrity(&db, vec![Coin::Xmr]).await.unwrap(), 1);
+    }
+}
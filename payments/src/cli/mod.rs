@@ -0,0 +1,556 @@
+//! Command line interface
+//!
+//! With no subcommand the binary runs the payment processor service.
+//! Subcommands are one-shot operator tools that exit when done.
+
+pub mod check_config;
+pub mod db;
+
+use crate::accounting;
+use crate::config::Config;
+use crate::db::{Coin, Database, PaymentStatus, TeamMember};
+use crate::distribution;
+use crate::wallets::{
+    aleo::AleoWallet, bitcoind::BitcoindWallet, monero::MoneroWallet, tari::TariWallet, Wallet,
+};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+use rust_decimal::Decimal;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+
+/// Dormant report threshold for coins without a dormant policy
+const DEFAULT_DORMANT_DAYS: u32 = 30;
+
+/// Default config location used by the installer
+pub const DEFAULT_CONFIG_PATH: &str = "/opt/solopool/payments/config/config.toml";
+
+#[derive(Debug, Parser)]
+#[command(name = "solopool-payments", version, about = "Solo Pool payment processor")]
+pub struct Cli {
+    /// Path to config.toml
+    #[arg(short, long, global = true, value_name = "PATH")]
+    config: Option<String>,
+
+    /// Path to config.toml (positional form used by the systemd unit)
+    #[arg(value_name = "CONFIG", hide = true)]
+    config_positional: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// Config path from `--config`, the positional argument, or the default
+    pub fn config_path(&self) -> &str {
+        self.config
+            .as_deref()
+            .or(self.config_positional.as_deref())
+            .unwrap_or(DEFAULT_CONFIG_PATH)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Validate the config and probe each wallet RPC and pool API
+    CheckConfig {
+        /// Only run the static checks, do not contact wallets or pools
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// List miner balances, highest pending first
+    Balances {
+        /// Only this coin (default: all)
+        #[arg(long)]
+        coin: Option<Coin>,
+        /// Only this wallet address
+        #[arg(long)]
+        address: Option<String>,
+        /// Only balances with at least this much pending (atomic units)
+        #[arg(long)]
+        min: Option<Decimal>,
+        /// Maximum rows per coin
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+
+    /// List payments, newest first
+    Payments {
+        /// Only this coin (default: all)
+        #[arg(long)]
+        coin: Option<Coin>,
+        /// Only payments with this status (pending_approval, pending, processing, confirmed, failed)
+        #[arg(long)]
+        status: Option<PaymentStatus>,
+        /// Only payments to this wallet address
+        #[arg(long)]
+        address: Option<String>,
+        /// Maximum rows per coin
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+
+    /// Show how a block's reward was distributed
+    Block {
+        coin: Coin,
+        height: i64,
+    },
+
+    /// Re-run reward distribution for a block from the shares in the database
+    Redistribute {
+        coin: Coin,
+        height: i64,
+        /// Block ID, if more than one block is recorded at the height
+        #[arg(long)]
+        id: Option<i64>,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Replay every distributed block into a scratch database and compare balances
+    ///
+    /// The live database is not changed. Override the reward scheme to see
+    /// what balances would be under other parameters.
+    Recompute {
+        /// Only this coin (default: all)
+        #[arg(long)]
+        coin: Option<Coin>,
+        /// Share window before each block, in seconds
+        #[arg(long, default_value_t = distribution::WINDOW_SECS)]
+        window_secs: i64,
+        /// Pay team logins as miners instead of splitting them between members
+        #[arg(long)]
+        no_teams: bool,
+        /// Leave out the configured pool fee
+        #[arg(long)]
+        no_fee: bool,
+        /// Keep the scratch database at this path (must not exist)
+        #[arg(long, value_name = "PATH")]
+        scratch: Option<PathBuf>,
+        /// Maximum rows per coin
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+
+    /// Mark a payment confirmed or failed after manual investigation
+    MarkPayment {
+        payment_id: String,
+        status: ManualStatus,
+        /// Transaction hash to record with the payment
+        #[arg(long)]
+        tx_hash: Option<String>,
+        /// Reason, stored as the payment's error message
+        #[arg(long)]
+        note: Option<String>,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Check balances against the ledger and payment history
+    CheckIntegrity {
+        /// Only this coin (default: all)
+        #[arg(long)]
+        coin: Option<Coin>,
+    },
+
+    /// Compare liabilities to miners with the pool wallet balances
+    Solvency {
+        /// Only this coin (default: all enabled coins)
+        #[arg(long)]
+        coin: Option<Coin>,
+    },
+
+    /// Export double-entry accounting records (rewards, miner credits, payouts, fees)
+    Export {
+        /// Only this coin (default: all)
+        #[arg(long)]
+        coin: Option<Coin>,
+        /// First day to include, YYYY-MM-DD (UTC; default: everything)
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        /// Last day to include, YYYY-MM-DD (UTC; default: today)
+        #[arg(long)]
+        to: Option<NaiveDate>,
+        #[arg(long, value_enum, default_value_t = accounting::Format::Csv)]
+        format: accounting::Format,
+        /// Write to this file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+
+    /// Report balances still owed to miners that stopped submitting shares
+    Dormant {
+        /// Only this coin (default: all)
+        #[arg(long)]
+        coin: Option<Coin>,
+        /// Days without shares (default: the coin's dormant.pay_after_days, or 30)
+        #[arg(long)]
+        days: Option<u32>,
+        /// Maximum rows per coin
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+
+    /// Move a miner's abandoned balance back to pending so it is paid again
+    Reclaim {
+        coin: Coin,
+        address: String,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Stop all payouts until resumed (the running service picks it up next cycle)
+    Pause {
+        /// Only this coin (default: all coins)
+        #[arg(long)]
+        coin: Option<Coin>,
+        /// Why payouts are paused, shown in /api/stats
+        #[arg(long)]
+        reason: String,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Lift a payout pause and list any that remain
+    Resume {
+        /// Only this coin's pause (default: the global pause)
+        #[arg(long)]
+        coin: Option<Coin>,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// List teams and the miners redirected to them
+    Teams {
+        /// Only this coin (default: all)
+        #[arg(long)]
+        coin: Option<Coin>,
+    },
+
+    /// Create a team or replace its members (applies to blocks distributed from now on)
+    SetTeam {
+        coin: Coin,
+        /// Team id, also usable as a mining login (letters, digits, '-')
+        id: String,
+        /// Members as ADDRESS=PERCENT; percentages must add up to 100
+        #[arg(required = true, value_name = "ADDRESS=PERCENT", value_parser = parse_team_member)]
+        members: Vec<TeamMember>,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Delete a team and the redirects to it
+    DeleteTeam {
+        coin: Coin,
+        id: String,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Send a miner's rewards to a team, or stop doing so without --team
+    Redirect {
+        coin: Coin,
+        address: String,
+        /// Team to redirect to (default: remove the redirect)
+        #[arg(long)]
+        team: Option<String>,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+/// Parse ADDRESS=PERCENT (split on the last '=')
+fn parse_team_member(value: &str) -> Result<TeamMember, String> {
+    let (address, percent) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected ADDRESS=PERCENT, got '{}'", value))?;
+    let percent = percent
+        .trim_end_matches('%')
+        .parse::<Decimal>()
+        .map_err(|e| format!("invalid percent '{}': {}", percent, e))?;
+
+    Ok(TeamMember {
+        wallet_address: address.to_string(),
+        percent,
+    })
+}
+
+/// Payment states an operator can set by hand
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ManualStatus {
+    Confirmed,
+    Failed,
+}
+
+impl From<ManualStatus> for PaymentStatus {
+    fn from(status: ManualStatus) -> Self {
+        match status {
+            ManualStatus::Confirmed => PaymentStatus::Confirmed,
+            ManualStatus::Failed => PaymentStatus::Failed,
+        }
+    }
+}
+
+/// Run a subcommand, returning the process exit code
+pub async fn run(command: Command, config: Config) -> anyhow::Result<i32> {
+    if let Command::CheckConfig { offline } = command {
+        return check_config::run(&config, offline).await;
+    }
+
+    if !config.database.path.exists() {
+        anyhow::bail!("Database not found at {:?}", config.database.path);
+    }
+    let database = Database::new(&config.database.path).await?;
+
+    match command {
+        Command::CheckConfig { .. } => unreachable!("handled above"),
+        Command::Balances {
+            coin,
+            address,
+            min,
+            limit,
+        } => db::balances(&database, coins(coin), address.as_deref(), min, limit).await,
+        Command::Payments {
+            coin,
+            status,
+            address,
+            limit,
+        } => db::payments(&database, coins(coin), status, address.as_deref(), limit).await,
+        Command::Block { coin, height } => db::block(&database, coin, height).await,
+        Command::Redistribute {
+            coin,
+            height,
+            id,
+            yes,
+        } => {
+            let params = distribution::Params {
+                fee: distribution::PoolFee::from_config(config.pool_fee(coin)),
+                ..distribution::Params::default()
+            };
+            db::redistribute(&database, coin, &params, height, id, yes).await
+        }
+        Command::Recompute {
+            coin,
+            window_secs,
+            no_teams,
+            no_fee,
+            scratch,
+            limit,
+        } => {
+            let coins = coins(coin)
+                .into_iter()
+                .map(|c| {
+                    let params = distribution::Params {
+                        window_secs,
+                        teams: !no_teams,
+                        fee: match no_fee {
+                            true => None,
+                            false => distribution::PoolFee::from_config(config.pool_fee(c)),
+                        },
+                    };
+                    (c, params)
+                })
+                .collect();
+            db::recompute(&database, &config.database.path, coins, scratch, limit).await
+        }
+        Command::MarkPayment {
+            payment_id,
+            status,
+            tx_hash,
+            note,
+            yes,
+        } => {
+            db::mark_payment(
+                &database,
+                &payment_id,
+                status.into(),
+                tx_hash.as_deref(),
+                note.as_deref(),
+                yes,
+            )
+            .await
+        }
+        Command::CheckIntegrity { coin } => db::check_integrity(&database, coins(coin)).await,
+        Command::Solvency { coin } => {
+            let wallets = match coin {
+                Some(coin) => vec![(coin, pool_wallet(&config, coin))],
+                None => Coin::ALL
+                    .into_iter()
+                    .filter_map(|c| pool_wallet(&config, c).map(|w| (c, Some(w))))
+                    .collect(),
+            };
+            db::solvency(&database, wallets).await
+        }
+        Command::Export {
+            coin,
+            from,
+            to,
+            format,
+            output,
+        } => db::export(&database, coins(coin), from, to, format, output.as_deref()).await,
+        Command::Dormant { coin, days, limit } => {
+            let coins: Vec<(Coin, u32)> = coins(coin)
+                .into_iter()
+                .map(|c| (c, days.unwrap_or_else(|| dormant_days(&config, c))))
+                .collect();
+            db::dormant(&database, coins, limit).await
+        }
+        Command::Reclaim { coin, address, yes } => db::reclaim(&database, coin, &address, yes).await,
+        Command::Pause { coin, reason, yes } => db::pause(&database, coin, &reason, yes).await,
+        Command::Resume { coin, yes } => db::resume(&database, coin, yes).await,
+        Command::Teams { coin } => db::teams(&database, coins(coin)).await,
+        Command::SetTeam {
+            coin,
+            id,
+            members,
+            yes,
+        } => db::set_team(&database, coin, &id, &members, yes).await,
+        Command::DeleteTeam { coin, id, yes } => db::delete_team(&database, coin, &id, yes).await,
+        Command::Redirect {
+            coin,
+            address,
+            team,
+            yes,
+        } => db::redirect(&database, coin, &address, team.as_deref(), yes).await,
+    }
+}
+
+/// Days without shares after which a coin's balances count as dormant
+fn dormant_days(config: &Config, coin: Coin) -> u32 {
+    let dormant = match coin {
+        Coin::Xmr => config.xmr.as_ref().and_then(|c| c.dormant.as_ref()),
+        Coin::Xtm => config.xtm.as_ref().and_then(|c| c.dormant.as_ref()),
+        Coin::Aleo => config.aleo.as_ref().and_then(|c| c.dormant.as_ref()),
+        Coin::Btc => config.btc.as_ref().and_then(|c| c.dormant.as_ref()),
+        Coin::Bch => config.bch.as_ref().and_then(|c| c.dormant.as_ref()),
+        Coin::Dgb => config.dgb.as_ref().and_then(|c| c.dormant.as_ref()),
+    };
+    dormant.map(|d| d.pay_after_days).unwrap_or(DEFAULT_DORMANT_DAYS)
+}
+
+/// Pool wallet client of an enabled coin
+fn pool_wallet(config: &Config, coin: Coin) -> Option<Box<dyn Wallet>> {
+    let wallet: Box<dyn Wallet> = match coin {
+        Coin::Xmr => {
+            let xmr = config.xmr.as_ref().filter(|c| c.enabled)?;
+            Box::new(MoneroWallet::new(&xmr.wallet_rpc_url, xmr.mixin))
+        }
+        Coin::Xtm => {
+            let xtm = config.xtm.as_ref().filter(|c| c.enabled)?;
+            Box::new(TariWallet::new(&xtm.wallet_rpc_url))
+        }
+        Coin::Aleo => {
+            let aleo = config.aleo.as_ref().filter(|c| c.enabled)?;
+            Box::new(AleoWallet::new(
+                &aleo.node_rpc_url,
+                &aleo.pool_wallet_address,
+                &aleo.pool_private_key,
+            ))
+        }
+        Coin::Btc | Coin::Bch | Coin::Dgb => {
+            let coin_config = match coin {
+                Coin::Btc => &config.btc,
+                Coin::Bch => &config.bch,
+                _ => &config.dgb,
+            };
+            let coin_config = coin_config.as_ref().filter(|c| c.enabled)?;
+            Box::new(BitcoindWallet::from_config(coin, coin_config))
+        }
+    };
+    Some(wallet)
+}
+
+/// One coin, or all of them
+fn coins(coin: Option<Coin>) -> Vec<Coin> {
+    coin.map(|c| vec![c]).unwrap_or_else(|| Coin::ALL.to_vec())
+}
+
+/// Ask before writing to the database
+///
+/// `--yes` skips the prompt; without a terminal the write is refused.
+fn confirm(question: &str, yes: bool) -> anyhow::Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("Not running in a terminal; re-run with --yes to apply the change");
+    }
+
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Status {
+    fn label(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warning => "WARN",
+            Status::Error => "ERROR",
+        }
+    }
+}
+
+/// Check results grouped by section, printed in order of first appearance
+#[derive(Default)]
+struct Report {
+    lines: Vec<(String, Status, String)>,
+}
+
+impl Report {
+    fn add(&mut self, section: impl Into<String>, status: Status, message: impl Into<String>) {
+        self.lines.push((section.into(), status, message.into()));
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.lines.iter().filter(|(_, s, _)| *s == status).count()
+    }
+
+    /// Print the report; returns the exit code (1 if any error)
+    fn print(&self, subject: &str) -> i32 {
+        let mut sections: Vec<&str> = Vec::new();
+        for (section, _, _) in &self.lines {
+            if !sections.contains(&section.as_str()) {
+                sections.push(section);
+            }
+        }
+
+        for section in sections {
+            println!("[{}]", section);
+            for (_, status, message) in self.lines.iter().filter(|(s, _, _)| s == section) {
+                println!("  {:<5}  {}", status.label(), message);
+            }
+            println!();
+        }
+
+        let errors = self.count(Status::Error);
+        let warnings = self.count(Status::Warning);
+        if errors == 0 {
+            println!("{} OK ({} warning(s))", subject, warnings);
+            0
+        } else {
+            println!("{} has {} error(s) and {} warning(s)", subject, errors, warnings);
+            1
+        }
+    }
+}
@@ -1,5 +1,8 @@
 //! Configuration for the payment processor service
 
+use crate::db::Coin;
+use crate::events::EventKind;
+use crate::webhooks;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -27,6 +30,22 @@ pub struct Config {
     /// ALEO payment configuration
     #[serde(default)]
     pub aleo: Option<AleoConfig>,
+
+    /// Bitcoin (BTC) payment configuration (CKPool in pool mode)
+    #[serde(default)]
+    pub btc: Option<CoinConfig>,
+
+    /// Bitcoin Cash (BCH) payment configuration (CKPool in pool mode)
+    #[serde(default)]
+    pub bch: Option<CoinConfig>,
+
+    /// DigiByte (DGB) payment configuration (CKPool in pool mode)
+    #[serde(default)]
+    pub dgb: Option<CoinConfig>,
+
+    /// Outbound webhooks for pool events
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -42,6 +61,15 @@ pub struct ServiceConfig {
     /// Log level (trace, debug, info, warn, error)
     #[serde(default = "default_log_level")]
     pub log_level: String,
+
+    /// How long to wait for running cycles to finish on shutdown (seconds)
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout_secs: u64,
+
+    /// Random delay of up to this many seconds added to each scheduled run,
+    /// so coins don't all hit the wallets at once
+    #[serde(default)]
+    pub schedule_jitter_secs: u64,
 }
 
 fn default_share_scan_interval() -> u64 {
@@ -56,6 +84,10 @@ fn default_log_level() -> String {
     "info".to_string()
 }
 
+fn default_shutdown_timeout() -> u64 {
+    30
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DatabaseConfig {
     /// Path to SQLite database file
@@ -95,24 +127,280 @@ fn default_api_port() -> u16 {
     8090
 }
 
+/// Default monero-pool API URL (XMR)
+pub const DEFAULT_XMR_POOL_API_URL: &str = "http://127.0.0.1:4243";
+
+/// Default merge mining proxy API URL (XTM)
+pub const DEFAULT_XTM_MERGE_PROXY_API_URL: &str = "http://127.0.0.1:18081";
+
+/// Default minotari miner API URL (XTM)
+pub const DEFAULT_XTM_MINOTARI_API_URL: &str = "http://127.0.0.1:18084";
+
+/// Default ALEO pool server API URL
+pub const DEFAULT_ALEO_POOL_API_URL: &str = "http://127.0.0.1:4000";
+
 /// Pool type for determining which API implementation to use
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum PoolType {
     /// monero-pool (for XMR in monero_only mode)
+    #[default]
     MoneroPool,
     /// Tari merge mining proxy (for XMR and XTM in merge mode)
     MergeProxy,
     /// Minotari miner (for XTM in tari_only mode)
     MinotariMiner,
+    /// CKPool in pool mode, read from its share logs (for BTC, BCH and DGB)
+    Ckpool,
 }
 
-impl Default for PoolType {
-    fn default() -> Self {
-        PoolType::MoneroPool
+impl PoolType {
+    /// Name as written in config.toml
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PoolType::MoneroPool => "monero_pool",
+            PoolType::MergeProxy => "merge_proxy",
+            PoolType::MinotariMiner => "minotari_miner",
+            PoolType::Ckpool => "ckpool",
+        }
+    }
+}
+
+/// Where the recorded reward for a found block comes from
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardSource {
+    /// The reward reported (or estimated) by the pool API
+    #[default]
+    Pool,
+    /// The coinbase output actually received by the pool wallet
+    Wallet,
+}
+
+fn default_coinbase_grace() -> u64 {
+    7200 // 2 hours
+}
+
+fn default_consolidation_interval() -> u64 {
+    86400 // Once a day
+}
+
+fn default_consolidation_min_outputs() -> u32 {
+    20
+}
+
+fn default_xmr_daemon_rpc_url() -> String {
+    "http://127.0.0.1:18081".to_string()
+}
+
+fn default_max_sync_lag() -> u64 {
+    2
+}
+
+fn default_refresh_timeout() -> u64 {
+    60
+}
+
+fn default_dormant_pay_after_days() -> u32 {
+    30
+}
+
+/// Policy for balances of miners that stopped submitting shares
+///
+/// Below-minimum balances are otherwise an open liability forever: after
+/// `pay_after_days` without shares they are paid out regardless of
+/// min_payout, and whatever is left after `abandon_after_days` moves to the
+/// abandoned account until the miner returns or an operator reclaims it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DormantConfig {
+    /// Enable the dormant balance policy
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Days without shares before a balance is paid regardless of min_payout
+    #[serde(default = "default_dormant_pay_after_days")]
+    pub pay_after_days: u32,
+
+    /// Smallest dormant balance worth paying, i.e. the network fee floor (atomic units)
+    #[serde(default)]
+    pub pay_min_amount: Decimal,
+
+    /// Days without shares before the remaining balance is abandoned; never if unset
+    #[serde(default)]
+    pub abandon_after_days: Option<u32>,
+}
+
+/// Pool fee taken from each block reward before the miners' split
+///
+/// `percent` of the reward, rounded down to whole atomic units, plus a
+/// fixed `donation` per block is credited to `address`. That balance is
+/// then paid out like any miner's.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PoolFeeConfig {
+    /// Enable the pool fee
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Percentage of each block reward
+    #[serde(default)]
+    pub percent: Decimal,
+
+    /// Fixed amount per block on top of the percentage (atomic units)
+    #[serde(default)]
+    pub donation: Decimal,
+
+    /// Address credited with the fee
+    pub address: String,
+}
+
+/// monero-wallet-rpc lifecycle management (XMR only)
+///
+/// Before each payout the pool wallet is checked: if wallet-rpc has no
+/// wallet open, or a different one, `file` is (re)opened; the wallet is then
+/// refreshed and its height compared with the daemon's. Payouts are skipped
+/// while the wallet is more than `max_sync_lag` blocks behind.
+///
+/// Reopening needs wallet-rpc started with `--wallet-dir` instead of
+/// `--wallet-file`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WalletManagerConfig {
+    /// Enable wallet lifecycle management
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Wallet file name, relative to wallet-rpc's --wallet-dir
+    pub file: String,
+
+    /// File holding the wallet password (first line)
+    #[serde(default)]
+    pub password_file: Option<PathBuf>,
+
+    /// Wallet password, if not read from password_file
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// monerod RPC URL, used for the daemon height
+    #[serde(default = "default_xmr_daemon_rpc_url")]
+    pub daemon_rpc_url: String,
+
+    /// Blocks the wallet may trail the daemon before payouts are skipped
+    #[serde(default = "default_max_sync_lag")]
+    pub max_sync_lag: u64,
+
+    /// Seconds a wallet refresh may take before payouts are skipped for the cycle
+    #[serde(default = "default_refresh_timeout")]
+    pub refresh_timeout_secs: u64,
+}
+
+impl WalletManagerConfig {
+    /// The wallet password, read from password_file if set
+    pub fn load_password(&self) -> anyhow::Result<String> {
+        match &self.password_file {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path).map_err(|e| {
+                    anyhow::anyhow!("Failed to read wallet password file {}: {}", path.display(), e)
+                })?;
+                Ok(contents.lines().next().unwrap_or_default().to_string())
+            }
+            None => Ok(self.password.clone().unwrap_or_default()),
+        }
     }
 }
 
+/// Pool wallet output consolidation (XMR only)
+///
+/// Sweeps the many small coinbase outputs a solo pool collects back to the
+/// pool wallet as a few large ones, so batch payouts stay within the
+/// transaction size limit and pay less in fees.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConsolidationConfig {
+    /// Enable scheduled consolidation
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Minimum time between consolidations (seconds)
+    #[serde(default = "default_consolidation_interval")]
+    pub interval_secs: u64,
+
+    /// Only sweep outputs below this amount (atomic units); all outputs if unset
+    #[serde(default)]
+    pub below_amount: Option<Decimal>,
+
+    /// Only consolidate once at least this many unlocked outputs qualify
+    #[serde(default = "default_consolidation_min_outputs")]
+    pub min_outputs: u32,
+
+    /// Also sweep unmixable dust outputs (sweep_dust)
+    #[serde(default)]
+    pub sweep_unmixable: bool,
+}
+
+/// An outbound webhook
+///
+/// Matching events are queued in the database and POSTed with retries
+/// and exponential backoff until the target answers 2xx.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookConfig {
+    /// Name used in logs and the delivery queue
+    pub name: String,
+
+    pub url: String,
+
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Event types to send (e.g. "block_found", "payment_failed"); all if empty
+    #[serde(default)]
+    pub events: Vec<String>,
+
+    /// JSON body with {{field}} placeholders from the event; the event JSON if unset
+    #[serde(default)]
+    pub template: Option<String>,
+
+    /// Signs each body with HMAC-SHA256 when set
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    /// Header carrying the signature, as "sha256=<hex>"
+    #[serde(default = "default_signature_header")]
+    pub signature_header: String,
+
+    /// Attempts before a delivery is given up
+    #[serde(default = "default_webhook_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_signature_header() -> String {
+    "X-Solopool-Signature".to_string()
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    10
+}
+
+/// Payout safety limits
+///
+/// A payout cycle that would exceed any limit sends nothing and pauses the
+/// coin's payouts until an operator resumes them. Amounts in atomic units.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PayoutLimits {
+    /// Largest single payment
+    #[serde(default)]
+    pub max_per_payment: Option<Decimal>,
+
+    /// Largest total sent in one payment cycle
+    #[serde(default)]
+    pub max_per_cycle: Option<Decimal>,
+
+    /// Largest total sent in any 24 hours
+    #[serde(default)]
+    pub max_per_day: Option<Decimal>,
+
+    /// Largest cycle total as a percentage of the wallet balance
+    #[serde(default)]
+    pub max_wallet_percent: Option<Decimal>,
+}
+
 /// Configuration for XMR and XTM (similar wallet RPC interface)
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CoinConfig {
@@ -151,9 +439,61 @@ pub struct CoinConfig {
     #[serde(default)]
     pub pool_type: PoolType,
 
+    /// Node ZMQ publisher announcing new blocks (monerod `zmq-pub`,
+    /// bitcoind `zmqpubhashblock`); wakes block processing right away
+    #[serde(default)]
+    pub zmq_url: Option<String>,
+
     /// Mixin/ring size for transactions (XMR)
     #[serde(default = "default_mixin")]
     pub mixin: u32,
+
+    /// Where block rewards come from: "pool" (pool API) or "wallet" (coinbase in the pool wallet)
+    #[serde(default)]
+    pub reward_source: RewardSource,
+
+    /// With reward_source = "wallet": how long to wait for a block's coinbase
+    /// to appear in the wallet before flagging the block (seconds)
+    #[serde(default = "default_coinbase_grace")]
+    pub coinbase_grace_secs: u64,
+
+    /// Unlocked balance to keep in the hot wallet on top of what miners are owed
+    /// (atomic units). Anything above is swept to cold_address after each payment cycle.
+    #[serde(default)]
+    pub hot_wallet_target: Option<Decimal>,
+
+    /// Cold storage address that receives the excess hot wallet balance
+    #[serde(default)]
+    pub cold_address: Option<String>,
+
+    /// Send a wallet_balance_low event when the unlocked balance drops below
+    /// this amount (atomic units)
+    #[serde(default)]
+    pub low_balance_alert: Option<Decimal>,
+
+    /// Payouts of at least this amount are held until approved through the API
+    #[serde(default)]
+    pub approval_threshold: Option<Decimal>,
+
+    /// Pool wallet output consolidation (XMR only)
+    #[serde(default)]
+    pub consolidation: Option<ConsolidationConfig>,
+
+    /// Wallet-rpc lifecycle management (XMR only)
+    #[serde(default)]
+    pub wallet_manager: Option<WalletManagerConfig>,
+
+    /// Payout safety limits
+    #[serde(default)]
+    pub limits: PayoutLimits,
+
+    /// Inactive and dust balance policy
+    #[serde(default)]
+    pub dormant: Option<DormantConfig>,
+
+    /// Pool fee deducted from block rewards
+    #[serde(default)]
+    pub pool_fee: Option<PoolFeeConfig>,
 }
 
 fn default_enabled() -> bool {
@@ -191,6 +531,45 @@ pub struct AleoConfig {
     /// Pool API URL (if available)
     #[serde(default)]
     pub pool_api_url: Option<String>,
+
+    /// Where block rewards come from: "pool" (pool API) or "wallet" (puzzle reward credited on chain)
+    #[serde(default)]
+    pub reward_source: RewardSource,
+
+    /// With reward_source = "wallet": how long to wait for the reward to
+    /// appear on chain before flagging the block (seconds)
+    #[serde(default = "default_coinbase_grace")]
+    pub coinbase_grace_secs: u64,
+
+    /// Unlocked balance to keep in the hot wallet on top of what miners are owed
+    /// (atomic units). Anything above is swept to cold_address after each payment cycle.
+    #[serde(default)]
+    pub hot_wallet_target: Option<Decimal>,
+
+    /// Cold storage address that receives the excess hot wallet balance
+    #[serde(default)]
+    pub cold_address: Option<String>,
+
+    /// Send a wallet_balance_low event when the unlocked balance drops below
+    /// this amount (atomic units)
+    #[serde(default)]
+    pub low_balance_alert: Option<Decimal>,
+
+    /// Payouts of at least this amount are held until approved through the API
+    #[serde(default)]
+    pub approval_threshold: Option<Decimal>,
+
+    /// Payout safety limits
+    #[serde(default)]
+    pub limits: PayoutLimits,
+
+    /// Inactive and dust balance policy
+    #[serde(default)]
+    pub dormant: Option<DormantConfig>,
+
+    /// Pool fee deducted from block rewards
+    #[serde(default)]
+    pub pool_fee: Option<PoolFeeConfig>,
 }
 
 impl Config {
@@ -226,6 +605,12 @@ impl Config {
             if let Ok(val) = std::env::var("XMR_POOL_WALLET") {
                 xmr.pool_wallet_address = val;
             }
+            if let Some(ref mut manager) = xmr.wallet_manager {
+                if let Ok(val) = std::env::var("XMR_WALLET_PASSWORD") {
+                    manager.password = Some(val);
+                    manager.password_file = None;
+                }
+            }
         }
 
         // XTM overrides
@@ -238,6 +623,22 @@ impl Config {
             }
         }
 
+        // BTC / BCH / DGB overrides
+        for (prefix, coin) in [
+            ("BTC", &mut config.btc),
+            ("BCH", &mut config.bch),
+            ("DGB", &mut config.dgb),
+        ] {
+            if let Some(coin) = coin {
+                if let Ok(val) = std::env::var(format!("{}_WALLET_RPC_URL", prefix)) {
+                    coin.wallet_rpc_url = val;
+                }
+                if let Ok(val) = std::env::var(format!("{}_POOL_WALLET", prefix)) {
+                    coin.pool_wallet_address = val;
+                }
+            }
+        }
+
         // ALEO overrides
         if let Some(ref mut aleo) = config.aleo {
             if let Ok(val) = std::env::var("ALEO_NODE_RPC_URL") {
@@ -253,6 +654,630 @@ impl Config {
 
         Ok(config)
     }
+
+    /// Pool fee section of a coin, enabled or not
+    pub fn pool_fee(&self, coin: Coin) -> Option<&PoolFeeConfig> {
+        match coin {
+            Coin::Xmr => self.xmr.as_ref().and_then(|c| c.pool_fee.as_ref()),
+            Coin::Xtm => self.xtm.as_ref().and_then(|c| c.pool_fee.as_ref()),
+            Coin::Aleo => self.aleo.as_ref().and_then(|c| c.pool_fee.as_ref()),
+            Coin::Btc => self.btc.as_ref().and_then(|c| c.pool_fee.as_ref()),
+            Coin::Bch => self.bch.as_ref().and_then(|c| c.pool_fee.as_ref()),
+            Coin::Dgb => self.dgb.as_ref().and_then(|c| c.pool_fee.as_ref()),
+        }
+    }
+}
+
+/// Severity of a configuration problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The service will not work correctly with this setting
+    Error,
+    /// Suspicious but not fatal
+    Warning,
+}
+
+/// A problem found by `Config::validate`
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    /// Config section the problem belongs to (e.g. "xmr")
+    pub section: &'static str,
+    pub message: String,
+}
+
+/// Collects issues while validating
+#[derive(Default)]
+struct Issues(Vec<ConfigIssue>);
+
+impl Issues {
+    fn error(&mut self, section: &'static str, message: impl Into<String>) {
+        self.0.push(ConfigIssue {
+            severity: Severity::Error,
+            section,
+            message: message.into(),
+        });
+    }
+
+    fn warning(&mut self, section: &'static str, message: impl Into<String>) {
+        self.0.push(ConfigIssue {
+            severity: Severity::Warning,
+            section,
+            message: message.into(),
+        });
+    }
+
+    fn check_url(&mut self, section: &'static str, field: &str, url: &str) {
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => {}
+            Ok(parsed) => self.error(
+                section,
+                format!("{} has unsupported scheme '{}': {}", field, parsed.scheme(), url),
+            ),
+            Err(e) => self.error(section, format!("{} is not a valid URL ({}): {}", field, e, url)),
+        }
+    }
+
+    fn check_zmq_url(&mut self, section: &'static str, url: Option<&str>) {
+        if let Some(url) = url {
+            if !url.strip_prefix("tcp://").is_some_and(|addr| addr.contains(':')) {
+                self.error(section, format!("zmq_url must be tcp://host:port: {}", url));
+            }
+        }
+    }
+
+    fn check_cold_sweep(
+        &mut self,
+        section: &'static str,
+        hot_wallet_target: Option<Decimal>,
+        cold_address: Option<&str>,
+        pool_wallet_address: &str,
+        check_address: fn(&str) -> Result<(), String>,
+    ) {
+        match (hot_wallet_target, cold_address) {
+            (Some(target), Some(address)) => {
+                if target.is_sign_negative() {
+                    self.error(section, format!("hot_wallet_target must not be negative (got {})", target));
+                }
+                if is_placeholder(address) {
+                    self.error(section, format!("cold_address is not set (found '{}')", address));
+                } else if let Err(e) = check_address(address) {
+                    self.error(section, format!("cold_address {}", e));
+                } else if address == pool_wallet_address {
+                    self.error(section, "cold_address is the pool wallet address");
+                }
+            }
+            (Some(_), None) => self.warning(
+                section,
+                "hot_wallet_target is set without cold_address, cold sweeps are disabled",
+            ),
+            (None, Some(_)) => self.warning(
+                section,
+                "cold_address is set without hot_wallet_target, cold sweeps are disabled",
+            ),
+            (None, None) => {}
+        }
+    }
+
+    fn check_approval_threshold(&mut self, section: &'static str, threshold: Option<Decimal>, api_token: &str) {
+        let Some(threshold) = threshold else {
+            return;
+        };
+        if threshold <= Decimal::ZERO {
+            self.error(section, format!("approval_threshold must be positive (got {})", threshold));
+        }
+        if api_token.is_empty() {
+            self.warning(
+                section,
+                "approval_threshold is set without api.token, held payouts cannot be approved",
+            );
+        }
+    }
+
+    fn check_limits(&mut self, section: &'static str, limits: &PayoutLimits) {
+        let amounts = [
+            ("max_per_payment", limits.max_per_payment),
+            ("max_per_cycle", limits.max_per_cycle),
+            ("max_per_day", limits.max_per_day),
+        ];
+        for (name, value) in amounts {
+            if let Some(value) = value.filter(|v| *v <= Decimal::ZERO) {
+                self.error(section, format!("limits.{} must be greater than 0 (got {})", name, value));
+            }
+        }
+
+        if let Some(percent) = limits.max_wallet_percent {
+            if percent <= Decimal::ZERO || percent > Decimal::ONE_HUNDRED {
+                self.error(
+                    section,
+                    format!("limits.max_wallet_percent must be between 0 and 100 (got {})", percent),
+                );
+            }
+        }
+    }
+
+    fn check_dormant(&mut self, section: &'static str, dormant: Option<&DormantConfig>) {
+        let Some(dormant) = dormant.filter(|d| d.enabled) else {
+            return;
+        };
+
+        if dormant.pay_after_days == 0 {
+            self.error(section, "dormant.pay_after_days must be greater than 0");
+        }
+        if dormant.pay_min_amount.is_sign_negative() {
+            self.error(
+                section,
+                format!("dormant.pay_min_amount must not be negative (got {})", dormant.pay_min_amount),
+            );
+        }
+        if let Some(abandon) = dormant.abandon_after_days.filter(|d| *d <= dormant.pay_after_days) {
+            self.error(
+                section,
+                format!(
+                    "dormant.abandon_after_days ({}) must be greater than pay_after_days ({})",
+                    abandon, dormant.pay_after_days
+                ),
+            );
+        }
+    }
+
+    fn check_pool_fee(
+        &mut self,
+        section: &'static str,
+        fee: Option<&PoolFeeConfig>,
+        pool_wallet_address: &str,
+        check_address: fn(&str) -> Result<(), String>,
+    ) {
+        let Some(fee) = fee.filter(|f| f.enabled) else {
+            return;
+        };
+
+        if fee.percent.is_sign_negative() || fee.percent >= Decimal::ONE_HUNDRED {
+            self.error(
+                section,
+                format!("pool_fee.percent must be at least 0 and below 100 (got {})", fee.percent),
+            );
+        }
+        if fee.donation.is_sign_negative() || !fee.donation.fract().is_zero() {
+            self.error(
+                section,
+                format!("pool_fee.donation must be a whole number of atomic units (got {})", fee.donation),
+            );
+        }
+        if fee.percent.is_zero() && fee.donation.is_zero() {
+            self.warning(section, "pool_fee is enabled but both percent and donation are 0");
+        }
+
+        if is_placeholder(&fee.address) {
+            self.error(section, format!("pool_fee.address is not set (found '{}')", fee.address));
+        } else if let Err(e) = check_address(&fee.address) {
+            self.error(section, format!("pool_fee.address {}", e));
+        } else if fee.address == pool_wallet_address {
+            self.error(section, "pool_fee.address is the pool wallet address");
+        }
+    }
+
+    fn check_min_payout(&mut self, section: &'static str, min_payout: Decimal) {
+        if min_payout.is_sign_negative() {
+            self.error(section, format!("min_payout must not be negative (got {})", min_payout));
+        }
+    }
+}
+
+/// Placeholder values shipped in config.toml.example
+fn is_placeholder(value: &str) -> bool {
+    value.trim().is_empty() || value.starts_with("YOUR_")
+}
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_ALPHABET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Check a Monero pool wallet address
+///
+/// Coinbase outputs must go to a primary address, so subaddresses and
+/// integrated addresses are rejected. Stagenet and testnet addresses pass
+/// (see `xmr_network`).
+fn check_xmr_address(address: &str) -> Result<(), String> {
+    if !address.chars().all(|c| BASE58_ALPHABET.contains(c)) {
+        return Err("contains characters outside the base58 alphabet".to_string());
+    }
+
+    match (address.chars().next(), address.len()) {
+        (Some('4' | '5' | '9' | 'A'), 95) => Ok(()),
+        (Some('8' | '7' | 'B'), 95) => {
+            Err("is a subaddress; block rewards must go to the wallet's primary address".to_string())
+        }
+        (Some('4' | '5' | '9' | 'A'), 106) => Err("is an integrated address; use the primary address".to_string()),
+        (_, 95) => Err(
+            "is not a primary address (expected 4... on mainnet, 5... on stagenet, 9... or A... on testnet)"
+                .to_string(),
+        ),
+        (_, len) => Err(format!("has length {}, expected 95", len)),
+    }
+}
+
+/// Network of a Monero primary address, if it isn't mainnet
+fn xmr_network(address: &str) -> Option<&'static str> {
+    match address.chars().next()? {
+        '5' => Some("stagenet"),
+        '9' | 'A' => Some("testnet"),
+        _ => None,
+    }
+}
+
+/// Check a Tari wallet address (base58, hex public key, or emoji ID)
+fn check_xtm_address(address: &str) -> Result<(), String> {
+    if address.chars().any(char::is_whitespace) {
+        return Err("contains whitespace".to_string());
+    }
+
+    if !address.is_ascii() {
+        // Emoji ID
+        return match address.chars().count() {
+            33 | 91 => Ok(()),
+            n => Err(format!("emoji address has {} characters, expected 33 or 91", n)),
+        };
+    }
+
+    let is_hex = address.chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex && (address.len() == 64 || address.len() == 66) {
+        return Ok(());
+    }
+
+    if address.chars().all(|c| BASE58_ALPHABET.contains(c)) && (40..=120).contains(&address.len()) {
+        return Ok(());
+    }
+
+    Err("is not a base58, hex or emoji Tari address".to_string())
+}
+
+/// Check an ALEO address (bech32m, aleo1 + 58 characters)
+fn check_aleo_address(address: &str) -> Result<(), String> {
+    let Some(data) = address.strip_prefix("aleo1") else {
+        return Err("must start with aleo1".to_string());
+    };
+    if address.len() != 63 {
+        return Err(format!("has length {}, expected 63", address.len()));
+    }
+    if !data.chars().all(|c| BECH32_ALPHABET.contains(c)) {
+        return Err("contains characters outside the bech32 alphabet".to_string());
+    }
+    Ok(())
+}
+
+/// Check a base58 or bech32 address of a bitcoind-compatible chain
+fn check_bitcoin_address(address: &str, bech32_hrps: &[&str]) -> Result<(), String> {
+    let lower = address.to_lowercase();
+    if let Some(data) = bech32_hrps
+        .iter()
+        .find_map(|hrp| lower.strip_prefix(&format!("{}1", hrp)))
+    {
+        if address != lower && address != address.to_uppercase() {
+            return Err("mixes upper and lower case".to_string());
+        }
+        if !data.chars().all(|c| BECH32_ALPHABET.contains(c)) {
+            return Err("contains characters outside the bech32 alphabet".to_string());
+        }
+        if !(39..=90).contains(&address.len()) {
+            return Err(format!("has length {}, expected a bech32 address of 39 to 90 characters", address.len()));
+        }
+        return Ok(());
+    }
+
+    if address.chars().all(|c| BASE58_ALPHABET.contains(c)) && (26..=35).contains(&address.len()) {
+        return Ok(());
+    }
+
+    Err(format!("is not a base58 or bech32 ({}1...) address", bech32_hrps[0]))
+}
+
+/// Check a Bitcoin address (legacy, P2SH or bech32)
+fn check_btc_address(address: &str) -> Result<(), String> {
+    check_bitcoin_address(address, &["bc", "tb", "bcrt"])
+}
+
+/// Check a DigiByte address (legacy, P2SH or bech32)
+fn check_dgb_address(address: &str) -> Result<(), String> {
+    check_bitcoin_address(address, &["dgb", "dgbt", "dgbrt"])
+}
+
+/// Check a Bitcoin Cash address (CashAddr, with or without prefix, or legacy)
+fn check_bch_address(address: &str) -> Result<(), String> {
+    let lower = address.to_lowercase();
+    let payload = ["bitcoincash:", "bchtest:", "bchreg:"]
+        .iter()
+        .find_map(|prefix| lower.strip_prefix(prefix))
+        .unwrap_or(&lower);
+
+    if payload.starts_with('q') || payload.starts_with('p') {
+        if !payload.chars().all(|c| BECH32_ALPHABET.contains(c)) {
+            return Err("contains characters outside the CashAddr alphabet".to_string());
+        }
+        if payload.len() != 42 {
+            return Err(format!("has a CashAddr payload of {} characters, expected 42", payload.len()));
+        }
+        return Ok(());
+    }
+
+    if address.chars().all(|c| BASE58_ALPHABET.contains(c)) && (26..=35).contains(&address.len()) {
+        return Ok(());
+    }
+
+    Err("is not a CashAddr (bitcoincash:q...) or legacy address".to_string())
+}
+
+impl Config {
+    /// Check the configuration for problems that can be found without
+    /// contacting any wallet or pool (see `check-config` for live checks)
+    ///
+    /// Disabled coins are skipped so their placeholders do not count.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Issues::default();
+
+        // [service]
+        if self.service.share_scan_interval_secs == 0 {
+            issues.error("service", "share_scan_interval_secs must be greater than 0");
+        }
+        if self.service.payment_interval_secs == 0 {
+            issues.error("service", "payment_interval_secs must be greater than 0");
+        }
+        if !["trace", "debug", "info", "warn", "error"].contains(&self.service.log_level.to_lowercase().as_str()) {
+            issues.warning(
+                "service",
+                format!("log_level '{}' is not one of trace, debug, info, warn, error", self.service.log_level),
+            );
+        }
+
+        // [database]
+        if let Some(parent) = self.database.path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                issues.warning("database", format!("directory {:?} does not exist", parent));
+            }
+        }
+
+        // [api]
+        if format!("{}:{}", self.api.listen, self.api.port)
+            .parse::<std::net::SocketAddr>()
+            .is_err()
+        {
+            issues.error(
+                "api",
+                format!("listen address '{}' with port {} is not a valid socket address", self.api.listen, self.api.port),
+            );
+        }
+        if self.api.token.is_empty() {
+            issues.warning("api", "token is empty, API authentication is disabled");
+        }
+
+        let xmr = self.xmr.as_ref().filter(|c| c.enabled);
+        let xtm = self.xtm.as_ref().filter(|c| c.enabled);
+        let aleo = self.aleo.as_ref().filter(|c| c.enabled);
+        let bitcoin_family = [
+            ("btc", self.btc.as_ref().filter(|c| c.enabled), check_btc_address as fn(&str) -> Result<(), String>),
+            ("bch", self.bch.as_ref().filter(|c| c.enabled), check_bch_address),
+            ("dgb", self.dgb.as_ref().filter(|c| c.enabled), check_dgb_address),
+        ];
+
+        if xmr.is_none() && xtm.is_none() && aleo.is_none() && bitcoin_family.iter().all(|(_, c, _)| c.is_none()) {
+            issues.warning("service", "no coins are enabled, the service will only serve the API");
+        }
+
+        // [xmr]
+        if let Some(xmr) = xmr {
+            if !matches!(xmr.pool_type, PoolType::MoneroPool | PoolType::MergeProxy) {
+                issues.error(
+                    "xmr",
+                    format!("pool_type '{}' is not valid for XMR (use monero_pool or merge_proxy)", xmr.pool_type.as_str()),
+                );
+            }
+            Self::validate_coin_wallet(&mut issues, "xmr", xmr, check_xmr_address, &self.api.token);
+            if check_xmr_address(&xmr.pool_wallet_address).is_ok() {
+                if let Some(network) = xmr_network(&xmr.pool_wallet_address) {
+                    issues.warning(
+                        "xmr",
+                        format!("pool_wallet_address is a {} address; monerod and wallet-rpc must run on {} too", network, network),
+                    );
+                }
+            }
+            issues.check_zmq_url("xmr", xmr.zmq_url.as_deref());
+
+            if let Some(consolidation) = xmr.consolidation.as_ref().filter(|c| c.enabled) {
+                if consolidation.interval_secs == 0 {
+                    issues.error("xmr", "consolidation.interval_secs must be greater than 0");
+                }
+                if consolidation.below_amount.is_some_and(|a| a <= Decimal::ZERO) {
+                    issues.error("xmr", "consolidation.below_amount must be greater than 0");
+                }
+            }
+
+            if let Some(manager) = xmr.wallet_manager.as_ref().filter(|m| m.enabled) {
+                if manager.file.trim().is_empty() {
+                    issues.error("xmr", "wallet_manager.file is empty");
+                }
+                if manager.file.contains('/') {
+                    issues.error("xmr", "wallet_manager.file must be a name inside wallet-rpc's --wallet-dir, not a path");
+                }
+                if let Err(e) = manager.load_password() {
+                    issues.error("xmr", e.to_string());
+                }
+                issues.check_url("xmr", "wallet_manager.daemon_rpc_url", &manager.daemon_rpc_url);
+            }
+        }
+
+        // [xtm]
+        if let Some(xtm) = xtm {
+            if !matches!(xtm.pool_type, PoolType::MergeProxy | PoolType::MinotariMiner) {
+                issues.error(
+                    "xtm",
+                    format!("pool_type '{}' is not valid for XTM (use merge_proxy or minotari_miner)", xtm.pool_type.as_str()),
+                );
+            }
+            Self::validate_coin_wallet(&mut issues, "xtm", xtm, check_xtm_address, &self.api.token);
+
+            if xtm.consolidation.as_ref().is_some_and(|c| c.enabled) {
+                issues.warning("xtm", "consolidation is only supported for XMR and will be ignored");
+            }
+            if xtm.zmq_url.is_some() {
+                issues.warning("xtm", "zmq_url is not supported for XTM and will be ignored (use POST /api/notify/xtm)");
+            }
+            if xtm.wallet_manager.as_ref().is_some_and(|m| m.enabled) {
+                issues.warning("xtm", "wallet_manager is only supported for XMR and will be ignored");
+            }
+        }
+
+        // Merge mining pays XMR and XTM from the same proxy
+        if let (Some(xmr), Some(xtm)) = (xmr, xtm) {
+            if (xmr.pool_type == PoolType::MergeProxy) != (xtm.pool_type == PoolType::MergeProxy) {
+                issues.warning(
+                    "xtm",
+                    format!(
+                        "xmr uses pool_type '{}' but xtm uses '{}'; merge mining expects merge_proxy for both",
+                        xmr.pool_type.as_str(),
+                        xtm.pool_type.as_str()
+                    ),
+                );
+            }
+        }
+
+        // [aleo]
+        if let Some(aleo) = aleo {
+            if is_placeholder(&aleo.pool_wallet_address) {
+                issues.error(
+                    "aleo",
+                    format!("pool_wallet_address is not set (found '{}')", aleo.pool_wallet_address),
+                );
+            } else if let Err(e) = check_aleo_address(&aleo.pool_wallet_address) {
+                issues.error("aleo", format!("pool_wallet_address {}", e));
+            }
+
+            if is_placeholder(&aleo.pool_private_key) {
+                issues.error("aleo", "pool_private_key is not set");
+            } else if !aleo.pool_private_key.starts_with("APrivateKey1") || aleo.pool_private_key.len() != 59 {
+                issues.error("aleo", "pool_private_key is not an ALEO private key (APrivateKey1..., 59 characters)");
+            }
+
+            issues.check_url("aleo", "node_rpc_url", &aleo.node_rpc_url);
+            if let Some(url) = &aleo.pool_api_url {
+                issues.check_url("aleo", "pool_api_url", url);
+            }
+            issues.check_min_payout("aleo", aleo.min_payout);
+            issues.check_approval_threshold("aleo", aleo.approval_threshold, &self.api.token);
+            issues.check_limits("aleo", &aleo.limits);
+            issues.check_dormant("aleo", aleo.dormant.as_ref());
+            issues.check_pool_fee(
+                "aleo",
+                aleo.pool_fee.as_ref(),
+                &aleo.pool_wallet_address,
+                check_aleo_address,
+            );
+            issues.check_cold_sweep(
+                "aleo",
+                aleo.hot_wallet_target,
+                aleo.cold_address.as_deref(),
+                &aleo.pool_wallet_address,
+                check_aleo_address,
+            );
+        }
+
+        // [btc], [bch], [dgb]
+        for (section, coin, check_address) in bitcoin_family {
+            let Some(coin) = coin else {
+                continue;
+            };
+            if coin.pool_type != PoolType::Ckpool {
+                issues.error(
+                    section,
+                    format!(
+                        "pool_type '{}' is not valid for {} (use ckpool)",
+                        coin.pool_type.as_str(),
+                        section.to_uppercase()
+                    ),
+                );
+            }
+            if coin.wallet_rpc_user.is_none() || coin.wallet_rpc_password.is_none() {
+                issues.warning(section, "wallet_rpc_user/wallet_rpc_password are not set; bitcoind requires RPC auth");
+            }
+            if !coin.pool_data_path.is_dir() {
+                issues.warning(
+                    section,
+                    format!("pool_data_path {:?} is not a directory (expected the CKPool log directory)", coin.pool_data_path),
+                );
+            }
+            Self::validate_coin_wallet(&mut issues, section, coin, check_address, &self.api.token);
+            issues.check_zmq_url(section, coin.zmq_url.as_deref());
+
+            if coin.consolidation.as_ref().is_some_and(|c| c.enabled) {
+                issues.warning(section, "consolidation is only supported for XMR and will be ignored");
+            }
+            if coin.wallet_manager.as_ref().is_some_and(|m| m.enabled) {
+                issues.warning(section, "wallet_manager is only supported for XMR and will be ignored");
+            }
+        }
+
+        // [[webhooks]]
+        let mut webhook_names = std::collections::HashSet::new();
+        for webhook in self.webhooks.iter().filter(|w| w.enabled) {
+            if webhook.name.trim().is_empty() {
+                issues.error("webhooks", "a webhook has no name");
+            } else if !webhook_names.insert(webhook.name.as_str()) {
+                issues.error("webhooks", format!("webhook name '{}' is used more than once", webhook.name));
+            }
+            issues.check_url("webhooks", &format!("{}.url", webhook.name), &webhook.url);
+            for event in &webhook.events {
+                if !EventKind::NAMES.contains(&event.as_str()) {
+                    issues.error(
+                        "webhooks",
+                        format!("{}: unknown event '{}' (use {})", webhook.name, event, EventKind::NAMES.join(", ")),
+                    );
+                }
+            }
+            if let Some(template) = &webhook.template {
+                if let Err(e) = webhooks::check_template(template) {
+                    issues.error("webhooks", format!("{}: template {}", webhook.name, e));
+                }
+            }
+            if webhook.max_attempts == 0 {
+                issues.error("webhooks", format!("{}: max_attempts must be greater than 0", webhook.name));
+            }
+        }
+
+        issues.0
+    }
+
+    /// Checks shared by the wallet RPC configs (XMR, XTM, BTC, BCH, DGB)
+    fn validate_coin_wallet(
+        issues: &mut Issues,
+        section: &'static str,
+        coin: &CoinConfig,
+        check_address: fn(&str) -> Result<(), String>,
+        api_token: &str,
+    ) {
+        if is_placeholder(&coin.pool_wallet_address) {
+            issues.error(
+                section,
+                format!("pool_wallet_address is not set (found '{}')", coin.pool_wallet_address),
+            );
+        } else if let Err(e) = check_address(&coin.pool_wallet_address) {
+            issues.error(section, format!("pool_wallet_address {}", e));
+        }
+
+        issues.check_url(section, "wallet_rpc_url", &coin.wallet_rpc_url);
+        if let Some(url) = &coin.pool_api_url {
+            issues.check_url(section, "pool_api_url", url);
+        }
+        issues.check_min_payout(section, coin.min_payout);
+        issues.check_approval_threshold(section, coin.approval_threshold, api_token);
+        issues.check_limits(section, &coin.limits);
+        issues.check_dormant(section, coin.dormant.as_ref());
+        issues.check_pool_fee(section, coin.pool_fee.as_ref(), &coin.pool_wallet_address, check_address);
+        issues.check_cold_sweep(
+            section,
+            coin.hot_wallet_target,
+            coin.cold_address.as_deref(),
+            &coin.pool_wallet_address,
+            check_address,
+        );
+    }
 }
 
 impl Default for Config {
@@ -262,6 +1287,8 @@ impl Default for Config {
                 share_scan_interval_secs: default_share_scan_interval(),
                 payment_interval_secs: default_payment_interval(),
                 log_level: default_log_level(),
+                shutdown_timeout_secs: default_shutdown_timeout(),
+                schedule_jitter_secs: 0,
             },
             database: DatabaseConfig {
                 path: default_db_path(),
@@ -274,6 +1301,142 @@ impl Default for Config {
             xmr: None,
             xtm: None,
             aleo: None,
+            btc: None,
+            bch: None,
+            dgb: None,
+            webhooks: Vec::new(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xmr(prefix: &str, len: usize) -> String {
+        format!("{}{}", prefix, "A".repeat(len - prefix.len()))
+    }
+
+    /// Config with only XMR enabled, paying to `address`
+    fn xmr_config(address: &str) -> Config {
+        let xmr: CoinConfig = toml::from_str(&format!(
+            r#"
+            pool_wallet_address = "{}"
+            wallet_rpc_url = "http://127.0.0.1:18082/json_rpc"
+            pool_data_path = "/var/lib/monero-pool"
+            "#,
+            address
+        ))
+        .unwrap();
+
+        let mut config = Config::default();
+        config.api.token = "secret".to_string();
+        config.xmr = Some(xmr);
+        config
+    }
+
+    fn messages(config: &Config, severity: Severity) -> Vec<String> {
+        config
+            .validate()
+            .into_iter()
+            .filter(|issue| issue.severity == severity)
+            .map(|issue| format!("{}: {}", issue.section, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn xmr_addresses_must_be_primary() {
+        assert!(check_xmr_address(&xmr("4", 95)).is_ok());
+        assert!(check_xmr_address(&xmr("5", 95)).is_ok());
+        assert!(check_xmr_address(&xmr("9", 95)).is_ok());
+        assert!(check_xmr_address(&xmr("A", 95)).is_ok());
+
+        for subaddress in ["8", "7", "B"] {
+            let err = check_xmr_address(&xmr(subaddress, 95)).unwrap_err();
+            assert!(err.contains("subaddress"), "{}", err);
+        }
+        assert!(check_xmr_address(&xmr("4", 106))
+            .unwrap_err()
+            .contains("integrated"));
+        assert!(check_xmr_address(&xmr("4", 94))
+            .unwrap_err()
+            .contains("length 94"));
+        assert!(check_xmr_address(&xmr("40", 95))
+            .unwrap_err()
+            .contains("base58"));
+        assert!(check_xmr_address(&xmr("1", 95))
+            .unwrap_err()
+            .contains("not a primary address"));
+
+        assert_eq!(xmr_network(&xmr("4", 95)), None);
+        assert_eq!(xmr_network(&xmr("5", 95)), Some("stagenet"));
+        assert_eq!(xmr_network(&xmr("9", 95)), Some("testnet"));
+    }
+
+    #[test]
+    fn xtm_addresses() {
+        assert!(check_xtm_address(&"ab".repeat(32)).is_ok());
+        assert!(check_xtm_address(&"ab".repeat(33)).is_ok());
+        assert!(check_xtm_address(&"12".repeat(35)).is_ok());
+        assert!(check_xtm_address(&"🐢".repeat(33)).is_ok());
+
+        assert!(check_xtm_address(&"🐢".repeat(32))
+            .unwrap_err()
+            .contains("32 characters"));
+        assert!(check_xtm_address("ab cd")
+            .unwrap_err()
+            .contains("whitespace"));
+        assert!(check_xtm_address("abc").is_err());
+    }
+
+    #[test]
+    fn aleo_addresses() {
+        let address = format!("aleo1{}", "q".repeat(58));
+        assert!(check_aleo_address(&address).is_ok());
+
+        assert!(check_aleo_address(&format!("aleo2{}", "q".repeat(58)))
+            .unwrap_err()
+            .contains("aleo1"));
+        assert!(check_aleo_address(&format!("aleo1{}", "q".repeat(57)))
+            .unwrap_err()
+            .contains("length 62"));
+        assert!(check_aleo_address(&format!("aleo1{}b", "q".repeat(57)))
+            .unwrap_err()
+            .contains("bech32"));
+    }
+
+    #[test]
+    fn validate_reports_placeholders_as_errors_and_testnets_as_warnings() {
+        let placeholder = xmr_config("YOUR_XMR_POOL_WALLET_ADDRESS");
+        let errors = messages(&placeholder, Severity::Error);
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.starts_with("xmr: pool_wallet_address is not set")),
+            "{:?}",
+            errors
+        );
+
+        let mainnet = xmr_config(&xmr("4", 95));
+        assert!(messages(&mainnet, Severity::Error).is_empty());
+        let warnings = messages(&mainnet, Severity::Warning);
+        assert!(
+            !warnings.iter().any(|w| w.starts_with("xmr:")),
+            "{:?}",
+            warnings
+        );
+
+        let stagenet = xmr_config(&xmr("5", 95));
+        assert!(messages(&stagenet, Severity::Error).is_empty());
+        let warnings = messages(&stagenet, Severity::Warning);
+        assert!(
+            warnings.iter().any(|w| w.contains("stagenet address")),
+            "{:?}",
+            warnings
+        );
+
+        let mut disabled = xmr_config("YOUR_XMR_POOL_WALLET_ADDRESS");
+        disabled.xmr.as_mut().unwrap().enabled = false;
+        assert!(messages(&disabled, Severity::Error).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+//! Pool wallet output consolidation (XMR)
+//!
+//! Every block a solo pool finds leaves one more coinbase output in the pool
+//! wallet. Over time a batch payout has to spend so many small inputs that it
+//! runs into the transaction size limit and pays a large fee. Consolidation
+//! sweeps those outputs back to the pool wallet as a few large ones.
+//!
+//! Runs right after a payment cycle, so it never overlaps a payout and the
+//! swept outputs have a full payment interval to unlock. Payouts are held
+//! while a consolidation transaction is unconfirmed, and nothing is swept
+//! while a payout awaits approval or relay.
+
+use crate::config::ConsolidationConfig;
+use crate::db::{Coin, Database};
+use crate::wallets::monero::MoneroWallet;
+use crate::wallets::to_atomic;
+use chrono::Utc;
+use tracing::{debug, info};
+
+/// Schedules and sends consolidation sweeps for one coin
+pub struct Consolidator {
+    coin: Coin,
+    wallet: MoneroWallet,
+    db: Database,
+    config: ConsolidationConfig,
+}
+
+impl Consolidator {
+    pub fn new(coin: Coin, wallet: MoneroWallet, db: Database, config: ConsolidationConfig) -> Self {
+        Self {
+            coin,
+            wallet,
+            db,
+            config,
+        }
+    }
+
+    /// Consolidate if one is due and enough outputs qualify
+    ///
+    /// Returns the id of the recorded consolidation, if one was sent.
+    pub async fn run(&self) -> anyhow::Result<Option<String>> {
+        if self.db.has_pending_consolidation(self.coin).await? {
+            debug!(coin = %self.coin, "Previous consolidation still unconfirmed");
+            return Ok(None);
+        }
+
+        // A held payout's prepared transaction spends specific outputs, which
+        // the sweep would spend first and leave it unrelayable
+        if self.db.has_open_payout_approval(self.coin).await? {
+            debug!(coin = %self.coin, "Payout awaiting approval or relay, not consolidating");
+            return Ok(None);
+        }
+
+        let last = self.db.list_consolidations(self.coin, None, 1).await?;
+        if let Some(last) = last.first() {
+            let elapsed = (Utc::now() - last.created_at).num_seconds();
+            if elapsed < self.config.interval_secs as i64 {
+                return Ok(None);
+            }
+        }
+
+        let below_amount = self.config.below_amount.map(to_atomic).transpose()?;
+        let outputs = self.wallet.count_unlocked_outputs(below_amount).await?;
+        if outputs < self.config.min_outputs {
+            debug!(
+                coin = %self.coin,
+                outputs = outputs,
+                min_outputs = self.config.min_outputs,
+                "Not enough outputs to consolidate"
+            );
+            return Ok(None);
+        }
+
+        let mut sweep = self.wallet.sweep_to_self(below_amount).await?;
+
+        if self.config.sweep_unmixable {
+            let dust = self.wallet.sweep_dust().await?;
+            sweep.tx_hashes.extend(dust.tx_hashes);
+            sweep.amount += dust.amount;
+            sweep.fee += dust.fee;
+        }
+
+        if sweep.tx_hashes.is_empty() {
+            return Ok(None);
+        }
+
+        let id = self
+            .db
+            .record_consolidation(self.coin, &sweep.tx_hashes, outputs, sweep.amount, sweep.fee)
+            .await?;
+
+        info!(
+            coin = %self.coin,
+            consolidation_id = %id,
+            outputs = outputs,
+            transactions = sweep.tx_hashes.len(),
+            amount = %sweep.amount,
+            fee = %sweep.fee,
+            "Consolidation sent, payouts held until it confirms"
+        );
+
+        Ok(Some(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{ApprovalStatus, TempDatabase};
+    use axum::{routing::post, Json, Router};
+    use rust_decimal::Decimal;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    /// Wallet RPC with 20 unlocked outputs that logs the sweeps it is asked for
+    async fn wallet_rpc() -> (String, Arc<Mutex<Vec<String>>>) {
+        let sweeps: Arc<Mutex<Vec<String>>> = Arc::default();
+        let log = sweeps.clone();
+        let app = Router::new().route(
+            "/json_rpc",
+            post(move |Json(request): Json<Value>| {
+                let log = log.clone();
+                async move {
+                    let method = request["method"].as_str().unwrap_or_default().to_string();
+                    let result = match method.as_str() {
+                        "incoming_transfers" => json!({
+                            "transfers": vec![json!({"amount": 1_000, "spent": false, "unlocked": true}); 20]
+                        }),
+                        "get_address" => json!({"address": "pool"}),
+                        _ => {
+                            log.lock().unwrap().push(method);
+                            json!({"tx_hash_list": ["sweep"], "amount_list": [20_000], "fee_list": [30]})
+                        }
+                    };
+                    Json(json!({"jsonrpc": "2.0", "id": "0", "result": result}))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (url, sweeps)
+    }
+
+    fn config() -> ConsolidationConfig {
+        ConsolidationConfig {
+            enabled: true,
+            interval_secs: 0,
+            below_amount: None,
+            min_outputs: 10,
+            sweep_unmixable: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn sweeps_once_enough_outputs_qualify_and_waits_for_confirmation() {
+        let db = TempDatabase::new().await;
+
+        let (url, sweeps) = wallet_rpc().await;
+        let mut strict = config();
+        strict.min_outputs = 21;
+        let consolidator =
+            Consolidator::new(Coin::Xmr, MoneroWallet::new(&url, 15), db.clone(), strict);
+        assert_eq!(consolidator.run().await.unwrap(), None);
+        assert!(sweeps.lock().unwrap().is_empty());
+
+        let consolidator =
+            Consolidator::new(Coin::Xmr, MoneroWallet::new(&url, 15), db.clone(), config());
+        let id = consolidator
+            .run()
+            .await
+            .unwrap()
+            .expect("consolidation sent");
+        let sent = db.list_consolidations(Coin::Xmr, None, 10).await.unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].id, id);
+        assert_eq!(sent[0].amount, Decimal::from(20_000));
+        assert!(db.has_pending_consolidation(Coin::Xmr).await.unwrap());
+
+        // Nothing more until the first sweep confirms
+        assert_eq!(consolidator.run().await.unwrap(), None);
+        assert_eq!(*sweeps.lock().unwrap(), vec!["sweep_all".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn does_not_sweep_the_inputs_of_a_held_payout() {
+        let db = TempDatabase::new().await;
+        let (url, sweeps) = wallet_rpc().await;
+        let consolidator =
+            Consolidator::new(Coin::Xmr, MoneroWallet::new(&url, 15), db.clone(), config());
+
+        let payouts = [("alice".to_string(), Decimal::from(500))];
+        let id = db
+            .create_payout_approval(
+                Coin::Xmr,
+                &payouts,
+                Some("prepared"),
+                None,
+                Some("blob"),
+                Some(Decimal::ONE),
+            )
+            .await
+            .unwrap();
+        assert_eq!(consolidator.run().await.unwrap(), None);
+
+        assert!(db.decide_payout_approval(&id, true, None).await.unwrap());
+        assert_eq!(consolidator.run().await.unwrap(), None);
+        assert!(sweeps.lock().unwrap().is_empty());
+
+        let sent = [("alice".to_string(), "prepared".to_string(), None)];
+        db.mark_payout_relayed(&id, &sent).await.unwrap();
+        let approval = db.get_payout_approval(&id).await.unwrap().unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Relayed);
+        assert!(consolidator.run().await.unwrap().is_some());
+        assert_eq!(*sweeps.lock().unwrap(), vec!["sweep_all".to_string()]);
+    }
+}
@@ -7,19 +7,43 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use sqlx::{sqlite::SqlitePoolOptions, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
 /// Supported coins for payment processing
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
 #[sqlx(rename_all = "lowercase")]
 pub enum Coin {
     Xmr,
     Xtm,
     Aleo,
+    Btc,
+    Bch,
+    Dgb,
+}
+
+impl Coin {
+    /// All coins handled by the payment processor
+    pub const ALL: [Coin; 6] = [Coin::Xmr, Coin::Xtm, Coin::Aleo, Coin::Btc, Coin::Bch, Coin::Dgb];
+
+    /// Decimal places of one whole coin (atomic units per coin = 10^decimals)
+    pub fn decimals(&self) -> u32 {
+        match self {
+            Coin::Xmr => 12,
+            Coin::Xtm | Coin::Aleo => 6,
+            Coin::Btc | Coin::Bch | Coin::Dgb => 8,
+        }
+    }
+
+    /// Convert an amount in atomic units to whole coins
+    pub fn human_amount(&self, atomic: Decimal) -> Decimal {
+        (atomic * Decimal::new(1, self.decimals())).normalize()
+    }
 }
 
 impl std::fmt::Display for Coin {
@@ -28,6 +52,9 @@ impl std::fmt::Display for Coin {
             Coin::Xmr => write!(f, "xmr"),
             Coin::Xtm => write!(f, "xtm"),
             Coin::Aleo => write!(f, "aleo"),
+            Coin::Btc => write!(f, "btc"),
+            Coin::Bch => write!(f, "bch"),
+            Coin::Dgb => write!(f, "dgb"),
         }
     }
 }
@@ -40,6 +67,9 @@ impl std::str::FromStr for Coin {
             "xmr" => Ok(Coin::Xmr),
             "xtm" => Ok(Coin::Xtm),
             "aleo" => Ok(Coin::Aleo),
+            "btc" => Ok(Coin::Btc),
+            "bch" => Ok(Coin::Bch),
+            "dgb" => Ok(Coin::Dgb),
             _ => Err(anyhow::anyhow!("Unknown coin: {}", s)),
         }
     }
@@ -47,6 +77,7 @@ impl std::str::FromStr for Coin {
 
 /// A share submitted by a miner
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct Share {
     pub id: i64,
     pub coin: Coin,
@@ -74,18 +105,74 @@ pub struct MinerBalance {
     pub last_share: Option<DateTime<Utc>>,
     /// Last payment timestamp
     pub last_payment: Option<DateTime<Utc>>,
+    /// Fraction of an atomic unit earned but not yet credited to the pending balance
+    pub carry_balance: Decimal,
+    /// Balance of a dormant miner set aside until claimed; still owed
+    pub abandoned_balance: Decimal,
 }
 
 /// Payment status
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(rename_all = "lowercase")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
 pub enum PaymentStatus {
+    /// Held for operator approval; the amount stays reserved
+    PendingApproval,
     Pending,
     Processing,
     Confirmed,
     Failed,
 }
 
+impl PaymentStatus {
+    /// All payment states
+    pub const ALL: [PaymentStatus; 5] = [
+        PaymentStatus::PendingApproval,
+        PaymentStatus::Pending,
+        PaymentStatus::Processing,
+        PaymentStatus::Confirmed,
+        PaymentStatus::Failed,
+    ];
+
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentStatus::PendingApproval => "pending_approval",
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Processing => "processing",
+            PaymentStatus::Confirmed => "confirmed",
+            PaymentStatus::Failed => "failed",
+        }
+    }
+
+    /// Parse the database representation, treating unknown values as pending
+    fn from_db(s: &str) -> Self {
+        match s {
+            "pending_approval" => PaymentStatus::PendingApproval,
+            "processing" => PaymentStatus::Processing,
+            "confirmed" => PaymentStatus::Confirmed,
+            "failed" => PaymentStatus::Failed,
+            _ => PaymentStatus::Pending,
+        }
+    }
+}
+
+impl std::fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PaymentStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PaymentStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Unknown payment status: {}", s))
+    }
+}
+
 /// A payment record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Payment {
@@ -98,6 +185,288 @@ pub struct Payment {
     pub created_at: DateTime<Utc>,
     pub confirmed_at: Option<DateTime<Utc>>,
     pub error_message: Option<String>,
+    /// Payment proof key (Monero tx key, Tari kernel signature)
+    pub tx_key: Option<String>,
+}
+
+/// State of a payout held for operator approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    /// Waiting for an operator
+    PendingApproval,
+    /// Approved, to be relayed on the next payment cycle
+    Approved,
+    /// Broadcast; its payments are confirmed like any other
+    Relayed,
+    Rejected,
+    /// Approved but could not be relayed
+    Failed,
+}
+
+impl ApprovalStatus {
+    /// All approval states
+    pub const ALL: [ApprovalStatus; 5] = [
+        ApprovalStatus::PendingApproval,
+        ApprovalStatus::Approved,
+        ApprovalStatus::Relayed,
+        ApprovalStatus::Rejected,
+        ApprovalStatus::Failed,
+    ];
+
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalStatus::PendingApproval => "pending_approval",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Relayed => "relayed",
+            ApprovalStatus::Rejected => "rejected",
+            ApprovalStatus::Failed => "failed",
+        }
+    }
+
+    fn from_db(s: &str) -> Self {
+        ApprovalStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .unwrap_or(ApprovalStatus::PendingApproval)
+    }
+}
+
+impl std::fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ApprovalStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ApprovalStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Unknown approval status: {}", s))
+    }
+}
+
+/// A payout held for operator approval
+///
+/// Either a signed transaction that hasn't been broadcast (`tx_metadata`
+/// set) or, for wallets that can't hold one, the intent to send it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutApproval {
+    pub id: String,
+    pub coin: Coin,
+    pub status: ApprovalStatus,
+    pub total: Decimal,
+    /// Network fee of the prepared transaction
+    pub fee: Option<Decimal>,
+    pub tx_hash: Option<String>,
+    pub tx_metadata: Option<String>,
+    /// Payments in the payout
+    pub payments: Vec<Payment>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+}
+
+/// State of a pool wallet consolidation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsolidationStatus {
+    /// Sent, waiting for confirmations (payouts are held)
+    Pending,
+    Confirmed,
+    Failed,
+}
+
+impl ConsolidationStatus {
+    /// All consolidation states
+    pub const ALL: [ConsolidationStatus; 3] = [
+        ConsolidationStatus::Pending,
+        ConsolidationStatus::Confirmed,
+        ConsolidationStatus::Failed,
+    ];
+
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsolidationStatus::Pending => "pending",
+            ConsolidationStatus::Confirmed => "confirmed",
+            ConsolidationStatus::Failed => "failed",
+        }
+    }
+
+    fn from_db(s: &str) -> Self {
+        ConsolidationStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .unwrap_or(ConsolidationStatus::Pending)
+    }
+}
+
+impl std::fmt::Display for ConsolidationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A payout pause, for one coin or all of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutPause {
+    /// Paused coin, or None for the global pause
+    pub coin: Option<Coin>,
+    pub reason: String,
+    /// Who set it: "api", "cli" or "limits"
+    pub source: String,
+    pub paused_at: DateTime<Utc>,
+}
+
+/// Scope key of the global pause in `payout_pauses`
+const PAUSE_ALL: &str = "all";
+
+/// Addresses that share one mining identity's rewards by fixed percentages
+///
+/// Miners can mine as the team (the team id as their login) or have their
+/// own address redirected to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Team {
+    pub coin: Coin,
+    pub id: String,
+    pub members: Vec<TeamMember>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A team member and their percentage of the team's rewards
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamMember {
+    pub wallet_address: String,
+    pub percent: Decimal,
+}
+
+/// A sweep of the pool wallet's outputs back to itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Consolidation {
+    pub id: String,
+    pub coin: Coin,
+    pub tx_hashes: Vec<String>,
+    /// Outputs that qualified when the sweep was started
+    pub outputs: i64,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub status: ConsolidationStatus,
+    pub created_at: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub error_message: Option<String>,
+}
+
+/// How a scheduled job run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobOutcome {
+    Success,
+    /// Ran to the end, but some stages failed
+    Partial,
+    /// Stopped early on purpose (pool offline, payouts paused, ...)
+    Skipped,
+    Failed,
+}
+
+impl JobOutcome {
+    /// All job outcomes
+    pub const ALL: [JobOutcome; 4] = [
+        JobOutcome::Success,
+        JobOutcome::Partial,
+        JobOutcome::Skipped,
+        JobOutcome::Failed,
+    ];
+
+    /// Database representation of the outcome
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobOutcome::Success => "success",
+            JobOutcome::Partial => "partial",
+            JobOutcome::Skipped => "skipped",
+            JobOutcome::Failed => "failed",
+        }
+    }
+
+    fn from_db(s: &str) -> Self {
+        JobOutcome::ALL
+            .into_iter()
+            .find(|outcome| outcome.as_str() == s)
+            .unwrap_or(JobOutcome::Failed)
+    }
+}
+
+impl std::fmt::Display for JobOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One run of a scheduled job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub id: i64,
+    pub job: String,
+    pub coin: Coin,
+    /// What started it: "interval", "block" or "manual"
+    pub trigger: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub outcome: JobOutcome,
+    /// Failed stages, or why the run was skipped
+    pub error: Option<String>,
+    /// Shares and blocks recorded, or payments sent
+    pub items: i64,
+}
+
+/// A persisted service event, for stream replay
+#[derive(Debug, Clone)]
+pub struct StoredEvent {
+    pub id: i64,
+    pub coin: Coin,
+    pub kind: String,
+    /// The event body as JSON
+    pub data: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// State of a queued webhook delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// Waiting for its next attempt
+    Pending,
+    Delivered,
+    /// Gave up after max_attempts
+    Failed,
+}
+
+impl DeliveryStatus {
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+/// A webhook delivery waiting to be sent
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook: String,
+    pub event_id: i64,
+    pub event_type: String,
+    /// Rendered request body
+    pub body: String,
+    /// Attempts made so far
+    pub attempts: u32,
 }
 
 /// Block found by the pool
@@ -113,6 +482,550 @@ pub struct BlockFound {
     pub timestamp: DateTime<Utc>,
     /// Whether reward has been distributed
     pub distributed: bool,
+    /// Where the recorded reward stands against the pool wallet
+    pub reward_status: RewardStatus,
+    /// Reward reported by the pool API, when it differs from the recorded one
+    pub reported_reward: Option<Decimal>,
+    /// Transaction (or block) in which the wallet received the coinbase
+    pub coinbase_tx: Option<String>,
+}
+
+/// Verification state of a block's reward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardStatus {
+    /// Reward as reported by the pool API (no wallet verification)
+    Reported,
+    /// Waiting for the coinbase to show up in the pool wallet
+    AwaitingCoinbase,
+    /// Reward matched against the coinbase received by the pool wallet
+    Verified,
+    /// Coinbase never appeared in the pool wallet (orphaned or not ours)
+    Missing,
+}
+
+impl RewardStatus {
+    /// All reward states
+    pub const ALL: [RewardStatus; 4] = [
+        RewardStatus::Reported,
+        RewardStatus::AwaitingCoinbase,
+        RewardStatus::Verified,
+        RewardStatus::Missing,
+    ];
+
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RewardStatus::Reported => "reported",
+            RewardStatus::AwaitingCoinbase => "awaiting_coinbase",
+            RewardStatus::Verified => "verified",
+            RewardStatus::Missing => "missing",
+        }
+    }
+
+    /// Parse the database representation, treating unknown values as reported
+    fn from_db(s: &str) -> Self {
+        RewardStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .unwrap_or(RewardStatus::Reported)
+    }
+}
+
+impl std::fmt::Display for RewardStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Kind of ledger entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEntryType {
+    /// Balance carried over from before ledger tracking
+    OpeningBalance,
+    /// Share of a block reward credited to a miner
+    BlockReward,
+    /// Reversal of earlier block reward credits before a redistribution
+    BlockReversal,
+    /// Fractional atomic units held over to the miner's next reward
+    ///
+    /// Moves the miner's carry balance instead of the pending balance.
+    RoundingCarry,
+    /// Excess hot wallet balance sent to the cold address
+    ///
+    /// Pool funds, not a miner credit: touches no balance.
+    ColdSweep,
+    /// Dormant balance moved to (negative) or reclaimed from (positive)
+    /// the abandoned account
+    ///
+    /// Moves the amount between the pending and abandoned balances.
+    Abandoned,
+}
+
+impl LedgerEntryType {
+    /// Database representation of the entry type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerEntryType::OpeningBalance => "opening_balance",
+            LedgerEntryType::BlockReward => "block_reward",
+            LedgerEntryType::BlockReversal => "block_reversal",
+            LedgerEntryType::RoundingCarry => "rounding_carry",
+            LedgerEntryType::ColdSweep => "cold_sweep",
+            LedgerEntryType::Abandoned => "abandoned",
+        }
+    }
+
+    fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "opening_balance" => Some(LedgerEntryType::OpeningBalance),
+            "block_reward" => Some(LedgerEntryType::BlockReward),
+            "block_reversal" => Some(LedgerEntryType::BlockReversal),
+            "rounding_carry" => Some(LedgerEntryType::RoundingCarry),
+            "cold_sweep" => Some(LedgerEntryType::ColdSweep),
+            "abandoned" => Some(LedgerEntryType::Abandoned),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for LedgerEntryType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A credit (or debit, if negative) to a miner's pending balance
+///
+/// Every change to `pending_balance + total_paid` has a ledger entry,
+/// so balances can be checked against their history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub id: i64,
+    pub coin: Coin,
+    pub wallet_address: String,
+    pub entry_type: LedgerEntryType,
+    pub amount: Decimal,
+    /// Block the entry belongs to, for block rewards and reversals
+    pub block_id: Option<i64>,
+    /// Payment the entry belongs to
+    pub payment_id: Option<String>,
+    pub memo: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A miner's part of a block reward, as written to the ledger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockCredit {
+    pub wallet_address: String,
+    /// Whole atomic units credited to the pending balance
+    pub amount: Decimal,
+    /// Change to the miner's carry balance (fractional atomic units)
+    pub carry: Decimal,
+    /// Ledger memo for this credit (e.g. the team it was split from)
+    pub memo: Option<String>,
+}
+
+/// A ledger entry to be written
+struct LedgerCredit<'a> {
+    coin: Coin,
+    wallet_address: &'a str,
+    entry_type: LedgerEntryType,
+    amount: Decimal,
+    block_id: Option<i64>,
+    memo: Option<&'a str>,
+}
+
+type PaymentRow = (
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+type BlockRow = (
+    i64,
+    String,
+    i64,
+    String,
+    String,
+    String,
+    String,
+    String,
+    i32,
+    String,
+    Option<String>,
+    Option<String>,
+);
+type BalanceRow = (
+    String,
+    String,
+    String,
+    String,
+    i64,
+    Option<String>,
+    Option<String>,
+    String,
+    String,
+);
+type ApprovalRow = (
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<String>,
+);
+type ConsolidationRow = (
+    String,
+    String,
+    String,
+    i64,
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+);
+type JobRunRow = (i64, String, String, String, String, i64, String, Option<String>, i64);
+type EventRow = (i64, String, String, String, String);
+type WebhookDeliveryRow = (i64, String, i64, String, String, i64);
+type LedgerRow = (i64, String, String, String, String, Option<i64>, Option<String>, Option<String>, String);
+
+/// Parse a stored amount
+///
+/// Older versions did balance arithmetic in REAL, which can leave values
+/// in scientific notation (e.g. "1.5e+15").
+pub fn parse_amount(value: &str) -> Decimal {
+    value
+        .parse()
+        .or_else(|_| Decimal::from_scientific(value))
+        .unwrap_or_default()
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+}
+
+fn payment_from_row(row: PaymentRow, coin: Coin) -> Payment {
+    Payment {
+        id: row.0,
+        coin: row.1.parse().unwrap_or(coin),
+        wallet_address: row.2,
+        amount: parse_amount(&row.3),
+        tx_hash: row.4,
+        status: PaymentStatus::from_db(&row.5),
+        created_at: parse_timestamp(&row.6).unwrap_or_else(Utc::now),
+        confirmed_at: row.7.as_deref().and_then(parse_timestamp),
+        error_message: row.8,
+        tx_key: row.9,
+    }
+}
+
+fn block_from_row(row: BlockRow, coin: Coin) -> BlockFound {
+    BlockFound {
+        id: row.0,
+        coin: row.1.parse().unwrap_or(coin),
+        block_height: row.2,
+        block_hash: row.3,
+        reward: parse_amount(&row.4),
+        finder_wallet: row.5,
+        finder_worker: row.6,
+        timestamp: parse_timestamp(&row.7).unwrap_or_else(Utc::now),
+        distributed: row.8 != 0,
+        reward_status: RewardStatus::from_db(&row.9),
+        reported_reward: row.10.as_deref().map(parse_amount),
+        coinbase_tx: row.11,
+    }
+}
+
+fn approval_from_row(row: ApprovalRow, payments: Vec<Payment>) -> PayoutApproval {
+    PayoutApproval {
+        id: row.0,
+        coin: row.1.parse().unwrap_or(Coin::Xmr),
+        status: ApprovalStatus::from_db(&row.2),
+        total: parse_amount(&row.3),
+        fee: row.4.as_deref().map(parse_amount),
+        tx_hash: row.5,
+        tx_metadata: row.6,
+        payments,
+        created_at: parse_timestamp(&row.7).unwrap_or_else(Utc::now),
+        decided_at: row.8.as_deref().and_then(parse_timestamp),
+        note: row.9,
+    }
+}
+
+fn consolidation_from_row(row: ConsolidationRow, coin: Coin) -> Consolidation {
+    Consolidation {
+        id: row.0,
+        coin: row.1.parse().unwrap_or(coin),
+        tx_hashes: row.2.split(',').filter(|h| !h.is_empty()).map(String::from).collect(),
+        outputs: row.3,
+        amount: parse_amount(&row.4),
+        fee: parse_amount(&row.5),
+        status: ConsolidationStatus::from_db(&row.6),
+        created_at: parse_timestamp(&row.7).unwrap_or_else(Utc::now),
+        confirmed_at: row.8.as_deref().and_then(parse_timestamp),
+        error_message: row.9,
+    }
+}
+
+fn job_run_from_row(row: JobRunRow) -> Option<JobRun> {
+    Some(JobRun {
+        id: row.0,
+        job: row.1,
+        coin: row.2.parse().ok()?,
+        trigger: row.3,
+        started_at: parse_timestamp(&row.4).unwrap_or_else(Utc::now),
+        duration_ms: row.5,
+        outcome: JobOutcome::from_db(&row.6),
+        error: row.7,
+        items: row.8,
+    })
+}
+
+fn balance_from_row(row: BalanceRow, coin: Coin) -> MinerBalance {
+    MinerBalance {
+        wallet_address: row.0,
+        coin: row.1.parse().unwrap_or(coin),
+        pending_balance: parse_amount(&row.2),
+        total_paid: parse_amount(&row.3),
+        total_shares: row.4,
+        last_share: row.5.as_deref().and_then(parse_timestamp),
+        last_payment: row.6.as_deref().and_then(parse_timestamp),
+        carry_balance: parse_amount(&row.7),
+        abandoned_balance: parse_amount(&row.8),
+    }
+}
+
+fn ledger_from_row(row: LedgerRow, coin: Coin) -> Option<LedgerEntry> {
+    Some(LedgerEntry {
+        id: row.0,
+        coin: row.1.parse().unwrap_or(coin),
+        wallet_address: row.2,
+        entry_type: LedgerEntryType::from_db(&row.3)?,
+        amount: parse_amount(&row.4),
+        block_id: row.5,
+        payment_id: row.6,
+        memo: row.7,
+        created_at: parse_timestamp(&row.8).unwrap_or_else(Utc::now),
+    })
+}
+
+/// Add deltas to a miner's pending balance and total paid
+async fn adjust_balance(
+    conn: &mut SqliteConnection,
+    coin: Coin,
+    wallet_address: &str,
+    pending_delta: Decimal,
+    paid_delta: Decimal,
+    last_payment: Option<&str>,
+) -> Result<()> {
+    let coin_str = coin.to_string();
+
+    let current = sqlx::query_as::<_, (String, String)>(
+        "SELECT pending_balance, total_paid FROM balances WHERE wallet_address = ? AND coin = ?",
+    )
+    .bind(wallet_address)
+    .bind(&coin_str)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match current {
+        Some((pending, paid)) => {
+            let pending = parse_amount(&pending) + pending_delta;
+            let paid = parse_amount(&paid) + paid_delta;
+
+            sqlx::query(
+                r#"
+                UPDATE balances
+                SET pending_balance = ?, total_paid = ?, last_payment = COALESCE(?, last_payment)
+                WHERE wallet_address = ? AND coin = ?
+                "#,
+            )
+            .bind(pending.normalize().to_string())
+            .bind(paid.normalize().to_string())
+            .bind(last_payment)
+            .bind(wallet_address)
+            .bind(&coin_str)
+            .execute(&mut *conn)
+            .await?;
+        }
+        None => {
+            sqlx::query(
+                r#"
+                INSERT INTO balances (wallet_address, coin, pending_balance, total_paid, last_payment)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(wallet_address)
+            .bind(&coin_str)
+            .bind(pending_delta.normalize().to_string())
+            .bind(paid_delta.normalize().to_string())
+            .bind(last_payment)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add a delta to a miner's carry balance
+async fn adjust_carry(
+    conn: &mut SqliteConnection,
+    coin: Coin,
+    wallet_address: &str,
+    delta: Decimal,
+) -> Result<()> {
+    let coin_str = coin.to_string();
+
+    let current = sqlx::query_as::<_, (String,)>(
+        "SELECT carry_balance FROM balances WHERE wallet_address = ? AND coin = ?",
+    )
+    .bind(wallet_address)
+    .bind(&coin_str)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match current {
+        Some((carry,)) => {
+            sqlx::query("UPDATE balances SET carry_balance = ? WHERE wallet_address = ? AND coin = ?")
+                .bind((parse_amount(&carry) + delta).normalize().to_string())
+                .bind(wallet_address)
+                .bind(&coin_str)
+                .execute(&mut *conn)
+                .await?;
+        }
+        None => {
+            sqlx::query("INSERT INTO balances (wallet_address, coin, carry_balance) VALUES (?, ?, ?)")
+                .bind(wallet_address)
+                .bind(&coin_str)
+                .bind(delta.normalize().to_string())
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add a delta to a miner's abandoned balance (the balance row must exist)
+async fn adjust_abandoned(
+    conn: &mut SqliteConnection,
+    coin: Coin,
+    wallet_address: &str,
+    delta: Decimal,
+) -> Result<()> {
+    let coin_str = coin.to_string();
+
+    let (abandoned,) = sqlx::query_as::<_, (String,)>(
+        "SELECT abandoned_balance FROM balances WHERE wallet_address = ? AND coin = ?",
+    )
+    .bind(wallet_address)
+    .bind(&coin_str)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE balances SET abandoned_balance = ? WHERE wallet_address = ? AND coin = ?")
+        .bind((parse_amount(&abandoned) + delta).normalize().to_string())
+        .bind(wallet_address)
+        .bind(&coin_str)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Write a ledger entry and apply it to the miner's pending (or carry) balance
+async fn record_credit(conn: &mut SqliteConnection, credit: LedgerCredit<'_>) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO ledger_entries (coin, wallet_address, entry_type, amount, block_id, memo, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(credit.coin.to_string())
+    .bind(credit.wallet_address)
+    .bind(credit.entry_type.as_str())
+    .bind(credit.amount.normalize().to_string())
+    .bind(credit.block_id)
+    .bind(credit.memo)
+    .bind(Utc::now().to_rfc3339())
+    .execute(&mut *conn)
+    .await?;
+
+    if credit.entry_type == LedgerEntryType::RoundingCarry {
+        return adjust_carry(conn, credit.coin, credit.wallet_address, credit.amount).await;
+    }
+
+    adjust_balance(
+        conn,
+        credit.coin,
+        credit.wallet_address,
+        credit.amount,
+        Decimal::ZERO,
+        None,
+    )
+    .await?;
+
+    if credit.entry_type == LedgerEntryType::Abandoned {
+        adjust_abandoned(conn, credit.coin, credit.wallet_address, -credit.amount).await?;
+    }
+
+    Ok(())
+}
+
+/// Write a block's credits: whole units to pending, fractions to carry
+async fn record_block_credits(
+    conn: &mut SqliteConnection,
+    block: &BlockFound,
+    credits: &[BlockCredit],
+    memo: Option<&str>,
+) -> Result<()> {
+    for credit in credits {
+        let entries = [
+            (LedgerEntryType::BlockReward, credit.amount),
+            (LedgerEntryType::RoundingCarry, credit.carry),
+        ];
+        let memo = match (memo, credit.memo.as_deref()) {
+            (Some(memo), Some(own)) => Some(format!("{}; {}", memo, own)),
+            (memo, own) => memo.or(own).map(str::to_string),
+        };
+
+        for (entry_type, amount) in entries {
+            if amount.is_zero() {
+                continue;
+            }
+
+            record_credit(
+                conn,
+                LedgerCredit {
+                    coin: block.coin,
+                    wallet_address: &credit.wallet_address,
+                    entry_type,
+                    amount,
+                    block_id: Some(block.id),
+                    memo: memo.as_deref(),
+                },
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
 }
 
 /// Database connection and operations
@@ -190,6 +1103,8 @@ impl Database {
                 total_shares INTEGER NOT NULL DEFAULT 0,
                 last_share TEXT,
                 last_payment TEXT,
+                carry_balance TEXT NOT NULL DEFAULT '0',
+                abandoned_balance TEXT NOT NULL DEFAULT '0',
                 PRIMARY KEY (wallet_address, coin)
             );
 
@@ -202,7 +1117,9 @@ impl Database {
                 status TEXT NOT NULL,
                 created_at TEXT NOT NULL,
                 confirmed_at TEXT,
-                error_message TEXT
+                error_message TEXT,
+                approval_id TEXT,
+                tx_key TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_payments_wallet ON payments(coin, wallet_address);
@@ -217,14 +1134,140 @@ impl Database {
                 finder_wallet TEXT NOT NULL,
                 finder_worker TEXT NOT NULL,
                 timestamp TEXT NOT NULL,
-                distributed INTEGER NOT NULL DEFAULT 0
+                distributed INTEGER NOT NULL DEFAULT 0,
+                reward_status TEXT NOT NULL DEFAULT 'reported',
+                reported_reward TEXT,
+                coinbase_tx TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_blocks_coin ON blocks(coin);
             CREATE UNIQUE INDEX IF NOT EXISTS idx_blocks_hash ON blocks(block_hash);
 
-            CREATE TABLE IF NOT EXISTS pool_stats (
-                coin TEXT PRIMARY KEY,
+            CREATE TABLE IF NOT EXISTS ledger_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                coin TEXT NOT NULL,
+                wallet_address TEXT NOT NULL,
+                entry_type TEXT NOT NULL,
+                amount TEXT NOT NULL,
+                block_id INTEGER,
+                payment_id TEXT,
+                memo TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_ledger_wallet ON ledger_entries(coin, wallet_address);
+            CREATE INDEX IF NOT EXISTS idx_ledger_block ON ledger_entries(block_id);
+
+            CREATE TABLE IF NOT EXISTS payout_pauses (
+                scope TEXT PRIMARY KEY,
+                reason TEXT NOT NULL,
+                source TEXT NOT NULL,
+                paused_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS payout_approvals (
+                id TEXT PRIMARY KEY,
+                coin TEXT NOT NULL,
+                status TEXT NOT NULL,
+                total TEXT NOT NULL,
+                fee TEXT,
+                tx_hash TEXT,
+                tx_metadata TEXT,
+                created_at TEXT NOT NULL,
+                decided_at TEXT,
+                note TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_approvals_status ON payout_approvals(coin, status);
+
+            CREATE TABLE IF NOT EXISTS teams (
+                coin TEXT NOT NULL,
+                id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (coin, id)
+            );
+
+            CREATE TABLE IF NOT EXISTS team_members (
+                coin TEXT NOT NULL,
+                team_id TEXT NOT NULL,
+                wallet_address TEXT NOT NULL,
+                percent TEXT NOT NULL,
+                PRIMARY KEY (coin, team_id, wallet_address)
+            );
+
+            CREATE TABLE IF NOT EXISTS team_redirects (
+                coin TEXT NOT NULL,
+                wallet_address TEXT NOT NULL,
+                team_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (coin, wallet_address)
+            );
+
+            CREATE TABLE IF NOT EXISTS consolidations (
+                id TEXT PRIMARY KEY,
+                coin TEXT NOT NULL,
+                tx_hashes TEXT NOT NULL,
+                outputs INTEGER NOT NULL,
+                amount TEXT NOT NULL,
+                fee TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                confirmed_at TEXT,
+                error_message TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_consolidations_status ON consolidations(coin, status);
+
+            CREATE TABLE IF NOT EXISTS job_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                job TEXT NOT NULL,
+                coin TEXT NOT NULL,
+                trigger TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL,
+                error TEXT,
+                items INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(coin, job, started_at);
+            CREATE INDEX IF NOT EXISTS idx_job_runs_started ON job_runs(started_at);
+
+            CREATE TABLE IF NOT EXISTS events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                coin TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                data TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_events_created ON events(created_at);
+
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                webhook TEXT NOT NULL,
+                event_id INTEGER NOT NULL,
+                event_type TEXT NOT NULL,
+                body TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT NOT NULL,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                delivered_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+
+            -- Last event queued for webhooks, so restarts pick up where they left off
+            CREATE TABLE IF NOT EXISTS webhook_cursor (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                event_id INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS pool_stats (
+                coin TEXT PRIMARY KEY,
                 total_shares INTEGER NOT NULL DEFAULT 0,
                 total_blocks INTEGER NOT NULL DEFAULT 0,
                 total_paid TEXT NOT NULL DEFAULT '0',
@@ -236,6 +1279,87 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
+        // Columns added after the initial schema
+        self.add_column_if_missing("blocks", "reward_status", "TEXT NOT NULL DEFAULT 'reported'")
+            .await?;
+        self.add_column_if_missing("blocks", "reported_reward", "TEXT").await?;
+        self.add_column_if_missing("blocks", "coinbase_tx", "TEXT").await?;
+        self.add_column_if_missing("balances", "carry_balance", "TEXT NOT NULL DEFAULT '0'")
+            .await?;
+        self.add_column_if_missing("balances", "abandoned_balance", "TEXT NOT NULL DEFAULT '0'")
+            .await?;
+        self.add_column_if_missing("payments", "approval_id", "TEXT").await?;
+        self.add_column_if_missing("payments", "tx_key", "TEXT").await?;
+
+        self.seed_opening_balances().await?;
+
+        Ok(())
+    }
+
+    /// Add a column to an existing table unless it is already there
+    async fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let (exists,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?",
+        )
+        .bind(table)
+        .bind(column)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if exists == 0 {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Record existing balances as opening ledger entries
+    ///
+    /// Only runs while the ledger is empty, so databases created before
+    /// ledger tracking start out consistent.
+    async fn seed_opening_balances(&self) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+
+        let (entries,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM ledger_entries")
+            .fetch_one(&self.pool)
+            .await?;
+        if entries > 0 {
+            return Ok(());
+        }
+
+        let rows = sqlx::query_as::<_, (String, String, String, String)>(
+            "SELECT wallet_address, coin, pending_balance, total_paid FROM balances",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        for (wallet_address, coin, pending, paid) in rows {
+            let amount = parse_amount(&pending) + parse_amount(&paid);
+            if amount.is_zero() {
+                continue;
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO ledger_entries (coin, wallet_address, entry_type, amount, memo, created_at)
+                VALUES (?, ?, ?, ?, 'balance before ledger tracking', ?)
+                "#,
+            )
+            .bind(&coin)
+            .bind(&wallet_address)
+            .bind(LedgerEntryType::OpeningBalance.as_str())
+            .bind(amount.normalize().to_string())
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
         Ok(())
     }
 
@@ -283,357 +1407,1890 @@ impl Database {
                 last_share = excluded.last_share
             "#,
         )
-        .bind(wallet_address)
-        .bind(&coin_str)
-        .bind(&timestamp_str)
-        .execute(&self.pool)
+        .bind(wallet_address)
+        .bind(&coin_str)
+        .bind(&timestamp_str)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Record a block found (serialized write)
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_block(
+        &self,
+        coin: Coin,
+        block_height: i64,
+        block_hash: &str,
+        reward: Decimal,
+        finder_wallet: &str,
+        finder_worker: &str,
+        reward_status: RewardStatus,
+    ) -> Result<i64> {
+        let _lock = self.write_lock.lock().await;
+        let now = Utc::now();
+        let coin_str = coin.to_string();
+        let reward_str = reward.to_string();
+        let timestamp_str = now.to_rfc3339();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO blocks (coin, block_height, block_hash, reward, finder_wallet, finder_worker, timestamp, reward_status)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(block_hash) DO NOTHING
+            "#,
+        )
+        .bind(&coin_str)
+        .bind(block_height)
+        .bind(block_hash)
+        .bind(&reward_str)
+        .bind(finder_wallet)
+        .bind(finder_worker)
+        .bind(&timestamp_str)
+        .bind(reward_status.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Get undistributed blocks for a coin
+    pub async fn get_undistributed_blocks(&self, coin: Coin) -> Result<Vec<BlockFound>> {
+        let coin_str = coin.to_string();
+
+        let rows = sqlx::query_as::<_, BlockRow>(
+            r#"
+            SELECT id, coin, block_height, block_hash, reward, finder_wallet, finder_worker, timestamp, distributed,
+                   reward_status, reported_reward, coinbase_tx
+            FROM blocks
+            WHERE coin = ? AND distributed = 0 AND reward_status IN ('reported', 'verified')
+            ORDER BY block_height ASC
+            "#,
+        )
+        .bind(&coin_str)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| block_from_row(row, coin)).collect())
+    }
+
+    /// Get undistributed blocks whose coinbase still needs to be found in the wallet
+    ///
+    /// Includes blocks already flagged missing that were found after `recheck_since`,
+    /// in case the wallet was behind when they were flagged.
+    pub async fn get_blocks_awaiting_coinbase(
+        &self,
+        coin: Coin,
+        recheck_since: DateTime<Utc>,
+    ) -> Result<Vec<BlockFound>> {
+        let rows = sqlx::query_as::<_, BlockRow>(
+            r#"
+            SELECT id, coin, block_height, block_hash, reward, finder_wallet, finder_worker, timestamp, distributed,
+                   reward_status, reported_reward, coinbase_tx
+            FROM blocks
+            WHERE coin = ?1 AND distributed = 0
+              AND (reward_status = 'awaiting_coinbase'
+                   OR (reward_status = 'missing' AND timestamp >= ?2))
+            ORDER BY block_height ASC
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(recheck_since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| block_from_row(row, coin)).collect())
+    }
+
+    /// Record the coinbase received by the pool wallet for a block (serialized write)
+    ///
+    /// The block's reward becomes the amount actually received; the pool's
+    /// figure is kept in `reported_reward`.
+    pub async fn set_block_coinbase(
+        &self,
+        block_id: i64,
+        amount: Decimal,
+        coinbase_tx: &str,
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+
+        sqlx::query(
+            r#"
+            UPDATE blocks
+            SET reported_reward = COALESCE(reported_reward, reward),
+                reward = ?, coinbase_tx = ?, reward_status = ?
+            WHERE id = ? AND distributed = 0
+            "#,
+        )
+        .bind(amount.normalize().to_string())
+        .bind(coinbase_tx)
+        .bind(RewardStatus::Verified.as_str())
+        .bind(block_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Set a block's reward status (serialized write)
+    pub async fn set_block_reward_status(&self, block_id: i64, status: RewardStatus) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+
+        sqlx::query("UPDATE blocks SET reward_status = ? WHERE id = ?")
+            .bind(status.as_str())
+            .bind(block_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Count blocks for a coin grouped by reward status
+    pub async fn count_blocks_by_reward_status(&self, coin: Coin) -> Result<Vec<(RewardStatus, i64)>> {
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT reward_status, COUNT(*)
+            FROM blocks
+            WHERE coin = ?
+            GROUP BY reward_status
+            "#,
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(status, count)| (RewardStatus::from_db(&status), count))
+            .collect())
+    }
+
+    /// Get all blocks for a coin, oldest first
+    pub async fn get_blocks(&self, coin: Coin) -> Result<Vec<BlockFound>> {
+        let rows = sqlx::query_as::<_, BlockRow>(
+            r#"
+            SELECT id, coin, block_height, block_hash, reward, finder_wallet, finder_worker, timestamp, distributed,
+                   reward_status, reported_reward, coinbase_tx
+            FROM blocks
+            WHERE coin = ?
+            ORDER BY block_height ASC
+            "#,
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| block_from_row(row, coin)).collect())
+    }
+
+    /// Get the blocks recorded at a height (more than one after a reorg)
+    pub async fn get_blocks_at_height(&self, coin: Coin, height: i64) -> Result<Vec<BlockFound>> {
+        let rows = sqlx::query_as::<_, BlockRow>(
+            r#"
+            SELECT id, coin, block_height, block_hash, reward, finder_wallet, finder_worker, timestamp, distributed,
+                   reward_status, reported_reward, coinbase_tx
+            FROM blocks
+            WHERE coin = ? AND block_height = ?
+            ORDER BY id ASC
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(height)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| block_from_row(row, coin)).collect())
+    }
+
+    /// Credit a block's reward to miners and mark it distributed (serialized write)
+    ///
+    /// Runs in a single transaction so a block is never half distributed.
+    pub async fn apply_block_distribution(
+        &self,
+        block: &BlockFound,
+        credits: &[BlockCredit],
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        record_block_credits(&mut tx, block, credits, None).await?;
+
+        sqlx::query("UPDATE blocks SET distributed = 1 WHERE id = ?")
+            .bind(block.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Reverse a block's recorded credits and apply new ones (serialized write)
+    ///
+    /// Existing credits are netted per miner and reversed with
+    /// `block_reversal` entries, then the new credits are written. Miners who
+    /// were already paid can end up with a negative pending balance, which
+    /// is deducted from their next rewards.
+    pub async fn redistribute_block(
+        &self,
+        block: &BlockFound,
+        credits: &[BlockCredit],
+        memo: &str,
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        let existing = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT wallet_address, entry_type, amount FROM ledger_entries WHERE block_id = ?",
+        )
+        .bind(block.id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // Reward credits and carries are reversed separately, as they move different balances
+        let mut net: Vec<(String, bool, Decimal)> = Vec::new();
+        for (wallet_address, entry_type, amount) in existing {
+            let amount = parse_amount(&amount);
+            let is_carry = LedgerEntryType::from_db(&entry_type) == Some(LedgerEntryType::RoundingCarry);
+            match net
+                .iter_mut()
+                .find(|(w, c, _)| *w == wallet_address && *c == is_carry)
+            {
+                Some((_, _, total)) => *total += amount,
+                None => net.push((wallet_address, is_carry, amount)),
+            }
+        }
+
+        for (wallet_address, is_carry, amount) in net.iter().filter(|(_, _, a)| !a.is_zero()) {
+            let entry_type = if *is_carry {
+                LedgerEntryType::RoundingCarry
+            } else {
+                LedgerEntryType::BlockReversal
+            };
+
+            record_credit(
+                &mut tx,
+                LedgerCredit {
+                    coin: block.coin,
+                    wallet_address,
+                    entry_type,
+                    amount: -*amount,
+                    block_id: Some(block.id),
+                    memo: Some(memo),
+                },
+            )
+            .await?;
+        }
+
+        record_block_credits(&mut tx, block, credits, Some(memo)).await?;
+
+        sqlx::query("UPDATE blocks SET distributed = 1 WHERE id = ?")
+            .bind(block.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Undo every block credit for a coin and mark its blocks undistributed (serialized write)
+    ///
+    /// Deletes the block reward, reversal and carry ledger entries and takes
+    /// them back out of the balances. Only meant for a scratch copy made with
+    /// `copy_to`, before replaying distribution.
+    pub async fn clear_block_credits(&self, coin: Coin) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        let existing = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT wallet_address, entry_type, amount FROM ledger_entries WHERE coin = ? AND block_id IS NOT NULL",
+        )
+        .bind(coin.to_string())
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut net: HashMap<(String, bool), Decimal> = HashMap::new();
+        for (wallet_address, entry_type, amount) in existing {
+            let is_carry = LedgerEntryType::from_db(&entry_type) == Some(LedgerEntryType::RoundingCarry);
+            *net.entry((wallet_address, is_carry)).or_default() += parse_amount(&amount);
+        }
+
+        for ((wallet_address, is_carry), amount) in net.iter().filter(|(_, a)| !a.is_zero()) {
+            if *is_carry {
+                adjust_carry(&mut tx, coin, wallet_address, -*amount).await?;
+            } else {
+                adjust_balance(&mut tx, coin, wallet_address, -*amount, Decimal::ZERO, None).await?;
+            }
+        }
+
+        sqlx::query("DELETE FROM ledger_entries WHERE coin = ? AND block_id IS NOT NULL")
+            .bind(coin.to_string())
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE blocks SET distributed = 0 WHERE coin = ?")
+            .bind(coin.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get the ledger entries recorded for a block
+    pub async fn get_block_ledger(&self, block: &BlockFound) -> Result<Vec<LedgerEntry>> {
+        let rows = sqlx::query_as::<_, LedgerRow>(
+            r#"
+            SELECT id, coin, wallet_address, entry_type, amount, block_id, payment_id, memo, created_at
+            FROM ledger_entries
+            WHERE block_id = ?
+            ORDER BY id ASC
+            "#,
+        )
+        .bind(block.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| ledger_from_row(row, block.coin))
+            .collect())
+    }
+
+    /// Get all ledger entries for a coin
+    pub async fn get_ledger_entries(&self, coin: Coin) -> Result<Vec<LedgerEntry>> {
+        let rows = sqlx::query_as::<_, LedgerRow>(
+            r#"
+            SELECT id, coin, wallet_address, entry_type, amount, block_id, payment_id, memo, created_at
+            FROM ledger_entries
+            WHERE coin = ?
+            ORDER BY id ASC
+            "#,
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| ledger_from_row(row, coin))
+            .collect())
+    }
+
+    /// Get a coin's ledger entries created in `[from, to)`
+    pub async fn get_ledger_entries_between(
+        &self,
+        coin: Coin,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LedgerEntry>> {
+        let rows = sqlx::query_as::<_, LedgerRow>(
+            r#"
+            SELECT id, coin, wallet_address, entry_type, amount, block_id, payment_id, memo, created_at
+            FROM ledger_entries
+            WHERE coin = ? AND created_at >= ? AND created_at < ?
+            ORDER BY id ASC
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| ledger_from_row(row, coin))
+            .collect())
+    }
+
+    /// Get all balances above minimum payout threshold
+    pub async fn get_payable_balances(
+        &self,
+        coin: Coin,
+        min_payout: Decimal,
+    ) -> Result<Vec<MinerBalance>> {
+        let coin_str = coin.to_string();
+        let min_str = min_payout.to_string();
+
+        let rows = sqlx::query_as::<_, BalanceRow>(
+            r#"
+            SELECT wallet_address, coin, pending_balance, total_paid, total_shares, last_share, last_payment, carry_balance, abandoned_balance
+            FROM balances
+            WHERE coin = ? AND CAST(pending_balance AS REAL) >= CAST(? AS REAL)
+            "#,
+        )
+        .bind(&coin_str)
+        .bind(&min_str)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| balance_from_row(row, coin)).collect())
+    }
+
+    /// Total owed to miners for a coin: positive pending, carry and abandoned balances
+    pub async fn get_liabilities(&self, coin: Coin) -> Result<Decimal> {
+        let rows = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT pending_balance, carry_balance, abandoned_balance FROM balances WHERE coin = ?",
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|(pending, carry, abandoned)| {
+                [pending, carry, abandoned]
+                    .into_iter()
+                    .map(|amount| parse_amount(amount).max(Decimal::ZERO))
+                    .sum::<Decimal>()
+            })
+            .sum())
+    }
+
+    /// Balances still owed to miners without a share since `since`
+    ///
+    /// Includes miners with no share on record. Largest liability first.
+    pub async fn get_dormant_balances(&self, coin: Coin, since: DateTime<Utc>) -> Result<Vec<MinerBalance>> {
+        let rows = sqlx::query_as::<_, BalanceRow>(
+            r#"
+            SELECT wallet_address, coin, pending_balance, total_paid, total_shares, last_share, last_payment, carry_balance, abandoned_balance
+            FROM balances
+            WHERE coin = ?
+              AND (last_share IS NULL OR last_share < ?)
+              AND (pending_balance != '0' OR abandoned_balance != '0')
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut balances: Vec<MinerBalance> = rows.into_iter().map(|row| balance_from_row(row, coin)).collect();
+        balances.sort_by_key(|b| std::cmp::Reverse(b.pending_balance + b.abandoned_balance));
+        Ok(balances)
+    }
+
+    /// Move a miner's pending balance to the abandoned account (serialized write)
+    pub async fn abandon_balance(
+        &self,
+        coin: Coin,
+        wallet_address: &str,
+        amount: Decimal,
+        memo: &str,
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        record_credit(
+            &mut tx,
+            LedgerCredit {
+                coin,
+                wallet_address,
+                entry_type: LedgerEntryType::Abandoned,
+                amount: -amount,
+                block_id: None,
+                memo: Some(memo),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Move a miner's whole abandoned balance back to pending (serialized write)
+    ///
+    /// Returns the amount reclaimed (zero if nothing was abandoned).
+    pub async fn reclaim_abandoned(&self, coin: Coin, wallet_address: &str, memo: &str) -> Result<Decimal> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        let abandoned = sqlx::query_as::<_, (String,)>(
+            "SELECT abandoned_balance FROM balances WHERE wallet_address = ? AND coin = ?",
+        )
+        .bind(wallet_address)
+        .bind(coin.to_string())
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|(amount,)| parse_amount(&amount))
+        .unwrap_or_default();
+
+        if abandoned <= Decimal::ZERO {
+            return Ok(Decimal::ZERO);
+        }
+
+        record_credit(
+            &mut tx,
+            LedgerCredit {
+                coin,
+                wallet_address,
+                entry_type: LedgerEntryType::Abandoned,
+                amount: abandoned,
+                block_id: None,
+                memo: Some(memo),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(abandoned)
+    }
+
+    /// Record a sweep of excess hot wallet funds to cold storage (serialized write)
+    pub async fn record_cold_sweep(
+        &self,
+        coin: Coin,
+        cold_address: &str,
+        amount: Decimal,
+        tx_hash: &str,
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+
+        sqlx::query(
+            r#"
+            INSERT INTO ledger_entries (coin, wallet_address, entry_type, amount, memo, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(cold_address)
+        .bind(LedgerEntryType::ColdSweep.as_str())
+        .bind(amount.normalize().to_string())
+        .bind(format!("tx {}", tx_hash))
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get every miner's carry balance for a coin
+    pub async fn get_carry_balances(&self, coin: Coin) -> Result<HashMap<String, Decimal>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT wallet_address, carry_balance FROM balances WHERE coin = ? AND carry_balance != '0'",
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(wallet_address, carry)| (wallet_address, parse_amount(&carry)))
+            .collect())
+    }
+
+    /// Get a miner's balance for a specific coin
+    pub async fn get_miner_balance(
+        &self,
+        coin: Coin,
+        wallet_address: &str,
+    ) -> Result<Option<MinerBalance>> {
+        let coin_str = coin.to_string();
+
+        let row = sqlx::query_as::<_, BalanceRow>(
+            r#"
+            SELECT wallet_address, coin, pending_balance, total_paid, total_shares, last_share, last_payment, carry_balance, abandoned_balance
+            FROM balances
+            WHERE coin = ? AND wallet_address = ?
+            "#,
+        )
+        .bind(&coin_str)
+        .bind(wallet_address)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| balance_from_row(row, coin)))
+    }
+
+    /// Get all balance rows for a coin
+    pub async fn get_all_balances(&self, coin: Coin) -> Result<Vec<MinerBalance>> {
+        let rows = sqlx::query_as::<_, BalanceRow>(
+            r#"
+            SELECT wallet_address, coin, pending_balance, total_paid, total_shares, last_share, last_payment, carry_balance, abandoned_balance
+            FROM balances
+            WHERE coin = ?
+            "#,
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| balance_from_row(row, coin)).collect())
+    }
+
+    /// Create a payment record (serialized write)
+    pub async fn create_payment(
+        &self,
+        coin: Coin,
+        wallet_address: &str,
+        amount: Decimal,
+    ) -> Result<String> {
+        let _lock = self.write_lock.lock().await;
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let coin_str = coin.to_string();
+        let amount_str = amount.to_string();
+        let timestamp_str = now.to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO payments (id, coin, wallet_address, amount, status, created_at)
+            VALUES (?, ?, ?, ?, 'pending', ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&coin_str)
+        .bind(wallet_address)
+        .bind(&amount_str)
+        .bind(&timestamp_str)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Record the transaction of a payment that was broadcast (serialized write)
+    pub async fn mark_payment_sent(
+        &self,
+        payment_id: &str,
+        tx_hash: &str,
+        tx_key: Option<&str>,
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+
+        sqlx::query("UPDATE payments SET status = ?, tx_hash = ?, tx_key = ? WHERE id = ?")
+            .bind(PaymentStatus::Processing.as_str())
+            .bind(tx_hash)
+            .bind(tx_key)
+            .bind(payment_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Store a payment proof key looked up after sending (serialized write)
+    pub async fn set_payment_tx_key(&self, payment_id: &str, tx_key: &str) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+
+        sqlx::query("UPDATE payments SET tx_key = ? WHERE id = ?")
+            .bind(tx_key)
+            .bind(payment_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Update payment status (serialized write)
+    pub async fn update_payment_status(
+        &self,
+        payment_id: &str,
+        status: PaymentStatus,
+        tx_hash: Option<&str>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let status_str = status.as_str();
+
+        let confirmed_at = if status == PaymentStatus::Confirmed {
+            Some(Utc::now().to_rfc3339())
+        } else {
+            None
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE payments
+            SET status = ?, tx_hash = COALESCE(?, tx_hash), confirmed_at = ?, error_message = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(status_str)
+        .bind(tx_hash)
+        .bind(&confirmed_at)
+        .bind(error_message)
+        .bind(payment_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // If confirmed, move the amount from pending to paid
+        if status == PaymentStatus::Confirmed {
+            let payment = sqlx::query_as::<_, (String, String, String)>(
+                "SELECT coin, wallet_address, amount FROM payments WHERE id = ?",
+            )
+            .bind(payment_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some((coin, wallet_address, amount)) = payment {
+                let amount = parse_amount(&amount);
+                adjust_balance(
+                    &mut tx,
+                    coin.parse()?,
+                    &wallet_address,
+                    -amount,
+                    amount,
+                    confirmed_at.as_deref(),
+                )
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get pending payments for a coin
+    pub async fn get_pending_payments(&self, coin: Coin) -> Result<Vec<Payment>> {
+        let coin_str = coin.to_string();
+
+        let rows = sqlx::query_as::<_, PaymentRow>(
+            r#"
+            SELECT id, coin, wallet_address, amount, tx_hash, status, created_at, confirmed_at, error_message, tx_key
+            FROM payments
+            WHERE coin = ? AND status IN ('pending', 'processing')
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(&coin_str)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| payment_from_row(row, coin)).collect())
+    }
+
+    /// Get a payment by ID
+    pub async fn get_payment(&self, payment_id: &str) -> Result<Option<Payment>> {
+        let row = sqlx::query_as::<_, PaymentRow>(
+            r#"
+            SELECT id, coin, wallet_address, amount, tx_hash, status, created_at, confirmed_at, error_message, tx_key
+            FROM payments
+            WHERE id = ?
+            "#,
+        )
+        .bind(payment_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| payment_from_row(row, Coin::Xmr)))
+    }
+
+    /// List payments, newest first, with optional filters
+    pub async fn list_payments(
+        &self,
+        coin: Coin,
+        status: Option<PaymentStatus>,
+        wallet_address: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Payment>> {
+        let status_str = status.map(|s| s.as_str());
+
+        let rows = sqlx::query_as::<_, PaymentRow>(
+            r#"
+            SELECT id, coin, wallet_address, amount, tx_hash, status, created_at, confirmed_at, error_message, tx_key
+            FROM payments
+            WHERE coin = ?1
+              AND (?2 IS NULL OR status = ?2)
+              AND (?3 IS NULL OR wallet_address = ?3)
+            ORDER BY created_at DESC
+            LIMIT ?4
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(status_str)
+        .bind(wallet_address)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| payment_from_row(row, coin)).collect())
+    }
+
+    /// Set a payment's status after manual investigation (serialized write)
+    ///
+    /// Moving a payment into or out of `confirmed` moves its amount between
+    /// the miner's pending balance and total paid, the same as the processor
+    /// does when it confirms a payment.
+    pub async fn override_payment_status(
+        &self,
+        payment: &Payment,
+        status: PaymentStatus,
+        tx_hash: Option<&str>,
+        note: Option<&str>,
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        let confirmed_at = (status == PaymentStatus::Confirmed).then_some(now.as_str());
+
+        sqlx::query(
+            r#"
+            UPDATE payments
+            SET status = ?, tx_hash = COALESCE(?, tx_hash), confirmed_at = ?, error_message = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(tx_hash)
+        .bind(confirmed_at)
+        .bind(note)
+        .bind(&payment.id)
+        .execute(&mut *tx)
+        .await?;
+
+        let was_confirmed = payment.status == PaymentStatus::Confirmed;
+        let is_confirmed = status == PaymentStatus::Confirmed;
+
+        if is_confirmed && !was_confirmed {
+            adjust_balance(
+                &mut tx,
+                payment.coin,
+                &payment.wallet_address,
+                -payment.amount,
+                payment.amount,
+                Some(&now),
+            )
+            .await?;
+        } else if was_confirmed && !is_confirmed {
+            adjust_balance(
+                &mut tx,
+                payment.coin,
+                &payment.wallet_address,
+                payment.amount,
+                -payment.amount,
+                None,
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Pause payouts for a coin, or for all coins with None
+    ///
+    /// An existing pause for the same scope keeps its original reason.
+    pub async fn set_payout_pause(&self, coin: Option<Coin>, reason: &str, source: &str) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let scope = coin.map(|c| c.to_string()).unwrap_or_else(|| PAUSE_ALL.to_string());
+
+        sqlx::query(
+            r#"
+            INSERT INTO payout_pauses (scope, reason, source, paused_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(scope) DO NOTHING
+            "#,
+        )
+        .bind(scope)
+        .bind(reason)
+        .bind(source)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lift a pause; returns false if the scope wasn't paused
+    pub async fn clear_payout_pause(&self, coin: Option<Coin>) -> Result<bool> {
+        let _lock = self.write_lock.lock().await;
+        let scope = coin.map(|c| c.to_string()).unwrap_or_else(|| PAUSE_ALL.to_string());
+
+        let result = sqlx::query("DELETE FROM payout_pauses WHERE scope = ?")
+            .bind(scope)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// All active pauses, global first
+    pub async fn list_payout_pauses(&self) -> Result<Vec<PayoutPause>> {
+        let rows = sqlx::query_as::<_, (String, String, String, String)>(
+            r#"
+            SELECT scope, reason, source, paused_at
+            FROM payout_pauses
+            ORDER BY scope = ? DESC, scope ASC
+            "#,
+        )
+        .bind(PAUSE_ALL)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(scope, reason, source, paused_at)| PayoutPause {
+                coin: scope.parse().ok(),
+                reason,
+                source,
+                paused_at: parse_timestamp(&paused_at).unwrap_or_else(Utc::now),
+            })
+            .collect())
+    }
+
+    /// The pause that stops a coin's payouts, if any (global first)
+    pub async fn get_payout_pause(&self, coin: Coin) -> Result<Option<PayoutPause>> {
+        Ok(self
+            .list_payout_pauses()
+            .await?
+            .into_iter()
+            .find(|p| p.coin.is_none_or(|c| c == coin)))
+    }
+
+    /// All teams for a coin, by id
+    pub async fn get_teams(&self, coin: Coin) -> Result<Vec<Team>> {
+        let teams = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT id, created_at, updated_at FROM teams WHERE coin = ? ORDER BY id ASC",
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let members = sqlx::query_as::<_, (String, String, String)>(
+            r#"
+            SELECT team_id, wallet_address, percent
+            FROM team_members
+            WHERE coin = ?
+            ORDER BY CAST(percent AS REAL) DESC, wallet_address ASC
+            "#,
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(teams
+            .into_iter()
+            .map(|(id, created_at, updated_at)| Team {
+                coin,
+                members: members
+                    .iter()
+                    .filter(|(team_id, _, _)| *team_id == id)
+                    .map(|(_, wallet_address, percent)| TeamMember {
+                        wallet_address: wallet_address.clone(),
+                        percent: parse_amount(percent),
+                    })
+                    .collect(),
+                id,
+                created_at: parse_timestamp(&created_at).unwrap_or_else(Utc::now),
+                updated_at: parse_timestamp(&updated_at).unwrap_or_else(Utc::now),
+            })
+            .collect())
+    }
+
+    /// Create a team or replace its members (serialized write)
+    ///
+    /// Members are not checked here; see `distribution::check_team_members`.
+    pub async fn set_team(&self, coin: Coin, id: &str, members: &[TeamMember]) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO teams (coin, id, created_at, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(coin, id) DO UPDATE SET updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(id)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM team_members WHERE coin = ? AND team_id = ?")
+            .bind(coin.to_string())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        for member in members {
+            sqlx::query(
+                "INSERT INTO team_members (coin, team_id, wallet_address, percent) VALUES (?, ?, ?, ?)",
+            )
+            .bind(coin.to_string())
+            .bind(id)
+            .bind(&member.wallet_address)
+            .bind(member.percent.to_string())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Delete a team and the redirects to it; returns false if it didn't exist
+    pub async fn delete_team(&self, coin: Coin, id: &str) -> Result<bool> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        for table in ["team_members", "team_redirects"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE coin = ? AND team_id = ?", table))
+                .bind(coin.to_string())
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let result = sqlx::query("DELETE FROM teams WHERE coin = ? AND id = ?")
+            .bind(coin.to_string())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Miner addresses redirected to a team, as address -> team id
+    pub async fn get_team_redirects(&self, coin: Coin) -> Result<HashMap<String, String>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT wallet_address, team_id FROM team_redirects WHERE coin = ?",
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Send a miner's rewards to a team (serialized write)
+    pub async fn set_team_redirect(&self, coin: Coin, wallet_address: &str, team_id: &str) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+
+        let exists = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM teams WHERE coin = ? AND id = ?")
+            .bind(coin.to_string())
+            .bind(team_id)
+            .fetch_one(&self.pool)
+            .await?;
+        if exists.0 == 0 {
+            anyhow::bail!("No {} team '{}'", coin, team_id);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO team_redirects (coin, wallet_address, team_id, created_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(coin, wallet_address) DO UPDATE SET
+                team_id = excluded.team_id,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(wallet_address)
+        .bind(team_id)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stop redirecting a miner; returns false if it wasn't redirected
+    pub async fn clear_team_redirect(&self, coin: Coin, wallet_address: &str) -> Result<bool> {
+        let _lock = self.write_lock.lock().await;
+
+        let result = sqlx::query("DELETE FROM team_redirects WHERE coin = ? AND wallet_address = ?")
+            .bind(coin.to_string())
+            .bind(wallet_address)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Total of payments sent (or being sent) since a time
+    pub async fn get_paid_since(&self, coin: Coin, since: DateTime<Utc>) -> Result<Decimal> {
+        let rows = sqlx::query_as::<_, (String,)>(
+            r#"
+            SELECT amount
+            FROM payments
+            WHERE coin = ? AND status IN ('pending', 'processing', 'confirmed') AND created_at >= ?
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|(amount,)| parse_amount(amount)).sum())
+    }
+
+    /// Amounts already committed to unfinished payments, per miner
+    ///
+    /// Pending balances are only reduced once a payment confirms, so these
+    /// must be subtracted before paying a balance again.
+    pub async fn get_reserved_amounts(&self, coin: Coin) -> Result<HashMap<String, Decimal>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT wallet_address, amount
+            FROM payments
+            WHERE coin = ? AND status IN ('pending_approval', 'pending', 'processing')
+            "#,
+        )
+        .bind(coin.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut reserved: HashMap<String, Decimal> = HashMap::new();
+        for (wallet_address, amount) in rows {
+            *reserved.entry(wallet_address).or_default() += parse_amount(&amount);
+        }
+
+        Ok(reserved)
+    }
+
+    /// Sum of payment amounts per status for a coin
+    pub async fn get_payment_totals(&self, coin: Coin) -> Result<HashMap<PaymentStatus, Decimal>> {
+        let rows = sqlx::query_as::<_, (String, String)>("SELECT status, amount FROM payments WHERE coin = ?")
+            .bind(coin.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut totals: HashMap<PaymentStatus, Decimal> = HashMap::new();
+        for (status, amount) in rows {
+            *totals.entry(PaymentStatus::from_db(&status)).or_default() += parse_amount(&amount);
+        }
+
+        Ok(totals)
+    }
+
+    /// Hold a payout for operator approval (serialized write)
+    ///
+    /// Writes the approval and a `pending_approval` payment per recipient in
+    /// one transaction, which reserves the amounts until it is decided.
+    pub async fn create_payout_approval(
+        &self,
+        coin: Coin,
+        payments: &[(String, Decimal)],
+        tx_hash: Option<&str>,
+        tx_key: Option<&str>,
+        tx_metadata: Option<&str>,
+        fee: Option<Decimal>,
+    ) -> Result<String> {
+        let _lock = self.write_lock.lock().await;
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+        let total: Decimal = payments.iter().map(|(_, amount)| *amount).sum();
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO payout_approvals (id, coin, status, total, fee, tx_hash, tx_metadata, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(coin.to_string())
+        .bind(ApprovalStatus::PendingApproval.as_str())
+        .bind(total.normalize().to_string())
+        .bind(fee.map(|f| f.normalize().to_string()))
+        .bind(tx_hash)
+        .bind(tx_metadata)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        for (wallet_address, amount) in payments {
+            sqlx::query(
+                r#"
+                INSERT INTO payments (id, coin, wallet_address, amount, tx_hash, tx_key, status, created_at, approval_id)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(coin.to_string())
+            .bind(wallet_address)
+            .bind(amount.normalize().to_string())
+            .bind(tx_hash)
+            .bind(tx_key)
+            .bind(PaymentStatus::PendingApproval.as_str())
+            .bind(&now)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// Get a payout approval with its payments
+    pub async fn get_payout_approval(&self, id: &str) -> Result<Option<PayoutApproval>> {
+        let row = sqlx::query_as::<_, ApprovalRow>(
+            r#"
+            SELECT id, coin, status, total, fee, tx_hash, tx_metadata, created_at, decided_at, note
+            FROM payout_approvals
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => {
+                let payments = self.get_approval_payments(&row.0).await?;
+                Ok(Some(approval_from_row(row, payments)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get a coin's payout approvals, newest first
+    pub async fn list_payout_approvals(
+        &self,
+        coin: Coin,
+        status: Option<ApprovalStatus>,
+        limit: i64,
+    ) -> Result<Vec<PayoutApproval>> {
+        let rows = sqlx::query_as::<_, ApprovalRow>(
+            r#"
+            SELECT id, coin, status, total, fee, tx_hash, tx_metadata, created_at, decided_at, note
+            FROM payout_approvals
+            WHERE coin = ?1 AND (?2 IS NULL OR status = ?2)
+            ORDER BY created_at DESC
+            LIMIT ?3
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(status.map(|s| s.as_str()))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut approvals = Vec::with_capacity(rows.len());
+        for row in rows {
+            let payments = self.get_approval_payments(&row.0).await?;
+            approvals.push(approval_from_row(row, payments));
+        }
+
+        Ok(approvals)
+    }
+
+    async fn get_approval_payments(&self, approval_id: &str) -> Result<Vec<Payment>> {
+        let rows = sqlx::query_as::<_, PaymentRow>(
+            r#"
+            SELECT id, coin, wallet_address, amount, tx_hash, status, created_at, confirmed_at, error_message, tx_key
+            FROM payments
+            WHERE approval_id = ?
+            ORDER BY wallet_address ASC
+            "#,
+        )
+        .bind(approval_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| payment_from_row(row, Coin::Xmr))
+            .collect())
+    }
+
+    /// Approve or reject a payout awaiting approval (serialized write)
+    ///
+    /// Returns false if the payout is no longer awaiting approval. Rejecting
+    /// fails its payments, which releases the reserved balances.
+    pub async fn decide_payout_approval(
+        &self,
+        id: &str,
+        approve: bool,
+        note: Option<&str>,
+    ) -> Result<bool> {
+        let _lock = self.write_lock.lock().await;
+        let now = Utc::now().to_rfc3339();
+        let status = if approve {
+            ApprovalStatus::Approved
+        } else {
+            ApprovalStatus::Rejected
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            "UPDATE payout_approvals SET status = ?, decided_at = ?, note = ? WHERE id = ? AND status = ?",
+        )
+        .bind(status.as_str())
+        .bind(&now)
+        .bind(note)
+        .bind(id)
+        .bind(ApprovalStatus::PendingApproval.as_str())
+        .execute(&mut *tx)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if !approve {
+            let reason = match note {
+                Some(note) => format!("rejected by operator: {}", note),
+                None => "rejected by operator".to_string(),
+            };
+
+            sqlx::query("UPDATE payments SET status = ?, error_message = ? WHERE approval_id = ?")
+                .bind(PaymentStatus::Failed.as_str())
+                .bind(reason)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Whether a coin has a payout awaiting approval or relay
+    pub async fn has_open_payout_approval(&self, coin: Coin) -> Result<bool> {
+        let (open,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM payout_approvals WHERE coin = ? AND status IN (?, ?)",
+        )
+        .bind(coin.to_string())
+        .bind(ApprovalStatus::PendingApproval.as_str())
+        .bind(ApprovalStatus::Approved.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(open > 0)
+    }
+
+    /// Record that an approved payout was broadcast (serialized write)
+    ///
+    /// `sent` holds each paid address with its transaction hash and proof
+    /// key; payments whose address is missing from it are failed.
+    pub async fn mark_payout_relayed(
+        &self,
+        id: &str,
+        sent: &[(String, String, Option<String>)],
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        let payments = sqlx::query_as::<_, (String, String)>(
+            "SELECT id, wallet_address FROM payments WHERE approval_id = ?",
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for (payment_id, wallet_address) in payments {
+            let sent_tx = sent.iter().find(|(address, _, _)| *address == wallet_address);
+
+            let (status, error) = match sent_tx {
+                Some(_) => (PaymentStatus::Processing, None),
+                None => (PaymentStatus::Failed, Some("not sent when relaying approved payout")),
+            };
+
+            sqlx::query(
+                r#"
+                UPDATE payments
+                SET status = ?, tx_hash = COALESCE(?, tx_hash), tx_key = COALESCE(?, tx_key), error_message = ?
+                WHERE id = ?
+                "#,
+            )
+            .bind(status.as_str())
+            .bind(sent_tx.map(|(_, tx_hash, _)| tx_hash))
+            .bind(sent_tx.and_then(|(_, _, tx_key)| tx_key.as_deref()))
+            .bind(error)
+            .bind(&payment_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query("UPDATE payout_approvals SET status = ? WHERE id = ?")
+            .bind(ApprovalStatus::Relayed.as_str())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Record that an approved payout could not be relayed (serialized write)
+    pub async fn fail_payout_approval(&self, id: &str, error_message: &str) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE payout_approvals SET status = ?, note = ? WHERE id = ?")
+            .bind(ApprovalStatus::Failed.as_str())
+            .bind(error_message)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE payments SET status = ?, error_message = ? WHERE approval_id = ?")
+            .bind(PaymentStatus::Failed.as_str())
+            .bind(error_message)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Record a consolidation that was just sent (serialized write)
+    pub async fn record_consolidation(
+        &self,
+        coin: Coin,
+        tx_hashes: &[String],
+        outputs: u32,
+        amount: Decimal,
+        fee: Decimal,
+    ) -> Result<String> {
+        let _lock = self.write_lock.lock().await;
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query(
+            r#"
+            INSERT INTO consolidations (id, coin, tx_hashes, outputs, amount, fee, status, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(coin.to_string())
+        .bind(tx_hashes.join(","))
+        .bind(outputs as i64)
+        .bind(amount.normalize().to_string())
+        .bind(fee.normalize().to_string())
+        .bind(ConsolidationStatus::Pending.as_str())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Update a consolidation's status (serialized write)
+    pub async fn update_consolidation_status(
+        &self,
+        id: &str,
+        status: ConsolidationStatus,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+
+        let confirmed_at = (status == ConsolidationStatus::Confirmed).then(|| Utc::now().to_rfc3339());
+
+        sqlx::query(
+            "UPDATE consolidations SET status = ?, confirmed_at = ?, error_message = ? WHERE id = ?",
+        )
+        .bind(status.as_str())
+        .bind(confirmed_at)
+        .bind(error_message)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get a coin's consolidations, newest first
+    pub async fn list_consolidations(
+        &self,
+        coin: Coin,
+        status: Option<ConsolidationStatus>,
+        limit: i64,
+    ) -> Result<Vec<Consolidation>> {
+        let rows = sqlx::query_as::<_, ConsolidationRow>(
+            r#"
+            SELECT id, coin, tx_hashes, outputs, amount, fee, status, created_at, confirmed_at, error_message
+            FROM consolidations
+            WHERE coin = ?1 AND (?2 IS NULL OR status = ?2)
+            ORDER BY created_at DESC
+            LIMIT ?3
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(status.map(|s| s.as_str()))
+        .bind(limit)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(result.last_insert_rowid())
+        Ok(rows
+            .into_iter()
+            .map(|row| consolidation_from_row(row, coin))
+            .collect())
     }
 
-    /// Record a block found (serialized write)
-    pub async fn record_block(
+    /// Whether a consolidation for a coin is still waiting for confirmations
+    pub async fn has_pending_consolidation(&self, coin: Coin) -> Result<bool> {
+        Ok(!self
+            .list_consolidations(coin, Some(ConsolidationStatus::Pending), 1)
+            .await?
+            .is_empty())
+    }
+
+    /// Record a finished job run (serialized write)
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_job_run(
         &self,
+        job: &str,
         coin: Coin,
-        block_height: i64,
-        block_hash: &str,
-        reward: Decimal,
-        finder_wallet: &str,
-        finder_worker: &str,
+        trigger: &str,
+        started_at: DateTime<Utc>,
+        duration_ms: i64,
+        outcome: JobOutcome,
+        error: Option<&str>,
+        items: i64,
     ) -> Result<i64> {
         let _lock = self.write_lock.lock().await;
-        let now = Utc::now();
-        let coin_str = coin.to_string();
-        let reward_str = reward.to_string();
-        let timestamp_str = now.to_rfc3339();
 
         let result = sqlx::query(
             r#"
-            INSERT INTO blocks (coin, block_height, block_hash, reward, finder_wallet, finder_worker, timestamp)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
-            ON CONFLICT(block_hash) DO NOTHING
+            INSERT INTO job_runs (job, coin, trigger, started_at, duration_ms, outcome, error, items)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .bind(&coin_str)
-        .bind(block_height)
-        .bind(block_hash)
-        .bind(&reward_str)
-        .bind(finder_wallet)
-        .bind(finder_worker)
-        .bind(&timestamp_str)
+        .bind(job)
+        .bind(coin.to_string())
+        .bind(trigger)
+        .bind(started_at.to_rfc3339())
+        .bind(duration_ms)
+        .bind(outcome.as_str())
+        .bind(error)
+        .bind(items)
         .execute(&self.pool)
         .await?;
 
         Ok(result.last_insert_rowid())
     }
 
-    /// Get undistributed blocks for a coin
-    pub async fn get_undistributed_blocks(&self, coin: Coin) -> Result<Vec<BlockFound>> {
-        let coin_str = coin.to_string();
-
-        let rows = sqlx::query_as::<_, (i64, String, i64, String, String, String, String, String, i32)>(
+    /// Get job runs, newest first, optionally for one coin and/or job
+    pub async fn list_job_runs(
+        &self,
+        coin: Option<Coin>,
+        job: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<JobRun>> {
+        let rows = sqlx::query_as::<_, JobRunRow>(
             r#"
-            SELECT id, coin, block_height, block_hash, reward, finder_wallet, finder_worker, timestamp, distributed
-            FROM blocks
-            WHERE coin = ? AND distributed = 0
-            ORDER BY block_height ASC
+            SELECT id, job, coin, trigger, started_at, duration_ms, outcome, error, items
+            FROM job_runs
+            WHERE (?1 IS NULL OR coin = ?1) AND (?2 IS NULL OR job = ?2)
+            ORDER BY started_at DESC, id DESC
+            LIMIT ?3
             "#,
         )
-        .bind(&coin_str)
+        .bind(coin.map(|c| c.to_string()))
+        .bind(job)
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
-        let blocks = rows
-            .into_iter()
-            .map(|row| BlockFound {
-                id: row.0,
-                coin: row.1.parse().unwrap_or(coin),
-                block_height: row.2,
-                block_hash: row.3,
-                reward: row.4.parse().unwrap_or_default(),
-                finder_wallet: row.5,
-                finder_worker: row.6,
-                timestamp: DateTime::parse_from_rfc3339(&row.7)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-                distributed: row.8 != 0,
-            })
-            .collect();
-
-        Ok(blocks)
+        Ok(rows.into_iter().filter_map(job_run_from_row).collect())
     }
 
-    /// Mark a block as distributed (serialized write)
-    pub async fn mark_block_distributed(&self, block_id: i64) -> Result<()> {
+    /// Delete job runs started before a cutoff (serialized write)
+    pub async fn prune_job_runs(&self, before: DateTime<Utc>) -> Result<u64> {
         let _lock = self.write_lock.lock().await;
-        sqlx::query("UPDATE blocks SET distributed = 1 WHERE id = ?")
-            .bind(block_id)
+
+        let result = sqlx::query("DELETE FROM job_runs WHERE started_at < ?")
+            .bind(before.to_rfc3339())
             .execute(&self.pool)
             .await?;
-        Ok(())
+
+        Ok(result.rows_affected())
     }
 
-    /// Add to a miner's pending balance (serialized write)
-    pub async fn add_pending_balance(
-        &self,
-        coin: Coin,
-        wallet_address: &str,
-        amount: Decimal,
-    ) -> Result<()> {
+    /// Persist a service event; returns its id and timestamp (serialized write)
+    pub async fn record_event(&self, coin: Coin, kind: &str, data: &str) -> Result<(i64, DateTime<Utc>)> {
         let _lock = self.write_lock.lock().await;
-        let coin_str = coin.to_string();
-        let amount_str = amount.to_string();
+        let created_at = Utc::now();
 
-        sqlx::query(
-            r#"
-            INSERT INTO balances (wallet_address, coin, pending_balance)
-            VALUES (?, ?, ?)
-            ON CONFLICT(wallet_address, coin) DO UPDATE SET
-                pending_balance = CAST(
-                    (CAST(pending_balance AS REAL) + CAST(? AS REAL)) AS TEXT
-                )
-            "#,
-        )
-        .bind(wallet_address)
-        .bind(&coin_str)
-        .bind(&amount_str)
-        .bind(&amount_str)
-        .execute(&self.pool)
-        .await?;
+        let result = sqlx::query("INSERT INTO events (coin, kind, data, created_at) VALUES (?, ?, ?, ?)")
+            .bind(coin.to_string())
+            .bind(kind)
+            .bind(data)
+            .bind(created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
 
-        Ok(())
+        Ok((result.last_insert_rowid(), created_at))
     }
 
-    /// Get all balances above minimum payout threshold
-    pub async fn get_payable_balances(
+    /// Get events after an id, oldest first, optionally for one coin
+    pub async fn list_events_after(
         &self,
-        coin: Coin,
-        min_payout: Decimal,
-    ) -> Result<Vec<MinerBalance>> {
-        let coin_str = coin.to_string();
-        let min_str = min_payout.to_string();
-
-        let rows = sqlx::query_as::<_, (String, String, String, String, i64, Option<String>, Option<String>)>(
+        after_id: i64,
+        coin: Option<Coin>,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>> {
+        let rows = sqlx::query_as::<_, EventRow>(
             r#"
-            SELECT wallet_address, coin, pending_balance, total_paid, total_shares, last_share, last_payment
-            FROM balances
-            WHERE coin = ? AND CAST(pending_balance AS REAL) >= CAST(? AS REAL)
+            SELECT id, coin, kind, data, created_at
+            FROM events
+            WHERE id > ?1 AND (?2 IS NULL OR coin = ?2)
+            ORDER BY id
+            LIMIT ?3
             "#,
         )
-        .bind(&coin_str)
-        .bind(&min_str)
+        .bind(after_id)
+        .bind(coin.map(|c| c.to_string()))
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
-        let balances = rows
+        Ok(rows
             .into_iter()
-            .map(|row| MinerBalance {
-                wallet_address: row.0,
-                coin: row.1.parse().unwrap_or(coin),
-                pending_balance: row.2.parse().unwrap_or_default(),
-                total_paid: row.3.parse().unwrap_or_default(),
-                total_shares: row.4,
-                last_share: row.5.and_then(|s| {
-                    DateTime::parse_from_rfc3339(&s)
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .ok()
-                }),
-                last_payment: row.6.and_then(|s| {
-                    DateTime::parse_from_rfc3339(&s)
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .ok()
-                }),
+            .filter_map(|row| {
+                Some(StoredEvent {
+                    id: row.0,
+                    coin: row.1.parse().ok()?,
+                    kind: row.2,
+                    data: row.3,
+                    created_at: parse_timestamp(&row.4).unwrap_or_else(Utc::now),
+                })
             })
-            .collect();
+            .collect())
+    }
 
-        Ok(balances)
+    /// Delete events created before a cutoff (serialized write)
+    pub async fn prune_events(&self, before: DateTime<Utc>) -> Result<u64> {
+        let _lock = self.write_lock.lock().await;
+
+        let result = sqlx::query("DELETE FROM events WHERE created_at < ?")
+            .bind(before.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
     }
 
-    /// Get a miner's balance for a specific coin
-    pub async fn get_miner_balance(
+    /// Id of the newest event, 0 if there are none
+    pub async fn latest_event_id(&self) -> Result<i64> {
+        let id: Option<i64> = sqlx::query_scalar("SELECT MAX(id) FROM events")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(id.unwrap_or(0))
+    }
+
+    /// Last event queued for webhooks, if any ever was
+    pub async fn get_webhook_cursor(&self) -> Result<Option<i64>> {
+        Ok(sqlx::query_scalar("SELECT event_id FROM webhook_cursor WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?)
+    }
+
+    /// Queue an event's webhook deliveries and move the cursor past it (serialized write)
+    ///
+    /// `deliveries` are (webhook name, rendered body) pairs; with none, only
+    /// the cursor moves.
+    pub async fn enqueue_webhook_deliveries(
         &self,
-        coin: Coin,
-        wallet_address: &str,
-    ) -> Result<Option<MinerBalance>> {
-        let coin_str = coin.to_string();
+        event_id: i64,
+        event_type: &str,
+        deliveries: &[(String, String)],
+    ) -> Result<()> {
+        let _lock = self.write_lock.lock().await;
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now().to_rfc3339();
 
-        let row = sqlx::query_as::<_, (String, String, String, String, i64, Option<String>, Option<String>)>(
-            r#"
-            SELECT wallet_address, coin, pending_balance, total_paid, total_shares, last_share, last_payment
-            FROM balances
-            WHERE coin = ? AND wallet_address = ?
-            "#,
+        for (webhook, body) in deliveries {
+            sqlx::query(
+                r#"
+                INSERT INTO webhook_deliveries
+                    (webhook, event_id, event_type, body, status, attempts, next_attempt_at, created_at)
+                VALUES (?, ?, ?, ?, ?, 0, ?, ?)
+                "#,
+            )
+            .bind(webhook)
+            .bind(event_id)
+            .bind(event_type)
+            .bind(body)
+            .bind(DeliveryStatus::Pending.as_str())
+            .bind(&now)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO webhook_cursor (id, event_id) VALUES (1, ?) ON CONFLICT(id) DO UPDATE SET event_id = excluded.event_id",
         )
-        .bind(&coin_str)
-        .bind(wallet_address)
-        .fetch_optional(&self.pool)
+        .bind(event_id)
+        .execute(&mut *tx)
         .await?;
 
-        Ok(row.map(|row| MinerBalance {
-            wallet_address: row.0,
-            coin: row.1.parse().unwrap_or(coin),
-            pending_balance: row.2.parse().unwrap_or_default(),
-            total_paid: row.3.parse().unwrap_or_default(),
-            total_shares: row.4,
-            last_share: row.5.and_then(|s| {
-                DateTime::parse_from_rfc3339(&s)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .ok()
-            }),
-            last_payment: row.6.and_then(|s| {
-                DateTime::parse_from_rfc3339(&s)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .ok()
-            }),
-        }))
+        tx.commit().await?;
+        Ok(())
     }
 
-    /// Create a payment record (serialized write)
-    pub async fn create_payment(
+    /// Pending webhook deliveries due by `now`, oldest first
+    pub async fn get_due_webhook_deliveries(
         &self,
-        coin: Coin,
-        wallet_address: &str,
-        amount: Decimal,
-    ) -> Result<String> {
-        let _lock = self.write_lock.lock().await;
-        let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
-        let coin_str = coin.to_string();
-        let amount_str = amount.to_string();
-        let timestamp_str = now.to_rfc3339();
-
-        sqlx::query(
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        let rows = sqlx::query_as::<_, WebhookDeliveryRow>(
             r#"
-            INSERT INTO payments (id, coin, wallet_address, amount, status, created_at)
-            VALUES (?, ?, ?, ?, 'pending', ?)
+            SELECT id, webhook, event_id, event_type, body, attempts
+            FROM webhook_deliveries
+            WHERE status = ? AND next_attempt_at <= ?
+            ORDER BY next_attempt_at, id
+            LIMIT ?
             "#,
         )
-        .bind(&id)
-        .bind(&coin_str)
-        .bind(wallet_address)
-        .bind(&amount_str)
-        .bind(&timestamp_str)
-        .execute(&self.pool)
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(now.to_rfc3339())
+        .bind(limit)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(id)
+        Ok(rows
+            .into_iter()
+            .map(|row| WebhookDelivery {
+                id: row.0,
+                webhook: row.1,
+                event_id: row.2,
+                event_type: row.3,
+                body: row.4,
+                attempts: row.5 as u32,
+            })
+            .collect())
     }
 
-    /// Update payment status (serialized write)
-    pub async fn update_payment_status(
+    /// Record a webhook attempt (serialized write)
+    ///
+    /// Failed attempts are retried at `retry_at`; without one the delivery
+    /// is given up.
+    pub async fn record_webhook_attempt(
         &self,
-        payment_id: &str,
-        status: PaymentStatus,
-        tx_hash: Option<&str>,
-        error_message: Option<&str>,
+        id: i64,
+        error: Option<&str>,
+        retry_at: Option<DateTime<Utc>>,
     ) -> Result<()> {
         let _lock = self.write_lock.lock().await;
-        let status_str = match status {
-            PaymentStatus::Pending => "pending",
-            PaymentStatus::Processing => "processing",
-            PaymentStatus::Confirmed => "confirmed",
-            PaymentStatus::Failed => "failed",
-        };
+        let now = Utc::now().to_rfc3339();
 
-        let confirmed_at = if status == PaymentStatus::Confirmed {
-            Some(Utc::now().to_rfc3339())
-        } else {
-            None
+        let (status, next_attempt_at, delivered_at) = match (error, retry_at) {
+            (None, _) => (DeliveryStatus::Delivered, now.clone(), Some(now)),
+            (Some(_), Some(retry_at)) => (DeliveryStatus::Pending, retry_at.to_rfc3339(), None),
+            (Some(_), None) => (DeliveryStatus::Failed, now, None),
         };
 
         sqlx::query(
             r#"
-            UPDATE payments
-            SET status = ?, tx_hash = COALESCE(?, tx_hash), confirmed_at = ?, error_message = ?
+            UPDATE webhook_deliveries
+            SET status = ?, attempts = attempts + 1, next_attempt_at = ?, last_error = ?, delivered_at = ?
             WHERE id = ?
             "#,
         )
-        .bind(status_str)
-        .bind(tx_hash)
-        .bind(confirmed_at)
-        .bind(error_message)
-        .bind(payment_id)
+        .bind(status.as_str())
+        .bind(next_attempt_at)
+        .bind(error)
+        .bind(delivered_at)
+        .bind(id)
         .execute(&self.pool)
         .await?;
 
-        // If confirmed, update the balance
-        if status == PaymentStatus::Confirmed {
-            let now = Utc::now().to_rfc3339();
+        Ok(())
+    }
 
-            sqlx::query(
-                r#"
-                UPDATE balances
-                SET pending_balance = CAST(
-                        (CAST(pending_balance AS REAL) - (
-                            SELECT CAST(amount AS REAL) FROM payments WHERE id = ?
-                        )) AS TEXT
-                    ),
-                    total_paid = CAST(
-                        (CAST(total_paid AS REAL) + (
-                            SELECT CAST(amount AS REAL) FROM payments WHERE id = ?
-                        )) AS TEXT
-                    ),
-                    last_payment = ?
-                WHERE wallet_address = (SELECT wallet_address FROM payments WHERE id = ?)
-                  AND coin = (SELECT coin FROM payments WHERE id = ?)
-                "#,
-            )
-            .bind(payment_id)
-            .bind(payment_id)
-            .bind(&now)
-            .bind(payment_id)
-            .bind(payment_id)
+    /// Delete finished webhook deliveries created before a cutoff (serialized write)
+    pub async fn prune_webhook_deliveries(&self, before: DateTime<Utc>) -> Result<u64> {
+        let _lock = self.write_lock.lock().await;
+
+        let result = sqlx::query("DELETE FROM webhook_deliveries WHERE status != ? AND created_at < ?")
+            .bind(DeliveryStatus::Pending.as_str())
+            .bind(before.to_rfc3339())
             .execute(&self.pool)
             .await?;
-        }
 
+        Ok(result.rows_affected())
+    }
+
+    /// Check the database answers queries
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
         Ok(())
     }
 
-    /// Get pending payments for a coin
-    pub async fn get_pending_payments(&self, coin: Coin) -> Result<Vec<Payment>> {
+    /// Write a consistent copy of the whole database to a new file
+    pub async fn copy_to(&self, path: &Path) -> Result<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().as_ref())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Count broadcast payments still unconfirmed since before `sent_before`
+    pub async fn count_stuck_payments(&self, coin: Coin, sent_before: DateTime<Utc>) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM payments
+            WHERE coin = ? AND status = ? AND tx_hash IS NOT NULL AND created_at < ?
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(PaymentStatus::Processing.as_str())
+        .bind(sent_before.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Count payment records for a coin grouped by status
+    pub async fn count_payments_by_status(&self, coin: Coin) -> Result<Vec<(PaymentStatus, i64)>> {
         let coin_str = coin.to_string();
 
-        let rows = sqlx::query_as::<_, (String, String, String, String, Option<String>, String, String, Option<String>, Option<String>)>(
+        let rows = sqlx::query_as::<_, (String, i64)>(
             r#"
-            SELECT id, coin, wallet_address, amount, tx_hash, status, created_at, confirmed_at, error_message
+            SELECT status, COUNT(*)
             FROM payments
-            WHERE coin = ? AND status IN ('pending', 'processing')
-            ORDER BY created_at ASC
+            WHERE coin = ?
+            GROUP BY status
             "#,
         )
         .bind(&coin_str)
         .fetch_all(&self.pool)
         .await?;
 
-        let payments = rows
+        Ok(rows
             .into_iter()
-            .map(|row| Payment {
-                id: row.0,
-                coin: row.1.parse().unwrap_or(coin),
-                wallet_address: row.2,
-                amount: row.3.parse().unwrap_or_default(),
-                tx_hash: row.4,
-                status: match row.5.as_str() {
-                    "processing" => PaymentStatus::Processing,
-                    "confirmed" => PaymentStatus::Confirmed,
-                    "failed" => PaymentStatus::Failed,
-                    _ => PaymentStatus::Pending,
-                },
-                created_at: DateTime::parse_from_rfc3339(&row.6)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-                confirmed_at: row.7.and_then(|s| {
-                    DateTime::parse_from_rfc3339(&s)
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .ok()
-                }),
-                error_message: row.8,
+            .filter_map(|(status, count)| {
+                PaymentStatus::ALL
+                    .into_iter()
+                    .find(|s| s.as_str() == status)
+                    .map(|s| (s, count))
             })
-            .collect();
+            .collect())
+    }
+
+    /// Get a coin's payments confirmed in `[from, to)`, oldest first
+    pub async fn get_confirmed_payments_between(
+        &self,
+        coin: Coin,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Payment>> {
+        let rows = sqlx::query_as::<_, PaymentRow>(
+            r#"
+            SELECT id, coin, wallet_address, amount, tx_hash, status, created_at, confirmed_at, error_message, tx_key
+            FROM payments
+            WHERE coin = ? AND status = ? AND confirmed_at >= ? AND confirmed_at < ?
+            ORDER BY confirmed_at ASC
+            "#,
+        )
+        .bind(coin.to_string())
+        .bind(PaymentStatus::Confirmed.as_str())
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(payments)
+        Ok(rows.into_iter().map(|row| payment_from_row(row, coin)).collect())
     }
 
     /// Get payment history for a miner
@@ -645,9 +3302,9 @@ impl Database {
     ) -> Result<Vec<Payment>> {
         let coin_str = coin.to_string();
 
-        let rows = sqlx::query_as::<_, (String, String, String, String, Option<String>, String, String, Option<String>, Option<String>)>(
+        let rows = sqlx::query_as::<_, PaymentRow>(
             r#"
-            SELECT id, coin, wallet_address, amount, tx_hash, status, created_at, confirmed_at, error_message
+            SELECT id, coin, wallet_address, amount, tx_hash, status, created_at, confirmed_at, error_message, tx_key
             FROM payments
             WHERE coin = ? AND wallet_address = ?
             ORDER BY created_at DESC
@@ -660,33 +3317,7 @@ impl Database {
         .fetch_all(&self.pool)
         .await?;
 
-        let payments = rows
-            .into_iter()
-            .map(|row| Payment {
-                id: row.0,
-                coin: row.1.parse().unwrap_or(coin),
-                wallet_address: row.2,
-                amount: row.3.parse().unwrap_or_default(),
-                tx_hash: row.4,
-                status: match row.5.as_str() {
-                    "processing" => PaymentStatus::Processing,
-                    "confirmed" => PaymentStatus::Confirmed,
-                    "failed" => PaymentStatus::Failed,
-                    _ => PaymentStatus::Pending,
-                },
-                created_at: DateTime::parse_from_rfc3339(&row.6)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-                confirmed_at: row.7.and_then(|s| {
-                    DateTime::parse_from_rfc3339(&s)
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .ok()
-                }),
-                error_message: row.8,
-            })
-            .collect();
-
-        Ok(payments)
+        Ok(rows.into_iter().map(|row| payment_from_row(row, coin)).collect())
     }
 
     /// Get share count for a miner in a time range (for proportional payout calculation)
@@ -772,3 +3403,81 @@ impl Database {
         Ok(rows.into_iter().map(|r| r.0).collect())
     }
 }
+
+/// Database in a temporary file for tests
+///
+/// The file and its WAL files are deleted on drop, so keep the guard alive
+/// for as long as any clone of the database is in use: the pool opens
+/// connections lazily and would otherwise recreate an empty file.
+#[cfg(test)]
+pub struct TempDatabase {
+    db: Database,
+    path: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TempDatabase {
+    pub async fn new() -> Self {
+        let path = Self::temp_path();
+        let db = Database::new(&path).await.expect("open temporary database");
+        Self { db, path }
+    }
+
+    /// Copy of this database in another temporary file
+    pub async fn copy(&self) -> Self {
+        let path = Self::temp_path();
+        self.db.copy_to(&path).await.expect("copy temporary database");
+        let db = Database::new(&path).await.expect("open database copy");
+        Self { db, path }
+    }
+
+    /// Move a miner's last share back by `days`
+    pub async fn backdate_last_share(&self, coin: Coin, wallet_address: &str, days: i64) {
+        let last_share = Utc::now() - chrono::Duration::days(days);
+        sqlx::query("UPDATE balances SET last_share = ? WHERE coin = ? AND wallet_address = ?")
+            .bind(last_share.to_rfc3339())
+            .bind(coin.to_string())
+            .bind(wallet_address)
+            .execute(&self.db.pool)
+            .await
+            .expect("backdate last share");
+    }
+
+    /// Make every later update of a miner's payment records fail
+    pub async fn fail_payment_updates(&self, wallet_address: &str) {
+        let trigger = format!(
+            "CREATE TRIGGER fail_payment_updates BEFORE UPDATE ON payments
+             WHEN OLD.wallet_address = '{}'
+             BEGIN SELECT RAISE(ABORT, 'payment update failed'); END",
+            wallet_address
+        );
+        sqlx::query(&trigger)
+            .execute(&self.db.pool)
+            .await
+            .expect("create failing trigger");
+    }
+
+    fn temp_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("solopool-test-{}.db", Uuid::new_v4()))
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDatabase {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.db
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.path.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
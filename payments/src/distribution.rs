@@ -0,0 +1,886 @@
+//! Block reward distribution
+//!
+//! Splits a block reward between the miners who submitted shares in the
+//! window before the block was found. Shared by the coin processors and the
+//! operator `redistribute` command so both compute the same split.
+//!
+//! Miner balances only ever receive whole atomic units, since that is all a
+//! wallet can send. The fraction of a unit left over from each miner's exact
+//! share is carried forward in their carry balance and added to their share
+//! of the next block, so nothing is lost to rounding: for every block the
+//! whole units credited plus the change in carry equal the reward exactly.
+//!
+//! Teams split their part between members by percentage. Members are
+//! weighted before allocation, so their parts go through the same carry
+//! handling and still add up to the reward exactly.
+//!
+//! A pool fee comes off the reward first, in whole atomic units, and is
+//! credited to the fee address; the miners split what is left.
+//!
+//! `replay` rebuilds every block's credits with other `Params`, for the
+//! operator `recompute` command to compare against the live balances.
+
+use crate::config::PoolFeeConfig;
+use crate::db::{BlockCredit, BlockFound, Coin, Database, Team, TeamMember};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::{HashMap, HashSet};
+
+/// Share window before a block used for the split (seconds)
+pub const WINDOW_SECS: i64 = 3600;
+
+/// Decimal places kept for fractions of an atomic unit
+///
+/// Keeps every exact share representable, so sums of shares and carries
+/// never lose precision.
+pub const CARRY_SCALE: u32 = 12;
+
+/// Weight of one share, so a share split by a two-decimal percentage
+/// still has a whole weight (100% = 10,000)
+const SHARE_WEIGHT: i64 = 10_000;
+
+/// Longest team id
+const MAX_TEAM_ID_LEN: usize = 32;
+
+/// Pool fee taken from each block reward
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolFee {
+    /// Percentage of the reward
+    pub percent: Decimal,
+    /// Fixed amount per block (atomic units)
+    pub donation: Decimal,
+    /// Address credited with the fee
+    pub address: String,
+}
+
+impl PoolFee {
+    /// The enabled fee of a coin config
+    pub fn from_config(config: Option<&PoolFeeConfig>) -> Option<Self> {
+        config.filter(|f| f.enabled).map(|f| Self {
+            percent: f.percent,
+            donation: f.donation,
+            address: f.address.clone(),
+        })
+    }
+
+    /// Fee on a reward: whole atomic units, never more than the reward
+    pub fn amount(&self, reward: Decimal) -> Decimal {
+        let percent = (reward * self.percent / Decimal::ONE_HUNDRED).floor();
+        (percent + self.donation.trunc())
+            .min(reward.floor())
+            .max(Decimal::ZERO)
+    }
+}
+
+impl std::fmt::Display for PoolFee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.percent.is_zero(), self.donation.is_zero()) {
+            (false, true) => write!(f, "pool fee {}%", self.percent.normalize()),
+            (true, false) => write!(f, "pool fee {} per block", self.donation.normalize()),
+            _ => write!(
+                f,
+                "pool fee {}% + {} per block",
+                self.percent.normalize(),
+                self.donation.normalize()
+            ),
+        }
+    }
+}
+
+/// Reward scheme parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    /// Share window before a block (seconds)
+    pub window_secs: i64,
+    /// Split team shares between members (otherwise a team login is paid as a miner)
+    pub teams: bool,
+    /// Pool fee deducted before the split
+    pub fee: Option<PoolFee>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            window_secs: WINDOW_SECS,
+            teams: true,
+            fee: None,
+        }
+    }
+}
+
+/// A miner's part of a block reward
+#[derive(Debug, Clone)]
+pub struct Credit {
+    pub wallet_address: String,
+    /// Shares in the window (0 for a finder-only credit)
+    pub shares: i64,
+    /// Whole atomic units credited to the pending balance
+    pub amount: Decimal,
+    /// Change to the miner's carry balance
+    pub carry: Decimal,
+    /// Teams this credit was split from, for the ledger
+    pub memo: Option<String>,
+    /// The pool fee rather than a miner's part
+    pub fee: bool,
+}
+
+/// A wallet's allocation weight after team splits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitWeight {
+    pub wallet_address: String,
+    /// Shares scaled by `SHARE_WEIGHT`, times the percentage for team members
+    pub weight: i64,
+    /// Shares the wallet mined itself or through its teams
+    pub shares: i64,
+    /// "team <id>: <percent>% of <shares> shares" per team
+    pub teams: Vec<String>,
+}
+
+/// Calculate each miner's part of a block reward
+///
+/// Rewards are proportional to share count in the window. If nobody
+/// submitted shares the finder receives the full reward. Team shares are
+/// split between the members at the team's current percentages. With a
+/// pool fee in `params` the fee is the first credit and the miners split
+/// the rest.
+pub async fn calculate_with(
+    db: &Database,
+    block: &BlockFound,
+    params: &Params,
+) -> anyhow::Result<Vec<Credit>> {
+    let window_start = chrono::DateTime::from_timestamp(block.timestamp.timestamp() - params.window_secs, 0)
+        .unwrap_or(block.timestamp);
+    let window_end = block.timestamp;
+
+    let total_shares = db
+        .get_total_shares_in_range(block.coin, window_start, window_end)
+        .await?;
+
+    let mut weights = Vec::new();
+    if total_shares > 0 {
+        let miners = db
+            .get_miners_in_range(block.coin, window_start, window_end)
+            .await?;
+
+        for miner_wallet in miners {
+            let miner_shares = db
+                .get_share_count_in_range(block.coin, &miner_wallet, window_start, window_end)
+                .await?;
+
+            if miner_shares > 0 {
+                weights.push((miner_wallet, miner_shares));
+            }
+        }
+    }
+
+    // The finder's single share only picks who gets the reward, so a team still splits it
+    let finder_only = weights.is_empty();
+    if finder_only {
+        weights.push((block.finder_wallet.clone(), 1));
+    }
+
+    let (teams, redirects) = match params.teams {
+        true => (
+            db.get_teams(block.coin)
+                .await?
+                .into_iter()
+                .map(|t| (t.id.clone(), t))
+                .collect(),
+            db.get_team_redirects(block.coin).await?,
+        ),
+        false => (HashMap::new(), HashMap::new()),
+    };
+    let split = apply_teams(&weights, &teams, &redirects);
+
+    let scaled: Vec<(String, i64)> = split
+        .iter()
+        .map(|s| (s.wallet_address.clone(), s.weight))
+        .collect();
+    let fee = params
+        .fee
+        .as_ref()
+        .map(|fee| (fee, fee.amount(block.reward)))
+        .filter(|(_, amount)| !amount.is_zero());
+    let reward = block.reward - fee.map(|(_, amount)| amount).unwrap_or_default();
+
+    let carries = db.get_carry_balances(block.coin).await?;
+    let mut credits = allocate(reward, &scaled, &carries);
+
+    for (credit, split) in credits.iter_mut().zip(split) {
+        credit.shares = if finder_only { 0 } else { split.shares };
+        if !split.teams.is_empty() {
+            credit.memo = Some(split.teams.join("; "));
+        }
+    }
+
+    if let Some((fee, amount)) = fee {
+        credits.insert(
+            0,
+            Credit {
+                wallet_address: fee.address.clone(),
+                shares: 0,
+                amount,
+                carry: Decimal::ZERO,
+                memo: Some(fee.to_string()),
+                fee: true,
+            },
+        );
+    }
+
+    Ok(credits)
+}
+
+/// Blocks handled by `replay`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Replayed {
+    pub blocks: usize,
+    /// Distributed before ledger tracking; their credits are in opening balances
+    pub untracked: usize,
+}
+
+/// Redistribute every distributed block of a coin from scratch
+///
+/// Clears all block credits, then distributes the blocks again in height
+/// order (so carries build up as they did) with `params`. Teams are applied
+/// as they are now, not as they were when each block was found. Only for a
+/// scratch copy of the database.
+pub async fn replay(db: &Database, coin: Coin, params: &Params) -> anyhow::Result<Replayed> {
+    let tracked: HashSet<i64> = db
+        .get_ledger_entries(coin)
+        .await?
+        .into_iter()
+        .filter_map(|e| e.block_id)
+        .collect();
+
+    let mut blocks: Vec<BlockFound> = db
+        .get_blocks(coin)
+        .await?
+        .into_iter()
+        .filter(|b| b.distributed)
+        .collect();
+    blocks.sort_by_key(|b| (b.block_height, b.id));
+    let (blocks, untracked): (Vec<_>, Vec<_>) = blocks.into_iter().partition(|b| tracked.contains(&b.id));
+
+    db.clear_block_credits(coin).await?;
+
+    for block in &blocks {
+        let credits = calculate_with(db, block, params).await?;
+        db.apply_block_distribution(block, &per_wallet(&credits)).await?;
+    }
+
+    Ok(Replayed {
+        blocks: blocks.len(),
+        untracked: untracked.len(),
+    })
+}
+
+/// Replace team logins and miners redirected to a team with the team's members
+///
+/// Returns one weight per wallet, in order of first appearance. A redirect
+/// to a team that no longer exists is ignored.
+pub fn apply_teams(
+    weights: &[(String, i64)],
+    teams: &HashMap<String, Team>,
+    redirects: &HashMap<String, String>,
+) -> Vec<SplitWeight> {
+    let mut split: Vec<SplitWeight> = Vec::new();
+    let mut add = |wallet_address: &str, weight: i64, shares: i64, team: Option<String>| {
+        let index = match split.iter().position(|s| s.wallet_address == wallet_address) {
+            Some(index) => index,
+            None => {
+                split.push(SplitWeight {
+                    wallet_address: wallet_address.to_string(),
+                    weight: 0,
+                    shares: 0,
+                    teams: Vec::new(),
+                });
+                split.len() - 1
+            }
+        };
+        let entry = &mut split[index];
+        entry.weight += weight;
+        entry.shares += shares;
+        entry.teams.extend(team);
+    };
+
+    for (identity, shares) in weights {
+        let shares = (*shares).max(0);
+        let team = teams
+            .get(identity)
+            .or_else(|| redirects.get(identity).and_then(|id| teams.get(id)));
+
+        match team {
+            Some(team) => {
+                for member in &team.members {
+                    add(
+                        &member.wallet_address,
+                        shares * percent_weight(member.percent),
+                        shares,
+                        Some(format!(
+                            "team {}: {}% of {} shares",
+                            team.id,
+                            member.percent.normalize(),
+                            shares
+                        )),
+                    );
+                }
+            }
+            None => add(identity, shares * SHARE_WEIGHT, shares, None),
+        }
+    }
+
+    split
+}
+
+/// Weight of one share for a member with this percentage
+fn percent_weight(percent: Decimal) -> i64 {
+    (percent * Decimal::from(SHARE_WEIGHT) / Decimal::ONE_HUNDRED)
+        .trunc()
+        .to_i64()
+        .unwrap_or(0)
+}
+
+/// Check a team id: it doubles as a mining login, so keep it to letters,
+/// digits and dashes (pools split workers on '.' and '_')
+pub fn check_team_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || id.len() > MAX_TEAM_ID_LEN {
+        return Err(format!("team id must be 1 to {} characters", MAX_TEAM_ID_LEN));
+    }
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err("team id may only contain letters, digits and '-'".to_string());
+    }
+    Ok(())
+}
+
+/// Check a team's members: distinct addresses with percentages of at
+/// most two decimals that add up to exactly 100
+pub fn check_team_members(members: &[TeamMember]) -> Result<(), String> {
+    if members.is_empty() {
+        return Err("a team needs at least one member".to_string());
+    }
+
+    for (i, member) in members.iter().enumerate() {
+        if member.wallet_address.is_empty() {
+            return Err("member address is empty".to_string());
+        }
+        if members[..i].iter().any(|m| m.wallet_address == member.wallet_address) {
+            return Err(format!("{} is listed twice", member.wallet_address));
+        }
+        if member.percent <= Decimal::ZERO || member.percent > Decimal::ONE_HUNDRED {
+            return Err(format!(
+                "{}: percent must be above 0 and at most 100 (got {})",
+                member.wallet_address, member.percent
+            ));
+        }
+        if member.percent.normalize().scale() > 2 {
+            return Err(format!(
+                "{}: percent {} has more than two decimals",
+                member.wallet_address, member.percent
+            ));
+        }
+    }
+
+    let total: Decimal = members.iter().map(|m| m.percent).sum();
+    if total != Decimal::ONE_HUNDRED {
+        return Err(format!("member percentages add up to {}, not 100", total.normalize()));
+    }
+
+    Ok(())
+}
+
+/// Split a reward by weight into whole atomic units plus carry changes
+///
+/// Each miner's exact share (to `CARRY_SCALE` places) is added to their
+/// existing carry; the whole units of that are credited and the rest
+/// becomes the new carry. The largest weight absorbs the truncation of the
+/// other shares, so the exact shares always add up to the reward. If every
+/// weight is zero the reward is split evenly.
+pub fn allocate(
+    reward: Decimal,
+    weights: &[(String, i64)],
+    carries: &HashMap<String, Decimal>,
+) -> Vec<Credit> {
+    if weights.is_empty() {
+        return Vec::new();
+    }
+
+    let mut effective: Vec<i64> = weights.iter().map(|(_, w)| (*w).max(0)).collect();
+    if effective.iter().all(|w| *w == 0) {
+        effective.iter_mut().for_each(|w| *w = 1);
+    }
+    let total = Decimal::from(effective.iter().sum::<i64>());
+
+    // First of the largest weights
+    let largest = effective
+        .iter()
+        .enumerate()
+        .max_by_key(|(i, w)| (**w, std::cmp::Reverse(*i)))
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    let mut exact: Vec<Decimal> = effective
+        .iter()
+        .map(|w| {
+            let weight = Decimal::from(*w);
+            reward
+                .checked_mul(weight)
+                .map(|scaled| scaled / total)
+                .unwrap_or_else(|| reward * (weight / total))
+                .round_dp_with_strategy(CARRY_SCALE, RoundingStrategy::ToZero)
+        })
+        .collect();
+
+    let others: Decimal = exact
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != largest)
+        .map(|(_, amount)| *amount)
+        .sum();
+    exact[largest] = reward - others;
+
+    weights
+        .iter()
+        .zip(exact)
+        .map(|((wallet_address, shares), share)| {
+            let carried = carries.get(wallet_address).copied().unwrap_or_default();
+            let owed = share + carried;
+
+            // A negative carry (left by a redistribution) is worked off before crediting
+            let amount = if owed.is_sign_positive() { owed.floor() } else { Decimal::ZERO };
+
+            Credit {
+                wallet_address: wallet_address.clone(),
+                shares: *shares,
+                amount,
+                carry: share - amount,
+                memo: None,
+                fee: false,
+            }
+        })
+        .collect()
+}
+
+/// Collapse credits into one entry per wallet
+pub fn per_wallet(credits: &[Credit]) -> Vec<BlockCredit> {
+    let mut totals: Vec<BlockCredit> = Vec::new();
+
+    for credit in credits {
+        match totals.iter_mut().find(|c| c.wallet_address == credit.wallet_address) {
+            Some(total) => {
+                total.amount += credit.amount;
+                total.carry += credit.carry;
+                if total.memo.is_none() {
+                    total.memo = credit.memo.clone();
+                }
+            }
+            None => totals.push(BlockCredit {
+                wallet_address: credit.wallet_address.clone(),
+                amount: credit.amount,
+                carry: credit.carry,
+                memo: credit.memo.clone(),
+            }),
+        }
+    }
+
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TempDatabase;
+    use proptest::prelude::*;
+
+    fn miners(shares: &[i64]) -> Vec<(String, i64)> {
+        shares
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (format!("miner{}", i), *s))
+            .collect()
+    }
+
+    fn team(id: &str, percents: &[(&str, i64)]) -> HashMap<String, Team> {
+        let now = chrono::Utc::now();
+        let team = Team {
+            coin: crate::db::Coin::Xmr,
+            id: id.to_string(),
+            members: percents
+                .iter()
+                .map(|(address, percent)| TeamMember {
+                    wallet_address: address.to_string(),
+                    percent: Decimal::from(*percent),
+                })
+                .collect(),
+            created_at: now,
+            updated_at: now,
+        };
+        HashMap::from([(id.to_string(), team)])
+    }
+
+    fn split_weights(split: &[SplitWeight]) -> Vec<(String, i64)> {
+        split.iter().map(|s| (s.wallet_address.clone(), s.weight)).collect()
+    }
+
+    fn reward_strategy() -> impl Strategy<Value = Decimal> {
+        (0u64..=10_000_000_000_000_000).prop_map(Decimal::from)
+    }
+
+    proptest! {
+        #[test]
+        fn credits_and_carries_equal_reward(
+            reward in reward_strategy(),
+            shares in prop::collection::vec(0i64..1_000_000, 1..50),
+        ) {
+            let credits = allocate(reward, &miners(&shares), &HashMap::new());
+            let total: Decimal = credits.iter().map(|c| c.amount + c.carry).sum();
+            prop_assert_eq!(total, reward);
+        }
+
+        #[test]
+        fn credits_are_whole_atomic_units(
+            reward in reward_strategy(),
+            shares in prop::collection::vec(0i64..1_000_000, 1..50),
+            carried in prop::collection::vec(0u64..1_000_000_000_000, 50),
+        ) {
+            let weights = miners(&shares);
+            let carries: HashMap<String, Decimal> = weights
+                .iter()
+                .zip(&carried)
+                .map(|((w, _), c)| (w.clone(), Decimal::new(*c as i64, CARRY_SCALE)))
+                .collect();
+
+            for credit in allocate(reward, &weights, &carries) {
+                prop_assert_eq!(credit.amount.fract(), Decimal::ZERO);
+                prop_assert!(!credit.amount.is_sign_negative());
+
+                // Carry stays below one atomic unit
+                let new_carry = carries[&credit.wallet_address] + credit.carry;
+                prop_assert!(new_carry >= Decimal::ZERO && new_carry < Decimal::ONE);
+            }
+        }
+
+        #[test]
+        fn carried_fractions_are_paid_out_over_blocks(
+            reward in 1u64..1_000_000,
+            shares in prop::collection::vec(1i64..1_000, 1..20),
+            blocks in 1usize..20,
+        ) {
+            let weights = miners(&shares);
+            let reward = Decimal::from(reward);
+            let mut carries: HashMap<String, Decimal> = HashMap::new();
+            let mut credited = Decimal::ZERO;
+
+            for _ in 0..blocks {
+                for credit in allocate(reward, &weights, &carries) {
+                    credited += credit.amount;
+                    *carries.entry(credit.wallet_address).or_default() += credit.carry;
+                }
+            }
+
+            // Everything not yet credited is held as carry, less than a unit per miner
+            let held: Decimal = carries.values().copied().sum();
+            prop_assert_eq!(credited + held, reward * Decimal::from(blocks));
+            prop_assert!(held < Decimal::from(weights.len()));
+        }
+
+        #[test]
+        fn reward_split_is_proportional(
+            reward in reward_strategy(),
+            shares in prop::collection::vec(1i64..1_000_000, 1..50),
+        ) {
+            let total: i64 = shares.iter().sum();
+            let credits = allocate(reward, &miners(&shares), &HashMap::new());
+
+            for (credit, share) in credits.iter().zip(&shares) {
+                let exact = reward * Decimal::from(*share) / Decimal::from(total);
+                prop_assert!((credit.amount - exact).abs() < Decimal::ONE);
+            }
+        }
+
+        #[test]
+        fn team_split_credits_equal_reward(
+            reward in reward_strategy(),
+            shares in prop::collection::vec(0i64..1_000_000, 1..20),
+            first in 1i64..100,
+        ) {
+            let teams = team("miner0", &[("alice", first), ("bob", 100 - first)]);
+            let split = apply_teams(&miners(&shares), &teams, &HashMap::new());
+            let credits = allocate(reward, &split_weights(&split), &HashMap::new());
+            let total: Decimal = credits.iter().map(|c| c.amount + c.carry).sum();
+            prop_assert_eq!(total, reward);
+        }
+    }
+
+    #[test]
+    fn team_allocation_fans_out_to_members() {
+        let teams = team("rig", &[("alice", 70), ("bob", 30)]);
+        let redirects = HashMap::from([("carol".to_string(), "rig".to_string())]);
+        let weights = vec![
+            ("rig".to_string(), 6),
+            ("dave".to_string(), 5),
+            ("carol".to_string(), 4),
+        ];
+        let split = apply_teams(&weights, &teams, &redirects);
+
+        let addresses: Vec<&str> = split.iter().map(|s| s.wallet_address.as_str()).collect();
+        assert_eq!(addresses, vec!["alice", "bob", "dave"]);
+        assert_eq!(split[0].teams.len(), 2);
+        assert_eq!(split[0].teams[0], "team rig: 70% of 6 shares");
+
+        let credits = allocate(Decimal::from(1_000), &split_weights(&split), &HashMap::new());
+        let amounts: Vec<Decimal> = credits.iter().map(|c| c.amount).collect();
+        assert_eq!(amounts, vec![Decimal::from(466), Decimal::from(200), Decimal::from(333)]);
+        let total: Decimal = credits.iter().map(|c| c.amount + c.carry).sum();
+        assert_eq!(total, Decimal::from(1_000));
+    }
+
+    #[test]
+    fn team_members_must_add_up_to_100() {
+        let members = |percents: &[&str]| -> Vec<TeamMember> {
+            percents
+                .iter()
+                .enumerate()
+                .map(|(i, p)| TeamMember {
+                    wallet_address: format!("addr{}", i),
+                    percent: p.parse().unwrap(),
+                })
+                .collect()
+        };
+
+        assert!(check_team_members(&members(&["33.33", "33.33", "33.34"])).is_ok());
+        assert!(check_team_members(&members(&["50", "49.99"])).is_err());
+        assert!(check_team_members(&members(&["99.999", "0.001"])).is_err());
+        assert!(check_team_members(&members(&["100", "0"])).is_err());
+        assert!(check_team_members(&[]).is_err());
+        assert!(check_team_id("shared-rig-1").is_ok());
+        assert!(check_team_id("rig.worker").is_err());
+    }
+
+    #[test]
+    fn finder_only_block_credits_full_reward() {
+        let weights = vec![("finder".to_string(), 0)];
+        let credits = allocate(Decimal::from(600_000_000_000u64), &weights, &HashMap::new());
+
+        assert_eq!(credits.len(), 1);
+        assert_eq!(credits[0].amount, Decimal::from(600_000_000_000u64));
+        assert_eq!(credits[0].carry, Decimal::ZERO);
+    }
+
+    #[test]
+    fn thirds_carry_the_remainder() {
+        let credits = allocate(Decimal::from(100), &miners(&[1, 1, 1]), &HashMap::new());
+        let amounts: Vec<Decimal> = credits.iter().map(|c| c.amount).collect();
+
+        assert_eq!(amounts, vec![Decimal::from(33); 3]);
+        let carried: Decimal = credits.iter().map(|c| c.carry).sum();
+        assert_eq!(carried, Decimal::ONE);
+    }
+
+    #[test]
+    fn negative_carry_is_worked_off_first() {
+        let weights = vec![("miner".to_string(), 1)];
+        let carries = HashMap::from([("miner".to_string(), Decimal::from(-150))]);
+        let credits = allocate(Decimal::from(100), &weights, &carries);
+
+        assert_eq!(credits[0].amount, Decimal::ZERO);
+        assert_eq!(credits[0].carry, Decimal::from(100));
+    }
+
+    async fn owed(db: &Database) -> HashMap<String, Decimal> {
+        db.get_all_balances(Coin::Xmr)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|b| (b.wallet_address, b.pending_balance + b.carry_balance))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn replay_rebuilds_balances_and_applies_params() {
+        let db = TempDatabase::new().await;
+
+        let members = [("alice", 50), ("bob", 50)].map(|(address, percent)| TeamMember {
+            wallet_address: address.to_string(),
+            percent: Decimal::from(percent),
+        });
+        db.set_team(Coin::Xmr, "crew", &members).await.unwrap();
+        for login in ["crew", "crew", "carol"] {
+            db.record_share(Coin::Xmr, login, "rig", Decimal::ONE, None, false)
+                .await
+                .unwrap();
+        }
+        db.record_block(
+            Coin::Xmr,
+            100,
+            "hash",
+            Decimal::from(1_000),
+            "carol",
+            "rig",
+            crate::db::RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        let block = db.get_undistributed_blocks(Coin::Xmr).await.unwrap().remove(0);
+        let credits = calculate_with(&db, &block, &Params::default()).await.unwrap();
+        db.apply_block_distribution(&block, &per_wallet(&credits)).await.unwrap();
+        let live = owed(&db).await;
+
+        // Same parameters give the same balances
+        let same = db.copy().await;
+        let replayed = replay(&same, Coin::Xmr, &Params::default()).await.unwrap();
+        assert_eq!(replayed, Replayed { blocks: 1, untracked: 0 });
+        assert_eq!(owed(&same).await, live);
+
+        // Without teams the team login is paid as a miner
+        let solo = db.copy().await;
+        let params = Params {
+            teams: false,
+            ..Params::default()
+        };
+        replay(&solo, Coin::Xmr, &params).await.unwrap();
+        let recomputed = owed(&solo).await;
+        assert_eq!(recomputed["alice"], Decimal::ZERO);
+        assert_eq!(recomputed["crew"].round_dp(6), Decimal::new(666_666_667, 6));
+        assert_eq!(recomputed.values().copied().sum::<Decimal>(), Decimal::from(1_000));
+
+        // The live database is untouched
+        assert_eq!(owed(&db).await, live);
+        assert_eq!(live["alice"] + live["bob"] + live["carol"], Decimal::from(1_000));
+    }
+
+    #[tokio::test]
+    async fn redirected_miners_are_split_through_their_team_until_it_is_deleted() {
+        let db = TempDatabase::new().await;
+
+        let members = [("carol", 60), ("dave", 40)].map(|(address, percent)| TeamMember {
+            wallet_address: address.to_string(),
+            percent: Decimal::from(percent),
+        });
+        db.set_team(Coin::Xmr, "crew", &members).await.unwrap();
+        db.set_team_redirect(Coin::Xmr, "bob", "crew")
+            .await
+            .unwrap();
+        assert!(db
+            .set_team_redirect(Coin::Xmr, "bob", "nobody")
+            .await
+            .is_err());
+        for login in ["alice", "bob"] {
+            db.record_share(Coin::Xmr, login, "rig", Decimal::ONE, None, false)
+                .await
+                .unwrap();
+        }
+        db.record_block(
+            Coin::Xmr,
+            100,
+            "hash",
+            Decimal::from(1_000),
+            "bob",
+            "rig",
+            crate::db::RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        let block = db
+            .get_undistributed_blocks(Coin::Xmr)
+            .await
+            .unwrap()
+            .remove(0);
+        let amounts = |credits: &[Credit]| -> HashMap<String, Decimal> {
+            credits
+                .iter()
+                .map(|c| (c.wallet_address.clone(), c.amount))
+                .collect()
+        };
+
+        let credits = calculate_with(&db, &block, &Params::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            amounts(&credits),
+            HashMap::from([
+                ("alice".to_string(), Decimal::from(500)),
+                ("carol".to_string(), Decimal::from(300)),
+                ("dave".to_string(), Decimal::from(200)),
+            ])
+        );
+        let carol = credits
+            .iter()
+            .find(|c| c.wallet_address == "carol")
+            .unwrap();
+        assert_eq!(carol.memo.as_deref(), Some("team crew: 60% of 1 shares"));
+
+        // Deleting the team drops the redirect, so bob is paid himself again
+        assert!(db.delete_team(Coin::Xmr, "crew").await.unwrap());
+        assert!(db.get_team_redirects(Coin::Xmr).await.unwrap().is_empty());
+        let credits = calculate_with(&db, &block, &Params::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            amounts(&credits),
+            HashMap::from([
+                ("alice".to_string(), Decimal::from(500)),
+                ("bob".to_string(), Decimal::from(500)),
+            ])
+        );
+    }
+
+    #[test]
+    fn pool_fee_is_whole_units_capped_at_reward() {
+        let fee = PoolFee {
+            percent: Decimal::new(15, 1),
+            donation: Decimal::from(7),
+            address: "fee".to_string(),
+        };
+
+        assert_eq!(fee.amount(Decimal::from(1_000)), Decimal::from(22));
+        assert_eq!(fee.amount(Decimal::from(999)), Decimal::from(21));
+        assert_eq!(fee.amount(Decimal::from(5)), Decimal::from(5));
+        assert_eq!(fee.to_string(), "pool fee 1.5% + 7 per block");
+    }
+
+    #[tokio::test]
+    async fn pool_fee_comes_off_the_reward_before_the_split() {
+        let db = TempDatabase::new().await;
+
+        for login in ["alice", "bob"] {
+            db.record_share(Coin::Xmr, login, "rig", Decimal::ONE, None, false)
+                .await
+                .unwrap();
+        }
+        db.record_block(
+            Coin::Xmr,
+            100,
+            "hash",
+            Decimal::from(1_000),
+            "alice",
+            "rig",
+            crate::db::RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        let block = db.get_undistributed_blocks(Coin::Xmr).await.unwrap().remove(0);
+
+        let params = Params {
+            fee: Some(PoolFee {
+                percent: Decimal::from(2),
+                donation: Decimal::from(10),
+                address: "fee".to_string(),
+            }),
+            ..Params::default()
+        };
+        let credits = calculate_with(&db, &block, &params).await.unwrap();
+
+        assert!(credits[0].fee);
+        assert_eq!(credits[0].wallet_address, "fee");
+        assert_eq!(credits[0].amount, Decimal::from(30));
+        assert_eq!(credits[0].memo.as_deref(), Some("pool fee 2% + 10 per block"));
+        let miners: Vec<Decimal> = credits[1..].iter().map(|c| c.amount).collect();
+        assert_eq!(miners, vec![Decimal::from(485); 2]);
+
+        // The fee address is owed its fee like any miner balance
+        db.apply_block_distribution(&block, &per_wallet(&credits)).await.unwrap();
+        let owed = owed(&db).await;
+        assert_eq!(owed["fee"], Decimal::from(30));
+        assert_eq!(owed.values().copied().sum::<Decimal>(), Decimal::from(1_000));
+    }
+}
@@ -0,0 +1,375 @@
+//! Payment service events
+//!
+//! Processors publish what they do (shares synced, blocks found and
+//! verified, rewards distributed, payments sent and settled) to a bus.
+//! Every event is stored before it is broadcast, so `GET /api/events`
+//! subscribers that reconnect with `Last-Event-ID` get what they missed.
+
+use crate::db::{Coin, Database, StoredEvent};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::Duration;
+use tracing::{debug, warn};
+
+/// Live events buffered per subscriber before it has to catch up from the database
+const BUS_CAPACITY: usize = 1024;
+
+/// Most events read from the database at once when replaying
+const REPLAY_BATCH: i64 = 500;
+
+/// How long events are kept for replay
+const EVENT_RETENTION_DAYS: i64 = 7;
+
+/// How often old events are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// What happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    /// New shares were recorded from the pool API
+    SharesSynced { shares: u64, newest_share: i64 },
+    BlockFound {
+        height: i64,
+        hash: String,
+        reward: Decimal,
+        finder: String,
+    },
+    /// The block's reward is final: its coinbase arrived in the pool wallet
+    /// (`reward_source = "wallet"`), or the pool-reported reward is being
+    /// distributed (`reward_source = "pool"`, no coinbase tx)
+    BlockConfirmed {
+        height: i64,
+        hash: String,
+        reward: Decimal,
+        coinbase_tx: Option<String>,
+    },
+    /// The block's coinbase didn't arrive in the pool wallet within the
+    /// grace period; its reward is held, not distributed
+    CoinbaseMissing { height: i64, hash: String },
+    /// A block's reward was credited to miner balances
+    RewardsDistributed {
+        height: i64,
+        hash: String,
+        amount: Decimal,
+        miners: usize,
+        /// Pool fee credited to the fee address (not part of `amount`)
+        pool_fee: Decimal,
+    },
+    PaymentSent {
+        payment_id: String,
+        address: String,
+        amount: Decimal,
+        tx_hash: String,
+    },
+    PaymentConfirmed {
+        payment_id: String,
+        address: String,
+        amount: Decimal,
+        tx_hash: String,
+    },
+    PaymentFailed {
+        payment_id: String,
+        address: String,
+        amount: Decimal,
+        reason: String,
+    },
+    /// The unlocked pool wallet balance fell below `low_balance_alert`
+    WalletBalanceLow { balance: Decimal, threshold: Decimal },
+    /// The pool API stopped answering
+    PoolOffline,
+}
+
+impl EventKind {
+    /// Every event name
+    pub const NAMES: [&'static str; 10] = [
+        "shares_synced",
+        "block_found",
+        "block_confirmed",
+        "coinbase_missing",
+        "rewards_distributed",
+        "payment_sent",
+        "payment_confirmed",
+        "payment_failed",
+        "wallet_balance_low",
+        "pool_offline",
+    ];
+
+    /// SSE event name, same as the JSON `type`
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::SharesSynced { .. } => "shares_synced",
+            EventKind::BlockFound { .. } => "block_found",
+            EventKind::BlockConfirmed { .. } => "block_confirmed",
+            EventKind::CoinbaseMissing { .. } => "coinbase_missing",
+            EventKind::RewardsDistributed { .. } => "rewards_distributed",
+            EventKind::PaymentSent { .. } => "payment_sent",
+            EventKind::PaymentConfirmed { .. } => "payment_confirmed",
+            EventKind::PaymentFailed { .. } => "payment_failed",
+            EventKind::WalletBalanceLow { .. } => "wallet_balance_low",
+            EventKind::PoolOffline => "pool_offline",
+        }
+    }
+}
+
+/// A published event
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub id: i64,
+    pub coin: Coin,
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl Event {
+    fn from_stored(stored: StoredEvent) -> Option<Self> {
+        match serde_json::from_str(&stored.data) {
+            Ok(kind) => Some(Event {
+                id: stored.id,
+                coin: stored.coin,
+                created_at: stored.created_at,
+                kind,
+            }),
+            Err(e) => {
+                warn!(id = stored.id, kind = %stored.kind, error = %e, "Skipping unreadable stored event");
+                None
+            }
+        }
+    }
+}
+
+/// Stores events and fans them out to subscribers
+#[derive(Clone)]
+pub struct EventBus {
+    db: Database,
+    sender: broadcast::Sender<Event>,
+    /// Keeps broadcast order the same as id order
+    publish_lock: Arc<Mutex<()>>,
+}
+
+impl EventBus {
+    pub fn new(db: Database) -> Self {
+        let (sender, _) = broadcast::channel(BUS_CAPACITY);
+        Self {
+            db,
+            sender,
+            publish_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Store an event and send it to live subscribers
+    pub async fn publish(&self, coin: Coin, kind: EventKind) -> anyhow::Result<()> {
+        let _lock = self.publish_lock.lock().await;
+
+        let data = serde_json::to_string(&kind)?;
+        let (id, created_at) = self.db.record_event(coin, kind.name(), &data).await?;
+
+        // No subscribers is fine; the event is stored for replay
+        let _ = self.sender.send(Event {
+            id,
+            coin,
+            created_at,
+            kind,
+        });
+        Ok(())
+    }
+
+    /// Events after `last_event_id` (none if None), then live events
+    ///
+    /// A subscriber that falls behind the live buffer catches up from the
+    /// database, so it never skips an event.
+    pub fn subscribe(
+        &self,
+        coin: Option<Coin>,
+        last_event_id: Option<i64>,
+    ) -> impl Stream<Item = Event> + Send + 'static {
+        let state = Subscription {
+            db: self.db.clone(),
+            // Subscribe before reading the backlog so nothing falls in between
+            receiver: self.sender.subscribe(),
+            coin,
+            last_id: last_event_id,
+            replaying: last_event_id.is_some(),
+            backlog: VecDeque::new(),
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let event = state.next().await?;
+            Some((event, state))
+        })
+    }
+
+    /// Delete events older than EVENT_RETENTION_DAYS until shutdown
+    pub async fn prune_history(&self, mut shutdown: broadcast::Receiver<()>) {
+        loop {
+            let cutoff = Utc::now() - chrono::Duration::days(EVENT_RETENTION_DAYS);
+            match self.db.prune_events(cutoff).await {
+                Ok(0) => {}
+                Ok(pruned) => debug!(pruned, "Pruned old events"),
+                Err(e) => warn!(error = %e, "Failed to prune events"),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(PRUNE_INTERVAL) => {}
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+}
+
+/// One subscriber's position in the event stream
+struct Subscription {
+    db: Database,
+    receiver: broadcast::Receiver<Event>,
+    coin: Option<Coin>,
+    /// Last event id delivered or queued; None until then when no replay was asked for
+    last_id: Option<i64>,
+    /// Reading stored events rather than the live bus
+    replaying: bool,
+    backlog: VecDeque<Event>,
+}
+
+impl Subscription {
+    /// The next event for this subscriber; None once the bus is gone
+    async fn next(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.backlog.pop_front() {
+                return Some(event);
+            }
+
+            if let (true, Some(last_id)) = (self.replaying, self.last_id) {
+                match self.db.list_events_after(last_id, self.coin, REPLAY_BATCH).await {
+                    Ok(stored) if !stored.is_empty() => {
+                        // Unreadable rows still move the position forward
+                        self.last_id = stored.last().map(|e| e.id);
+                        self.backlog.extend(stored.into_iter().filter_map(Event::from_stored));
+                        continue;
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!(error = %e, "Failed to read stored events, continuing with live events"),
+                }
+                self.replaying = false;
+            }
+
+            // Caught up; wait for live events
+            loop {
+                match self.receiver.recv().await {
+                    Ok(event) => {
+                        if self.last_id.is_some_and(|last| event.id <= last) {
+                            continue;
+                        }
+                        if self.coin.is_some_and(|coin| coin != event.coin) {
+                            continue;
+                        }
+                        self.last_id = Some(event.id);
+                        return Some(event);
+                    }
+                    // Fell behind: replay the gap from the database
+                    Err(broadcast::error::RecvError::Lagged(_)) if self.last_id.is_some() => {
+                        self.replaying = true;
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TempDatabase;
+    use futures::StreamExt;
+
+    fn block_found(height: i64) -> EventKind {
+        EventKind::BlockFound {
+            height,
+            hash: format!("hash{}", height),
+            reward: Decimal::new(6, 1),
+            finder: "miner".to_string(),
+        }
+    }
+
+    async fn next(stream: &mut (impl Stream<Item = Event> + Unpin)) -> Event {
+        tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("no event")
+            .expect("stream ended")
+    }
+
+    #[tokio::test]
+    async fn replays_after_last_event_id_then_streams_live() {
+        let db = TempDatabase::new().await;
+        let bus = EventBus::new(db.clone());
+        for height in 1..=3 {
+            bus.publish(Coin::Xmr, block_found(height)).await.unwrap();
+        }
+
+        let mut stream = Box::pin(bus.subscribe(None, Some(1)));
+        assert_eq!(next(&mut stream).await.kind, block_found(2));
+        assert_eq!(next(&mut stream).await.kind, block_found(3));
+
+        bus.publish(Coin::Xmr, block_found(4)).await.unwrap();
+        let live = next(&mut stream).await;
+        assert_eq!((live.id, live.kind), (4, block_found(4)));
+    }
+
+    #[tokio::test]
+    async fn without_last_event_id_only_live_events_for_the_coin() {
+        let db = TempDatabase::new().await;
+        let bus = EventBus::new(db.clone());
+        bus.publish(Coin::Xmr, block_found(1)).await.unwrap();
+
+        let mut stream = Box::pin(bus.subscribe(Some(Coin::Xmr), None));
+        bus.publish(Coin::Btc, block_found(2)).await.unwrap();
+        bus.publish(Coin::Xmr, block_found(3)).await.unwrap();
+
+        let event = next(&mut stream).await;
+        assert_eq!((event.coin, event.kind), (Coin::Xmr, block_found(3)));
+    }
+
+    #[tokio::test]
+    async fn lagging_subscriber_catches_up_from_database() {
+        let db = TempDatabase::new().await;
+        let bus = EventBus::new(db.clone());
+        bus.publish(Coin::Xmr, block_found(0)).await.unwrap();
+        let mut stream = Box::pin(bus.subscribe(None, Some(0)));
+        assert_eq!(next(&mut stream).await.id, 1);
+
+        for height in 1..=(BUS_CAPACITY as i64 + 10) {
+            bus.publish(Coin::Xmr, block_found(height)).await.unwrap();
+        }
+
+        for expected in 2..=(BUS_CAPACITY as i64 + 11) {
+            assert_eq!(next(&mut stream).await.id, expected);
+        }
+    }
+
+    #[test]
+    fn event_json_is_flat_and_typed() {
+        let event = Event {
+            id: 7,
+            coin: Coin::Xmr,
+            created_at: Utc::now(),
+            kind: EventKind::PaymentFailed {
+                payment_id: "p1".to_string(),
+                address: "addr".to_string(),
+                amount: Decimal::new(15, 1),
+                reason: "double spend".to_string(),
+            },
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "payment_failed");
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["amount"], "1.5");
+        assert_eq!(event.kind.name(), "payment_failed");
+    }
+}
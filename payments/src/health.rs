@@ -0,0 +1,409 @@
+//! Service health report
+//!
+//! Processors record when each stage of their cycles last succeeded. The
+//! health endpoint combines that with live probes of every enabled coin's
+//! pool API and wallet into one report with an overall status:
+//! - `down`: the database, a pool API or a wallet RPC is unreachable
+//! - `degraded`: a stage has not succeeded for several intervals, or
+//!   payments have been waiting for confirmation for too long
+//! - `ok`: everything else
+
+use crate::db::{Coin, Database};
+use crate::pools::PoolApi;
+use crate::wallets::Wallet;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+/// How long a single pool or wallet probe may take
+const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A broadcast payment unconfirmed for this long counts as stuck
+const STUCK_PAYMENT_SECS: i64 = 3 * 3600;
+
+/// Share sync and block checks may miss this many intervals before they count as stale
+const STALE_INTERVALS: i32 = 3;
+
+/// Overall or per-coin health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+/// Health of the whole service
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub service: &'static str,
+    pub checked_at: String,
+    pub uptime_secs: i64,
+    /// Database errors, if the database could not be queried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_error: Option<String>,
+    pub coins: BTreeMap<String, CoinHealthReport>,
+}
+
+/// Health of one coin's processor
+#[derive(Debug, Serialize)]
+pub struct CoinHealthReport {
+    pub status: HealthStatus,
+    pub pool_online: bool,
+    pub wallet_reachable: bool,
+    /// Unlocked pool wallet balance (atomic units), for authenticated requests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet_balance: Option<String>,
+    pub last_share_sync: Option<String>,
+    pub last_block_check: Option<String>,
+    pub last_payment_cycle: Option<String>,
+    /// Age of the newest share ingested, in seconds
+    pub ingestion_lag_secs: Option<i64>,
+    /// Payments broadcast but unconfirmed for over STUCK_PAYMENT_SECS
+    pub stuck_payments: i64,
+    pub payouts_paused: bool,
+    /// Why the coin is not ok
+    pub problems: Vec<String>,
+}
+
+/// What a processor has recorded about its coin
+struct CoinEntry {
+    pool_api: Arc<dyn PoolApi>,
+    wallet: Arc<dyn Wallet>,
+    last_share_sync: Option<DateTime<Utc>>,
+    last_block_check: Option<DateTime<Utc>>,
+    last_payment_cycle: Option<DateTime<Utc>>,
+    newest_share: Option<DateTime<Utc>>,
+}
+
+/// Cycle timestamps and probe targets shared by the processors and the API
+#[derive(Clone)]
+pub struct Health {
+    started_at: DateTime<Utc>,
+    share_interval: Duration,
+    payment_interval: Duration,
+    coins: Arc<Mutex<HashMap<Coin, CoinEntry>>>,
+}
+
+impl Health {
+    pub fn new(share_interval_secs: u64, payment_interval_secs: u64) -> Self {
+        Self {
+            started_at: Utc::now(),
+            share_interval: Duration::seconds(share_interval_secs as i64),
+            payment_interval: Duration::seconds(payment_interval_secs as i64),
+            coins: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Add a coin to the report
+    pub fn register(&self, coin: Coin, pool_api: Arc<dyn PoolApi>, wallet: Arc<dyn Wallet>) {
+        self.lock().insert(
+            coin,
+            CoinEntry {
+                pool_api,
+                wallet,
+                last_share_sync: None,
+                last_block_check: None,
+                last_payment_cycle: None,
+                newest_share: None,
+            },
+        );
+    }
+
+    /// Pool wallet of a registered coin
+    pub fn wallet(&self, coin: Coin) -> Option<Arc<dyn Wallet>> {
+        self.lock().get(&coin).map(|entry| entry.wallet.clone())
+    }
+
+    /// A share sync succeeded; `newest_share` is the latest share's unix timestamp
+    pub fn record_share_sync(&self, coin: Coin, newest_share: i64) {
+        self.update(coin, |entry| {
+            entry.last_share_sync = Some(Utc::now());
+            if newest_share > 0 {
+                entry.newest_share = Utc.timestamp_opt(newest_share, 0).single();
+            }
+        });
+    }
+
+    /// The pool API was checked for new blocks
+    pub fn record_block_check(&self, coin: Coin) {
+        self.update(coin, |entry| entry.last_block_check = Some(Utc::now()));
+    }
+
+    /// A payment cycle processed the coin's balances
+    pub fn record_payment_cycle(&self, coin: Coin) {
+        self.update(coin, |entry| entry.last_payment_cycle = Some(Utc::now()));
+    }
+
+    /// Probe every registered coin and build the report
+    ///
+    /// Wallet balances are only included when `include_balances` is set.
+    pub async fn report(&self, db: &Database, include_balances: bool) -> HealthReport {
+        let now = Utc::now();
+        let mut probes = JoinSet::new();
+        let mut database_error = None;
+
+        let entries: Vec<_> = self
+            .lock()
+            .iter()
+            .map(|(coin, entry)| {
+                (
+                    *coin,
+                    entry.pool_api.clone(),
+                    entry.wallet.clone(),
+                    [entry.last_share_sync, entry.last_block_check, entry.last_payment_cycle],
+                    entry.newest_share,
+                )
+            })
+            .collect();
+
+        for (coin, pool_api, wallet, _, _) in &entries {
+            let (coin, pool_api, wallet) = (*coin, pool_api.clone(), wallet.clone());
+            probes.spawn(async move {
+                let online = timeout(PROBE_TIMEOUT, pool_api.is_online()).await.unwrap_or(false);
+                let balance = match timeout(PROBE_TIMEOUT, wallet.get_balance()).await {
+                    Ok(Ok(balance)) => Ok(balance),
+                    Ok(Err(e)) => Err(e.to_string()),
+                    Err(_) => Err(format!("no response within {}s", PROBE_TIMEOUT.as_secs())),
+                };
+                (coin, online, balance)
+            });
+        }
+
+        let mut probed = HashMap::new();
+        while let Some(result) = probes.join_next().await {
+            if let Ok((coin, online, balance)) = result {
+                probed.insert(coin, (online, balance));
+            }
+        }
+
+        let mut coins = BTreeMap::new();
+        for (coin, _, _, [share_sync, block_check, payment_cycle], newest_share) in entries {
+            let (pool_online, balance) = probed
+                .remove(&coin)
+                .unwrap_or((false, Err("probe failed".to_string())));
+            let mut problems = Vec::new();
+            let mut status = HealthStatus::Ok;
+
+            if !pool_online {
+                problems.push("pool API is offline".to_string());
+                status = HealthStatus::Down;
+            }
+            if let Err(e) = &balance {
+                problems.push(format!("wallet RPC unreachable: {}", e));
+                status = HealthStatus::Down;
+            }
+
+            let stuck_payments = match db
+                .count_stuck_payments(coin, now - Duration::seconds(STUCK_PAYMENT_SECS))
+                .await
+            {
+                Ok(count) => count,
+                Err(e) => {
+                    database_error = Some(e.to_string());
+                    0
+                }
+            };
+            if stuck_payments > 0 {
+                problems.push(format!(
+                    "{} payment(s) unconfirmed for over {}h",
+                    stuck_payments,
+                    STUCK_PAYMENT_SECS / 3600
+                ));
+                status = status.max(HealthStatus::Degraded);
+            }
+
+            let payouts_paused = match db.get_payout_pause(coin).await {
+                Ok(pause) => pause.is_some(),
+                Err(e) => {
+                    database_error = Some(e.to_string());
+                    false
+                }
+            };
+
+            let stale_after = self.share_interval * STALE_INTERVALS;
+            let mut stages = vec![
+                ("share sync", share_sync, stale_after),
+                ("block check", block_check, stale_after),
+            ];
+            // A paused coin skips its payment cycles on purpose
+            if !payouts_paused {
+                stages.push(("payment cycle", payment_cycle, self.payment_interval * 2 + self.share_interval));
+            }
+            for (stage, last, stale_after) in stages {
+                let since = last.unwrap_or(self.started_at);
+                if now - since > stale_after {
+                    problems.push(match last {
+                        Some(last) => format!("no successful {} since {}", stage, last.to_rfc3339()),
+                        None => format!("no successful {} since startup", stage),
+                    });
+                    status = status.max(HealthStatus::Degraded);
+                }
+            }
+
+            coins.insert(
+                coin.to_string(),
+                CoinHealthReport {
+                    status,
+                    pool_online,
+                    wallet_reachable: balance.is_ok(),
+                    wallet_balance: balance
+                        .ok()
+                        .filter(|_| include_balances)
+                        .map(|b| b.to_string()),
+                    last_share_sync: share_sync.map(|t| t.to_rfc3339()),
+                    last_block_check: block_check.map(|t| t.to_rfc3339()),
+                    last_payment_cycle: payment_cycle.map(|t| t.to_rfc3339()),
+                    ingestion_lag_secs: newest_share.map(|t| (now - t).num_seconds().max(0)),
+                    stuck_payments,
+                    payouts_paused,
+                    problems,
+                },
+            );
+        }
+
+        if let Err(e) = db.ping().await {
+            database_error = Some(e.to_string());
+        }
+
+        let mut status = coins.values().map(|c| c.status).max().unwrap_or(HealthStatus::Ok);
+        if database_error.is_some() {
+            status = HealthStatus::Down;
+        }
+
+        HealthReport {
+            status,
+            service: "solopool-payments",
+            checked_at: now.to_rfc3339(),
+            uptime_secs: (now - self.started_at).num_seconds(),
+            database_error,
+            coins,
+        }
+    }
+
+    fn update(&self, coin: Coin, f: impl FnOnce(&mut CoinEntry)) {
+        if let Some(entry) = self.lock().get_mut(&coin) {
+            f(entry);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Coin, CoinEntry>> {
+        self.coins.lock().expect("health lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TempDatabase;
+    use crate::pools::monero_pool::MoneroPoolApi;
+    use crate::wallets::monero::MoneroWallet;
+    use axum::{
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde_json::json;
+
+    /// monero-pool API and wallet RPC on one mock server
+    async fn backend() -> String {
+        let app = Router::new()
+            .route("/stats", get(|| async { Json(json!({})) }))
+            .route(
+                "/json_rpc",
+                post(|| async {
+                    Json(json!({
+                        "jsonrpc": "2.0",
+                        "id": "0",
+                        "result": {"balance": 2_000, "unlocked_balance": 1_500}
+                    }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    /// XMR health probing `url`, started `age_secs` ago with 60s share and 600s payment intervals
+    fn health(url: &str, age_secs: i64) -> Health {
+        let mut health = Health::new(60, 600);
+        health.started_at = Utc::now() - Duration::seconds(age_secs);
+        health.register(
+            Coin::Xmr,
+            Arc::new(MoneroPoolApi::new(url)),
+            Arc::new(MoneroWallet::new(url, 15)),
+        );
+        health
+    }
+
+    #[tokio::test]
+    async fn reachable_backends_are_ok_and_balances_need_auth() {
+        let db = TempDatabase::new().await;
+        let health = health(&backend().await, 0);
+
+        let report = health.report(&db, false).await;
+        assert_eq!(report.status, HealthStatus::Ok);
+        let xmr = &report.coins["xmr"];
+        assert!(xmr.pool_online && xmr.wallet_reachable);
+        assert!(xmr.problems.is_empty());
+        assert_eq!(xmr.wallet_balance, None);
+
+        let report = health.report(&db, true).await;
+        assert_eq!(report.coins["xmr"].wallet_balance.as_deref(), Some("1500"));
+    }
+
+    #[tokio::test]
+    async fn unreachable_pool_or_wallet_is_down() {
+        let db = TempDatabase::new().await;
+        let report = health("http://127.0.0.1:1", 0).report(&db, false).await;
+
+        assert_eq!(report.status, HealthStatus::Down);
+        let xmr = &report.coins["xmr"];
+        assert_eq!(xmr.status, HealthStatus::Down);
+        assert!(!xmr.pool_online && !xmr.wallet_reachable);
+        assert_eq!(xmr.problems[0], "pool API is offline");
+        assert!(xmr.problems[1].starts_with("wallet RPC unreachable"));
+    }
+
+    #[tokio::test]
+    async fn stages_degrade_after_missed_intervals() {
+        let db = TempDatabase::new().await;
+        let url = backend().await;
+
+        // Share sync and block checks may miss three 60s intervals
+        let report = health(&url, 170).report(&db, false).await;
+        assert_eq!(report.status, HealthStatus::Ok);
+
+        // Payment cycles get two 600s intervals plus one share interval
+        let health = health(&url, 1_300);
+        let report = health.report(&db, false).await;
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(
+            report.coins["xmr"].problems,
+            vec![
+                "no successful share sync since startup",
+                "no successful block check since startup",
+                "no successful payment cycle since startup",
+            ]
+        );
+
+        health.record_share_sync(Coin::Xmr, Utc::now().timestamp() - 30);
+        health.record_block_check(Coin::Xmr);
+        let report = health.report(&db, false).await;
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(report.coins["xmr"].problems.len(), 1);
+        assert!(matches!(report.coins["xmr"].ingestion_lag_secs, Some(30..=31)));
+
+        // A paused coin skips payment cycles on purpose
+        db.set_payout_pause(Some(Coin::Xmr), "maintenance", "api")
+            .await
+            .unwrap();
+        let report = health.report(&db, false).await;
+        assert_eq!(report.status, HealthStatus::Ok);
+        assert!(report.coins["xmr"].payouts_paused);
+    }
+}
@@ -1,44 +1,84 @@
 //! Solo Pool Payment Processor
 //!
-//! Tracks shares and distributes rewards to miners for XMR, XTM, and ALEO pools.
+//! Tracks shares and distributes rewards to miners for XMR, XTM, ALEO, and
+//! the bitcoind-family (BTC, BCH, DGB) pools.
 //!
 //! Supports multiple mining modes:
 //! - monero_only: XMR via monero-pool
 //! - merge: XMR + XTM via merge mining proxy
 //! - tari_only: Direct solo mining (no payment processor needed for XTM)
+//! - ckpool: BTC / BCH / DGB via CKPool in pool mode
 
+mod accounting;
 mod api;
+mod cli;
 mod config;
+mod consolidation;
 mod db;
+mod distribution;
+mod events;
+mod health;
+mod metrics;
+mod notify;
 mod pools;
 mod processor;
+mod scheduler;
+mod shutdown;
+mod solvency;
+mod wallet_manager;
 mod wallets;
+mod webhooks;
 
 use api::ApiState;
-use config::{Config, PoolType};
+use clap::Parser;
+use cli::Cli;
+use config::{
+    Config, PoolType, Severity, DEFAULT_ALEO_POOL_API_URL, DEFAULT_XMR_POOL_API_URL,
+    DEFAULT_XTM_MERGE_PROXY_API_URL, DEFAULT_XTM_MINOTARI_API_URL,
+};
+use consolidation::Consolidator;
 use db::{Coin, Database};
-use pools::{aleo::AleoPoolApi, minotari::MinotariMinerApi, monero_pool::MoneroPoolApi, tari::TariMergeProxyApi, PoolApi};
-use processor::CoinProcessor;
-use wallets::{aleo::AleoWallet, monero::MoneroWallet, tari::TariWallet, Wallet};
-
+use distribution::PoolFee;
+use events::EventBus;
+use health::Health;
+use metrics::Metrics;
+use notify::{BlockNotify, ZmqTopic};
+use pools::{
+    aleo::AleoPoolApi, ckpool::CkPoolApi, minotari::MinotariMinerApi, monero_pool::MoneroPoolApi,
+    tari::TariMergeProxyApi, PoolApi,
+};
+use processor::{CoinProcessor, CoinSettings};
+use scheduler::Scheduler;
+use shutdown::Shutdown;
+use wallet_manager::WalletManager;
+use wallets::{
+    aleo::AleoWallet, bitcoind::BitcoindWallet, monero::MoneroWallet, tari::TariWallet, Wallet,
+};
+use webhooks::Webhooks;
+
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::signal;
 use tokio::sync::broadcast;
-use tokio::time::{interval, Duration};
-use tracing::{error, info, Level};
+use tokio::time::Duration;
+use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Parse command line args
-    let config_path = std::env::args()
-        .nth(1)
-        .unwrap_or_else(|| "/opt/solopool/payments/config/config.toml".to_string());
+    let cli = Cli::parse();
+    let config_path = cli.config_path().to_string();
 
     // Load configuration
     let config = Config::load_with_env(&config_path)?;
 
+    // Operator subcommands run once and exit
+    if let Some(command) = cli.command {
+        let code = cli::run(command, config).await?;
+        std::process::exit(code);
+    }
+
     // Initialize logging
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(&config.service.log_level));
@@ -50,6 +90,14 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Starting Solo Pool Payment Processor");
 
+    // Surface config problems early (run `check-config` for the full report)
+    for issue in config.validate() {
+        match issue.severity {
+            Severity::Error => error!(section = issue.section, "Config error: {}", issue.message),
+            Severity::Warning => warn!(section = issue.section, "Config warning: {}", issue.message),
+        }
+    }
+
     // Initialize database
     let db = Database::new(&config.database.path).await?;
     info!("Database initialized at {:?}", config.database.path);
@@ -57,10 +105,48 @@ async fn main() -> anyhow::Result<()> {
     // Create shutdown channel
     let (shutdown_tx, _) = broadcast::channel::<()>(1);
 
+    // Tracks running cycles so shutdown can drain them
+    let shutdown = Shutdown::new();
+
+    // Metrics shared by the API server and processors
+    let metrics = Metrics::new();
+
+    // Wallet clients for on-demand payment proofs (only Monero signs them)
+    let mut proof_wallets: HashMap<Coin, Arc<dyn Wallet>> = HashMap::new();
+    if let Some(xmr_config) = config.xmr.as_ref().filter(|c| c.enabled) {
+        let wallet = MoneroWallet::new(&xmr_config.wallet_rpc_url, xmr_config.mixin);
+        proof_wallets.insert(Coin::Xmr, Arc::new(wallet));
+    }
+
+    // Wakes processors when a node announces a new block
+    let block_notify = BlockNotify::new();
+
+    // Cycle timestamps and probe targets for /api/health
+    let health = Health::new(
+        config.service.share_scan_interval_secs,
+        config.service.payment_interval_secs,
+    );
+
+    // Blocks, rewards and payments for /api/events subscribers
+    let events = EventBus::new(db.clone());
+
+    // Runs every coin's processing and payment jobs and records their history
+    let scheduler = Scheduler::new(db.clone(), config.service.schedule_jitter_secs);
+
     // Start API server
     let api_state = ApiState {
         db: db.clone(),
         api_token: config.api.token.clone(),
+        metrics: metrics.clone(),
+        proof_wallets,
+        block_notify: block_notify.clone(),
+        health: health.clone(),
+        scheduler: scheduler.clone(),
+        events: events.clone(),
+        pool_fees: Coin::ALL
+            .into_iter()
+            .filter_map(|coin| PoolFee::from_config(config.pool_fee(coin)).map(|fee| (coin, fee)))
+            .collect(),
     };
     let api_router = api::create_router(api_state);
 
@@ -97,64 +183,83 @@ async fn main() -> anyhow::Result<()> {
             let share_interval = config.service.share_scan_interval_secs;
             let payment_interval = config.service.payment_interval_secs;
             let wallet = MoneroWallet::new(&xmr_config.wallet_rpc_url, xmr_config.mixin);
-            let pool_api_url = xmr_config.pool_api_url.as_deref().unwrap_or("http://127.0.0.1:4243");
+            let pool_api_url = xmr_config.pool_api_url.as_deref().unwrap_or(DEFAULT_XMR_POOL_API_URL);
+            let consolidator = xmr_config
+                .consolidation
+                .clone()
+                .filter(|c| c.enabled)
+                .map(|c| {
+                    let wallet = MoneroWallet::new(&xmr_config.wallet_rpc_url, xmr_config.mixin);
+                    Consolidator::new(Coin::Xmr, wallet, db.clone(), c)
+                });
+            let wallet_manager = xmr_config
+                .wallet_manager
+                .clone()
+                .filter(|m| m.enabled)
+                .map(|m| {
+                    let wallet = MoneroWallet::new(&xmr_config.wallet_rpc_url, xmr_config.mixin);
+                    WalletManager::new(Coin::Xmr, wallet, &xmr_config.pool_wallet_address, m)
+                });
 
             match xmr_config.pool_type {
                 PoolType::MoneroPool => {
                     // monero-pool API for monero_only mode
-                    let processor_shutdown = shutdown_tx.subscribe();
-                    let payment_shutdown = shutdown_tx.subscribe();
-
                     let pool_api = MoneroPoolApi::new(pool_api_url);
-                    let processor = Arc::new(CoinProcessor::new(
+                    let mut processor = CoinProcessor::new(
                         Coin::Xmr,
                         pool_api,
                         wallet,
                         db.clone(),
-                        xmr_config.min_payout,
-                    ));
-
-                    let proc = processor.clone();
-                    handles.push(tokio::spawn(async move {
-                        run_processor_loop(proc, share_interval, processor_shutdown).await;
-                    }));
-
-                    let proc = processor.clone();
-                    handles.push(tokio::spawn(async move {
-                        run_payment_loop(proc, payment_interval, payment_shutdown).await;
-                    }));
+                        CoinSettings::from(xmr_config),
+                        metrics.clone(),
+                        shutdown.clone(),
+                    )
+                    .with_health(health.clone())
+                    .with_events(events.clone());
+                    if let Some(consolidator) = consolidator {
+                        processor = processor.with_consolidation(consolidator);
+                    }
+                    if let Some(wallet_manager) = wallet_manager {
+                        processor = processor.with_wallet_manager(wallet_manager);
+                    }
+                    let processor = Arc::new(processor);
+
+                    schedule_processor(&scheduler, processor, share_interval, payment_interval, &block_notify);
 
                     info!("XMR payment processor started (monero-pool mode)");
                 }
                 PoolType::MergeProxy => {
                     // Merge mining proxy API for merge mode
-                    let processor_shutdown = shutdown_tx.subscribe();
-                    let payment_shutdown = shutdown_tx.subscribe();
-
                     let pool_api = TariMergeProxyApi::new(pool_api_url);
-                    let processor = Arc::new(CoinProcessor::new(
+                    let mut processor = CoinProcessor::new(
                         Coin::Xmr,
                         pool_api,
                         wallet,
                         db.clone(),
-                        xmr_config.min_payout,
-                    ));
-
-                    let proc = processor.clone();
-                    handles.push(tokio::spawn(async move {
-                        run_processor_loop(proc, share_interval, processor_shutdown).await;
-                    }));
-
-                    let proc = processor.clone();
-                    handles.push(tokio::spawn(async move {
-                        run_payment_loop(proc, payment_interval, payment_shutdown).await;
-                    }));
+                        CoinSettings::from(xmr_config),
+                        metrics.clone(),
+                        shutdown.clone(),
+                    )
+                    .with_health(health.clone())
+                    .with_events(events.clone());
+                    if let Some(consolidator) = consolidator {
+                        processor = processor.with_consolidation(consolidator);
+                    }
+                    if let Some(wallet_manager) = wallet_manager {
+                        processor = processor.with_wallet_manager(wallet_manager);
+                    }
+                    let processor = Arc::new(processor);
+
+                    schedule_processor(&scheduler, processor, share_interval, payment_interval, &block_notify);
 
                     info!("XMR payment processor started (merge mining mode)");
                 }
-                PoolType::MinotariMiner => {
-                    // MinotariMiner is not used for XMR, log warning
-                    error!("Invalid pool_type 'minotari_miner' for XMR - XMR requires 'monero_pool' or 'merge_proxy'");
+                PoolType::MinotariMiner | PoolType::Ckpool => {
+                    // Not used for XMR, log warning
+                    error!(
+                        "Invalid pool_type '{}' for XMR - XMR requires 'monero_pool' or 'merge_proxy'",
+                        xmr_config.pool_type.as_str()
+                    );
                 }
             }
         }
@@ -171,9 +276,7 @@ async fn main() -> anyhow::Result<()> {
             match xtm_config.pool_type {
                 PoolType::MergeProxy => {
                     // Merge mining proxy API for merge mode
-                    let processor_shutdown = shutdown_tx.subscribe();
-                    let payment_shutdown = shutdown_tx.subscribe();
-                    let pool_api_url = xtm_config.pool_api_url.as_deref().unwrap_or("http://127.0.0.1:18081");
+                    let pool_api_url = xtm_config.pool_api_url.as_deref().unwrap_or(DEFAULT_XTM_MERGE_PROXY_API_URL);
 
                     let pool_api = TariMergeProxyApi::new(pool_api_url);
                     let processor = Arc::new(CoinProcessor::new(
@@ -181,26 +284,20 @@ async fn main() -> anyhow::Result<()> {
                         pool_api,
                         wallet,
                         db.clone(),
-                        xtm_config.min_payout,
-                    ));
+                        CoinSettings::from(xtm_config),
+                        metrics.clone(),
+                        shutdown.clone(),
+                    )
+                    .with_health(health.clone())
+                    .with_events(events.clone()));
 
-                    let proc = processor.clone();
-                    handles.push(tokio::spawn(async move {
-                        run_processor_loop(proc, share_interval, processor_shutdown).await;
-                    }));
-
-                    let proc = processor.clone();
-                    handles.push(tokio::spawn(async move {
-                        run_payment_loop(proc, payment_interval, payment_shutdown).await;
-                    }));
+                    schedule_processor(&scheduler, processor, share_interval, payment_interval, &block_notify);
 
                     info!("XTM payment processor started (merge mining mode)");
                 }
                 PoolType::MinotariMiner => {
                     // Minotari miner API for tari_only mode
-                    let processor_shutdown = shutdown_tx.subscribe();
-                    let payment_shutdown = shutdown_tx.subscribe();
-                    let pool_api_url = xtm_config.pool_api_url.as_deref().unwrap_or("http://127.0.0.1:18084");
+                    let pool_api_url = xtm_config.pool_api_url.as_deref().unwrap_or(DEFAULT_XTM_MINOTARI_API_URL);
 
                     let pool_api = MinotariMinerApi::new(pool_api_url);
                     let processor = Arc::new(CoinProcessor::new(
@@ -208,24 +305,23 @@ async fn main() -> anyhow::Result<()> {
                         pool_api,
                         wallet,
                         db.clone(),
-                        xtm_config.min_payout,
-                    ));
-
-                    let proc = processor.clone();
-                    handles.push(tokio::spawn(async move {
-                        run_processor_loop(proc, share_interval, processor_shutdown).await;
-                    }));
+                        CoinSettings::from(xtm_config),
+                        metrics.clone(),
+                        shutdown.clone(),
+                    )
+                    .with_health(health.clone())
+                    .with_events(events.clone()));
 
-                    let proc = processor.clone();
-                    handles.push(tokio::spawn(async move {
-                        run_payment_loop(proc, payment_interval, payment_shutdown).await;
-                    }));
+                    schedule_processor(&scheduler, processor, share_interval, payment_interval, &block_notify);
 
                     info!("XTM payment processor started (tari_only mode)");
                 }
-                PoolType::MoneroPool => {
-                    // MoneroPool is not used for XTM, log warning
-                    error!("Invalid pool_type 'monero_pool' for XTM - XTM requires 'merge_proxy' or 'minotari_miner'");
+                PoolType::MoneroPool | PoolType::Ckpool => {
+                    // Not used for XTM, log warning
+                    error!(
+                        "Invalid pool_type '{}' for XTM - XTM requires 'merge_proxy' or 'minotari_miner'",
+                        xtm_config.pool_type.as_str()
+                    );
                 }
             }
         }
@@ -234,13 +330,11 @@ async fn main() -> anyhow::Result<()> {
     // ALEO processor
     if let Some(ref aleo_config) = config.aleo {
         if aleo_config.enabled {
-            let processor_shutdown = shutdown_tx.subscribe();
-            let payment_shutdown = shutdown_tx.subscribe();
             let share_interval = config.service.share_scan_interval_secs;
             let payment_interval = config.service.payment_interval_secs;
 
             let pool_api = AleoPoolApi::new(
-                aleo_config.pool_api_url.as_deref().unwrap_or("http://127.0.0.1:4000"),
+                aleo_config.pool_api_url.as_deref().unwrap_or(DEFAULT_ALEO_POOL_API_URL),
             );
             let wallet = AleoWallet::new(
                 &aleo_config.node_rpc_url,
@@ -252,40 +346,144 @@ async fn main() -> anyhow::Result<()> {
                 pool_api,
                 wallet,
                 db.clone(),
-                aleo_config.min_payout,
-            ));
-
-            // Share sync task
-            let proc = processor.clone();
-            handles.push(tokio::spawn(async move {
-                run_processor_loop(proc, share_interval, processor_shutdown).await;
-            }));
+                CoinSettings::from(aleo_config),
+                metrics.clone(),
+                shutdown.clone(),
+            )
+            .with_health(health.clone())
+            .with_events(events.clone()));
 
-            // Payment task
-            let proc = processor.clone();
-            handles.push(tokio::spawn(async move {
-                run_payment_loop(proc, payment_interval, payment_shutdown).await;
-            }));
+            schedule_processor(&scheduler, processor, share_interval, payment_interval, &block_notify);
 
             info!("ALEO payment processor started");
         }
     }
 
+    // BTC / BCH / DGB processors
+    // CKPool in pool mode pays every coinbase to the pool wallet; shares come from its share logs
+    for (coin, coin_config) in [
+        (Coin::Btc, &config.btc),
+        (Coin::Bch, &config.bch),
+        (Coin::Dgb, &config.dgb),
+    ] {
+        let Some(coin_config) = coin_config.as_ref().filter(|c| c.enabled) else {
+            continue;
+        };
+        if coin_config.pool_type != PoolType::Ckpool {
+            error!(
+                "Invalid pool_type '{}' for {} - {} requires 'ckpool'",
+                coin_config.pool_type.as_str(),
+                coin.to_string().to_uppercase(),
+                coin.to_string().to_uppercase()
+            );
+            continue;
+        }
+
+        let share_interval = config.service.share_scan_interval_secs;
+        let payment_interval = config.service.payment_interval_secs;
+
+        let wallet = BitcoindWallet::from_config(coin, coin_config);
+        let pool_api = CkPoolApi::new(&coin_config.pool_data_path, wallet.clone());
+        let processor = Arc::new(CoinProcessor::new(
+            coin,
+            pool_api,
+            wallet,
+            db.clone(),
+            CoinSettings::from(coin_config),
+            metrics.clone(),
+            shutdown.clone(),
+        )
+        .with_health(health.clone())
+        .with_events(events.clone()));
+
+        schedule_processor(&scheduler, processor, share_interval, payment_interval, &block_notify);
+
+        info!("{} payment processor started (ckpool mode)", coin.to_string().to_uppercase());
+    }
+
+    // Chain tip subscriptions, so processing doesn't wait for the next interval
+    for (coin, coin_config) in [
+        (Coin::Xmr, &config.xmr),
+        (Coin::Btc, &config.btc),
+        (Coin::Bch, &config.bch),
+        (Coin::Dgb, &config.dgb),
+    ] {
+        let Some(coin_config) = coin_config.as_ref().filter(|c| c.enabled) else {
+            continue;
+        };
+        let (Some(zmq_url), Some(topic)) = (coin_config.zmq_url.clone(), ZmqTopic::for_coin(coin)) else {
+            continue;
+        };
+
+        let zmq_shutdown = shutdown_tx.subscribe();
+        let notify = block_notify.clone();
+        handles.push(tokio::spawn(async move {
+            notify::subscribe(coin, zmq_url, topic, notify, zmq_shutdown).await;
+        }));
+    }
+
+    // Deliver events to [[webhooks]] targets before any job can publish
+    let webhooks = Webhooks::new(db.clone(), &config.webhooks);
+    if !webhooks.is_empty() {
+        handles.extend(webhooks.start(&events, &shutdown_tx).await?);
+    }
+
+    handles.extend(scheduler.start(&shutdown_tx));
+
+    let events_shutdown = shutdown_tx.subscribe();
+    handles.push(tokio::spawn(async move {
+        events.prune_history(events_shutdown).await;
+    }));
+
     info!("Payment processor ready");
 
     // Wait for shutdown signal
-    tokio::select! {
-        _ = signal::ctrl_c() => {
-            info!("Received Ctrl+C, shutting down...");
+    let signal_name = shutdown::wait_for_signal().await;
+    info!(signal = signal_name, "Received shutdown signal, draining running cycles...");
+
+    // Stop starting new cycles and give running ones time to finish
+    shutdown.begin_drain();
+    let drain_timeout = Duration::from_secs(config.service.shutdown_timeout_secs);
+    let interrupted = shutdown.wait_for_cycles(drain_timeout).await;
+
+    for cycle in &interrupted {
+        if cycle.cycle == metrics::CYCLE_PAYMENT {
+            error!(
+                coin = %cycle.coin,
+                cycle = cycle.cycle,
+                running_secs = cycle.started.elapsed().as_secs(),
+                "Payment cycle still running at shutdown deadline, interrupting \
+                 after the payment being sent is recorded"
+            );
+        } else {
+            warn!(
+                coin = %cycle.coin,
+                cycle = cycle.cycle,
+                running_secs = cycle.started.elapsed().as_secs(),
+                "Cycle still running at shutdown deadline, interrupting"
+            );
         }
     }
 
+    // Never abort a payout between its wallet call and its record
+    if shutdown.payouts_in_flight() > 0 {
+        warn!(
+            payouts = shutdown.payouts_in_flight(),
+            "Waiting for payouts in flight to be recorded before exiting"
+        );
+        shutdown.wait_for_payouts().await;
+    }
+
     // Send shutdown signal
     let _ = shutdown_tx.send(());
 
-    // Wait for all tasks to complete
+    // Wait for all tasks to complete, aborting any cycle that overran the drain timeout
     for handle in handles {
-        let _ = handle.await;
+        if interrupted.is_empty() {
+            let _ = handle.await;
+        } else {
+            handle.abort();
+        }
     }
     let _ = api_handle.await;
 
@@ -293,50 +491,27 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Run the processor loop (share sync, block processing, reward distribution)
-async fn run_processor_loop<P: PoolApi + 'static, W: Wallet + 'static>(
+/// Register a processor's jobs: processing (share sync, blocks, rewards),
+/// also woken by new blocks, and payments
+fn schedule_processor<P: PoolApi + 'static, W: Wallet + 'static>(
+    scheduler: &Scheduler,
     processor: Arc<CoinProcessor<P, W>>,
-    interval_secs: u64,
-    mut shutdown: broadcast::Receiver<()>,
+    share_interval: u64,
+    payment_interval: u64,
+    block_notify: &BlockNotify,
 ) {
-    let mut ticker = interval(Duration::from_secs(interval_secs));
+    let coin = processor.coin();
 
-    loop {
-        tokio::select! {
-            _ = ticker.tick() => {
-                if let Err(e) = processor.run_cycle().await {
-                    error!("Processor cycle error: {}", e);
-                }
-            }
-            _ = shutdown.recv() => {
-                info!("Processor loop shutting down");
-                break;
-            }
-        }
-    }
-}
-
-/// Run the payment loop
-async fn run_payment_loop<P: PoolApi + 'static, W: Wallet + 'static>(
-    processor: Arc<CoinProcessor<P, W>>,
-    interval_secs: u64,
-    mut shutdown: broadcast::Receiver<()>,
-) {
-    let mut ticker = interval(Duration::from_secs(interval_secs));
+    let proc = processor.clone();
+    scheduler.add(coin, metrics::CYCLE_PROCESS, share_interval, Some(block_notify.waker(coin)), move || {
+        let proc = proc.clone();
+        async move { proc.run_cycle().await }
+    });
 
-    loop {
-        tokio::select! {
-            _ = ticker.tick() => {
-                if let Err(e) = processor.run_payment_cycle().await {
-                    error!("Payment cycle error: {}", e);
-                }
-            }
-            _ = shutdown.recv() => {
-                info!("Payment loop shutting down");
-                break;
-            }
-        }
-    }
+    scheduler.add(coin, metrics::CYCLE_PAYMENT, payment_interval, None, move || {
+        let proc = processor.clone();
+        async move { proc.run_payment_cycle().await }
+    });
 }
 
 /// Wait for shutdown signal
@@ -0,0 +1,452 @@
+//! Prometheus metrics for the payment processor
+//!
+//! Exposed at GET /metrics in the Prometheus text format.
+//! Counters and timings are recorded by the coin processors as they run;
+//! values that live in the database (pending liabilities, payments and
+//! blocks by status, held payouts) are refreshed from SQLite on every scrape.
+
+use crate::db::{Coin, Database, PaymentStatus, RewardStatus};
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+/// Cycle label for the share sync / block / distribution cycle
+pub const CYCLE_PROCESS: &str = "process";
+/// Cycle label for the payout cycle
+pub const CYCLE_PAYMENT: &str = "payment";
+
+/// Metric registry shared by the API server and all coin processors
+///
+/// Cloning is cheap - all metric families are reference counted.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    shares_ingested: IntCounterVec,
+    ingestion_lag: GaugeVec,
+    blocks_recorded: IntCounterVec,
+    blocks_distributed: IntCounterVec,
+    pending_liabilities: GaugeVec,
+    wallet_unlocked_balance: GaugeVec,
+    wallet_total_balance: GaugeVec,
+    payments: IntGaugeVec,
+    blocks: IntGaugeVec,
+    payouts_held: IntGaugeVec,
+    payouts_paused: IntGaugeVec,
+    cycle_duration: HistogramVec,
+    cycle_errors: IntCounterVec,
+}
+
+impl Metrics {
+    /// Create and register all metric families
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let shares_ingested = IntCounterVec::new(
+            Opts::new(
+                "solopool_payments_shares_ingested_total",
+                "Shares synced from the pool API into the database",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let ingestion_lag = GaugeVec::new(
+            Opts::new(
+                "solopool_payments_share_ingestion_lag_seconds",
+                "Age of the newest share ingested from the pool API",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let blocks_recorded = IntCounterVec::new(
+            Opts::new(
+                "solopool_payments_blocks_recorded_total",
+                "Blocks found by the pool and recorded in the database",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let blocks_distributed = IntCounterVec::new(
+            Opts::new(
+                "solopool_payments_blocks_distributed_total",
+                "Blocks whose reward has been distributed to miner balances",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let pending_liabilities = GaugeVec::new(
+            Opts::new(
+                "solopool_payments_pending_liabilities_atomic",
+                "Sum of unpaid miner balances in atomic units",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let wallet_unlocked_balance = GaugeVec::new(
+            Opts::new(
+                "solopool_payments_wallet_unlocked_balance_atomic",
+                "Pool wallet unlocked balance in atomic units",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let wallet_total_balance = GaugeVec::new(
+            Opts::new(
+                "solopool_payments_wallet_total_balance_atomic",
+                "Pool wallet total balance (including locked) in atomic units",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let payments = IntGaugeVec::new(
+            Opts::new(
+                "solopool_payments_payments",
+                "Number of payment records by status",
+            ),
+            &["coin", "status"],
+        )
+        .expect("valid metric");
+
+        let blocks = IntGaugeVec::new(
+            Opts::new(
+                "solopool_payments_blocks",
+                "Number of recorded blocks by reward status",
+            ),
+            &["coin", "reward_status"],
+        )
+        .expect("valid metric");
+
+        let payouts_held = IntGaugeVec::new(
+            Opts::new(
+                "solopool_payments_payouts_held",
+                "1 while payouts are held for an unconfirmed wallet consolidation",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let payouts_paused = IntGaugeVec::new(
+            Opts::new(
+                "solopool_payments_payouts_paused",
+                "1 while payouts are paused by an operator or a tripped payout limit",
+            ),
+            &["coin"],
+        )
+        .expect("valid metric");
+
+        let cycle_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "solopool_payments_cycle_duration_seconds",
+                "Duration of processor and payment cycles",
+            )
+            .buckets(vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0]),
+            &["coin", "cycle"],
+        )
+        .expect("valid metric");
+
+        let cycle_errors = IntCounterVec::new(
+            Opts::new(
+                "solopool_payments_cycle_errors_total",
+                "Errors raised by a step of a processor or payment cycle",
+            ),
+            &["coin", "cycle", "stage"],
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(shares_ingested.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(ingestion_lag.clone()),
+            Box::new(blocks_recorded.clone()),
+            Box::new(blocks_distributed.clone()),
+            Box::new(pending_liabilities.clone()),
+            Box::new(wallet_unlocked_balance.clone()),
+            Box::new(wallet_total_balance.clone()),
+            Box::new(payments.clone()),
+            Box::new(blocks.clone()),
+            Box::new(payouts_held.clone()),
+            Box::new(payouts_paused.clone()),
+            Box::new(cycle_duration.clone()),
+            Box::new(cycle_errors.clone()),
+        ] {
+            registry.register(collector).expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            shares_ingested,
+            ingestion_lag,
+            blocks_recorded,
+            blocks_distributed,
+            pending_liabilities,
+            wallet_unlocked_balance,
+            wallet_total_balance,
+            payments,
+            blocks,
+            payouts_held,
+            payouts_paused,
+            cycle_duration,
+            cycle_errors,
+        }
+    }
+
+    /// Record shares synced from the pool API and the age of the newest one
+    pub fn record_shares(&self, coin: Coin, count: u64, newest_timestamp: i64) {
+        let label = coin.to_string();
+        self.shares_ingested.with_label_values(&[&label]).inc_by(count);
+
+        if newest_timestamp > 0 {
+            let lag = (chrono::Utc::now().timestamp() - newest_timestamp).max(0);
+            self.ingestion_lag
+                .with_label_values(&[&label])
+                .set(lag as f64);
+        }
+    }
+
+    /// Record newly found blocks
+    pub fn record_blocks(&self, coin: Coin, count: u64) {
+        self.blocks_recorded
+            .with_label_values(&[&coin.to_string()])
+            .inc_by(count);
+    }
+
+    /// Record a block whose reward was distributed
+    pub fn record_distribution(&self, coin: Coin) {
+        self.blocks_distributed
+            .with_label_values(&[&coin.to_string()])
+            .inc();
+    }
+
+    /// Record the pool wallet balances
+    pub fn set_wallet_balance(&self, coin: Coin, unlocked: Decimal, total: Decimal) {
+        let label = coin.to_string();
+        self.wallet_unlocked_balance
+            .with_label_values(&[&label])
+            .set(unlocked.to_f64().unwrap_or(0.0));
+        self.wallet_total_balance
+            .with_label_values(&[&label])
+            .set(total.to_f64().unwrap_or(0.0));
+    }
+
+    /// Record how long a cycle took
+    pub fn observe_cycle(&self, coin: Coin, cycle: &str, seconds: f64) {
+        self.cycle_duration
+            .with_label_values(&[&coin.to_string(), cycle])
+            .observe(seconds);
+    }
+
+    /// Record a failed step of a cycle
+    pub fn record_cycle_error(&self, coin: Coin, cycle: &str, stage: &str) {
+        self.cycle_errors
+            .with_label_values(&[&coin.to_string(), cycle, stage])
+            .inc();
+    }
+
+    /// Refresh database-backed gauges and encode all metrics
+    pub async fn render(&self, db: &Database) -> anyhow::Result<String> {
+        for coin in Coin::ALL {
+            let label = coin.to_string();
+
+            let balances = db.get_payable_balances(coin, Decimal::from(0)).await?;
+            let pending: Decimal = balances.iter().map(|b| b.pending_balance).sum();
+            self.pending_liabilities
+                .with_label_values(&[&label])
+                .set(pending.to_f64().unwrap_or(0.0));
+
+            let counts = db.count_payments_by_status(coin).await?;
+            for status in PaymentStatus::ALL {
+                let count = counts
+                    .iter()
+                    .find(|(s, _)| *s == status)
+                    .map(|(_, c)| *c)
+                    .unwrap_or(0);
+                self.payments
+                    .with_label_values(&[&label, status.as_str()])
+                    .set(count);
+            }
+
+            let counts = db.count_blocks_by_reward_status(coin).await?;
+            for status in RewardStatus::ALL {
+                let count = counts
+                    .iter()
+                    .filter(|(s, _)| *s == status)
+                    .map(|(_, c)| *c)
+                    .sum();
+                self.blocks
+                    .with_label_values(&[&label, status.as_str()])
+                    .set(count);
+            }
+
+            let held = db.has_pending_consolidation(coin).await?;
+            self.payouts_held
+                .with_label_values(&[&label])
+                .set(held as i64);
+
+            let paused = db.get_payout_pause(coin).await?.is_some();
+            self.payouts_paused
+                .with_label_values(&[&label])
+                .set(paused as i64);
+        }
+
+        let encoder = TextEncoder::new();
+        let mut buffer = Vec::new();
+        encoder.encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{BlockCredit, TempDatabase};
+
+    /// Whether `rendered` has a sample line for `series` with `value`
+    fn has_sample(rendered: &str, series: &str, value: &str) -> bool {
+        rendered
+            .lines()
+            .any(|line| line == format!("{} {}", series, value))
+    }
+
+    #[tokio::test]
+    async fn database_gauges_are_refreshed_on_every_scrape() {
+        let db = TempDatabase::new().await;
+        let metrics = Metrics::new();
+
+        db.record_share(Coin::Xmr, "alice", "rig", Decimal::ONE, None, false)
+            .await
+            .unwrap();
+        db.record_block(
+            Coin::Xmr,
+            100,
+            "a",
+            Decimal::from(900),
+            "alice",
+            "rig",
+            RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        let block = db
+            .get_undistributed_blocks(Coin::Xmr)
+            .await
+            .unwrap()
+            .remove(0);
+        let credit = BlockCredit {
+            wallet_address: "alice".to_string(),
+            amount: Decimal::from(900),
+            carry: Decimal::ZERO,
+            memo: None,
+        };
+        db.apply_block_distribution(&block, &[credit])
+            .await
+            .unwrap();
+        db.create_payment(Coin::Xmr, "alice", Decimal::from(900))
+            .await
+            .unwrap();
+        db.set_payout_pause(Some(Coin::Xmr), "maintenance", "operator")
+            .await
+            .unwrap();
+
+        let rendered = metrics.render(&db).await.unwrap();
+        for (series, value) in [
+            (
+                "solopool_payments_pending_liabilities_atomic{coin=\"xmr\"}",
+                "900",
+            ),
+            (
+                "solopool_payments_payments{coin=\"xmr\",status=\"pending\"}",
+                "1",
+            ),
+            (
+                "solopool_payments_payments{coin=\"xmr\",status=\"failed\"}",
+                "0",
+            ),
+            (
+                "solopool_payments_blocks{coin=\"xmr\",reward_status=\"reported\"}",
+                "1",
+            ),
+            ("solopool_payments_payouts_paused{coin=\"xmr\"}", "1"),
+            ("solopool_payments_payouts_paused{coin=\"btc\"}", "0"),
+            ("solopool_payments_payouts_held{coin=\"xmr\"}", "0"),
+        ] {
+            assert!(has_sample(&rendered, series, value), "{} {}", series, value);
+        }
+
+        // A global pause covers every coin, and clearing it shows on the next scrape
+        db.clear_payout_pause(Some(Coin::Xmr)).await.unwrap();
+        db.set_payout_pause(None, "maintenance", "operator")
+            .await
+            .unwrap();
+        let rendered = metrics.render(&db).await.unwrap();
+        assert!(has_sample(
+            &rendered,
+            "solopool_payments_payouts_paused{coin=\"btc\"}",
+            "1"
+        ));
+        db.clear_payout_pause(None).await.unwrap();
+        let rendered = metrics.render(&db).await.unwrap();
+        assert!(has_sample(
+            &rendered,
+            "solopool_payments_payouts_paused{coin=\"xmr\"}",
+            "0"
+        ));
+    }
+
+    #[tokio::test]
+    async fn cycle_counters_are_labelled_by_coin_cycle_and_stage() {
+        let db = TempDatabase::new().await;
+        let metrics = Metrics::new();
+
+        metrics.record_shares(Coin::Xtm, 3, chrono::Utc::now().timestamp() - 30);
+        metrics.record_shares(Coin::Xtm, 2, 0);
+        metrics.record_cycle_error(Coin::Xtm, CYCLE_PAYMENT, "process_payments");
+        metrics.record_cycle_error(Coin::Xtm, CYCLE_PAYMENT, "process_payments");
+        metrics.record_cycle_error(Coin::Xtm, CYCLE_PROCESS, "sync_shares");
+        metrics.observe_cycle(Coin::Xtm, CYCLE_PROCESS, 0.3);
+        metrics.set_wallet_balance(Coin::Xtm, Decimal::from(1_500), Decimal::from(2_000));
+
+        let rendered = metrics.render(&db).await.unwrap();
+        for (series, value) in [
+            ("solopool_payments_shares_ingested_total{coin=\"xtm\"}", "5"),
+            (
+                "solopool_payments_cycle_errors_total{coin=\"xtm\",cycle=\"payment\",stage=\"process_payments\"}",
+                "2",
+            ),
+            (
+                "solopool_payments_cycle_errors_total{coin=\"xtm\",cycle=\"process\",stage=\"sync_shares\"}",
+                "1",
+            ),
+            (
+                "solopool_payments_cycle_duration_seconds_bucket{coin=\"xtm\",cycle=\"process\",le=\"0.5\"}",
+                "1",
+            ),
+            ("solopool_payments_wallet_unlocked_balance_atomic{coin=\"xtm\"}", "1500"),
+            ("solopool_payments_wallet_total_balance_atomic{coin=\"xtm\"}", "2000"),
+        ] {
+            assert!(has_sample(&rendered, series, value), "{} {}", series, value);
+        }
+
+        // A batch without timestamps leaves the last known lag alone
+        let lag = rendered
+            .lines()
+            .find_map(|line| {
+                line.strip_prefix("solopool_payments_share_ingestion_lag_seconds{coin=\"xtm\"} ")
+            })
+            .unwrap();
+        assert!(matches!(lag, "30" | "31"), "lag {}", lag);
+    }
+}
@@ -0,0 +1,312 @@
+//! Chain tip notifications
+//!
+//! Processing cycles run every `share_scan_interval_secs`, so a found block
+//! can wait that long before it is recorded and credited. When a node
+//! announces a new chain tip, the coin's processor is woken right away; the
+//! interval timer stays as the fallback.
+//!
+//! Sources:
+//! - monerod's ZMQ `json-minimal-chain_main` topic (`--zmq-pub`)
+//! - bitcoind / BCHN / digibyted ZMQ `hashblock` (`zmqpubhashblock`)
+//! - `POST /api/notify/:coin`, for nodes that can run a block notify script
+
+use crate::db::Coin;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Notify};
+use tokio::time::Duration;
+use tracing::{debug, info, warn};
+use zeromq::{Socket, SocketRecv, SubSocket};
+
+/// Delay before resubscribing after the publisher went away
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Wakes coin processors when a new block arrives
+#[derive(Clone)]
+pub struct BlockNotify {
+    wakers: Arc<HashMap<Coin, Arc<Notify>>>,
+}
+
+impl BlockNotify {
+    pub fn new() -> Self {
+        let wakers = Coin::ALL
+            .into_iter()
+            .map(|coin| (coin, Arc::new(Notify::new())))
+            .collect();
+        Self {
+            wakers: Arc::new(wakers),
+        }
+    }
+
+    /// Wake the coin's processor; a wake-up while a cycle runs starts another right after
+    pub fn notify(&self, coin: Coin) {
+        self.wakers[&coin].notify_one();
+    }
+
+    /// The handle a coin's processor loop waits on
+    pub fn waker(&self, coin: Coin) -> Arc<Notify> {
+        self.wakers[&coin].clone()
+    }
+}
+
+impl Default for BlockNotify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ZMQ topic a node announces new chain tips on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZmqTopic {
+    /// monerod: `json-minimal-chain_main:{"first_height":..,"ids":[..]}`
+    MoneroChainMain,
+    /// bitcoind family: frames `hashblock`, 32-byte hash, sequence number
+    HashBlock,
+}
+
+impl ZmqTopic {
+    /// The topic for a coin's node, if it publishes one
+    pub fn for_coin(coin: Coin) -> Option<Self> {
+        match coin {
+            Coin::Xmr => Some(ZmqTopic::MoneroChainMain),
+            Coin::Btc | Coin::Bch | Coin::Dgb => Some(ZmqTopic::HashBlock),
+            Coin::Xtm | Coin::Aleo => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ZmqTopic::MoneroChainMain => "json-minimal-chain_main",
+            ZmqTopic::HashBlock => "hashblock",
+        }
+    }
+}
+
+/// A new chain tip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainTip {
+    /// Height of the tip, if the notification carries it
+    pub height: Option<u64>,
+    pub hash: String,
+}
+
+/// Decode a ZMQ notification; None if it isn't a chain tip on this topic
+pub fn parse_message(topic: ZmqTopic, frames: &[impl AsRef<[u8]>]) -> Option<ChainTip> {
+    match topic {
+        ZmqTopic::MoneroChainMain => {
+            let frame = std::str::from_utf8(frames.first()?.as_ref()).ok()?;
+            let json = frame.strip_prefix(topic.as_str())?.strip_prefix(':')?;
+            let chain: MoneroChainMain = serde_json::from_str(json).ok()?;
+            let hash = chain.ids.last()?.clone();
+            Some(ChainTip {
+                height: Some(chain.first_height + chain.ids.len() as u64 - 1),
+                hash,
+            })
+        }
+        ZmqTopic::HashBlock => {
+            if frames.first()?.as_ref() != topic.as_str().as_bytes() {
+                return None;
+            }
+            let hash = frames.get(1)?.as_ref();
+            if hash.len() != 32 {
+                return None;
+            }
+            Some(ChainTip {
+                height: None,
+                hash: hash.iter().map(|b| format!("{:02x}", b)).collect(),
+            })
+        }
+    }
+}
+
+/// Subscribe to a node's chain tip notifications until shutdown
+///
+/// Waits for the publisher if it isn't up yet and resubscribes if the
+/// connection is lost.
+pub async fn subscribe(
+    coin: Coin,
+    endpoint: String,
+    topic: ZmqTopic,
+    notify: BlockNotify,
+    mut shutdown: broadcast::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            result = listen(coin, &endpoint, topic, &notify) => {
+                if let Err(e) = result {
+                    warn!(coin = %coin, endpoint = %endpoint, error = %e, "ZMQ subscription lost, resubscribing");
+                }
+            }
+            _ = shutdown.recv() => break,
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(RESUBSCRIBE_DELAY) => {}
+            _ = shutdown.recv() => break,
+        }
+    }
+}
+
+/// Connect, subscribe and wake the processor on every new tip
+async fn listen(
+    coin: Coin,
+    endpoint: &str,
+    topic: ZmqTopic,
+    notify: &BlockNotify,
+) -> anyhow::Result<()> {
+    let mut socket = SubSocket::new();
+    socket.connect(endpoint).await?;
+    socket.subscribe(topic.as_str()).await?;
+    info!(coin = %coin, endpoint = %endpoint, topic = topic.as_str(), "Subscribed to chain tip notifications");
+
+    loop {
+        let message = socket.recv().await?;
+        match parse_message(topic, &message.into_vec()) {
+            Some(tip) => {
+                debug!(coin = %coin, height = ?tip.height, hash = %tip.hash, "New chain tip");
+                notify.notify(coin);
+            }
+            None => debug!(coin = %coin, "Ignoring unexpected ZMQ message"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MoneroChainMain {
+    first_height: u64,
+    #[serde(default)]
+    ids: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zeromq::{PubSocket, SocketSend, ZmqMessage};
+
+    const MONERO_TIP: &str = r#"json-minimal-chain_main:{"first_height":3100000,"first_prev_id":"aa","ids":["bb","cc"]}"#;
+
+    /// Bind a publisher on a free local port
+    async fn publisher() -> (PubSocket, String) {
+        let mut socket = PubSocket::new();
+        let endpoint = socket.bind("tcp://127.0.0.1:0").await.unwrap();
+        (socket, endpoint.to_string())
+    }
+
+    /// Publish until the subscriber wakes (it joins asynchronously)
+    async fn publish_until_woken(socket: &mut PubSocket, message: ZmqMessage, waker: &Notify) -> bool {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                socket.send(message.clone()).await.unwrap();
+                tokio::select! {
+                    _ = waker.notified() => return,
+                    _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+                }
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    fn hashblock_message() -> ZmqMessage {
+        let mut message = ZmqMessage::from("hashblock");
+        message.push_back(vec![0xab; 32].into());
+        message.push_back(1u32.to_le_bytes().to_vec().into());
+        message
+    }
+
+    #[test]
+    fn parses_monero_chain_main() {
+        let tip = parse_message(ZmqTopic::MoneroChainMain, &[MONERO_TIP]).unwrap();
+        assert_eq!(tip.height, Some(3_100_001));
+        assert_eq!(tip.hash, "cc");
+
+        let txpool = r#"json-minimal-txpool_add:[{"id":"dd"}]"#;
+        assert_eq!(parse_message(ZmqTopic::MoneroChainMain, &[txpool]), None);
+    }
+
+    #[test]
+    fn parses_hashblock() {
+        let frames = hashblock_message().into_vec();
+        let tip = parse_message(ZmqTopic::HashBlock, &frames).unwrap();
+        assert_eq!(tip.height, None);
+        assert_eq!(tip.hash, "ab".repeat(32));
+
+        assert_eq!(parse_message(ZmqTopic::HashBlock, &[&b"hashtx"[..], &[0u8; 32][..]]), None);
+        assert_eq!(parse_message(ZmqTopic::HashBlock, &[&b"hashblock"[..], &[0u8; 4][..]]), None);
+    }
+
+    #[tokio::test]
+    async fn monero_tip_wakes_processor() {
+        let (mut socket, endpoint) = publisher().await;
+        let notify = BlockNotify::new();
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let task = tokio::spawn(subscribe(
+            Coin::Xmr,
+            endpoint,
+            ZmqTopic::MoneroChainMain,
+            notify.clone(),
+            shutdown_tx.subscribe(),
+        ));
+
+        let woken = publish_until_woken(&mut socket, MONERO_TIP.into(), &notify.waker(Coin::Xmr)).await;
+        assert!(woken, "processor was not woken by chain_main");
+
+        shutdown_tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn hashblock_wakes_only_its_coin() {
+        let (mut socket, endpoint) = publisher().await;
+        let notify = BlockNotify::new();
+        let (shutdown_tx, _) = broadcast::channel(1);
+        tokio::spawn(subscribe(
+            Coin::Btc,
+            endpoint,
+            ZmqTopic::HashBlock,
+            notify.clone(),
+            shutdown_tx.subscribe(),
+        ));
+
+        let woken = publish_until_woken(&mut socket, hashblock_message(), &notify.waker(Coin::Btc)).await;
+        assert!(woken, "processor was not woken by hashblock");
+
+        // Other coins' processors keep their own schedule
+        let other = tokio::time::timeout(Duration::from_millis(200), notify.waker(Coin::Bch).notified()).await;
+        assert!(other.is_err());
+
+        shutdown_tx.send(()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn other_topics_are_ignored() {
+        let (mut socket, endpoint) = publisher().await;
+        let notify = BlockNotify::new();
+        let (shutdown_tx, _) = broadcast::channel(1);
+        tokio::spawn(subscribe(
+            Coin::Xmr,
+            endpoint,
+            ZmqTopic::MoneroChainMain,
+            notify.clone(),
+            shutdown_tx.subscribe(),
+        ));
+
+        // Wait until the subscription is live, then drain the permit it left
+        let waker = notify.waker(Coin::Xmr);
+        assert!(publish_until_woken(&mut socket, MONERO_TIP.into(), &waker).await);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let _ = tokio::time::timeout(Duration::from_millis(50), waker.notified()).await;
+
+        for _ in 0..5 {
+            socket
+                .send(r#"json-minimal-txpool_add:[{"id":"dd"}]"#.into())
+                .await
+                .unwrap();
+        }
+        let woken = tokio::time::timeout(Duration::from_millis(300), waker.notified()).await;
+        assert!(woken.is_err(), "txpool notification woke the processor");
+
+        shutdown_tx.send(()).unwrap();
+    }
+}
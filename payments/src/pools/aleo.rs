@@ -200,6 +200,7 @@ impl PoolApi for AleoPoolApi {
 // ALEO Pool API response types
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct AleoPoolStats {
     pool_hashrate: Option<f64>,
     connected_miners: Option<u32>,
@@ -210,6 +211,7 @@ struct AleoPoolStats {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct AleoMinerStats {
     address: Option<String>,
     hashrate: Option<f64>,
@@ -0,0 +1,360 @@
+//! CKPool integration (for BTC, BCH and DGB in pool mode)
+//!
+//! CKPool has no HTTP API, so this reads what it writes under its log
+//! directory:
+//! - `<height as 8 hex digits>/<workinfoid>.sharelog` - one JSON line per
+//!   share (requires `"logshares": true`)
+//! - `pool/pool.status` and `users/<username>` - hashrate and share counters
+//!
+//! Blocks come from the pool wallet instead: in pool mode every block pays its
+//! coinbase to `btcaddress`, which must be the configured pool wallet address.
+
+use super::{BlockInfo, MinerStats, PoolApi, PoolError, PoolResult, PoolStats, ShareInfo};
+use crate::wallets::bitcoind::{BitcoindWallet, CoinbaseTx};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// pool.status older than this means CKPool has stopped
+const STATUS_MAX_AGE: Duration = Duration::from_secs(300);
+
+/// A height directory gets a new share log with every work update, but the
+/// last one can still be appended to for a while after the chain moves on
+const HEIGHT_DIR_SLACK: Duration = Duration::from_secs(600);
+
+/// CKPool log reader
+pub struct CkPoolApi {
+    log_dir: PathBuf,
+    wallet: BitcoindWallet,
+    /// Share logs keep everything, so shares from before this start were
+    /// already recorded by a previous run
+    started_at: i64,
+}
+
+impl CkPoolApi {
+    /// Create a new CKPool reader
+    /// `log_dir` is CKPool's `logdir` (e.g. /opt/solopool/btc/ckpool/logs)
+    pub fn new(log_dir: &Path, wallet: BitcoindWallet) -> Self {
+        Self {
+            log_dir: log_dir.to_path_buf(),
+            wallet,
+            started_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Share log directories with their block height
+    fn height_dirs(&self) -> PoolResult<Vec<(i64, PathBuf)>> {
+        let entries = std::fs::read_dir(&self.log_dir).map_err(|e| {
+            PoolError::ConnectionFailed(format!("cannot read {:?}: {}", self.log_dir, e))
+        })?;
+
+        let mut dirs: Vec<(i64, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                let name = name.to_str()?;
+                if name.len() != 8 {
+                    return None;
+                }
+                let height = i64::from_str_radix(name, 16).ok()?;
+                Some((height, entry.path()))
+            })
+            .filter(|(_, path)| path.is_dir())
+            .collect();
+
+        dirs.sort_by_key(|(height, _)| *height);
+        Ok(dirs)
+    }
+
+    /// Accepted shares from the share logs in one height directory, skipping
+    /// files last written before `since_timestamp`
+    fn read_shares(dir: &Path, height: i64, since_timestamp: i64) -> PoolResult<Vec<ShareLogEntry>> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| PoolError::ApiError(format!("cannot read {:?}: {}", dir, e)))?;
+
+        let mut shares = Vec::new();
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("sharelog") {
+                continue;
+            }
+            if modified_secs(&path).is_some_and(|m| m < since_timestamp) {
+                continue;
+            }
+
+            // A file CKPool is appending to may end in a partial line
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| PoolError::ApiError(format!("cannot read {:?}: {}", path, e)))?;
+            for line in contents.lines() {
+                let Ok(mut share) = serde_json::from_str::<ShareLogEntry>(line) else {
+                    continue;
+                };
+                if !share.result {
+                    continue;
+                }
+                share.height = height;
+                shares.push(share);
+            }
+        }
+
+        Ok(shares)
+    }
+
+    /// Wallet and worker of the share that solved the block at `height`
+    ///
+    /// The solving share is the highest-difficulty share logged at that
+    /// height: it was the only one to meet the network target.
+    fn find_finder(&self, height: i64) -> (String, String) {
+        let dir = self.log_dir.join(format!("{:08x}", height));
+        let finder = Self::read_shares(&dir, height, 0)
+            .ok()
+            .and_then(|shares| {
+                shares
+                    .into_iter()
+                    .max_by(|a, b| a.sdiff.total_cmp(&b.sdiff))
+            });
+
+        match finder {
+            Some(share) => (share.wallet_address().to_string(), share.worker_name().to_string()),
+            None => ("unknown".to_string(), "ckpool".to_string()),
+        }
+    }
+
+    fn to_block_info(&self, coinbase: CoinbaseTx) -> BlockInfo {
+        let (finder_wallet, finder_worker) = self.find_finder(coinbase.block_height);
+        BlockInfo {
+            height: coinbase.block_height,
+            hash: coinbase.block_hash,
+            reward: coinbase.amount,
+            finder_wallet,
+            finder_worker,
+            timestamp: coinbase.block_time,
+        }
+    }
+
+    /// Read a CKPool status file (one JSON object per line) into one map
+    fn read_status(path: &Path) -> PoolResult<serde_json::Map<String, serde_json::Value>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| PoolError::ApiError(format!("cannot read {:?}: {}", path, e)))?;
+
+        let mut status = serde_json::Map::new();
+        for line in contents.lines() {
+            let value: serde_json::Value =
+                serde_json::from_str(line).map_err(|e| PoolError::ParseError(e.to_string()))?;
+            if let serde_json::Value::Object(map) = value {
+                status.extend(map);
+            }
+        }
+        Ok(status)
+    }
+
+    fn read_user(&self, username: &str) -> PoolResult<MinerStats> {
+        let contents = std::fs::read_to_string(self.log_dir.join("users").join(username))
+            .map_err(|e| PoolError::ApiError(format!("no stats for {}: {}", username, e)))?;
+        let user: UserStatus =
+            serde_json::from_str(&contents).map_err(|e| PoolError::ParseError(e.to_string()))?;
+
+        Ok(MinerStats {
+            wallet_address: username.to_string(),
+            hashrate: parse_hashrate(&user.hashrate5m),
+            hashrate_unit: "H/s".to_string(),
+            total_shares: user.shares as u64,
+            valid_shares: user.shares as u64,
+            invalid_shares: 0,
+            last_share: user.lastshare,
+        })
+    }
+}
+
+/// Modification time of a file, in Unix seconds
+fn modified_secs(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64)
+}
+
+/// Parse a CKPool hashrate such as "1.5T" into hashes per second
+fn parse_hashrate(value: &str) -> f64 {
+    let value = value.trim();
+    let (number, multiplier) = match value.chars().last() {
+        Some('K') => (&value[..value.len() - 1], 1e3),
+        Some('M') => (&value[..value.len() - 1], 1e6),
+        Some('G') => (&value[..value.len() - 1], 1e9),
+        Some('T') => (&value[..value.len() - 1], 1e12),
+        Some('P') => (&value[..value.len() - 1], 1e15),
+        Some('E') => (&value[..value.len() - 1], 1e18),
+        Some('Z') => (&value[..value.len() - 1], 1e21),
+        _ => (value, 1.0),
+    };
+    number.parse::<f64>().map(|n| n * multiplier).unwrap_or(0.0)
+}
+
+#[async_trait]
+impl PoolApi for CkPoolApi {
+    async fn is_online(&self) -> bool {
+        let Some(modified) = modified_secs(&self.log_dir.join("pool").join("pool.status")) else {
+            return false;
+        };
+        let now = chrono::Utc::now().timestamp();
+        now - modified <= STATUS_MAX_AGE.as_secs() as i64
+    }
+
+    async fn get_pool_stats(&self) -> PoolResult<PoolStats> {
+        let status = Self::read_status(&self.log_dir.join("pool").join("pool.status"))?;
+        let (current_height, network_difficulty) = self
+            .wallet
+            .chain_info()
+            .await
+            .map_err(|e| PoolError::ConnectionFailed(e.to_string()))?;
+
+        Ok(PoolStats {
+            hashrate: status
+                .get("hashrate5m")
+                .and_then(|v| v.as_str())
+                .map(parse_hashrate)
+                .unwrap_or(0.0),
+            hashrate_unit: "H/s".to_string(),
+            miners: status.get("Users").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+            // CKPool does not keep a block count; blocks are tracked from the wallet
+            blocks_found: 0,
+            current_height,
+            network_difficulty,
+            last_block_time: None,
+        })
+    }
+
+    async fn get_miner_stats(&self, wallet_address: &str) -> PoolResult<MinerStats> {
+        // Return empty stats if the miner has no status file yet
+        Ok(self.read_user(wallet_address).unwrap_or_else(|_| MinerStats {
+            wallet_address: wallet_address.to_string(),
+            hashrate: 0.0,
+            hashrate_unit: "H/s".to_string(),
+            total_shares: 0,
+            valid_shares: 0,
+            invalid_shares: 0,
+            last_share: None,
+        }))
+    }
+
+    async fn get_all_miners(&self) -> PoolResult<Vec<MinerStats>> {
+        let users_dir = self.log_dir.join("users");
+        let entries = std::fs::read_dir(&users_dir)
+            .map_err(|e| PoolError::ApiError(format!("cannot read {:?}: {}", users_dir, e)))?;
+
+        Ok(entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter_map(|username| self.read_user(&username).ok())
+            .collect())
+    }
+
+    async fn get_shares_since(&self, since_timestamp: i64) -> PoolResult<Vec<ShareInfo>> {
+        let since_timestamp = since_timestamp.max(self.started_at);
+        let mut shares = Vec::new();
+
+        for (height, dir) in self.height_dirs()? {
+            // New share logs bump the directory's mtime, so old heights can be skipped
+            if modified_secs(&dir).is_some_and(|m| m < since_timestamp - HEIGHT_DIR_SLACK.as_secs() as i64) {
+                continue;
+            }
+
+            for share in Self::read_shares(&dir, height, since_timestamp)? {
+                let timestamp = share.timestamp();
+                if timestamp <= since_timestamp {
+                    continue;
+                }
+                shares.push(ShareInfo {
+                    wallet_address: share.wallet_address().to_string(),
+                    worker_name: share.worker_name().to_string(),
+                    difficulty: Decimal::from_str(&share.diff.to_string()).unwrap_or_default(),
+                    block_height: Some(share.height),
+                    is_block: false,
+                    timestamp,
+                });
+            }
+        }
+
+        shares.sort_by_key(|s| s.timestamp);
+        Ok(shares)
+    }
+
+    async fn get_blocks(&self, limit: u32) -> PoolResult<Vec<BlockInfo>> {
+        let mut blocks = self.get_blocks_since_height(0).await?;
+        blocks.reverse();
+        blocks.truncate(limit as usize);
+        Ok(blocks)
+    }
+
+    async fn get_blocks_since_height(&self, height: i64) -> PoolResult<Vec<BlockInfo>> {
+        let coinbases = self
+            .wallet
+            .list_coinbase_since(height)
+            .await
+            .map_err(|e| PoolError::ConnectionFailed(e.to_string()))?;
+
+        Ok(coinbases
+            .into_iter()
+            .map(|coinbase| self.to_block_info(coinbase))
+            .collect())
+    }
+}
+
+// CKPool log types
+
+/// One line of a `.sharelog` file
+#[derive(Deserialize)]
+struct ShareLogEntry {
+    /// Difficulty the share was credited at (the client's current diff)
+    diff: f64,
+    /// Difficulty the share actually reached
+    #[serde(default)]
+    sdiff: f64,
+    #[serde(default)]
+    result: bool,
+    /// "seconds,nanoseconds"
+    createdate: String,
+    #[serde(default)]
+    workername: String,
+    #[serde(default)]
+    username: String,
+    /// Taken from the directory name, not the line
+    #[serde(skip)]
+    height: i64,
+}
+
+impl ShareLogEntry {
+    fn timestamp(&self) -> i64 {
+        self.createdate
+            .split(',')
+            .next()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Miners log in with their payout address as the username
+    fn wallet_address(&self) -> &str {
+        &self.username
+    }
+
+    /// Worker part of "address.worker" (or "address_worker")
+    fn worker_name(&self) -> &str {
+        self.workername
+            .strip_prefix(self.username.as_str())
+            .and_then(|rest| rest.strip_prefix(['.', '_']))
+            .filter(|w| !w.is_empty())
+            .unwrap_or("default")
+    }
+}
+
+/// `users/<username>` status file
+#[derive(Deserialize)]
+struct UserStatus {
+    #[serde(default)]
+    hashrate5m: String,
+    #[serde(default)]
+    lastshare: Option<i64>,
+    #[serde(default)]
+    shares: f64,
+}
@@ -42,7 +42,7 @@ impl MinotariMinerApi {
 
         let response = self
             .client
-            .post(&format!("{}/json_rpc", self.api_url))
+            .post(format!("{}/json_rpc", self.api_url))
             .json(&request)
             .send()
             .await
@@ -268,6 +268,7 @@ struct RpcError {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct MinerStatus {
     hashrate: Option<f64>,
     connected_miners: Option<u32>,
@@ -278,6 +279,7 @@ struct MinerStatus {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct MinerInfo {
     wallet_address: Option<String>,
     hashrate: Option<f64>,
@@ -6,6 +6,7 @@
 //! - Miner statistics
 
 pub mod aleo;
+pub mod ckpool;
 pub mod minotari;
 pub mod monero_pool;
 pub mod tari;
@@ -27,6 +28,7 @@ pub enum PoolError {
     ParseError(String),
 
     #[error("Pool offline")]
+    #[allow(dead_code)]
     PoolOffline,
 }
 
@@ -68,6 +70,7 @@ pub struct BlockInfo {
 
 /// Miner statistics from pool
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct MinerStats {
     /// Wallet address
     pub wallet_address: String,
@@ -87,6 +90,7 @@ pub struct MinerStats {
 
 /// Pool statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct PoolStats {
     /// Pool hashrate
     pub hashrate: f64,
@@ -111,12 +115,15 @@ pub trait PoolApi: Send + Sync {
     async fn is_online(&self) -> bool;
 
     /// Get pool statistics
+    #[allow(dead_code)]
     async fn get_pool_stats(&self) -> PoolResult<PoolStats>;
 
     /// Get statistics for a specific miner
+    #[allow(dead_code)]
     async fn get_miner_stats(&self, wallet_address: &str) -> PoolResult<MinerStats>;
 
     /// Get all active miners
+    #[allow(dead_code)]
     async fn get_all_miners(&self) -> PoolResult<Vec<MinerStats>>;
 
     /// Get recent shares (for a time range)
@@ -175,6 +175,7 @@ impl PoolApi for MoneroPoolApi {
 // monero-pool API response types
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct MoneroPoolStats {
     pool_hashrate: u64,
     connected_miners: u32,
@@ -187,6 +188,7 @@ struct MoneroPoolStats {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct MoneroPoolMiner {
     address: String,
     hashrate: u64,
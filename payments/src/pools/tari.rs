@@ -44,7 +44,7 @@ impl TariMergeProxyApi {
 
         let response = self
             .client
-            .post(&format!("{}/json_rpc", self.api_url))
+            .post(format!("{}/json_rpc", self.api_url))
             .json(&request)
             .send()
             .await
@@ -271,6 +271,7 @@ struct RpcError {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct ProxyStatus {
     hashrate: Option<f64>,
     connected_miners: Option<u32>,
@@ -281,6 +282,7 @@ struct ProxyStatus {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct MinerInfo {
     wallet_address: Option<String>,
     hashrate: Option<f64>,
@@ -6,27 +6,168 @@
 //! - Processing payments to miners
 //! - Confirming payments
 
-use crate::config::{AleoConfig, CoinConfig, Config};
-use crate::db::{Coin, Database, PaymentStatus};
-use crate::pools::{PoolApi, PoolResult};
-use crate::wallets::{TxStatus, Wallet, WalletResult};
+use crate::config::{AleoConfig, CoinConfig, DormantConfig, PayoutLimits, RewardSource};
+use crate::consolidation::Consolidator;
+use crate::db::{
+    ApprovalStatus, BlockFound, Coin, ConsolidationStatus, Database, MinerBalance, PaymentStatus,
+    RewardStatus,
+};
+use crate::distribution::{self, PoolFee};
+use crate::events::{EventBus, EventKind};
+use crate::health::Health;
+use crate::metrics::{Metrics, CYCLE_PAYMENT, CYCLE_PROCESS};
+use crate::pools::PoolApi;
+use crate::shutdown::Shutdown;
+use crate::wallet_manager::{WalletManager, WalletReadiness};
+use crate::wallets::{TxStatus, Wallet};
+use chrono::Utc;
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
+/// How long a consolidation transaction may be missing from the wallet before it counts as dropped
+const CONSOLIDATION_DROP_SECS: i64 = 3600;
+
+/// How long blocks flagged with a missing coinbase keep being rechecked
+const MISSING_COINBASE_RECHECK_SECS: i64 = 7 * 24 * 3600;
+
+/// Per-coin processing settings
+#[derive(Debug, Clone)]
+pub struct CoinSettings {
+    pub min_payout: Decimal,
+    pub reward_source: RewardSource,
+    pub coinbase_grace_secs: u64,
+    /// Hot wallet balance to keep above liabilities, with the cold address for the rest
+    pub cold_sweep: Option<(Decimal, String)>,
+    /// Payouts of at least this amount wait for operator approval
+    pub approval_threshold: Option<Decimal>,
+    pub limits: PayoutLimits,
+    /// Dormant balance policy, if enabled
+    pub dormant: Option<DormantConfig>,
+    /// Unlocked balance below which a wallet_balance_low event is sent
+    pub low_balance_alert: Option<Decimal>,
+    /// Pool fee taken from each block reward, if enabled
+    pub pool_fee: Option<PoolFee>,
+}
+
+impl From<&CoinConfig> for CoinSettings {
+    fn from(config: &CoinConfig) -> Self {
+        Self {
+            min_payout: config.min_payout,
+            reward_source: config.reward_source,
+            coinbase_grace_secs: config.coinbase_grace_secs,
+            cold_sweep: config.hot_wallet_target.zip(config.cold_address.clone()),
+            approval_threshold: config.approval_threshold,
+            limits: config.limits.clone(),
+            dormant: config.dormant.clone().filter(|d| d.enabled),
+            low_balance_alert: config.low_balance_alert,
+            pool_fee: PoolFee::from_config(config.pool_fee.as_ref()),
+        }
+    }
+}
+
+impl From<&AleoConfig> for CoinSettings {
+    fn from(config: &AleoConfig) -> Self {
+        Self {
+            min_payout: config.min_payout,
+            reward_source: config.reward_source,
+            coinbase_grace_secs: config.coinbase_grace_secs,
+            cold_sweep: config.hot_wallet_target.zip(config.cold_address.clone()),
+            approval_threshold: config.approval_threshold,
+            limits: config.limits.clone(),
+            dormant: config.dormant.clone().filter(|d| d.enabled),
+            low_balance_alert: config.low_balance_alert,
+            pool_fee: PoolFee::from_config(config.pool_fee.as_ref()),
+        }
+    }
+}
+
+/// First safety limit a payout would exceed, as a pause reason
+fn limit_breach(
+    limits: &PayoutLimits,
+    amounts: &[Decimal],
+    paid_today: Decimal,
+    wallet_balance: Decimal,
+) -> Option<String> {
+    let total: Decimal = amounts.iter().sum();
+
+    if let Some(max) = limits.max_per_payment {
+        if let Some(largest) = amounts.iter().copied().filter(|a| *a > max).max() {
+            return Some(format!("payment of {} exceeds max_per_payment {}", largest, max));
+        }
+    }
+    if let Some(max) = limits.max_per_cycle.filter(|max| total > *max) {
+        return Some(format!("cycle total {} exceeds max_per_cycle {}", total, max));
+    }
+    if let Some(max) = limits.max_per_day.filter(|max| paid_today + total > *max) {
+        return Some(format!(
+            "{} paid in the last 24h plus cycle total {} exceeds max_per_day {}",
+            paid_today, total, max
+        ));
+    }
+    if let Some(percent) = limits.max_wallet_percent {
+        let max = wallet_balance * percent / Decimal::ONE_HUNDRED;
+        if total > max {
+            return Some(format!(
+                "cycle total {} exceeds {}% of wallet balance {}",
+                total, percent, wallet_balance
+            ));
+        }
+    }
+
+    None
+}
+
+/// What one processing or payment cycle did
+#[derive(Debug, Default)]
+pub struct CycleReport {
+    /// Shares and blocks recorded, or payments sent
+    pub items: u64,
+    /// Stages that failed, as "stage: error"
+    pub errors: Vec<String>,
+    /// Why the cycle stopped early, if it did
+    pub skipped: Option<String>,
+}
+
+impl CycleReport {
+    fn failed(&mut self, stage: &str, error: anyhow::Error) {
+        self.errors.push(format!("{}: {}", stage, error));
+    }
+
+    fn skip(mut self, reason: &str) -> Self {
+        self.skipped = Some(reason.to_string());
+        self
+    }
+}
+
 /// Payment processor for a specific coin
 pub struct CoinProcessor<P: PoolApi, W: Wallet> {
     coin: Coin,
     pool_api: Arc<P>,
     wallet: Arc<W>,
     db: Database,
-    min_payout: Decimal,
+    settings: CoinSettings,
+    metrics: Metrics,
+    shutdown: Arc<Shutdown>,
+    /// Pool wallet output consolidation, run after each payment cycle
+    consolidator: Option<Consolidator>,
+    /// Opens and syncs the pool wallet before payouts
+    wallet_manager: Option<WalletManager>,
+    /// Records successful stages for the health report
+    health: Option<Health>,
+    /// Publishes blocks, rewards and payments to event stream subscribers
+    events: Option<EventBus>,
     /// Last processed block height
     last_block_height: Arc<RwLock<i64>>,
     /// Last share sync timestamp
     last_share_sync: Arc<RwLock<i64>>,
+    /// Alert state, so pool_offline and wallet_balance_low fire once per episode
+    pool_offline: AtomicBool,
+    balance_low: AtomicBool,
 }
 
 impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
@@ -35,19 +176,73 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
         pool_api: P,
         wallet: W,
         db: Database,
-        min_payout: Decimal,
+        settings: CoinSettings,
+        metrics: Metrics,
+        shutdown: Arc<Shutdown>,
     ) -> Self {
         Self {
             coin,
             pool_api: Arc::new(pool_api),
             wallet: Arc::new(wallet),
             db,
-            min_payout,
+            settings,
+            metrics,
+            shutdown,
+            consolidator: None,
+            wallet_manager: None,
+            health: None,
+            events: None,
             last_block_height: Arc::new(RwLock::new(0)),
             last_share_sync: Arc::new(RwLock::new(0)),
+            pool_offline: AtomicBool::new(false),
+            balance_low: AtomicBool::new(false),
         }
     }
 
+    /// Consolidate the pool wallet's outputs after payment cycles
+    pub fn with_consolidation(mut self, consolidator: Consolidator) -> Self {
+        self.consolidator = Some(consolidator);
+        self
+    }
+
+    /// Check the pool wallet is open and synced before payouts
+    pub fn with_wallet_manager(mut self, wallet_manager: WalletManager) -> Self {
+        self.wallet_manager = Some(wallet_manager);
+        self
+    }
+
+    /// Report this coin's pool, wallet and cycle timestamps in the health report
+    pub fn with_health(mut self, health: Health) -> Self
+    where
+        P: 'static,
+        W: 'static,
+    {
+        health.register(self.coin, self.pool_api.clone(), self.wallet.clone());
+        self.health = Some(health);
+        self
+    }
+
+    /// Publish this coin's blocks, rewards and payments as events
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Publish an event; failures are logged, never fatal to the cycle
+    async fn publish(&self, kind: EventKind) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        if let Err(e) = events.publish(self.coin, kind).await {
+            warn!(coin = %self.coin, error = %e, "Failed to publish event");
+        }
+    }
+
+    /// The coin this processor pays out
+    pub fn coin(&self) -> Coin {
+        self.coin
+    }
+
     /// Sync shares from pool API to local database
     pub async fn sync_shares(&self) -> anyhow::Result<u64> {
         let since = *self.last_share_sync.read().await;
@@ -79,6 +274,10 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
 
         // Update last sync timestamp
         *self.last_share_sync.write().await = latest_timestamp;
+        self.metrics.record_shares(self.coin, count, latest_timestamp);
+        if let Some(health) = &self.health {
+            health.record_share_sync(self.coin, latest_timestamp);
+        }
 
         if count > 0 {
             info!(
@@ -86,6 +285,11 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
                 count = count,
                 "Synced shares from pool API"
             );
+            self.publish(EventKind::SharesSynced {
+                shares: count,
+                newest_share: latest_timestamp,
+            })
+            .await;
         }
 
         Ok(count)
@@ -97,6 +301,9 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
 
         // Get new blocks from pool API
         let blocks = self.pool_api.get_blocks_since_height(last_height).await?;
+        if let Some(health) = &self.health {
+            health.record_block_check(self.coin);
+        }
 
         if blocks.is_empty() {
             return Ok(0);
@@ -105,6 +312,12 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
         let mut processed = 0;
         let mut highest_height = last_height;
 
+        // With wallet-sourced rewards, blocks wait for their coinbase before distribution
+        let reward_status = match self.settings.reward_source {
+            RewardSource::Pool => RewardStatus::Reported,
+            RewardSource::Wallet => RewardStatus::AwaitingCoinbase,
+        };
+
         for block in blocks {
             // Record block in database
             self.db
@@ -115,6 +328,7 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
                     block.reward,
                     &block.finder_wallet,
                     &block.finder_worker,
+                    reward_status,
                 )
                 .await?;
 
@@ -126,6 +340,13 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
                 finder = %block.finder_wallet,
                 "New block found"
             );
+            self.publish(EventKind::BlockFound {
+                height: block.height,
+                hash: block.hash,
+                reward: block.reward,
+                finder: block.finder_wallet,
+            })
+            .await;
 
             processed += 1;
 
@@ -136,90 +357,192 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
 
         // Update last processed height
         *self.last_block_height.write().await = highest_height;
+        self.metrics.record_blocks(self.coin, processed);
 
         Ok(processed)
     }
 
-    /// Distribute rewards for undistributed blocks
-    pub async fn distribute_rewards(&self) -> anyhow::Result<()> {
-        // Get undistributed blocks
-        let blocks = self.db.get_undistributed_blocks(self.coin).await?;
-
-        for block in blocks {
-            // Calculate time window for PPLNS (e.g., 1 hour before block)
-            let window_start = chrono::DateTime::from_timestamp(block.timestamp.timestamp() - 3600, 0)
-                .unwrap_or(block.timestamp);
-            let window_end = block.timestamp;
-
-            // Get all miners who submitted shares in the window
-            let miners = self
-                .db
-                .get_miners_in_range(self.coin, window_start, window_end)
-                .await?;
-
-            // Calculate total shares in window
-            let total_shares = self
-                .db
-                .get_total_shares_in_range(self.coin, window_start, window_end)
-                .await?;
+    /// Match blocks awaiting their coinbase against the pool wallet
+    ///
+    /// A found coinbase replaces the pool-reported reward with the amount the
+    /// wallet actually received. Blocks whose coinbase hasn't shown up within
+    /// the grace period are flagged missing and left undistributed. Nothing
+    /// is checked until the wallet is synced, and a block whose lookup fails
+    /// is retried on the next cycle.
+    pub async fn verify_block_rewards(&self) -> anyhow::Result<u64> {
+        if self.settings.reward_source != RewardSource::Wallet {
+            return Ok(0);
+        }
 
-            if total_shares == 0 {
-                // No shares - give full reward to finder
-                self.db
-                    .add_pending_balance(self.coin, &block.finder_wallet, block.reward)
-                    .await?;
+        // A wallet still syncing would report coinbases as missing
+        if self.wallet_ready(CYCLE_PROCESS, "reward verification").await.is_err() {
+            return Ok(0);
+        }
 
-                info!(
-                    coin = %self.coin,
-                    block_height = block.block_height,
-                    recipient = %block.finder_wallet,
-                    amount = %block.reward,
-                    "Full block reward assigned to finder (no shares in window)"
-                );
-            } else {
-                // Calculate proportional rewards
-                let mut distributed = Decimal::from(0);
+        let recheck_since = Utc::now() - chrono::Duration::seconds(MISSING_COINBASE_RECHECK_SECS);
+        let blocks = self
+            .db
+            .get_blocks_awaiting_coinbase(self.coin, recheck_since)
+            .await?;
 
-                for miner_wallet in &miners {
-                    let miner_shares = self
-                        .db
-                        .get_share_count_in_range(self.coin, miner_wallet, window_start, window_end)
-                        .await?;
+        let mut verified = 0;
 
-                    if miner_shares > 0 {
-                        // Calculate proportional reward
-                        let share_ratio = Decimal::from(miner_shares) / Decimal::from(total_shares);
-                        let reward = block.reward * share_ratio;
+        for block in blocks {
+            let height = u64::try_from(block.block_height).unwrap_or_default();
 
-                        self.db
-                            .add_pending_balance(self.coin, miner_wallet, reward)
-                            .await?;
+            let coinbase = match self.wallet.find_coinbase(height).await {
+                Ok(coinbase) => coinbase,
+                Err(e) => {
+                    warn!(
+                        coin = %self.coin,
+                        height = block.block_height,
+                        error = %e,
+                        "Failed to look up block coinbase, retrying next cycle"
+                    );
+                    self.metrics
+                        .record_cycle_error(self.coin, CYCLE_PROCESS, "verify_rewards");
+                    continue;
+                }
+            };
 
-                        distributed += reward;
+            match coinbase {
+                Some(coinbase) => {
+                    self.db
+                        .set_block_coinbase(block.id, coinbase.amount, &coinbase.tx_hash)
+                        .await?;
 
-                        info!(
+                    if coinbase.amount != block.reward {
+                        warn!(
                             coin = %self.coin,
-                            block_height = block.block_height,
-                            miner = %miner_wallet,
-                            shares = miner_shares,
-                            share_ratio = %share_ratio,
-                            reward = %reward,
-                            "Reward distributed to miner"
+                            height = block.block_height,
+                            reported = %block.reward,
+                            received = %coinbase.amount,
+                            "Coinbase amount differs from pool-reported reward"
                         );
                     }
+
+                    info!(
+                        coin = %self.coin,
+                        height = block.block_height,
+                        reward = %coinbase.amount,
+                        coinbase_tx = %coinbase.tx_hash,
+                        "Block reward verified against wallet coinbase"
+                    );
+                    self.publish(EventKind::BlockConfirmed {
+                        height: block.block_height,
+                        hash: block.block_hash,
+                        reward: coinbase.amount,
+                        coinbase_tx: Some(coinbase.tx_hash),
+                    })
+                    .await;
+
+                    verified += 1;
                 }
+                None => self.flag_missing_coinbase(&block).await?,
+            }
+        }
 
-                // Handle any rounding remainder - give to block finder
-                let remainder = block.reward - distributed;
-                if remainder > Decimal::from(0) {
-                    self.db
-                        .add_pending_balance(self.coin, &block.finder_wallet, remainder)
-                        .await?;
+        Ok(verified)
+    }
+
+    /// Flag a block whose coinbase is overdue
+    async fn flag_missing_coinbase(&self, block: &BlockFound) -> anyhow::Result<()> {
+        if block.reward_status != RewardStatus::AwaitingCoinbase {
+            return Ok(());
+        }
+
+        let age = (Utc::now() - block.timestamp).num_seconds();
+        if age < self.settings.coinbase_grace_secs as i64 {
+            return Ok(());
+        }
+
+        self.db
+            .set_block_reward_status(block.id, RewardStatus::Missing)
+            .await?;
+
+        error!(
+            coin = %self.coin,
+            height = block.block_height,
+            hash = %block.block_hash,
+            age_secs = age,
+            "Block coinbase not found in pool wallet - reward will not be distributed"
+        );
+        self.publish(EventKind::CoinbaseMissing {
+            height: block.block_height,
+            hash: block.block_hash.clone(),
+        })
+        .await;
+
+        Ok(())
+    }
+
+    /// Distribute rewards for undistributed blocks
+    pub async fn distribute_rewards(&self) -> anyhow::Result<()> {
+        // Get undistributed blocks
+        let blocks = self.db.get_undistributed_blocks(self.coin).await?;
+        let params = distribution::Params {
+            fee: self.settings.pool_fee.clone(),
+            ..distribution::Params::default()
+        };
+
+        for block in blocks {
+            let credits = distribution::calculate_with(&self.db, &block, &params).await?;
+
+            for credit in &credits {
+                if credit.fee {
+                    info!(
+                        coin = %self.coin,
+                        block_height = block.block_height,
+                        recipient = %credit.wallet_address,
+                        amount = %credit.amount,
+                        "Pool fee deducted from block reward"
+                    );
+                } else if credit.shares > 0 {
+                    info!(
+                        coin = %self.coin,
+                        block_height = block.block_height,
+                        miner = %credit.wallet_address,
+                        shares = credit.shares,
+                        reward = %credit.amount,
+                        "Reward distributed to miner"
+                    );
+                } else {
+                    info!(
+                        coin = %self.coin,
+                        block_height = block.block_height,
+                        recipient = %credit.wallet_address,
+                        amount = %credit.amount,
+                        "Full block reward assigned to finder (no shares in window)"
+                    );
                 }
             }
 
-            // Mark block as distributed
-            self.db.mark_block_distributed(block.id).await?;
+            // Credit balances and mark the block distributed in one transaction
+            let totals = distribution::per_wallet(&credits);
+            self.db.apply_block_distribution(&block, &totals).await?;
+            self.metrics.record_distribution(self.coin);
+
+            // Wallet-verified blocks were confirmed when their coinbase arrived
+            if self.settings.reward_source == RewardSource::Pool {
+                self.publish(EventKind::BlockConfirmed {
+                    height: block.block_height,
+                    hash: block.block_hash.clone(),
+                    reward: block.reward,
+                    coinbase_tx: None,
+                })
+                .await;
+            }
+
+            let (fees, miners): (Vec<_>, Vec<_>) = credits.iter().partition(|c| c.fee);
+            let miner_wallets: HashSet<&str> = miners.iter().map(|c| c.wallet_address.as_str()).collect();
+            self.publish(EventKind::RewardsDistributed {
+                height: block.block_height,
+                hash: block.block_hash,
+                amount: miners.iter().map(|c| c.amount).sum(),
+                miners: miner_wallets.len(),
+                pool_fee: fees.iter().map(|c| c.amount).sum(),
+            })
+            .await;
         }
 
         Ok(())
@@ -227,11 +550,64 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
 
     /// Process payments for balances above threshold
     pub async fn process_payments(&self) -> anyhow::Result<u64> {
+        // Payouts would spend the outputs being consolidated
+        if self.db.has_pending_consolidation(self.coin).await? {
+            info!(
+                coin = %self.coin,
+                "Consolidation transaction unconfirmed, holding payouts until next cycle"
+            );
+            return Ok(0);
+        }
+
+        // Miners without shares for a while are paid anything above the fee
+        // floor, so a balance below min_payout doesn't stay owed forever
+        let dormant_floor = self.settings.dormant.as_ref().map(|d| {
+            let cutoff = Utc::now() - chrono::Duration::days(d.pay_after_days.into());
+            (cutoff, d.pay_min_amount)
+        });
+        let is_dormant = |b: &MinerBalance| {
+            dormant_floor.is_some_and(|(cutoff, floor)| {
+                b.last_share.is_some_and(|t| t < cutoff) && b.pending_balance >= floor
+            })
+        };
+        let query_min = match dormant_floor {
+            Some((_, floor)) => floor.min(self.settings.min_payout),
+            None => self.settings.min_payout,
+        };
+
         // Get balances above minimum payout
-        let payable = self
-            .db
-            .get_payable_balances(self.coin, self.min_payout)
-            .await?;
+        let mut payable = self.db.get_payable_balances(self.coin, query_min).await?;
+
+        // Pending balances only drop once a payment confirms, so leave out
+        // what is already in flight or awaiting approval
+        let reserved = self.db.get_reserved_amounts(self.coin).await?;
+
+        // Only whole atomic units can be sent. Balances credited before
+        // rounding was introduced may hold a fraction, which stays pending.
+        for balance in &mut payable {
+            let in_flight = reserved.get(&balance.wallet_address).copied().unwrap_or_default();
+            balance.pending_balance = (balance.pending_balance - in_flight).trunc();
+        }
+        payable.retain(|b| {
+            b.pending_balance > Decimal::ZERO
+                && (b.pending_balance >= self.settings.min_payout || is_dormant(b))
+        });
+
+        // Large payouts are prepared here and only sent once approved
+        if let Some(threshold) = self.settings.approval_threshold {
+            let (held, rest): (Vec<_>, Vec<_>) = payable
+                .into_iter()
+                .partition(|b| b.pending_balance >= threshold);
+            payable = rest;
+
+            if !held.is_empty() {
+                let held: Vec<(String, Decimal)> = held
+                    .into_iter()
+                    .map(|b| (b.wallet_address, b.pending_balance))
+                    .collect();
+                self.hold_for_approval(&held).await?;
+            }
+        }
 
         if payable.is_empty() {
             return Ok(0);
@@ -241,6 +617,23 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
         let wallet_balance = self.wallet.get_balance().await?;
         let total_payout: Decimal = payable.iter().map(|b| b.pending_balance).sum();
 
+        // A payout beyond the safety limits points at a bad balance: pay
+        // nothing and leave it to an operator
+        let paid_today = self
+            .db
+            .get_paid_since(self.coin, Utc::now() - chrono::Duration::hours(24))
+            .await?;
+        let amounts: Vec<Decimal> = payable.iter().map(|b| b.pending_balance).collect();
+        if let Some(reason) = limit_breach(&self.settings.limits, &amounts, paid_today, wallet_balance) {
+            error!(coin = %self.coin, reason = %reason, "Payout limit exceeded, pausing payouts");
+            self.db
+                .set_payout_pause(Some(self.coin), &reason, "limits")
+                .await?;
+            self.metrics
+                .record_cycle_error(self.coin, CYCLE_PAYMENT, "payout_limit");
+            return Ok(0);
+        }
+
         if wallet_balance < total_payout {
             warn!(
                 coin = %self.coin,
@@ -251,17 +644,22 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
 
             // Process what we can - sort by balance (highest first)
             let mut sorted_payable = payable.clone();
-            sorted_payable.sort_by(|a, b| b.pending_balance.cmp(&a.pending_balance));
+            sorted_payable.sort_by_key(|b| std::cmp::Reverse(b.pending_balance));
 
             let mut remaining_balance = wallet_balance;
             let mut processed = 0;
+            let total = sorted_payable.len();
 
-            for balance in sorted_payable {
-                if remaining_balance >= balance.pending_balance {
-                    if self.send_payment(&balance.wallet_address, balance.pending_balance).await.is_ok() {
-                        remaining_balance -= balance.pending_balance;
-                        processed += 1;
-                    }
+            for (index, balance) in sorted_payable.into_iter().enumerate() {
+                if self.stop_requested_mid_payout(index, total) {
+                    break;
+                }
+
+                if remaining_balance >= balance.pending_balance
+                    && self.send_payment(&balance.wallet_address, balance.pending_balance).await.is_ok()
+                {
+                    remaining_balance -= balance.pending_balance;
+                    processed += 1;
                 }
             }
 
@@ -277,50 +675,104 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
             .map(|b| (b.wallet_address.clone(), b.pending_balance))
             .collect();
 
+        let Some(sending) = self.shutdown.begin_payout() else {
+            return Ok(0);
+        };
+
+        // Record every payment before the wallet call, so a transfer that
+        // went out stays reserved even if marking it sent fails below
+        let mut payment_ids = HashMap::new();
+        for (address, amount) in &payments {
+            let payment_id = self.db.create_payment(self.coin, address, *amount).await?;
+            payment_ids.insert(address.clone(), payment_id);
+        }
+
         match self.wallet.send_batch_payment(&payments).await {
             Ok(results) => {
-                for (address, tx_hash) in results {
-                    // Create payment record
+                for (address, sent) in results {
                     let amount = payable
                         .iter()
                         .find(|b| b.wallet_address == address)
                         .map(|b| b.pending_balance)
                         .unwrap_or_default();
+                    let Some(payment_id) = payment_ids.remove(&address) else {
+                        error!(
+                            coin = %self.coin,
+                            address = %address,
+                            tx_hash = %sent.tx_hash,
+                            "Wallet reported a payment that was not requested"
+                        );
+                        continue;
+                    };
+                    processed += 1;
 
-                    let payment_id = self
+                    // Already broadcast: keep going so the rest get recorded too
+                    if let Err(e) = self
                         .db
-                        .create_payment(self.coin, &address, amount)
-                        .await?;
-
-                    self.db
-                        .update_payment_status(
-                            &payment_id,
-                            PaymentStatus::Processing,
-                            Some(&tx_hash),
-                            None,
-                        )
-                        .await?;
+                        .mark_payment_sent(&payment_id, &sent.tx_hash, sent.tx_key.as_deref())
+                        .await
+                    {
+                        error!(
+                            coin = %self.coin,
+                            payment_id = %payment_id,
+                            address = %address,
+                            amount = %amount,
+                            tx_hash = %sent.tx_hash,
+                            error = %e,
+                            "Payment sent but not marked sent, left pending for an operator"
+                        );
+                        self.metrics
+                            .record_cycle_error(self.coin, CYCLE_PAYMENT, "record_payment");
+                        continue;
+                    }
 
                     info!(
                         coin = %self.coin,
                         address = %address,
                         amount = %amount,
-                        tx_hash = %tx_hash,
+                        tx_hash = %sent.tx_hash,
                         "Payment sent"
                     );
+                    self.publish(EventKind::PaymentSent {
+                        payment_id,
+                        address,
+                        amount,
+                        tx_hash: sent.tx_hash,
+                    })
+                    .await;
+                }
 
-                    processed += 1;
+                for (address, payment_id) in payment_ids {
+                    warn!(
+                        coin = %self.coin,
+                        payment_id = %payment_id,
+                        address = %address,
+                        "Payment missing from the batch result, left pending for an operator"
+                    );
                 }
             }
             Err(e) => {
+                drop(sending);
                 warn!(
                     coin = %self.coin,
                     error = %e,
                     "Batch payment failed, falling back to individual payments"
                 );
 
+                // Nothing was sent, so release the batch's records
+                let reason = format!("batch payment failed: {}", e);
+                for payment_id in payment_ids.values() {
+                    self.db
+                        .update_payment_status(payment_id, PaymentStatus::Failed, None, Some(&reason))
+                        .await?;
+                }
+
                 // Fall back to individual payments
-                for balance in &payable {
+                for (index, balance) in payable.iter().enumerate() {
+                    if self.stop_requested_mid_payout(index, payable.len()) {
+                        break;
+                    }
+
                     if self
                         .send_payment(&balance.wallet_address, balance.pending_balance)
                         .await
@@ -335,8 +787,270 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
         Ok(processed)
     }
 
+    /// Abandon or restore the balances of dormant miners
+    ///
+    /// Runs after payments, so anything above the fee floor has been paid
+    /// before the rest is set aside. Miners without any recorded share are
+    /// left alone, since how long they have been gone is unknown.
+    pub async fn apply_dormant_policy(&self) -> anyhow::Result<()> {
+        let Some(dormant) = &self.settings.dormant else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        let active_since = now - chrono::Duration::days(dormant.pay_after_days.into());
+        let abandon_before = dormant
+            .abandon_after_days
+            .map(|days| now - chrono::Duration::days(days.into()));
+        let reserved = self.db.get_reserved_amounts(self.coin).await?;
+
+        for balance in self.db.get_all_balances(self.coin).await? {
+            let Some(last_share) = balance.last_share else {
+                continue;
+            };
+
+            // Returning miners get their abandoned balance back
+            if balance.abandoned_balance > Decimal::ZERO && last_share >= active_since {
+                let amount = self
+                    .db
+                    .reclaim_abandoned(self.coin, &balance.wallet_address, "miner active again")
+                    .await?;
+                info!(
+                    coin = %self.coin,
+                    address = %balance.wallet_address,
+                    amount = %amount,
+                    "Abandoned balance restored"
+                );
+                continue;
+            }
+
+            // Balances with a payment in flight are settled by that payment first
+            if abandon_before.is_some_and(|before| last_share < before)
+                && balance.pending_balance > Decimal::ZERO
+                && !reserved.contains_key(&balance.wallet_address)
+            {
+                let memo = format!("no shares since {}", last_share.format("%Y-%m-%d"));
+                self.db
+                    .abandon_balance(self.coin, &balance.wallet_address, balance.pending_balance, &memo)
+                    .await?;
+                info!(
+                    coin = %self.coin,
+                    address = %balance.wallet_address,
+                    amount = %balance.pending_balance,
+                    last_share = %last_share,
+                    "Dormant balance abandoned"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prepare a payout without relaying it and record it for approval
+    ///
+    /// Wallets that can't hold a signed transaction get a queued intent
+    /// instead, which is sent as a normal batch once approved.
+    async fn hold_for_approval(&self, payments: &[(String, Decimal)]) -> anyhow::Result<()> {
+        let prepared = self.wallet.prepare_batch_payment(payments).await?;
+        let total: Decimal = payments.iter().map(|(_, amount)| *amount).sum();
+
+        let approval_id = self
+            .db
+            .create_payout_approval(
+                self.coin,
+                payments,
+                prepared.as_ref().map(|p| p.tx_hash.as_str()),
+                prepared.as_ref().and_then(|p| p.tx_key.as_deref()),
+                prepared.as_ref().map(|p| p.tx_metadata.as_str()),
+                prepared.as_ref().map(|p| p.fee),
+            )
+            .await?;
+
+        warn!(
+            coin = %self.coin,
+            approval_id = %approval_id,
+            recipients = payments.len(),
+            total = %total,
+            prepared = prepared.is_some(),
+            "Payout held for operator approval"
+        );
+
+        Ok(())
+    }
+
+    /// Relay payouts approved since the last cycle
+    ///
+    /// Called from the payment cycle once the pause and wallet checks have
+    /// passed. Returns how many approvals were relayed.
+    pub async fn relay_approved_payouts(&self) -> anyhow::Result<u64> {
+        // Relaying would spend the outputs being consolidated
+        if self.db.has_pending_consolidation(self.coin).await? {
+            return Ok(0);
+        }
+
+        let approved = self
+            .db
+            .list_payout_approvals(self.coin, Some(ApprovalStatus::Approved), 100)
+            .await?;
+
+        let mut relayed = 0;
+        let total = approved.len();
+        for (index, approval) in approved.into_iter().enumerate() {
+            if self.stop_requested_mid_payout(index, total) {
+                break;
+            }
+            let Some(_sending) = self.shutdown.begin_payout() else {
+                break;
+            };
+
+            let recipients: Vec<(String, Decimal)> = approval
+                .payments
+                .iter()
+                .map(|p| (p.wallet_address.clone(), p.amount))
+                .collect();
+
+            let result = match &approval.tx_metadata {
+                // The key was stored when the transaction was prepared
+                Some(metadata) => self.wallet.relay_prepared(metadata).await.map(|tx_hash| {
+                    recipients
+                        .iter()
+                        .map(|(address, _)| (address.clone(), tx_hash.clone(), None))
+                        .collect::<Vec<_>>()
+                }),
+                None => self.wallet.send_batch_payment(&recipients).await.map(|results| {
+                    results
+                        .into_iter()
+                        .map(|(address, sent)| (address, sent.tx_hash, sent.tx_key))
+                        .collect()
+                }),
+            };
+
+            match result {
+                Ok(sent) => {
+                    self.db.mark_payout_relayed(&approval.id, &sent).await?;
+                    relayed += 1;
+                    info!(
+                        coin = %self.coin,
+                        approval_id = %approval.id,
+                        total = %approval.total,
+                        "Approved payout relayed"
+                    );
+                }
+                Err(e) => {
+                    error!(
+                        coin = %self.coin,
+                        approval_id = %approval.id,
+                        error = %e,
+                        "Failed to relay approved payout"
+                    );
+                    self.db
+                        .fail_payout_approval(&approval.id, &e.to_string())
+                        .await?;
+                }
+            }
+        }
+
+        Ok(relayed)
+    }
+
+    /// Send the unlocked balance above target plus liabilities to the cold address
+    ///
+    /// Liabilities are everything owed to miners (pending and carry
+    /// balances) plus the rewards of blocks not distributed yet, including
+    /// those still awaiting their coinbase, so the hot wallet always keeps
+    /// enough to pay them.
+    pub async fn sweep_to_cold(&self) -> anyhow::Result<()> {
+        let Some((target, cold_address)) = &self.settings.cold_sweep else {
+            return Ok(());
+        };
+
+        // Nothing leaves the wallet while paused, including a pause set by
+        // this cycle's limit check
+        if self.db.get_payout_pause(self.coin).await?.is_some() {
+            return Ok(());
+        }
+
+        // Sweeping would spend the outputs being consolidated
+        if self.db.has_pending_consolidation(self.coin).await? {
+            return Ok(());
+        }
+
+        let unlocked = self.wallet.get_balance().await?;
+        let mut undistributed = self.db.get_undistributed_blocks(self.coin).await?;
+        undistributed.extend(
+            self.db
+                .get_blocks_awaiting_coinbase(self.coin, Utc::now())
+                .await?,
+        );
+        let undistributed: Decimal = undistributed.iter().map(|b| b.reward).sum();
+        let liabilities = self.db.get_liabilities(self.coin).await? + undistributed;
+        let excess = (unlocked - liabilities - *target).trunc();
+
+        if excess <= Decimal::ZERO {
+            return Ok(());
+        }
+
+        let Some(_sending) = self.shutdown.begin_payout() else {
+            return Ok(());
+        };
+        let tx_hash = self.wallet.send_payment(cold_address, excess).await?.tx_hash;
+        self.db
+            .record_cold_sweep(self.coin, cold_address, excess, &tx_hash)
+            .await?;
+
+        info!(
+            coin = %self.coin,
+            amount = %excess,
+            unlocked = %unlocked,
+            liabilities = %liabilities,
+            target = %target,
+            cold_address = %cold_address,
+            tx_hash = %tx_hash,
+            "Swept excess hot wallet balance to cold address"
+        );
+
+        Ok(())
+    }
+
+    /// Check for shutdown between individual payments
+    ///
+    /// The payment in flight has already been sent and recorded; only the
+    /// remaining payments are skipped.
+    fn stop_requested_mid_payout(&self, index: usize, total: usize) -> bool {
+        if !self.shutdown.is_draining() {
+            return false;
+        }
+
+        warn!(
+            coin = %self.coin,
+            sent = index,
+            skipped = total - index,
+            "Payment cycle interrupted by shutdown, remaining payments deferred to next run"
+        );
+        true
+    }
+
+    /// Check for shutdown between steps of a cycle
+    fn stop_requested(&self, cycle: &str, next_stage: &str) -> bool {
+        if !self.shutdown.is_draining() {
+            return false;
+        }
+
+        warn!(
+            coin = %self.coin,
+            cycle = cycle,
+            next_stage = next_stage,
+            "Cycle interrupted by shutdown"
+        );
+        true
+    }
+
     /// Send a single payment
     async fn send_payment(&self, address: &str, amount: Decimal) -> anyhow::Result<()> {
+        let Some(_sending) = self.shutdown.begin_payout() else {
+            anyhow::bail!("not sent, shutting down");
+        };
+
         // Create payment record
         let payment_id = self
             .db
@@ -345,23 +1059,25 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
 
         // Send payment
         match self.wallet.send_payment(address, amount).await {
-            Ok(tx_hash) => {
+            Ok(sent) => {
                 self.db
-                    .update_payment_status(
-                        &payment_id,
-                        PaymentStatus::Processing,
-                        Some(&tx_hash),
-                        None,
-                    )
+                    .mark_payment_sent(&payment_id, &sent.tx_hash, sent.tx_key.as_deref())
                     .await?;
 
                 info!(
                     coin = %self.coin,
                     address = %address,
                     amount = %amount,
-                    tx_hash = %tx_hash,
+                    tx_hash = %sent.tx_hash,
                     "Payment sent"
                 );
+                self.publish(EventKind::PaymentSent {
+                    payment_id,
+                    address: address.to_string(),
+                    amount,
+                    tx_hash: sent.tx_hash,
+                })
+                .await;
 
                 Ok(())
             }
@@ -382,12 +1098,38 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
                     error = %e,
                     "Payment failed"
                 );
+                self.publish(EventKind::PaymentFailed {
+                    payment_id,
+                    address: address.to_string(),
+                    amount,
+                    reason: e.to_string(),
+                })
+                .await;
 
                 Err(e.into())
             }
         }
     }
 
+    /// Look up and store a payment's proof key, logging failures
+    async fn store_tx_key(&self, payment_id: &str, tx_hash: &str) {
+        let result = match self.wallet.get_tx_key(tx_hash).await {
+            Ok(Some(tx_key)) => self.db.set_payment_tx_key(payment_id, &tx_key).await,
+            Ok(None) => Ok(()),
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            warn!(
+                coin = %self.coin,
+                payment_id = %payment_id,
+                tx_hash = %tx_hash,
+                error = %e,
+                "Failed to store payment proof key"
+            );
+        }
+    }
+
     /// Confirm pending payments
     pub async fn confirm_payments(&self) -> anyhow::Result<u64> {
         let pending = self.db.get_pending_payments(self.coin).await?;
@@ -407,6 +1149,12 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
                             )
                             .await?;
 
+                        // Some wallets only know the proof key once the
+                        // transaction is final (Tari kernel signature)
+                        if payment.tx_key.is_none() {
+                            self.store_tx_key(&payment.id, tx_hash).await;
+                        }
+
                         info!(
                             coin = %self.coin,
                             payment_id = %payment.id,
@@ -415,6 +1163,13 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
                             amount = %payment.amount,
                             "Payment confirmed"
                         );
+                        self.publish(EventKind::PaymentConfirmed {
+                            payment_id: payment.id.clone(),
+                            address: payment.wallet_address.clone(),
+                            amount: payment.amount,
+                            tx_hash: tx_hash.clone(),
+                        })
+                        .await;
 
                         confirmed += 1;
                     }
@@ -445,6 +1200,13 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
                             reason = %reason,
                             "Payment failed"
                         );
+                        self.publish(EventKind::PaymentFailed {
+                            payment_id: payment.id.clone(),
+                            address: payment.wallet_address.clone(),
+                            amount: payment.amount,
+                            reason,
+                        })
+                        .await;
                     }
                     Ok(TxStatus::NotFound) => {
                         warn!(
@@ -473,44 +1235,999 @@ impl<P: PoolApi, W: Wallet> CoinProcessor<P, W> {
         Ok(confirmed)
     }
 
+    /// Check unconfirmed consolidation transactions
+    ///
+    /// A consolidation is confirmed once all of its transactions are, and
+    /// failed if any of them failed or was dropped from the wallet.
+    pub async fn confirm_consolidations(&self) -> anyhow::Result<()> {
+        let pending = self
+            .db
+            .list_consolidations(self.coin, Some(ConsolidationStatus::Pending), i64::MAX)
+            .await?;
+
+        for consolidation in pending {
+            let age = (Utc::now() - consolidation.created_at).num_seconds();
+            let mut confirmed = 0;
+            let mut failure = None;
+
+            for tx_hash in &consolidation.tx_hashes {
+                match self.wallet.get_tx_status(tx_hash).await? {
+                    TxStatus::Confirmed => confirmed += 1,
+                    TxStatus::Failed(reason) => failure = Some(format!("{}: {}", tx_hash, reason)),
+                    TxStatus::NotFound if age > CONSOLIDATION_DROP_SECS => {
+                        failure = Some(format!("{}: dropped from wallet", tx_hash))
+                    }
+                    _ => {}
+                }
+            }
+
+            if let Some(reason) = failure {
+                self.db
+                    .update_consolidation_status(&consolidation.id, ConsolidationStatus::Failed, Some(&reason))
+                    .await?;
+
+                error!(
+                    coin = %self.coin,
+                    consolidation_id = %consolidation.id,
+                    reason = %reason,
+                    "Consolidation failed, resuming payouts"
+                );
+            } else if confirmed == consolidation.tx_hashes.len() {
+                self.db
+                    .update_consolidation_status(&consolidation.id, ConsolidationStatus::Confirmed, None)
+                    .await?;
+
+                info!(
+                    coin = %self.coin,
+                    consolidation_id = %consolidation.id,
+                    amount = %consolidation.amount,
+                    fee = %consolidation.fee,
+                    "Consolidation confirmed, resuming payouts"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the wallet can pay right now; logs and returns why not
+    async fn wallet_ready(&self, cycle: &str, action: &str) -> Result<(), String> {
+        let Some(manager) = &self.wallet_manager else {
+            return Ok(());
+        };
+
+        match manager.ensure_ready().await {
+            Ok(WalletReadiness::Ready) => Ok(()),
+            Ok(WalletReadiness::NotReady(reason)) => {
+                warn!(coin = %self.coin, reason = %reason, "Wallet not ready, skipping {}", action);
+                Err(format!("wallet not ready: {}", reason))
+            }
+            Err(e) => {
+                error!(coin = %self.coin, error = %e, "Failed to check wallet, skipping {}", action);
+                self.metrics.record_cycle_error(self.coin, cycle, "wallet_check");
+                Err(format!("wallet check failed: {}", e))
+            }
+        }
+    }
+
+    /// Refresh the wallet balance gauges
+    async fn update_wallet_metrics(&self) -> anyhow::Result<()> {
+        let unlocked = self.wallet.get_balance().await?;
+        let total = self.wallet.get_total_balance().await?;
+        self.metrics.set_wallet_balance(self.coin, unlocked, total);
+
+        if let Some(threshold) = self.settings.low_balance_alert {
+            let low = unlocked < threshold;
+            if low && !self.balance_low.swap(true, Ordering::SeqCst) {
+                warn!(coin = %self.coin, balance = %unlocked, threshold = %threshold, "Wallet balance low");
+                self.publish(EventKind::WalletBalanceLow {
+                    balance: unlocked,
+                    threshold,
+                })
+                .await;
+            } else if !low {
+                self.balance_low.store(false, Ordering::SeqCst);
+            }
+        }
+        Ok(())
+    }
+
     /// Run a complete processing cycle
-    pub async fn run_cycle(&self) -> anyhow::Result<()> {
+    pub async fn run_cycle(&self) -> anyhow::Result<CycleReport> {
+        let mut report = CycleReport::default();
+        if self.shutdown.is_draining() {
+            return Ok(report.skip("shutting down"));
+        }
+        let _active = self.shutdown.track(self.coin, CYCLE_PROCESS);
+        let started = Instant::now();
+
         // Check if pool is online
         if !self.pool_api.is_online().await {
             warn!(coin = %self.coin, "Pool is offline, skipping cycle");
-            return Ok(());
+            self.metrics
+                .record_cycle_error(self.coin, CYCLE_PROCESS, "pool_offline");
+            if !self.pool_offline.swap(true, Ordering::SeqCst) {
+                self.publish(EventKind::PoolOffline).await;
+            }
+            return Ok(report.skip("pool offline"));
         }
+        self.pool_offline.store(false, Ordering::SeqCst);
 
         // 1. Sync shares
-        if let Err(e) = self.sync_shares().await {
-            error!(coin = %self.coin, error = %e, "Failed to sync shares");
+        match self.sync_shares().await {
+            Ok(count) => report.items += count,
+            Err(e) => {
+                error!(coin = %self.coin, error = %e, "Failed to sync shares");
+                self.metrics
+                    .record_cycle_error(self.coin, CYCLE_PROCESS, "sync_shares");
+                report.failed("sync_shares", e);
+            }
         }
 
         // 2. Process new blocks
-        if let Err(e) = self.process_blocks().await {
-            error!(coin = %self.coin, error = %e, "Failed to process blocks");
+        if self.stop_requested(CYCLE_PROCESS, "process_blocks") {
+            return Ok(report.skip("stopped for shutdown"));
+        }
+        match self.process_blocks().await {
+            Ok(count) => report.items += count,
+            Err(e) => {
+                error!(coin = %self.coin, error = %e, "Failed to process blocks");
+                self.metrics
+                    .record_cycle_error(self.coin, CYCLE_PROCESS, "process_blocks");
+                report.failed("process_blocks", e);
+            }
         }
 
-        // 3. Distribute rewards
+        // 3. Verify block rewards against the wallet's coinbase
+        if self.stop_requested(CYCLE_PROCESS, "verify_rewards") {
+            return Ok(report.skip("stopped for shutdown"));
+        }
+        if let Err(e) = self.verify_block_rewards().await {
+            error!(coin = %self.coin, error = %e, "Failed to verify block rewards");
+            self.metrics
+                .record_cycle_error(self.coin, CYCLE_PROCESS, "verify_rewards");
+            report.failed("verify_rewards", e);
+        }
+
+        // 4. Distribute rewards
+        if self.stop_requested(CYCLE_PROCESS, "distribute_rewards") {
+            return Ok(report.skip("stopped for shutdown"));
+        }
         if let Err(e) = self.distribute_rewards().await {
             error!(coin = %self.coin, error = %e, "Failed to distribute rewards");
+            self.metrics
+                .record_cycle_error(self.coin, CYCLE_PROCESS, "distribute_rewards");
+            report.failed("distribute_rewards", e);
         }
 
-        // 4. Confirm pending payments
+        // 5. Confirm pending payments
+        if self.stop_requested(CYCLE_PROCESS, "confirm_payments") {
+            return Ok(report.skip("stopped for shutdown"));
+        }
         if let Err(e) = self.confirm_payments().await {
             error!(coin = %self.coin, error = %e, "Failed to confirm payments");
+            self.metrics
+                .record_cycle_error(self.coin, CYCLE_PROCESS, "confirm_payments");
+            report.failed("confirm_payments", e);
         }
 
-        Ok(())
+        // 6. Confirm pending consolidations
+        if let Err(e) = self.confirm_consolidations().await {
+            error!(coin = %self.coin, error = %e, "Failed to confirm consolidations");
+            self.metrics
+                .record_cycle_error(self.coin, CYCLE_PROCESS, "confirm_consolidations");
+            report.failed("confirm_consolidations", e);
+        }
+
+        // 7. Refresh wallet balance gauges
+        if let Err(e) = self.update_wallet_metrics().await {
+            warn!(coin = %self.coin, error = %e, "Failed to read wallet balance");
+            self.metrics
+                .record_cycle_error(self.coin, CYCLE_PROCESS, "wallet_balance");
+            report.failed("wallet_balance", e);
+        }
+
+        self.metrics
+            .observe_cycle(self.coin, CYCLE_PROCESS, started.elapsed().as_secs_f64());
+
+        Ok(report)
     }
 
     /// Run payment processing (called on payment interval)
-    pub async fn run_payment_cycle(&self) -> anyhow::Result<()> {
+    pub async fn run_payment_cycle(&self) -> anyhow::Result<CycleReport> {
+        let mut report = CycleReport::default();
+        if self.shutdown.is_draining() {
+            return Ok(report.skip("shutting down"));
+        }
+        let _active = self.shutdown.track(self.coin, CYCLE_PAYMENT);
+        let started = Instant::now();
+
+        // Kill switch: nothing leaves the wallet while paused
+        match self.db.get_payout_pause(self.coin).await {
+            Ok(None) => {}
+            Ok(Some(pause)) => {
+                warn!(
+                    coin = %self.coin,
+                    scope = pause.coin.map(|c| c.to_string()).as_deref().unwrap_or("all"),
+                    reason = %pause.reason,
+                    "Payouts paused, skipping payment cycle"
+                );
+                return Ok(report.skip(&format!("payouts paused: {}", pause.reason)));
+            }
+            Err(e) => {
+                error!(coin = %self.coin, error = %e, "Failed to read payout pause, skipping payment cycle");
+                self.metrics
+                    .record_cycle_error(self.coin, CYCLE_PAYMENT, "payout_pause");
+                return Err(e.context("failed to read payout pause"));
+            }
+        }
+
+        // The pool wallet must be open and synced before anything is sent
+        if let Err(reason) = self.wallet_ready(CYCLE_PAYMENT, "payment cycle").await {
+            return Ok(report.skip(&reason));
+        }
+
+        // Relay payouts approved by the operator since the last cycle
+        match self.relay_approved_payouts().await {
+            Ok(relayed) => report.items += relayed,
+            Err(e) => {
+                error!(coin = %self.coin, error = %e, "Failed to relay approved payouts");
+                self.metrics
+                    .record_cycle_error(self.coin, CYCLE_PAYMENT, "relay_approved");
+                report.failed("relay_approved", e);
+            }
+        }
+
         // Process payments for balances above threshold
-        if let Err(e) = self.process_payments().await {
-            error!(coin = %self.coin, error = %e, "Failed to process payments");
+        if self.stop_requested(CYCLE_PAYMENT, "process_payments") {
+            return Ok(report.skip("stopped for shutdown"));
+        }
+        match self.process_payments().await {
+            Ok(sent) => {
+                report.items += sent;
+                if let Some(health) = &self.health {
+                    health.record_payment_cycle(self.coin);
+                }
+            }
+            Err(e) => {
+                error!(coin = %self.coin, error = %e, "Failed to process payments");
+                self.metrics
+                    .record_cycle_error(self.coin, CYCLE_PAYMENT, "process_payments");
+                report.failed("process_payments", e);
+            }
         }
 
-        Ok(())
+        // Set aside (or restore) balances of dormant miners
+        if self.settings.dormant.is_some() {
+            if self.stop_requested(CYCLE_PAYMENT, "dormant") {
+                return Ok(report.skip("stopped for shutdown"));
+            }
+            if let Err(e) = self.apply_dormant_policy().await {
+                error!(coin = %self.coin, error = %e, "Failed to apply dormant balance policy");
+                self.metrics
+                    .record_cycle_error(self.coin, CYCLE_PAYMENT, "dormant");
+                report.failed("dormant", e);
+            }
+        }
+
+        // Move excess hot wallet funds to cold storage
+        if self.settings.cold_sweep.is_some() {
+            if self.stop_requested(CYCLE_PAYMENT, "cold_sweep") {
+                return Ok(report.skip("stopped for shutdown"));
+            }
+            if let Err(e) = self.sweep_to_cold().await {
+                error!(coin = %self.coin, error = %e, "Failed to sweep hot wallet to cold address");
+                self.metrics
+                    .record_cycle_error(self.coin, CYCLE_PAYMENT, "cold_sweep");
+                report.failed("cold_sweep", e);
+            }
+        }
+
+        // Consolidate outputs while no payout is running
+        if let Some(consolidator) = &self.consolidator {
+            if self.stop_requested(CYCLE_PAYMENT, "consolidate") {
+                return Ok(report.skip("stopped for shutdown"));
+            }
+            if let Err(e) = consolidator.run().await {
+                error!(coin = %self.coin, error = %e, "Failed to consolidate wallet outputs");
+                self.metrics
+                    .record_cycle_error(self.coin, CYCLE_PAYMENT, "consolidate");
+                report.failed("consolidate", e);
+            }
+        }
+
+        self.metrics
+            .observe_cycle(self.coin, CYCLE_PAYMENT, started.elapsed().as_secs_f64());
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{BlockCredit, TempDatabase};
+    use crate::pools::{BlockInfo, MinerStats, PoolResult, PoolStats, ShareInfo};
+    use crate::wallets::TestWallet;
+    use async_trait::async_trait;
+    use serde_json::Value;
+
+    /// Pool API that is online with nothing new to report
+    struct IdlePool;
+
+    #[async_trait]
+    impl PoolApi for IdlePool {
+        async fn is_online(&self) -> bool {
+            true
+        }
+
+        async fn get_pool_stats(&self) -> PoolResult<PoolStats> {
+            unimplemented!()
+        }
+
+        async fn get_miner_stats(&self, _wallet_address: &str) -> PoolResult<MinerStats> {
+            unimplemented!()
+        }
+
+        async fn get_all_miners(&self) -> PoolResult<Vec<MinerStats>> {
+            unimplemented!()
+        }
+
+        async fn get_shares_since(&self, _since_timestamp: i64) -> PoolResult<Vec<ShareInfo>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_blocks(&self, _limit: u32) -> PoolResult<Vec<BlockInfo>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_blocks_since_height(&self, _height: i64) -> PoolResult<Vec<BlockInfo>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn settings() -> CoinSettings {
+        CoinSettings {
+            min_payout: Decimal::from(10),
+            reward_source: RewardSource::Pool,
+            coinbase_grace_secs: 7200,
+            cold_sweep: None,
+            approval_threshold: None,
+            limits: PayoutLimits::default(),
+            dormant: None,
+            low_balance_alert: None,
+            pool_fee: None,
+        }
+    }
+
+    fn processor(
+        db: &Database,
+        wallet: TestWallet,
+        settings: CoinSettings,
+    ) -> CoinProcessor<IdlePool, TestWallet> {
+        CoinProcessor::new(
+            Coin::Xmr,
+            IdlePool,
+            wallet,
+            db.clone(),
+            settings,
+            Metrics::new(),
+            Shutdown::new(),
+        )
+    }
+
+    /// Credit miners' pending balances through a distributed block
+    async fn credit(db: &Database, height: i64, credits: &[(&str, i64)]) {
+        for (address, _) in credits {
+            db.record_share(Coin::Xmr, address, "rig", Decimal::ONE, None, false)
+                .await
+                .unwrap();
+        }
+        let reward: i64 = credits.iter().map(|(_, amount)| amount).sum();
+        db.record_block(
+            Coin::Xmr,
+            height,
+            &format!("block-{}", height),
+            Decimal::from(reward),
+            credits[0].0,
+            "rig",
+            RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        let block = db
+            .get_undistributed_blocks(Coin::Xmr)
+            .await
+            .unwrap()
+            .remove(0);
+        let credits: Vec<BlockCredit> = credits
+            .iter()
+            .map(|(address, amount)| BlockCredit {
+                wallet_address: address.to_string(),
+                amount: Decimal::from(*amount),
+                carry: Decimal::ZERO,
+                memo: None,
+            })
+            .collect();
+        db.apply_block_distribution(&block, &credits).await.unwrap();
+    }
+
+    /// Kinds and data of the events published so far
+    async fn published(db: &Database) -> Vec<(String, Value)> {
+        db.list_events_after(0, None, 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.kind, serde_json::from_str(&e.data).unwrap()))
+            .collect()
+    }
+
+    /// A block found by alice, with her share
+    async fn found_block(db: &Database, reward: i64, status: RewardStatus) {
+        db.record_share(Coin::Xmr, "alice", "rig", Decimal::ONE, None, false)
+            .await
+            .unwrap();
+        db.record_block(
+            Coin::Xmr,
+            100,
+            "a",
+            Decimal::from(reward),
+            "alice",
+            "rig",
+            status,
+        )
+        .await
+        .unwrap();
+    }
+
+    /// An approved payout of 500 to alice, prepared as "blob-alice"
+    async fn approved_payout(db: &Database) -> String {
+        let payouts = [("alice".to_string(), Decimal::from(500))];
+        let id = db
+            .create_payout_approval(
+                Coin::Xmr,
+                &payouts,
+                Some("prepared"),
+                None,
+                Some("blob-alice"),
+                Some(Decimal::ONE),
+            )
+            .await
+            .unwrap();
+        assert!(db.decide_payout_approval(&id, true, None).await.unwrap());
+        id
+    }
+
+    #[tokio::test]
+    async fn large_payouts_are_held_until_approved_and_relayed_by_the_payment_cycle() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.approval_threshold = Some(Decimal::from(100));
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings);
+        credit(&db, 100, &[("alice", 500), ("bob", 50)]).await;
+
+        processor.run_payment_cycle().await.unwrap();
+        assert_eq!(
+            processor.wallet.sent(),
+            vec![("bob".to_string(), Decimal::from(50))]
+        );
+        let held = db
+            .list_payout_approvals(Coin::Xmr, Some(ApprovalStatus::PendingApproval), 10)
+            .await
+            .unwrap();
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].total, Decimal::from(500));
+        assert_eq!(held[0].payments[0].status, PaymentStatus::PendingApproval);
+
+        // The held amount stays reserved while it waits
+        processor.run_payment_cycle().await.unwrap();
+        assert_eq!(processor.wallet.sent().len(), 1);
+        assert!(processor.wallet.relayed().is_empty());
+
+        assert!(db
+            .decide_payout_approval(&held[0].id, true, None)
+            .await
+            .unwrap());
+
+        // Only the payment cycle relays
+        processor.run_cycle().await.unwrap();
+        assert!(processor.wallet.relayed().is_empty());
+
+        let report = processor.run_payment_cycle().await.unwrap();
+        assert_eq!(report.items, 1);
+        assert_eq!(processor.wallet.relayed(), vec!["blob-alice".to_string()]);
+        let approval = db.get_payout_approval(&held[0].id).await.unwrap().unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Relayed);
+        assert_eq!(approval.payments[0].status, PaymentStatus::Processing);
+        assert_eq!(approval.payments[0].tx_hash.as_deref(), Some("prepared"));
+    }
+
+    #[tokio::test]
+    async fn approved_payouts_wait_for_a_pending_consolidation() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::default(), settings());
+        let id = approved_payout(&db).await;
+        let consolidation = db
+            .record_consolidation(
+                Coin::Xmr,
+                &["sweep".to_string()],
+                20,
+                Decimal::from(900),
+                Decimal::ONE,
+            )
+            .await
+            .unwrap();
+
+        processor.run_payment_cycle().await.unwrap();
+        assert!(processor.wallet.relayed().is_empty());
+        let approval = db.get_payout_approval(&id).await.unwrap().unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Approved);
+
+        db.update_consolidation_status(&consolidation, ConsolidationStatus::Confirmed, None)
+            .await
+            .unwrap();
+        processor.run_payment_cycle().await.unwrap();
+        assert_eq!(processor.wallet.relayed(), vec!["blob-alice".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn approved_payouts_are_not_relayed_while_paused() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::default(), settings());
+        let id = approved_payout(&db).await;
+        db.set_payout_pause(Some(Coin::Xmr), "investigating", "operator")
+            .await
+            .unwrap();
+
+        let report = processor.run_payment_cycle().await.unwrap();
+        assert!(report.skipped.is_some());
+        assert!(processor.wallet.relayed().is_empty());
+        let approval = db.get_payout_approval(&id).await.unwrap().unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Approved);
+    }
+
+    #[tokio::test]
+    async fn failed_relay_fails_the_approval_and_releases_its_payments() {
+        let db = TempDatabase::new().await;
+        let wallet = TestWallet {
+            fail_relay: true,
+            ..Default::default()
+        };
+        let processor = processor(&db, wallet, settings());
+        let id = approved_payout(&db).await;
+
+        processor.run_payment_cycle().await.unwrap();
+        let approval = db.get_payout_approval(&id).await.unwrap().unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Failed);
+        assert_eq!(approval.payments[0].status, PaymentStatus::Failed);
+        assert!(db.get_reserved_amounts(Coin::Xmr).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn excess_above_liabilities_and_target_is_swept_to_cold() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.cold_sweep = Some((Decimal::from(1_000), "cold".to_string()));
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings);
+        // Below min_payout, so it stays owed
+        credit(&db, 100, &[("alice", 5)]).await;
+
+        processor.run_payment_cycle().await.unwrap();
+        assert_eq!(
+            processor.wallet.sent(),
+            vec![("cold".to_string(), Decimal::from(8_995))]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_failed_record_after_a_batch_send_does_not_stop_the_rest() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings());
+        credit(&db, 100, &[("alice", 500), ("bob", 400), ("carol", 300)]).await;
+        db.fail_payment_updates("bob").await;
+
+        assert_eq!(processor.process_payments().await.unwrap(), 3);
+        for (address, status) in [
+            ("alice", PaymentStatus::Processing),
+            ("bob", PaymentStatus::Pending),
+            ("carol", PaymentStatus::Processing),
+        ] {
+            let payments = db.get_miner_payments(Coin::Xmr, address, 10).await.unwrap();
+            assert_eq!(payments.len(), 1);
+            assert_eq!(payments[0].status, status, "{}", address);
+        }
+
+        // Bob's unrecorded transfer still holds his balance back
+        assert_eq!(processor.process_payments().await.unwrap(), 0);
+        assert_eq!(processor.wallet.sent().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn cold_sweep_keeps_the_rewards_of_undistributed_blocks() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.cold_sweep = Some((Decimal::from(1_000), "cold".to_string()));
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings);
+        credit(&db, 100, &[("alice", 5)]).await;
+        for (height, reward, status) in [
+            (101, 2_000, RewardStatus::Reported),
+            (102, 1_000, RewardStatus::AwaitingCoinbase),
+        ] {
+            db.record_block(
+                Coin::Xmr,
+                height,
+                &format!("block-{}", height),
+                Decimal::from(reward),
+                "alice",
+                "rig",
+                status,
+            )
+            .await
+            .unwrap();
+        }
+
+        processor.sweep_to_cold().await.unwrap();
+        assert_eq!(
+            processor.wallet.sent(),
+            vec![("cold".to_string(), Decimal::from(5_995))]
+        );
+    }
+
+    #[tokio::test]
+    async fn no_cold_sweep_after_a_limit_breach_pauses_payouts() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.cold_sweep = Some((Decimal::from(1_000), "cold".to_string()));
+        settings.limits.max_per_payment = Some(Decimal::from(100));
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings);
+        credit(&db, 100, &[("alice", 500)]).await;
+
+        processor.run_payment_cycle().await.unwrap();
+        assert!(db.get_payout_pause(Coin::Xmr).await.unwrap().is_some());
+        assert!(processor.wallet.sent().is_empty());
+
+        // Nor by calling the stage directly while paused
+        processor.sweep_to_cold().await.unwrap();
+        assert!(processor.wallet.sent().is_empty());
+    }
+
+    #[test]
+    fn limit_breach_names_the_first_limit_exceeded() {
+        let amounts = [Decimal::from(300), Decimal::from(200)];
+        let limits = |f: fn(&mut PayoutLimits)| {
+            let mut limits = PayoutLimits::default();
+            f(&mut limits);
+            limits
+        };
+        let breach = |limits: &PayoutLimits, paid_today: i64| {
+            limit_breach(
+                limits,
+                &amounts,
+                Decimal::from(paid_today),
+                Decimal::from(1_000),
+            )
+        };
+
+        assert_eq!(breach(&PayoutLimits::default(), 0), None);
+        let per_payment = limits(|l| l.max_per_payment = Some(Decimal::from(250)));
+        assert!(breach(&per_payment, 0)
+            .unwrap()
+            .contains("payment of 300 exceeds max_per_payment"));
+        let per_cycle = limits(|l| l.max_per_cycle = Some(Decimal::from(499)));
+        assert!(breach(&per_cycle, 0).unwrap().contains("max_per_cycle"));
+        let per_day = limits(|l| l.max_per_day = Some(Decimal::from(800)));
+        assert_eq!(breach(&per_day, 300), None);
+        assert!(breach(&per_day, 301).unwrap().contains("max_per_day"));
+        let wallet = limits(|l| l.max_wallet_percent = Some(Decimal::from(50)));
+        assert_eq!(breach(&wallet, 0), None);
+        let wallet = limits(|l| l.max_wallet_percent = Some(Decimal::from(49)));
+        assert!(breach(&wallet, 0)
+            .unwrap()
+            .contains("49% of wallet balance"));
+    }
+
+    #[tokio::test]
+    async fn max_per_day_counts_payments_already_sent_and_pauses() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.limits.max_per_day = Some(Decimal::from(1_500));
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings);
+
+        credit(&db, 100, &[("alice", 500), ("bob", 500)]).await;
+        processor.run_payment_cycle().await.unwrap();
+        assert_eq!(processor.wallet.sent().len(), 2);
+
+        credit(&db, 101, &[("carol", 600)]).await;
+        processor.run_payment_cycle().await.unwrap();
+        assert_eq!(processor.wallet.sent().len(), 2);
+        let pause = db.get_payout_pause(Coin::Xmr).await.unwrap().unwrap();
+        assert_eq!(pause.source, "limits");
+        assert!(pause.reason.contains("max_per_day"));
+    }
+
+    #[tokio::test]
+    async fn a_global_pause_holds_payouts_until_cleared() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings());
+        credit(&db, 100, &[("alice", 500)]).await;
+
+        db.set_payout_pause(None, "maintenance", "api")
+            .await
+            .unwrap();
+        let pause = db.get_payout_pause(Coin::Xmr).await.unwrap().unwrap();
+        assert_eq!(pause.coin, None);
+        processor.run_payment_cycle().await.unwrap();
+        assert!(processor.wallet.sent().is_empty());
+
+        // Clearing the XMR pause does not lift the global one
+        assert!(!db.clear_payout_pause(Some(Coin::Xmr)).await.unwrap());
+        processor.run_payment_cycle().await.unwrap();
+        assert!(processor.wallet.sent().is_empty());
+
+        assert!(db.clear_payout_pause(None).await.unwrap());
+        processor.run_payment_cycle().await.unwrap();
+        assert_eq!(
+            processor.wallet.sent(),
+            vec![("alice".to_string(), Decimal::from(500))]
+        );
+    }
+
+    #[tokio::test]
+    async fn proof_key_is_fetched_when_the_wallet_had_none_at_send_time() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings());
+        credit(&db, 100, &[("alice", 500)]).await;
+
+        processor.run_payment_cycle().await.unwrap();
+        let payment = db
+            .list_payments(Coin::Xmr, None, None, 10)
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(payment.tx_hash.as_deref(), Some("tx-alice"));
+        assert_eq!(payment.tx_key, None);
+
+        processor.wallet.confirmed.store(true, Ordering::SeqCst);
+        assert_eq!(processor.confirm_payments().await.unwrap(), 1);
+        let payment = db.get_payment(&payment.id).await.unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Confirmed);
+        assert_eq!(payment.tx_key.as_deref(), Some("kernel-tx-alice"));
+    }
+
+    #[tokio::test]
+    async fn missing_coinbase_is_reported_as_missing_not_orphaned() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.reward_source = RewardSource::Wallet;
+        settings.coinbase_grace_secs = 0;
+        let processor =
+            processor(&db, TestWallet::default(), settings).with_events(EventBus::new(db.clone()));
+        found_block(&db, 1_000, RewardStatus::AwaitingCoinbase).await;
+
+        processor.run_cycle().await.unwrap();
+        let events = published(&db).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, "coinbase_missing");
+        assert_eq!(events[0].1["height"], 100);
+        assert!(db
+            .get_payable_balances(Coin::Xmr, Decimal::ONE)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Flagged once, not on every recheck
+        processor.run_cycle().await.unwrap();
+        assert_eq!(published(&db).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_failed_coinbase_lookup_does_not_hold_up_later_blocks() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.reward_source = RewardSource::Wallet;
+        settings.coinbase_grace_secs = 0;
+        let wallet = TestWallet {
+            coinbase: Some(Decimal::from(990)),
+            coinbase_error_at: Some(100),
+            ..Default::default()
+        };
+        let processor = processor(&db, wallet, settings);
+        for height in [100, 101] {
+            db.record_block(
+                Coin::Xmr,
+                height,
+                &format!("block-{}", height),
+                Decimal::from(1_000),
+                "alice",
+                "rig",
+                RewardStatus::AwaitingCoinbase,
+            )
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(processor.verify_block_rewards().await.unwrap(), 1);
+        let blocks = db.get_undistributed_blocks(Coin::Xmr).await.unwrap();
+        let verified: Vec<i64> = blocks.iter().map(|b| b.block_height).collect();
+        assert_eq!(verified, [101]);
+
+        // Still awaiting its coinbase, not flagged missing
+        let awaiting = db
+            .get_blocks_awaiting_coinbase(Coin::Xmr, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(awaiting.len(), 1);
+        assert_eq!(awaiting[0].reward_status, RewardStatus::AwaitingCoinbase);
+    }
+
+    #[tokio::test]
+    async fn rewards_are_not_verified_until_the_wallet_is_ready() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.reward_source = RewardSource::Wallet;
+        settings.coinbase_grace_secs = 0;
+        // Nothing listens here, so the readiness check fails
+        let manager = WalletManager::new(
+            Coin::Xmr,
+            crate::wallets::monero::MoneroWallet::new("http://127.0.0.1:9", 15),
+            "pool",
+            crate::config::WalletManagerConfig {
+                enabled: true,
+                file: "pool-wallet".to_string(),
+                password_file: None,
+                password: None,
+                daemon_rpc_url: "http://127.0.0.1:9".to_string(),
+                max_sync_lag: 2,
+                refresh_timeout_secs: 1,
+            },
+        );
+        let processor = processor(&db, TestWallet::default(), settings).with_wallet_manager(manager);
+        found_block(&db, 1_000, RewardStatus::AwaitingCoinbase).await;
+
+        assert_eq!(processor.verify_block_rewards().await.unwrap(), 0);
+        let awaiting = db
+            .get_blocks_awaiting_coinbase(Coin::Xmr, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(awaiting[0].reward_status, RewardStatus::AwaitingCoinbase);
+    }
+
+    #[tokio::test]
+    async fn wallet_coinbase_confirms_the_block_with_the_received_reward() {
+        let db = TempDatabase::new().await;
+        let mut settings = settings();
+        settings.reward_source = RewardSource::Wallet;
+        let wallet = TestWallet {
+            coinbase: Some(Decimal::from(990)),
+            ..Default::default()
+        };
+        let processor = processor(&db, wallet, settings).with_events(EventBus::new(db.clone()));
+        found_block(&db, 1_000, RewardStatus::AwaitingCoinbase).await;
+
+        processor.run_cycle().await.unwrap();
+        let events = published(&db).await;
+        let kinds: Vec<&str> = events.iter().map(|(kind, _)| kind.as_str()).collect();
+        assert_eq!(kinds, ["block_confirmed", "rewards_distributed"]);
+        assert_eq!(events[0].1["coinbase_tx"], "coinbase-100");
+        assert_eq!(events[0].1["reward"], "990");
+        assert_eq!(events[1].1["amount"], "990");
+    }
+
+    #[tokio::test]
+    async fn pool_reported_reward_is_confirmed_when_distributed() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::default(), settings())
+            .with_events(EventBus::new(db.clone()));
+        found_block(&db, 1_000, RewardStatus::Reported).await;
+
+        processor.run_cycle().await.unwrap();
+        let events = published(&db).await;
+        let kinds: Vec<&str> = events.iter().map(|(kind, _)| kind.as_str()).collect();
+        assert_eq!(kinds, ["block_confirmed", "rewards_distributed"]);
+        assert_eq!(events[0].1["reward"], "1000");
+        assert!(events[0].1["coinbase_tx"].is_null());
+
+        processor.run_cycle().await.unwrap();
+        assert_eq!(published(&db).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn no_payment_starts_once_shutdown_is_draining() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::with_balance(10_000), settings());
+        credit(&db, 100, &[("alice", 500), ("bob", 50)]).await;
+
+        processor.shutdown.begin_drain();
+        assert!(processor
+            .run_payment_cycle()
+            .await
+            .unwrap()
+            .skipped
+            .is_some());
+
+        // A cycle already past its start check sends nothing either, and
+        // leaves no unsent payment record behind
+        assert_eq!(processor.process_payments().await.unwrap(), 0);
+        assert!(processor.wallet.sent().is_empty());
+        assert!(db.get_pending_payments(Coin::Xmr).await.unwrap().is_empty());
+        assert!(db.get_reserved_amounts(Coin::Xmr).await.unwrap().is_empty());
+    }
+
+    fn dormant_settings() -> CoinSettings {
+        let mut settings = settings();
+        settings.dormant = Some(DormantConfig {
+            enabled: true,
+            pay_after_days: 30,
+            pay_min_amount: Decimal::from(3),
+            abandon_after_days: Some(90),
+        });
+        settings
+    }
+
+    #[tokio::test]
+    async fn dormant_balances_below_min_payout_are_paid_above_the_fee_floor() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::with_balance(10_000), dormant_settings());
+        credit(&db, 100, &[("alice", 5), ("bob", 2), ("carol", 5)]).await;
+        db.backdate_last_share(Coin::Xmr, "alice", 40).await;
+        db.backdate_last_share(Coin::Xmr, "bob", 40).await;
+
+        // Bob is below the fee floor and carol is still mining
+        processor.run_payment_cycle().await.unwrap();
+        assert_eq!(
+            processor.wallet.sent(),
+            vec![("alice".to_string(), Decimal::from(5))]
+        );
+        let bob = db
+            .get_miner_balance(Coin::Xmr, "bob")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(bob.pending_balance, Decimal::from(2));
+        assert_eq!(bob.abandoned_balance, Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn abandoned_balances_are_restored_when_the_miner_returns() {
+        let db = TempDatabase::new().await;
+        let processor = processor(&db, TestWallet::with_balance(10_000), dormant_settings());
+        credit(&db, 100, &[("alice", 2), ("bob", 2)]).await;
+        db.backdate_last_share(Coin::Xmr, "alice", 100).await;
+        db.backdate_last_share(Coin::Xmr, "bob", 60).await;
+
+        // Only alice has been gone past abandon_after_days
+        processor.run_payment_cycle().await.unwrap();
+        assert!(processor.wallet.sent().is_empty());
+        let alice = db
+            .get_miner_balance(Coin::Xmr, "alice")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alice.pending_balance, Decimal::ZERO);
+        assert_eq!(alice.abandoned_balance, Decimal::from(2));
+        let bob = db
+            .get_miner_balance(Coin::Xmr, "bob")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(bob.pending_balance, Decimal::from(2));
+        assert_eq!(
+            crate::cli::db::check_integrity(&db, vec![Coin::Xmr])
+                .await
+                .unwrap(),
+            0
+        );
+
+        db.record_share(Coin::Xmr, "alice", "rig", Decimal::ONE, None, false)
+            .await
+            .unwrap();
+        processor.run_payment_cycle().await.unwrap();
+        let alice = db
+            .get_miner_balance(Coin::Xmr, "alice")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alice.pending_balance, Decimal::from(2));
+        assert_eq!(alice.abandoned_balance, Decimal::ZERO);
     }
 }
@@ -0,0 +1,404 @@
+//! Job scheduler
+//!
+//! Every coin has a processing job (share sync, blocks, rewards) and a
+//! payment job. Each job runs in its own task, so a run never overlaps the
+//! previous run of the same job; a trigger that arrives mid-run starts the
+//! next run as soon as it finishes.
+//!
+//! A job runs when:
+//! - its interval (plus `schedule_jitter_secs` of random delay) elapses
+//! - a new block is announced, for jobs with a block waker
+//! - an operator asks for it (`POST /api/jobs/:coin/:job/run`)
+//!
+//! Every run is recorded in the `job_runs` table with its trigger,
+//! duration, outcome and item count.
+
+use crate::db::{Coin, Database, JobOutcome, JobRun};
+use crate::processor::CycleReport;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// How long run history is kept
+const RUN_RETENTION_DAYS: i64 = 14;
+
+/// How often old run history is pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// What started a run
+const TRIGGER_INTERVAL: &str = "interval";
+const TRIGGER_BLOCK: &str = "block";
+const TRIGGER_MANUAL: &str = "manual";
+
+type JobFuture = Pin<Box<dyn Future<Output = anyhow::Result<CycleReport>> + Send>>;
+type JobFn = Box<dyn Fn() -> JobFuture + Send + Sync>;
+
+/// A registered job
+struct Job {
+    name: &'static str,
+    coin: Coin,
+    interval: Duration,
+    /// Woken when the coin's node announces a new block
+    wake: Option<Arc<Notify>>,
+    /// Woken by operator "run now" requests
+    manual: Notify,
+    running: AtomicBool,
+    state: Mutex<JobState>,
+    run: JobFn,
+}
+
+#[derive(Default)]
+struct JobState {
+    next_run: Option<DateTime<Utc>>,
+    last_run: Option<JobRun>,
+}
+
+/// A job's schedule and its latest run
+#[derive(Debug, Serialize)]
+pub struct JobInfo {
+    pub job: &'static str,
+    pub coin: Coin,
+    pub interval_secs: u64,
+    pub running: bool,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<JobRun>,
+}
+
+/// Runs the coin processors' jobs and records their history
+#[derive(Clone)]
+pub struct Scheduler {
+    db: Database,
+    jitter: Duration,
+    jobs: Arc<Mutex<Vec<Arc<Job>>>>,
+}
+
+impl Scheduler {
+    pub fn new(db: Database, jitter_secs: u64) -> Self {
+        Self {
+            db,
+            jitter: Duration::from_secs(jitter_secs),
+            jobs: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Register a job; it starts running when `start` is called
+    pub fn add<F, Fut>(
+        &self,
+        coin: Coin,
+        name: &'static str,
+        interval_secs: u64,
+        wake: Option<Arc<Notify>>,
+        run: F,
+    ) where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<CycleReport>> + Send + 'static,
+    {
+        self.lock().push(Arc::new(Job {
+            name,
+            coin,
+            interval: Duration::from_secs(interval_secs),
+            wake,
+            manual: Notify::new(),
+            running: AtomicBool::new(false),
+            state: Mutex::new(JobState::default()),
+            run: Box::new(move || Box::pin(run())),
+        }));
+    }
+
+    /// Every job's schedule and latest run
+    pub fn jobs(&self) -> Vec<JobInfo> {
+        self.lock()
+            .iter()
+            .map(|job| {
+                let state = job.state();
+                JobInfo {
+                    job: job.name,
+                    coin: job.coin,
+                    interval_secs: job.interval.as_secs(),
+                    running: job.running.load(Ordering::SeqCst),
+                    next_run: state.next_run,
+                    last_run: state.last_run.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Ask a job to run now; returns whether it was already running
+    /// (the new run starts when that one ends), or None for an unknown job
+    pub fn trigger(&self, coin: Coin, name: &str) -> Option<bool> {
+        let job = self
+            .lock()
+            .iter()
+            .find(|job| job.coin == coin && job.name == name)
+            .cloned()?;
+        job.manual.notify_one();
+        Some(job.running.load(Ordering::SeqCst))
+    }
+
+    /// Spawn a task per job, plus history pruning, until shutdown
+    pub fn start(&self, shutdown: &broadcast::Sender<()>) -> Vec<JoinHandle<()>> {
+        let mut handles: Vec<_> = self
+            .lock()
+            .iter()
+            .map(|job| {
+                let (scheduler, job, shutdown) = (self.clone(), job.clone(), shutdown.subscribe());
+                tokio::spawn(async move { scheduler.run_job(job, shutdown).await })
+            })
+            .collect();
+
+        let (scheduler, shutdown) = (self.clone(), shutdown.subscribe());
+        handles.push(tokio::spawn(async move { scheduler.prune_history(shutdown).await }));
+
+        handles
+    }
+
+    async fn run_job(&self, job: Arc<Job>, mut shutdown: broadcast::Receiver<()>) {
+        let idle = Notify::new();
+        let wake = job.wake.as_deref().unwrap_or(&idle);
+        let mut next = Instant::now() + self.jitter();
+
+        loop {
+            let until_next = next.saturating_duration_since(Instant::now());
+            job.state().next_run = Some(Utc::now() + chrono::Duration::from_std(until_next).unwrap_or_default());
+
+            let trigger = tokio::select! {
+                _ = sleep_until(next) => TRIGGER_INTERVAL,
+                _ = wake.notified() => TRIGGER_BLOCK,
+                _ = job.manual.notified() => TRIGGER_MANUAL,
+                _ = shutdown.recv() => {
+                    info!(coin = %job.coin, job = job.name, "Job stopped");
+                    break;
+                }
+            };
+
+            // The interval restarts from every run, whatever started it
+            let started = Instant::now();
+            next = started + job.interval + self.jitter();
+            self.execute(&job, trigger).await;
+        }
+    }
+
+    /// Run a job once and record the outcome
+    async fn execute(&self, job: &Job, trigger: &'static str) {
+        let started_at = Utc::now();
+        let started = Instant::now();
+        job.running.store(true, Ordering::SeqCst);
+        debug!(coin = %job.coin, job = job.name, trigger, "Job started");
+
+        let result = (job.run)().await;
+
+        job.running.store(false, Ordering::SeqCst);
+        let duration_ms = started.elapsed().as_millis() as i64;
+
+        let (outcome, message, items) = match result {
+            Ok(report) => {
+                let items = report.items as i64;
+                if let Some(reason) = report.skipped {
+                    (JobOutcome::Skipped, Some(reason), items)
+                } else if !report.errors.is_empty() {
+                    (JobOutcome::Partial, Some(report.errors.join("; ")), items)
+                } else {
+                    (JobOutcome::Success, None, items)
+                }
+            }
+            Err(e) => {
+                error!(coin = %job.coin, job = job.name, error = %e, "Job failed");
+                (JobOutcome::Failed, Some(e.to_string()), 0)
+            }
+        };
+
+        let id = match self
+            .db
+            .record_job_run(
+                job.name,
+                job.coin,
+                trigger,
+                started_at,
+                duration_ms,
+                outcome,
+                message.as_deref(),
+                items,
+            )
+            .await
+        {
+            Ok(id) => id,
+            Err(e) => {
+                warn!(coin = %job.coin, job = job.name, error = %e, "Failed to record job run");
+                0
+            }
+        };
+
+        job.state().last_run = Some(JobRun {
+            id,
+            job: job.name.to_string(),
+            coin: job.coin,
+            trigger: trigger.to_string(),
+            started_at,
+            duration_ms,
+            outcome,
+            error: message,
+            items,
+        });
+    }
+
+    /// Delete run history older than RUN_RETENTION_DAYS
+    async fn prune_history(&self, mut shutdown: broadcast::Receiver<()>) {
+        loop {
+            let cutoff = Utc::now() - chrono::Duration::days(RUN_RETENTION_DAYS);
+            match self.db.prune_job_runs(cutoff).await {
+                Ok(0) => {}
+                Ok(pruned) => debug!(pruned, "Pruned old job runs"),
+                Err(e) => warn!(error = %e, "Failed to prune job runs"),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(PRUNE_INTERVAL) => {}
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+
+    /// A random delay of up to `schedule_jitter_secs`
+    fn jitter(&self) -> Duration {
+        let max_ms = self.jitter.as_millis() as u64;
+        if max_ms == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis((uuid::Uuid::new_v4().as_u128() % max_ms as u128) as u64)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Arc<Job>>> {
+        self.jobs.lock().expect("scheduler lock poisoned")
+    }
+}
+
+impl Job {
+    fn state(&self) -> std::sync::MutexGuard<'_, JobState> {
+        self.state.lock().expect("job state lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TempDatabase;
+    use std::sync::atomic::AtomicU32;
+
+    async fn wait_for_runs(db: &Database, count: usize) -> Vec<JobRun> {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let runs = db.list_job_runs(Some(Coin::Xmr), None, 10).await.unwrap();
+                if runs.len() >= count {
+                    return runs;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("job runs were not recorded")
+    }
+
+    #[tokio::test]
+    async fn manual_trigger_during_run_queues_one_run() {
+        let db = TempDatabase::new().await;
+        let scheduler = Scheduler::new(db.clone(), 0);
+        let active = Arc::new(AtomicBool::new(false));
+        let overlaps = Arc::new(AtomicU32::new(0));
+
+        let (job_active, job_overlaps) = (active.clone(), overlaps.clone());
+        scheduler.add(Coin::Xmr, "process", 3600, None, move || {
+            let (active, overlaps) = (job_active.clone(), job_overlaps.clone());
+            async move {
+                if active.swap(true, Ordering::SeqCst) {
+                    overlaps.fetch_add(1, Ordering::SeqCst);
+                }
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                active.store(false, Ordering::SeqCst);
+                Ok(CycleReport {
+                    items: 3,
+                    ..Default::default()
+                })
+            }
+        });
+
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let handles = scheduler.start(&shutdown_tx);
+
+        // The first interval run starts right away
+        while !scheduler.jobs()[0].running {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(scheduler.trigger(Coin::Xmr, "process"), Some(true));
+        assert_eq!(scheduler.trigger(Coin::Xmr, "process"), Some(true));
+        assert_eq!(scheduler.trigger(Coin::Xmr, "payment"), None);
+
+        let runs = wait_for_runs(&db, 2).await;
+        assert_eq!(runs[0].trigger, TRIGGER_MANUAL);
+        assert_eq!(runs[1].trigger, TRIGGER_INTERVAL);
+        assert!(runs.iter().all(|r| r.outcome == JobOutcome::Success && r.items == 3));
+        assert_eq!(overlaps.load(Ordering::SeqCst), 0);
+
+        // Both triggers arrived during one run, so only one extra run follows
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(db.list_job_runs(Some(Coin::Xmr), None, 10).await.unwrap().len(), 2);
+
+        shutdown_tx.send(()).unwrap();
+        for handle in handles {
+            tokio::time::timeout(Duration::from_secs(5), handle).await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn records_skips_and_failures() {
+        let db = TempDatabase::new().await;
+        let scheduler = Scheduler::new(db.clone(), 0);
+        let calls = Arc::new(AtomicU32::new(0));
+
+        let job_calls = calls.clone();
+        scheduler.add(Coin::Xmr, "payment", 3600, None, move || {
+            let calls = job_calls.clone();
+            async move {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Ok(CycleReport {
+                        skipped: Some("payouts paused: audit".to_string()),
+                        ..Default::default()
+                    }),
+                    1 => Ok(CycleReport {
+                        items: 1,
+                        errors: vec!["cold_sweep: wallet busy".to_string()],
+                        skipped: None,
+                    }),
+                    _ => Err(anyhow::anyhow!("database is locked")),
+                }
+            }
+        });
+
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let _handles = scheduler.start(&shutdown_tx);
+
+        wait_for_runs(&db, 1).await;
+        scheduler.trigger(Coin::Xmr, "payment");
+        wait_for_runs(&db, 2).await;
+        scheduler.trigger(Coin::Xmr, "payment");
+        let runs = wait_for_runs(&db, 3).await;
+
+        assert_eq!(runs[2].outcome, JobOutcome::Skipped);
+        assert_eq!(runs[2].error.as_deref(), Some("payouts paused: audit"));
+        assert_eq!(runs[1].outcome, JobOutcome::Partial);
+        assert_eq!(runs[1].items, 1);
+        assert_eq!(runs[0].outcome, JobOutcome::Failed);
+        assert_eq!(runs[0].error.as_deref(), Some("database is locked"));
+
+        let last = scheduler.jobs().remove(0).last_run.unwrap();
+        assert_eq!(last.id, runs[0].id);
+
+        shutdown_tx.send(()).unwrap();
+    }
+}
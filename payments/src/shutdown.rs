@@ -0,0 +1,248 @@
+//! Graceful shutdown coordination
+//!
+//! On SIGTERM/SIGINT/SIGHUP the service enters a draining state:
+//! - Cycles that have not started yet are skipped
+//! - Running cycles finish their current wallet call and DB write,
+//!   then stop before starting the next payment
+//! - Cycles still running when the drain timeout expires are logged
+//!   as interrupted and aborted, except that a payout between its wallet
+//!   call and the DB write recording it is always let finish: aborting
+//!   there would leave a broadcast payment unrecorded
+
+use crate::db::Coin;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A cycle that is currently running
+#[derive(Debug, Clone)]
+pub struct ActiveCycle {
+    pub coin: Coin,
+    pub cycle: &'static str,
+    pub started: Instant,
+}
+
+/// Shared shutdown state for all coin processors
+#[derive(Default)]
+pub struct Shutdown {
+    draining: AtomicBool,
+    next_id: AtomicU64,
+    active: Mutex<HashMap<u64, ActiveCycle>>,
+    /// Payouts between their wallet call and its DB record
+    payouts_in_flight: AtomicUsize,
+}
+
+impl Shutdown {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Stop starting new work
+    pub fn begin_drain(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the service is shutting down
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Register a running cycle; it is removed when the guard is dropped
+    pub fn track(self: &Arc<Self>, coin: Coin, cycle: &'static str) -> CycleGuard {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.active.lock().expect("shutdown lock poisoned").insert(
+            id,
+            ActiveCycle {
+                coin,
+                cycle,
+                started: Instant::now(),
+            },
+        );
+
+        CycleGuard {
+            id,
+            shutdown: self.clone(),
+        }
+    }
+
+    /// Cycles that are still running
+    pub fn active_cycles(&self) -> Vec<ActiveCycle> {
+        self.active
+            .lock()
+            .expect("shutdown lock poisoned")
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Start a payout's send section, unless the service is shutting down
+    ///
+    /// Hold the guard from the wallet call until the result is recorded.
+    /// Once draining no new payout starts, so `wait_for_payouts` only waits
+    /// for those already sending.
+    pub fn begin_payout(self: &Arc<Self>) -> Option<PayoutGuard> {
+        // Count first: either this sees the drain or the drain sees the count
+        self.payouts_in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = PayoutGuard {
+            shutdown: self.clone(),
+        };
+
+        if self.is_draining() {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Number of payouts being sent and recorded
+    pub fn payouts_in_flight(&self) -> usize {
+        self.payouts_in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no payout is between its wallet call and its record
+    ///
+    /// Has no deadline: a payout cut off here would be broadcast but
+    /// never recorded.
+    pub async fn wait_for_payouts(&self) {
+        while self.payouts_in_flight() > 0 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Wait until all running cycles finish or the timeout expires
+    ///
+    /// Returns the cycles that were still running at the deadline.
+    pub async fn wait_for_cycles(&self, timeout: Duration) -> Vec<ActiveCycle> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let active = self.active_cycles();
+            if active.is_empty() || Instant::now() >= deadline {
+                return active;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+/// Removes a cycle from the active set when dropped
+pub struct CycleGuard {
+    id: u64,
+    shutdown: Arc<Shutdown>,
+}
+
+impl Drop for CycleGuard {
+    fn drop(&mut self) {
+        if let Ok(mut active) = self.shutdown.active.lock() {
+            active.remove(&self.id);
+        }
+    }
+}
+
+/// Ends a payout's send section when dropped
+pub struct PayoutGuard {
+    shutdown: Arc<Shutdown>,
+}
+
+impl Drop for PayoutGuard {
+    fn drop(&mut self) {
+        self.shutdown.payouts_in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Wait for SIGINT (Ctrl+C), SIGTERM (systemd stop) or SIGHUP
+///
+/// Returns the name of the signal received.
+pub async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut sigterm = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+        let mut sighup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = sigterm.recv() => "SIGTERM",
+            _ = sighup.recv() => "SIGHUP",
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl+C"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracked_cycles_are_active_until_their_guard_drops() {
+        let shutdown = Shutdown::new();
+        let process = shutdown.track(Coin::Xmr, "process");
+        let payment = shutdown.track(Coin::Btc, "payment");
+
+        let mut active: Vec<_> = shutdown
+            .active_cycles()
+            .into_iter()
+            .map(|c| (c.coin, c.cycle))
+            .collect();
+        active.sort_by_key(|(_, cycle)| *cycle);
+        assert_eq!(active, [(Coin::Btc, "payment"), (Coin::Xmr, "process")]);
+
+        drop(process);
+        assert_eq!(shutdown.active_cycles().len(), 1);
+        drop(payment);
+        assert!(shutdown.active_cycles().is_empty());
+    }
+
+    #[tokio::test]
+    async fn wait_for_cycles_returns_once_they_finish() {
+        let shutdown = Shutdown::new();
+        let guard = shutdown.track(Coin::Xmr, "payment");
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            drop(guard);
+        });
+
+        let started = Instant::now();
+        assert!(shutdown
+            .wait_for_cycles(Duration::from_secs(10))
+            .await
+            .is_empty());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn wait_for_cycles_reports_what_overran_the_timeout() {
+        let shutdown = Shutdown::new();
+        let _guard = shutdown.track(Coin::Xmr, "payment");
+
+        let started = Instant::now();
+        let interrupted = shutdown.wait_for_cycles(Duration::from_millis(300)).await;
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].cycle, "payment");
+    }
+
+    #[tokio::test]
+    async fn payouts_already_sending_are_waited_for_and_no_new_one_starts() {
+        let shutdown = Shutdown::new();
+        let sending = shutdown.begin_payout().expect("not draining yet");
+        assert_eq!(shutdown.payouts_in_flight(), 1);
+
+        shutdown.begin_drain();
+        assert!(shutdown.begin_payout().is_none());
+        assert_eq!(shutdown.payouts_in_flight(), 1);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            drop(sending);
+        });
+        shutdown.wait_for_payouts().await;
+        assert_eq!(shutdown.payouts_in_flight(), 0);
+    }
+}
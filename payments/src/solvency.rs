@@ -0,0 +1,235 @@
+//! Solvency report
+//!
+//! Compares what the pool owes with what its wallet holds. Liabilities are
+//! the miner balances (including abandoned ones and payments not yet
+//! confirmed) plus rewards of blocks not distributed yet, less payments
+//! already broadcast: those have left the wallet but stay in the balances
+//! until they confirm.
+//!
+//! - `ok`: unlocked funds cover the liabilities
+//! - `warning`: only the total balance (including locked coinbases) does
+//! - `deficit`: the total balance does not
+//! - `unknown`: the wallet could not be asked
+
+use crate::db::{Coin, Database, PaymentStatus};
+use crate::wallets::Wallet;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::time::{timeout, Duration};
+
+/// How long each wallet balance query may take
+const WALLET_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SolvencyStatus {
+    Ok,
+    Warning,
+    Deficit,
+    Unknown,
+}
+
+impl SolvencyStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SolvencyStatus::Ok => "ok",
+            SolvencyStatus::Warning => "warning",
+            SolvencyStatus::Deficit => "deficit",
+            SolvencyStatus::Unknown => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for SolvencyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One coin's liabilities against its wallet, in atomic units
+#[derive(Debug, Clone, Serialize)]
+pub struct SolvencyReport {
+    pub coin: Coin,
+    pub status: SolvencyStatus,
+    pub checked_at: String,
+    /// Pending, carry and abandoned miner balances
+    pub owed_to_miners: Decimal,
+    /// Payments awaiting approval or sending (part of `owed_to_miners`)
+    pub payments_pending: Decimal,
+    /// Payments broadcast but not confirmed (part of `owed_to_miners`, no longer in the wallet)
+    pub payments_unconfirmed: Decimal,
+    /// Rewards of blocks not distributed yet
+    pub immature_rewards: Decimal,
+    /// What the wallet still has to cover
+    pub liabilities: Decimal,
+    pub wallet_unlocked: Option<Decimal>,
+    pub wallet_total: Option<Decimal>,
+    /// Total balance less liabilities; negative is a deficit
+    pub surplus: Option<Decimal>,
+    /// Unlocked balance less liabilities
+    pub unlocked_surplus: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet_error: Option<String>,
+}
+
+/// Build a coin's solvency report; without a wallet the status is `unknown`
+pub async fn report(
+    db: &Database,
+    coin: Coin,
+    wallet: Option<&dyn Wallet>,
+) -> anyhow::Result<SolvencyReport> {
+    let owed_to_miners = db.get_liabilities(coin).await?;
+
+    let payments = db.get_payment_totals(coin).await?;
+    let total = |statuses: &[PaymentStatus]| -> Decimal {
+        statuses
+            .iter()
+            .filter_map(|s| payments.get(s))
+            .copied()
+            .sum()
+    };
+    let payments_pending = total(&[PaymentStatus::PendingApproval, PaymentStatus::Pending]);
+    let payments_unconfirmed = total(&[PaymentStatus::Processing]);
+
+    let immature_rewards: Decimal = db
+        .get_undistributed_blocks(coin)
+        .await?
+        .iter()
+        .map(|b| b.reward)
+        .sum();
+
+    let liabilities = owed_to_miners - payments_unconfirmed + immature_rewards;
+
+    let balances = match wallet {
+        Some(wallet) => wallet_balances(wallet).await,
+        None => Err("wallet not configured".to_string()),
+    };
+    let (wallet_unlocked, wallet_total, wallet_error) = match balances {
+        Ok((unlocked, total)) => (Some(unlocked), Some(total), None),
+        Err(e) => (None, None, Some(e)),
+    };
+
+    let status = match (wallet_unlocked, wallet_total) {
+        (Some(_), Some(total)) if liabilities > total => SolvencyStatus::Deficit,
+        (Some(unlocked), Some(_)) if liabilities > unlocked => SolvencyStatus::Warning,
+        (Some(_), Some(_)) => SolvencyStatus::Ok,
+        _ => SolvencyStatus::Unknown,
+    };
+
+    Ok(SolvencyReport {
+        coin,
+        status,
+        checked_at: Utc::now().to_rfc3339(),
+        owed_to_miners: owed_to_miners.normalize(),
+        payments_pending: payments_pending.normalize(),
+        payments_unconfirmed: payments_unconfirmed.normalize(),
+        immature_rewards: immature_rewards.normalize(),
+        liabilities: liabilities.normalize(),
+        wallet_unlocked,
+        wallet_total,
+        surplus: wallet_total.map(|t| (t - liabilities).normalize()),
+        unlocked_surplus: wallet_unlocked.map(|u| (u - liabilities).normalize()),
+        wallet_error,
+    })
+}
+
+/// Unlocked and total pool wallet balance
+async fn wallet_balances(wallet: &dyn Wallet) -> Result<(Decimal, Decimal), String> {
+    let unlocked = timeout(WALLET_TIMEOUT, wallet.get_balance())
+        .await
+        .map_err(|_| "wallet RPC timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    let total = timeout(WALLET_TIMEOUT, wallet.get_total_balance())
+        .await
+        .map_err(|_| "wallet RPC timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    Ok((unlocked, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{BlockCredit, RewardStatus, TempDatabase};
+    use crate::wallets::TestWallet;
+
+    #[tokio::test]
+    async fn liabilities_exclude_sent_payments_and_include_undistributed_blocks() {
+        let db = TempDatabase::new().await;
+
+        // 1000 credited to alice, 300 of it already broadcast
+        db.record_share(Coin::Xmr, "alice", "rig", Decimal::ONE, None, false)
+            .await
+            .unwrap();
+        db.record_block(
+            Coin::Xmr,
+            100,
+            "a",
+            Decimal::from(1_000),
+            "alice",
+            "rig",
+            RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+        let block = db
+            .get_undistributed_blocks(Coin::Xmr)
+            .await
+            .unwrap()
+            .remove(0);
+        let credits = [BlockCredit {
+            wallet_address: "alice".to_string(),
+            amount: Decimal::from(1_000),
+            carry: Decimal::ZERO,
+            memo: None,
+        }];
+        db.apply_block_distribution(&block, &credits).await.unwrap();
+        let payment = db
+            .create_payment(Coin::Xmr, "alice", Decimal::from(300))
+            .await
+            .unwrap();
+        db.mark_payment_sent(&payment, "tx", None).await.unwrap();
+
+        // A block found but not distributed yet
+        db.record_block(
+            Coin::Xmr,
+            101,
+            "b",
+            Decimal::from(500),
+            "alice",
+            "rig",
+            RewardStatus::Reported,
+        )
+        .await
+        .unwrap();
+
+        let check = |unlocked: i64, total: i64| {
+            let wallet = TestWallet {
+                total: Some(Decimal::from(total)),
+                ..TestWallet::with_balance(unlocked)
+            };
+            let db = db.clone();
+            async move { report(&db, Coin::Xmr, Some(&wallet)).await.unwrap() }
+        };
+
+        let ok = check(1_200, 1_200).await;
+        assert_eq!(ok.owed_to_miners, Decimal::from(1_000));
+        assert_eq!(ok.payments_unconfirmed, Decimal::from(300));
+        assert_eq!(ok.immature_rewards, Decimal::from(500));
+        assert_eq!(ok.liabilities, Decimal::from(1_200));
+        assert_eq!(ok.status, SolvencyStatus::Ok);
+
+        let warning = check(700, 1_300).await;
+        assert_eq!(warning.status, SolvencyStatus::Warning);
+        assert_eq!(warning.unlocked_surplus, Some(Decimal::from(-500)));
+        assert_eq!(warning.surplus, Some(Decimal::from(100)));
+
+        let deficit = check(700, 1_100).await;
+        assert_eq!(deficit.status, SolvencyStatus::Deficit);
+        assert_eq!(deficit.surplus, Some(Decimal::from(-100)));
+
+        let unknown = report(&db, Coin::Xmr, None).await.unwrap();
+        assert_eq!(unknown.status, SolvencyStatus::Unknown);
+        assert_eq!(unknown.liabilities, Decimal::from(1_200));
+    }
+}
@@ -0,0 +1,355 @@
+//! monero-wallet-rpc lifecycle (XMR)
+//!
+//! monero-wallet-rpc serves whichever wallet happens to be open: after a
+//! restart it may have none, an operator may have opened a different one,
+//! and a freshly opened wallet needs time to scan the chain. A wallet that
+//! is behind reports a stale balance and builds transactions from outputs
+//! it doesn't know are spent.
+//!
+//! Before each payout the manager checks that the open wallet is the pool
+//! wallet, reopening the configured wallet file if it isn't, refreshes it
+//! and compares its height with the daemon's. Payouts wait while either is
+//! still catching up.
+
+use crate::config::WalletManagerConfig;
+use crate::db::Coin;
+use crate::wallets::monero::MoneroWallet;
+use crate::wallets::{Wallet, WalletError};
+use serde::Deserialize;
+use std::fmt;
+use tokio::sync::Mutex;
+use tokio::time::{timeout, Duration};
+use tracing::{debug, info, warn};
+
+/// Whether the pool wallet can be paid from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletReadiness {
+    Ready,
+    /// Payouts should wait, with the reason
+    NotReady(String),
+}
+
+impl fmt::Display for WalletReadiness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletReadiness::Ready => write!(f, "ready"),
+            WalletReadiness::NotReady(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Keeps the pool wallet open and synced in monero-wallet-rpc
+pub struct WalletManager {
+    coin: Coin,
+    wallet: MoneroWallet,
+    pool_wallet_address: String,
+    config: WalletManagerConfig,
+    daemon_url: String,
+    client: reqwest::Client,
+    /// The processing and payment loops share one wallet-rpc; never open a wallet twice at once
+    lock: Mutex<()>,
+}
+
+impl WalletManager {
+    pub fn new(
+        coin: Coin,
+        wallet: MoneroWallet,
+        pool_wallet_address: &str,
+        config: WalletManagerConfig,
+    ) -> Self {
+        let daemon_url = config.daemon_rpc_url.trim_end_matches('/');
+        let daemon_url = daemon_url.strip_suffix("/json_rpc").unwrap_or(daemon_url).to_string();
+
+        Self {
+            coin,
+            wallet,
+            pool_wallet_address: pool_wallet_address.to_string(),
+            config,
+            daemon_url,
+            client: reqwest::Client::new(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Make sure the pool wallet is open and synced before a payout
+    ///
+    /// Reopens the configured wallet file if wallet-rpc has none or the
+    /// wrong one open, then refreshes it. Errors mean wallet-rpc or the
+    /// daemon could not be reached.
+    pub async fn ensure_ready(&self) -> anyhow::Result<WalletReadiness> {
+        self.check(true).await
+    }
+
+    /// Report whether the pool wallet is ready without opening or refreshing it
+    pub async fn inspect(&self) -> anyhow::Result<WalletReadiness> {
+        self.check(false).await
+    }
+
+    async fn check(&self, repair: bool) -> anyhow::Result<WalletReadiness> {
+        let _guard = self.lock.lock().await;
+
+        let open = match self.wallet.get_address().await {
+            Ok(Some(address)) if address == self.pool_wallet_address => None,
+            Ok(address) => Some(format!(
+                "wallet-rpc has wallet {} open instead of the pool wallet",
+                address.as_deref().unwrap_or("(unknown)")
+            )),
+            Err(WalletError::WalletLocked) => Some("wallet-rpc has no wallet open".to_string()),
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Some(problem) = open {
+            if !repair {
+                return Ok(WalletReadiness::NotReady(problem));
+            }
+            warn!(coin = %self.coin, file = %self.config.file, "{}, opening the pool wallet", problem);
+            if let Some(reason) = self.open().await? {
+                return Ok(WalletReadiness::NotReady(reason));
+            }
+        }
+
+        if repair {
+            // A wallet far behind can take hours to scan; the lock must not
+            // be held that long
+            let limit = Duration::from_secs(self.config.refresh_timeout_secs);
+            match timeout(limit, self.wallet.refresh()).await {
+                Ok(fetched) => debug!(coin = %self.coin, blocks = fetched?, "Wallet refreshed"),
+                Err(_) => {
+                    return Ok(WalletReadiness::NotReady(format!(
+                        "wallet refresh still running after {}s",
+                        self.config.refresh_timeout_secs
+                    )))
+                }
+            }
+        }
+
+        let daemon = self.daemon_info().await?;
+        let wallet_height = self.wallet.get_height().await?;
+
+        Ok(sync_readiness(wallet_height, &daemon, self.config.max_sync_lag))
+    }
+
+    /// Open the configured wallet file; returns why payouts must wait, if they must
+    async fn open(&self) -> anyhow::Result<Option<String>> {
+        let password = self.config.load_password()?;
+
+        if let Err(e) = self.wallet.open_wallet(&self.config.file, &password).await {
+            return Ok(Some(format!("failed to open wallet file '{}': {}", self.config.file, e)));
+        }
+
+        match self.wallet.get_address().await? {
+            Some(address) if address == self.pool_wallet_address => {
+                info!(coin = %self.coin, file = %self.config.file, "Pool wallet opened");
+                Ok(None)
+            }
+            address => Ok(Some(format!(
+                "wallet file '{}' has address {}, not pool_wallet_address",
+                self.config.file,
+                address.as_deref().unwrap_or("(unknown)")
+            ))),
+        }
+    }
+
+    /// The daemon's height and sync target
+    async fn daemon_info(&self) -> anyhow::Result<DaemonInfo> {
+        let response: DaemonResponse = self
+            .client
+            .post(format!("{}/json_rpc", self.daemon_url))
+            .json(&serde_json::json!({"jsonrpc": "2.0", "id": "0", "method": "get_info"}))
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("monerod at {} unreachable: {}", self.daemon_url, e))?
+            .json()
+            .await?;
+
+        response
+            .result
+            .ok_or_else(|| anyhow::anyhow!("monerod get_info returned no result"))
+    }
+}
+
+/// Compare the wallet's height with the daemon's
+fn sync_readiness(wallet_height: u64, daemon: &DaemonInfo, max_lag: u64) -> WalletReadiness {
+    if daemon.target_height > daemon.height {
+        return WalletReadiness::NotReady(format!(
+            "monerod is syncing ({} of {} blocks)",
+            daemon.height, daemon.target_height
+        ));
+    }
+
+    let behind = daemon.height.saturating_sub(wallet_height);
+    if behind > max_lag {
+        return WalletReadiness::NotReady(format!(
+            "wallet is catching up ({} of {} blocks, {} behind)",
+            wallet_height, daemon.height, behind
+        ));
+    }
+
+    WalletReadiness::Ready
+}
+
+#[derive(Deserialize)]
+struct DaemonResponse {
+    result: Option<DaemonInfo>,
+}
+
+#[derive(Deserialize)]
+struct DaemonInfo {
+    height: u64,
+    /// Height the daemon is syncing towards; 0 once synced
+    #[serde(default)]
+    target_height: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    /// What the mock wallet-rpc and monerod report
+    struct Node {
+        address: &'static str,
+        wallet_height: u64,
+        daemon_height: u64,
+        target_height: u64,
+        refresh_delay: Duration,
+        height_error: bool,
+    }
+
+    impl Default for Node {
+        fn default() -> Self {
+            Self {
+                address: "pool",
+                wallet_height: 1_000,
+                daemon_height: 1_000,
+                target_height: 0,
+                refresh_delay: Duration::ZERO,
+                height_error: false,
+            }
+        }
+    }
+
+    /// Serve wallet-rpc and monerod JSON-RPC on one local port
+    async fn serve(node: Node) -> String {
+        let node = Arc::new(node);
+        let app = Router::new().route(
+            "/json_rpc",
+            post(move |Json(request): Json<Value>| {
+                let node = node.clone();
+                async move {
+                    let result = match request["method"].as_str().unwrap_or_default() {
+                        "get_address" => json!({"address": node.address}),
+                        "refresh" => {
+                            tokio::time::sleep(node.refresh_delay).await;
+                            json!({"blocks_fetched": 3, "received_money": false})
+                        }
+                        "get_height" if node.height_error => {
+                            return Json(json!({"jsonrpc": "2.0", "id": "0", "error": {"code": -1, "message": "busy"}}));
+                        }
+                        "get_height" => json!({"height": node.wallet_height}),
+                        "get_info" => json!({"height": node.daemon_height, "target_height": node.target_height}),
+                        method => panic!("unexpected method {}", method),
+                    };
+                    Json(json!({"jsonrpc": "2.0", "id": "0", "result": result}))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    async fn manager_for(node: Node) -> WalletManager {
+        let url = serve(node).await;
+        let config = WalletManagerConfig {
+            enabled: true,
+            file: "pool-wallet".to_string(),
+            password_file: None,
+            password: None,
+            daemon_rpc_url: url.clone(),
+            max_sync_lag: 2,
+            refresh_timeout_secs: 1,
+        };
+        WalletManager::new(Coin::Xmr, MoneroWallet::new(&url, 15), "pool", config)
+    }
+
+    #[tokio::test]
+    async fn synced_wallet_is_ready() {
+        let manager = manager_for(Node {
+            wallet_height: 999,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(
+            manager.ensure_ready().await.unwrap(),
+            WalletReadiness::Ready
+        );
+    }
+
+    #[tokio::test]
+    async fn lagging_wallet_or_syncing_daemon_is_not_ready() {
+        let manager = manager_for(Node {
+            wallet_height: 990,
+            ..Default::default()
+        })
+        .await;
+        let WalletReadiness::NotReady(reason) = manager.ensure_ready().await.unwrap() else {
+            panic!("lagging wallet reported ready");
+        };
+        assert!(reason.contains("10 behind"), "{}", reason);
+
+        let manager = manager_for(Node {
+            target_height: 2_000,
+            ..Default::default()
+        })
+        .await;
+        let WalletReadiness::NotReady(reason) = manager.ensure_ready().await.unwrap() else {
+            panic!("syncing daemon reported ready");
+        };
+        assert!(reason.contains("monerod is syncing"), "{}", reason);
+    }
+
+    #[tokio::test]
+    async fn rpc_errors_are_returned() {
+        let manager = manager_for(Node {
+            height_error: true,
+            ..Default::default()
+        })
+        .await;
+        assert!(manager.ensure_ready().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn wrong_wallet_is_reported_by_inspect() {
+        let manager = manager_for(Node {
+            address: "someone-else",
+            ..Default::default()
+        })
+        .await;
+        let WalletReadiness::NotReady(reason) = manager.inspect().await.unwrap() else {
+            panic!("wrong wallet reported ready");
+        };
+        assert!(reason.contains("someone-else"), "{}", reason);
+    }
+
+    #[tokio::test]
+    async fn slow_refresh_times_out_as_not_ready_and_releases_the_lock() {
+        let manager = manager_for(Node {
+            refresh_delay: Duration::from_secs(30),
+            ..Default::default()
+        })
+        .await;
+        let started = std::time::Instant::now();
+        let WalletReadiness::NotReady(reason) = manager.ensure_ready().await.unwrap() else {
+            panic!("refresh timeout reported ready");
+        };
+        assert!(reason.contains("refresh still running"), "{}", reason);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Inspecting doesn't refresh, and the lock is free again
+        assert_eq!(manager.inspect().await.unwrap(), WalletReadiness::Ready);
+    }
+}
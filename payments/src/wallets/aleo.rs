@@ -7,7 +7,7 @@
 //! Note: ALEO uses a different model - transactions are created and signed
 //! locally, then broadcast to the network.
 
-use super::{TxStatus, Wallet, WalletError, WalletResult};
+use super::{to_atomic, CoinbaseReward, SentTx, TxStatus, Wallet, WalletError, WalletResult};
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -78,6 +78,59 @@ impl AleoWallet {
     }
 }
 
+/// Parse a u64 that the node may return as a number or an Aleo literal ("123u64")
+fn parse_u64(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64(),
+        serde_json::Value::String(s) => s.trim_end_matches("u64").parse().ok(),
+        _ => None,
+    }
+}
+
+/// Puzzle reward earned by `address` in a block
+///
+/// The block's puzzle reward ratification is split between the block's
+/// solutions in proportion to their targets.
+fn puzzle_reward_for(block: &serde_json::Value, address: &str) -> Option<Decimal> {
+    let puzzle_reward = block
+        .get("ratifications")?
+        .as_array()?
+        .iter()
+        .find_map(|r| r.get("PuzzleReward").or_else(|| r.get("puzzle_reward")))
+        .and_then(parse_u64)?;
+
+    // "solutions" is either the list itself or an object wrapping it
+    let solutions = block.get("solutions")?;
+    let solutions = solutions
+        .get("solutions")
+        .unwrap_or(solutions)
+        .as_array()?;
+
+    let mut pool_target: u128 = 0;
+    let mut total_target: u128 = 0;
+    for solution in solutions {
+        let solution_address = solution
+            .pointer("/partial_solution/address")
+            .or_else(|| solution.get("address"))
+            .and_then(|a| a.as_str());
+        let target = solution
+            .get("target")
+            .and_then(parse_u64)
+            .unwrap_or(1) as u128;
+
+        total_target += target;
+        if solution_address == Some(address) {
+            pool_target += target;
+        }
+    }
+
+    if pool_target == 0 {
+        return None;
+    }
+
+    Some((Decimal::from(puzzle_reward) * Decimal::from(pool_target) / Decimal::from(total_target)).floor())
+}
+
 #[async_trait]
 impl Wallet for AleoWallet {
     async fn get_balance(&self) -> WalletResult<Decimal> {
@@ -111,6 +164,13 @@ impl Wallet for AleoWallet {
         self.get_balance().await
     }
 
+    async fn get_address(&self) -> WalletResult<Option<String>> {
+        // The node has no wallet: the address is derived from the private key
+        // locally, so only check that the node RPC is reachable
+        self.get_latest_height().await?;
+        Ok(None)
+    }
+
     async fn validate_address(&self, address: &str) -> WalletResult<bool> {
         // ALEO addresses start with "aleo1" and are 63 characters total
         // They use bech32m encoding
@@ -118,16 +178,13 @@ impl Wallet for AleoWallet {
         Ok(is_valid)
     }
 
-    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<String> {
+    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<SentTx> {
         if !self.validate_address(to_address).await? {
             return Err(WalletError::InvalidAddress(to_address.to_string()));
         }
 
         // Amount in microcredits
-        let amount_microcredits: u64 = amount
-            .to_string()
-            .parse()
-            .map_err(|_| WalletError::TransactionFailed("Invalid amount".to_string()))?;
+        let amount_microcredits = to_atomic(amount)?;
 
         // Build and broadcast a credits.aleo/transfer_public transaction
         // Note: In production, this would use the Aleo SDK to build and sign the transaction
@@ -158,21 +215,26 @@ impl Wallet for AleoWallet {
             .await
             .map_err(|e| WalletError::TransactionFailed(e.to_string()))?;
 
-        Ok(result.transaction_id)
+        // transfer_public is public on chain: the transaction ID alone proves
+        // the recipient and amount, there is no separate key
+        Ok(SentTx {
+            tx_hash: result.transaction_id,
+            tx_key: None,
+        })
     }
 
     async fn send_batch_payment(
         &self,
         payments: &[(String, Decimal)],
-    ) -> WalletResult<Vec<(String, String)>> {
+    ) -> WalletResult<Vec<(String, SentTx)>> {
         // ALEO doesn't support batch payments in a single transaction
         // We need to send individual transactions
         let mut results = Vec::new();
 
         for (address, amount) in payments {
             match self.send_payment(address, *amount).await {
-                Ok(tx_hash) => {
-                    results.push((address.clone(), tx_hash));
+                Ok(sent) => {
+                    results.push((address.clone(), sent));
                 }
                 Err(e) => {
                     tracing::error!("Failed to send payment to {}: {}", address, e);
@@ -187,6 +249,34 @@ impl Wallet for AleoWallet {
         Ok(results)
     }
 
+    async fn find_coinbase(&self, height: u64) -> WalletResult<Option<CoinbaseReward>> {
+        // No coinbase transaction: the puzzle reward is credited to the
+        // solution's address by the block's ratifications
+        let block: serde_json::Value = match self
+            .rpc_call("getBlock", vec![serde_json::json!(height)])
+            .await
+        {
+            Ok(block) => block,
+            Err(WalletError::RpcError(msg)) if msg.contains("not found") => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let Some(amount) = puzzle_reward_for(&block, &self.pool_address) else {
+            return Ok(None);
+        };
+
+        let block_hash = block
+            .get("block_hash")
+            .and_then(|h| h.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        Ok(Some(CoinbaseReward {
+            amount,
+            tx_hash: block_hash,
+        }))
+    }
+
     async fn get_tx_status(&self, tx_hash: &str) -> WalletResult<TxStatus> {
         // Query the transaction
         let params = vec![serde_json::json!(tx_hash)];
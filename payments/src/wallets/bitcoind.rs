@@ -0,0 +1,694 @@
+//! bitcoind wallet RPC integration (BTC, BCH, DGB)
+//!
+//! Uses the bitcoind-compatible JSON-RPC API for:
+//! - Balance queries (`getbalance`, `getbalances`)
+//! - Address validation (`validateaddress`)
+//! - Batch payments (`sendmany`)
+//! - Confirmation tracking (`gettransaction`)
+//!
+//! Amounts are kept in satoshis everywhere else; the RPC speaks whole coins,
+//! so they are converted at this boundary and sent as strings to avoid
+//! float rounding.
+
+use super::{to_atomic, CoinbaseReward, SentTx, TxStatus, Wallet, WalletError, WalletResult};
+use crate::config::CoinConfig;
+use crate::db::Coin;
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Satoshis per whole coin
+const SATS_PER_COIN: u64 = 100_000_000;
+
+/// Decimal places of a whole-coin amount
+const COIN_DECIMALS: u32 = 8;
+
+/// RPC_INVALID_ADDRESS_OR_KEY: unknown transaction or block
+const RPC_INVALID_ADDRESS_OR_KEY: i64 = -5;
+
+/// RPC_METHOD_NOT_FOUND
+const RPC_METHOD_NOT_FOUND: i64 = -32601;
+
+/// bitcoind wallet client
+#[derive(Clone)]
+pub struct BitcoindWallet {
+    rpc_url: String,
+    rpc_user: Option<String>,
+    rpc_password: Option<String>,
+    pool_address: String,
+    confirmations: u64,
+    client: reqwest::Client,
+}
+
+/// Coinbase output received by the pool address
+#[derive(Debug, Clone)]
+pub struct CoinbaseTx {
+    pub block_hash: String,
+    pub block_height: i64,
+    /// Amount received, in satoshis
+    pub amount: Decimal,
+    pub block_time: i64,
+}
+
+impl BitcoindWallet {
+    /// Create a new bitcoind wallet client
+    ///
+    /// `rpc_url` may point at a specific wallet (`http://host:port/wallet/<name>`);
+    /// node calls such as `getblockhash` are served on that path too.
+    pub fn new(
+        rpc_url: &str,
+        rpc_user: Option<&str>,
+        rpc_password: Option<&str>,
+        pool_address: &str,
+        confirmations: u64,
+    ) -> Self {
+        Self {
+            rpc_url: rpc_url.trim_end_matches('/').to_string(),
+            rpc_user: rpc_user.map(str::to_string),
+            rpc_password: rpc_password.map(str::to_string),
+            pool_address: pool_address.to_string(),
+            confirmations,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create the client for a `[btc]`, `[bch]` or `[dgb]` config section
+    pub fn from_config(coin: Coin, config: &CoinConfig) -> Self {
+        // DigiByte blocks come every 15 seconds, so it needs more of them
+        let confirmations = match coin {
+            Coin::Dgb => 40,
+            _ => 6,
+        };
+
+        Self::new(
+            &config.wallet_rpc_url,
+            config.wallet_rpc_user.as_deref(),
+            config.wallet_rpc_password.as_deref(),
+            &config.pool_wallet_address,
+            confirmations,
+        )
+    }
+
+    /// Make a JSON-RPC call, keeping the RPC error code for callers that care
+    ///
+    /// The outer result is the transport, the inner one the RPC answer.
+    async fn rpc_call_raw<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> WalletResult<Result<T, RpcError>> {
+        let request = JsonRpcRequest {
+            jsonrpc: "1.0".to_string(),
+            id: "solopool".to_string(),
+            method: method.to_string(),
+            params,
+        };
+
+        let mut builder = self.client.post(&self.rpc_url).json(&request);
+        if let Some(user) = &self.rpc_user {
+            builder = builder.basic_auth(user, self.rpc_password.as_ref());
+        }
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| WalletError::ConnectionFailed(e.to_string()))?;
+
+        // bitcoind answers RPC errors with HTTP 500 and a JSON body, but
+        // authentication failures with an empty 401
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(WalletError::ConnectionFailed(
+                "RPC authentication failed (check wallet_rpc_user and wallet_rpc_password)".to_string(),
+            ));
+        }
+
+        let rpc_response: JsonRpcResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| WalletError::RpcError(format!("HTTP {}: {}", status, e)))?;
+
+        if let Some(error) = rpc_response.error {
+            return Ok(Err(error));
+        }
+
+        rpc_response
+            .result
+            .map(Ok)
+            .ok_or_else(|| WalletError::RpcError("No result in response".to_string()))
+    }
+
+    /// Make a JSON-RPC call
+    async fn rpc_call<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> WalletResult<T> {
+        self.rpc_call_raw(method, params)
+            .await?
+            .map_err(|e| WalletError::RpcError(e.message))
+    }
+
+    /// Coinbase outputs paid to the pool address in blocks after `height`
+    /// (all of them if `height` is 0), oldest first
+    ///
+    /// Orphaned coinbases are left out.
+    pub async fn list_coinbase_since(&self, height: i64) -> WalletResult<Vec<CoinbaseTx>> {
+        let params = if height > 0 {
+            let block_hash: String = self
+                .rpc_call("getblockhash", serde_json::json!([height]))
+                .await?;
+            serde_json::json!([block_hash])
+        } else {
+            serde_json::json!([])
+        };
+
+        let result: ListSinceBlockResponse = self.rpc_call("listsinceblock", params).await?;
+
+        // Keyed by txid, in case the coinbase pays the pool address twice
+        let mut coinbases: BTreeMap<String, CoinbaseTx> = BTreeMap::new();
+        for tx in result.transactions {
+            if !matches!(tx.category.as_str(), "generate" | "immature")
+                || tx.address.as_deref() != Some(self.pool_address.as_str())
+            {
+                continue;
+            }
+            let Some(block_hash) = tx.blockhash else {
+                continue;
+            };
+
+            let amount = to_satoshis(tx.amount)?;
+            if let Some(existing) = coinbases.get_mut(&tx.txid) {
+                existing.amount += amount;
+                continue;
+            }
+
+            // Older forks don't report blockheight in listsinceblock
+            let block_height = match tx.blockheight {
+                Some(h) => h,
+                None => {
+                    let header: BlockHeaderResponse = self
+                        .rpc_call("getblockheader", serde_json::json!([block_hash]))
+                        .await?;
+                    header.height
+                }
+            };
+
+            coinbases.insert(
+                tx.txid,
+                CoinbaseTx {
+                    block_hash,
+                    block_height,
+                    amount,
+                    block_time: tx.blocktime.unwrap_or(tx.time),
+                },
+            );
+        }
+
+        let mut coinbases: Vec<CoinbaseTx> = coinbases
+            .into_values()
+            .filter(|c| c.block_height > height)
+            .collect();
+        coinbases.sort_by_key(|c| c.block_height);
+        Ok(coinbases)
+    }
+
+    /// Current chain height and network difficulty
+    pub async fn chain_info(&self) -> WalletResult<(i64, Decimal)> {
+        let info: BlockchainInfoResponse = self
+            .rpc_call("getblockchaininfo", serde_json::json!([]))
+            .await?;
+
+        let difficulty = Decimal::from_str(&info.difficulty.to_string()).unwrap_or_default();
+        Ok((info.blocks, difficulty))
+    }
+
+    /// Validate every address, then send one `sendmany` transaction
+    async fn send_many(&self, payments: &[(String, Decimal)]) -> WalletResult<String> {
+        let mut amounts = serde_json::Map::new();
+        for (address, amount) in payments {
+            if !self.validate_address(address).await? {
+                return Err(WalletError::InvalidAddress(address.clone()));
+            }
+            if amounts
+                .insert(address.clone(), serde_json::Value::String(to_coins(*amount)?))
+                .is_some()
+            {
+                return Err(WalletError::TransactionFailed(format!(
+                    "Address {} appears twice in one sendmany",
+                    address
+                )));
+            }
+        }
+
+        self.rpc_call("sendmany", serde_json::json!(["", amounts]))
+            .await
+            .map_err(|e| WalletError::TransactionFailed(e.to_string()))
+    }
+}
+
+/// Convert a whole-coin RPC amount to satoshis
+fn to_satoshis(amount: f64) -> WalletResult<Decimal> {
+    // f64's Display is the shortest string that round-trips, so an
+    // 8-decimal amount comes back exactly
+    let coins = Decimal::from_str(&amount.to_string())
+        .map_err(|e| WalletError::RpcError(format!("Invalid amount {}: {}", amount, e)))?;
+
+    Ok((coins * Decimal::from(SATS_PER_COIN)).round())
+}
+
+/// Convert satoshis to a whole-coin RPC amount string
+fn to_coins(amount: Decimal) -> WalletResult<String> {
+    let sats = i64::try_from(to_atomic(amount)?)
+        .map_err(|_| WalletError::TransactionFailed(format!("Invalid amount {}", amount)))?;
+
+    Ok(Decimal::new(sats, COIN_DECIMALS).to_string())
+}
+
+#[async_trait]
+impl Wallet for BitcoindWallet {
+    async fn get_balance(&self) -> WalletResult<Decimal> {
+        // Excludes immature coinbase outputs
+        let balance: f64 = self.rpc_call("getbalance", serde_json::json!([])).await?;
+        to_satoshis(balance)
+    }
+
+    async fn get_total_balance(&self) -> WalletResult<Decimal> {
+        match self
+            .rpc_call_raw::<GetBalancesResponse>("getbalances", serde_json::json!([]))
+            .await?
+        {
+            Ok(result) => Ok(to_satoshis(result.mine.trusted)?
+                + to_satoshis(result.mine.untrusted_pending)?
+                + to_satoshis(result.mine.immature)?),
+            // Forks without getbalances still report the totals in getwalletinfo
+            Err(e) if e.code == RPC_METHOD_NOT_FOUND => {
+                let info: WalletInfoResponse = self
+                    .rpc_call("getwalletinfo", serde_json::json!([]))
+                    .await?;
+                Ok(to_satoshis(info.balance)?
+                    + to_satoshis(info.unconfirmed_balance)?
+                    + to_satoshis(info.immature_balance)?)
+            }
+            Err(e) => Err(WalletError::RpcError(e.message)),
+        }
+    }
+
+    async fn get_address(&self) -> WalletResult<Option<String>> {
+        // An HD wallet has no single address; confirm it owns the pool address
+        let info: AddressInfoResponse = self
+            .rpc_call("getaddressinfo", serde_json::json!([self.pool_address]))
+            .await?;
+
+        if !info.ismine {
+            return Err(WalletError::RpcError(format!(
+                "pool address {} is not owned by this wallet",
+                self.pool_address
+            )));
+        }
+
+        Ok(Some(self.pool_address.clone()))
+    }
+
+    async fn validate_address(&self, address: &str) -> WalletResult<bool> {
+        let result: ValidateAddressResponse = self
+            .rpc_call("validateaddress", serde_json::json!([address]))
+            .await?;
+
+        Ok(result.isvalid)
+    }
+
+    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<SentTx> {
+        let txid = self.send_many(&[(to_address.to_string(), amount)]).await?;
+
+        // The txid is public proof of payment; there is no separate key
+        Ok(SentTx {
+            tx_hash: txid,
+            tx_key: None,
+        })
+    }
+
+    async fn send_batch_payment(
+        &self,
+        payments: &[(String, Decimal)],
+    ) -> WalletResult<Vec<(String, SentTx)>> {
+        if payments.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let txid = self.send_many(payments).await?;
+
+        Ok(payments
+            .iter()
+            .map(|(address, _)| {
+                (
+                    address.clone(),
+                    SentTx {
+                        tx_hash: txid.clone(),
+                        tx_key: None,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    async fn find_coinbase(&self, height: u64) -> WalletResult<Option<CoinbaseReward>> {
+        let block_hash: String = self
+            .rpc_call("getblockhash", serde_json::json!([height]))
+            .await?;
+        let block: BlockResponse = self
+            .rpc_call("getblock", serde_json::json!([block_hash, 1]))
+            .await?;
+
+        let Some(coinbase_txid) = block.tx.first() else {
+            return Ok(None);
+        };
+
+        // Not a wallet transaction: the block paid someone else
+        let tx = match self
+            .rpc_call_raw::<GetTransactionResponse>("gettransaction", serde_json::json!([coinbase_txid]))
+            .await?
+        {
+            Ok(tx) => tx,
+            Err(e) if e.code == RPC_INVALID_ADDRESS_OR_KEY => return Ok(None),
+            Err(e) => return Err(WalletError::RpcError(e.message)),
+        };
+
+        let mut amount = Decimal::ZERO;
+        for detail in &tx.details {
+            if matches!(detail.category.as_str(), "generate" | "immature")
+                && detail.address.as_deref() == Some(self.pool_address.as_str())
+            {
+                amount += to_satoshis(detail.amount)?;
+            }
+        }
+
+        if amount.is_zero() {
+            return Ok(None);
+        }
+
+        Ok(Some(CoinbaseReward {
+            amount,
+            tx_hash: coinbase_txid.clone(),
+        }))
+    }
+
+    async fn get_tx_status(&self, tx_hash: &str) -> WalletResult<TxStatus> {
+        let tx = match self
+            .rpc_call_raw::<GetTransactionResponse>("gettransaction", serde_json::json!([tx_hash]))
+            .await?
+        {
+            Ok(tx) => tx,
+            Err(e) if e.code == RPC_INVALID_ADDRESS_OR_KEY => return Ok(TxStatus::NotFound),
+            Err(e) => return Err(WalletError::RpcError(e.message)),
+        };
+
+        // Negative confirmations mean a conflicting transaction was mined
+        if tx.confirmations < 0 {
+            return Ok(TxStatus::Failed(format!(
+                "conflicts with a transaction {} blocks deep",
+                -tx.confirmations
+            )));
+        }
+
+        let confirmations = tx.confirmations as u64;
+        if confirmations >= self.required_confirmations() {
+            Ok(TxStatus::Confirmed)
+        } else if confirmations > 0 {
+            Ok(TxStatus::Confirming { confirmations })
+        } else {
+            Ok(TxStatus::Pending)
+        }
+    }
+
+    fn required_confirmations(&self) -> u64 {
+        self.confirmations
+    }
+}
+
+// JSON-RPC types
+
+#[derive(Serialize)]
+struct JsonRpcRequest {
+    jsonrpc: String,
+    id: String,
+    method: String,
+    params: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct GetBalancesResponse {
+    mine: MineBalances,
+}
+
+#[derive(Deserialize)]
+struct MineBalances {
+    trusted: f64,
+    #[serde(default)]
+    untrusted_pending: f64,
+    #[serde(default)]
+    immature: f64,
+}
+
+#[derive(Deserialize)]
+struct WalletInfoResponse {
+    balance: f64,
+    #[serde(default)]
+    unconfirmed_balance: f64,
+    #[serde(default)]
+    immature_balance: f64,
+}
+
+#[derive(Deserialize)]
+struct AddressInfoResponse {
+    #[serde(default)]
+    ismine: bool,
+}
+
+#[derive(Deserialize)]
+struct ValidateAddressResponse {
+    isvalid: bool,
+}
+
+#[derive(Deserialize)]
+struct BlockResponse {
+    #[serde(default)]
+    tx: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct BlockHeaderResponse {
+    height: i64,
+}
+
+#[derive(Deserialize)]
+struct BlockchainInfoResponse {
+    blocks: i64,
+    #[serde(default)]
+    difficulty: f64,
+}
+
+#[derive(Deserialize)]
+struct GetTransactionResponse {
+    confirmations: i64,
+    #[serde(default)]
+    details: Vec<TransactionDetail>,
+}
+
+#[derive(Deserialize)]
+struct TransactionDetail {
+    #[serde(default)]
+    address: Option<String>,
+    category: String,
+    amount: f64,
+}
+
+#[derive(Deserialize)]
+struct ListSinceBlockResponse {
+    #[serde(default)]
+    transactions: Vec<WalletTransaction>,
+}
+
+#[derive(Deserialize)]
+struct WalletTransaction {
+    txid: String,
+    #[serde(default)]
+    address: Option<String>,
+    category: String,
+    amount: f64,
+    #[serde(default)]
+    blockhash: Option<String>,
+    #[serde(default)]
+    blockheight: Option<i64>,
+    #[serde(default)]
+    blocktime: Option<i64>,
+    #[serde(default)]
+    time: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    const POOL: &str = "pool";
+
+    /// Answer one bitcoind RPC call, recording `sendmany` params
+    fn answer(request: &Value, sent: &Mutex<Vec<Value>>) -> (StatusCode, Value) {
+        let params = &request["params"];
+        let result = match request["method"].as_str().unwrap() {
+            "getblockhash" => json!(format!("hash-{}", params[0])),
+            "getblock" => {
+                let height = params[0].as_str().unwrap().trim_start_matches("hash-");
+                json!({"tx": [format!("cb-{}", height), "other"]})
+            }
+            "getblockheader" => json!({"height": 102}),
+            "gettransaction" if params[0] == "cb-100" => json!({
+                "confirmations": 3,
+                "details": [
+                    {"address": POOL, "category": "immature", "amount": 3.125},
+                    {"address": POOL, "category": "generate", "amount": 0.00000001},
+                    {"address": "miner", "category": "immature", "amount": 1.0}
+                ]
+            }),
+            "gettransaction" => {
+                let error = json!({"code": -5, "message": "Invalid or non-wallet transaction id"});
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    json!({"result": null, "error": error}),
+                );
+            }
+            "listsinceblock" => json!({"transactions": [
+                {"txid": "cb-102", "address": POOL, "category": "immature", "amount": 0.1,
+                 "blockhash": "hash-102", "time": 1},
+                {"txid": "cb-101", "address": POOL, "category": "generate", "amount": 6.25,
+                 "blockhash": "hash-101", "blockheight": 101, "time": 1},
+                {"txid": "cb-102", "address": POOL, "category": "immature", "amount": 0.2,
+                 "blockhash": "hash-102", "time": 1},
+                {"txid": "cb-100", "address": POOL, "category": "generate", "amount": 6.25,
+                 "blockhash": "hash-100", "blockheight": 100, "time": 1},
+                {"txid": "cb-99", "address": POOL, "category": "orphan", "amount": 6.25,
+                 "blockhash": "hash-99", "blockheight": 99, "time": 1},
+                {"txid": "cb-x", "address": "miner", "category": "generate", "amount": 6.25,
+                 "blockhash": "hash-103", "blockheight": 103, "time": 1},
+                {"txid": "in", "address": POOL, "category": "receive", "amount": 1.0,
+                 "blockhash": "hash-103", "blockheight": 103, "time": 1}
+            ]}),
+            "validateaddress" => json!({"isvalid": params[0] != "bad"}),
+            "sendmany" => {
+                sent.lock().unwrap().push(params.clone());
+                json!("txid-1")
+            }
+            method => panic!("unexpected RPC call {}", method),
+        };
+        (StatusCode::OK, json!({"result": result, "error": null}))
+    }
+
+    /// Mock bitcoind serving `answer`, with the recorded `sendmany` params
+    async fn node() -> (BitcoindWallet, Arc<Mutex<Vec<Value>>>) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let recorded = sent.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| {
+                let sent = sent.clone();
+                async move {
+                    let (status, body) = answer(&request, &sent);
+                    (status, Json(body))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (BitcoindWallet::new(&url, None, None, POOL, 6), recorded)
+    }
+
+    #[test]
+    fn rpc_amounts_convert_exactly_to_and_from_satoshis() {
+        assert_eq!(to_satoshis(0.1).unwrap(), Decimal::from(10_000_000));
+        assert_eq!(to_satoshis(0.00000001).unwrap(), Decimal::ONE);
+        assert_eq!(
+            to_satoshis(20_999_999.976_9).unwrap(),
+            Decimal::from(2_099_999_997_690_000u64)
+        );
+
+        assert_eq!(to_coins(Decimal::from(150_000_000)).unwrap(), "1.50000000");
+        assert_eq!(to_coins(Decimal::ONE).unwrap(), "0.00000001");
+        assert!(to_coins(Decimal::new(15, 1)).is_err());
+        assert!(to_coins(Decimal::from(-1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn coinbase_counts_only_outputs_to_the_pool_address() {
+        let (wallet, _) = node().await;
+
+        let reward = wallet.find_coinbase(100).await.unwrap().unwrap();
+        assert_eq!(reward.amount, Decimal::from(312_500_001));
+        assert_eq!(reward.tx_hash, "cb-100");
+
+        // Someone else's block is not a wallet transaction
+        assert!(wallet.find_coinbase(101).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn coinbases_since_a_height_are_merged_per_tx_and_sorted() {
+        let (wallet, _) = node().await;
+
+        let coinbases = wallet.list_coinbase_since(100).await.unwrap();
+        let found: Vec<(i64, Decimal)> = coinbases
+            .iter()
+            .map(|c| (c.block_height, c.amount))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (101, Decimal::from(625_000_000)),
+                (102, Decimal::from(30_000_000))
+            ]
+        );
+        assert_eq!(coinbases[1].block_hash, "hash-102");
+    }
+
+    #[tokio::test]
+    async fn batch_payments_send_whole_coin_strings_in_one_sendmany() {
+        let (wallet, sent) = node().await;
+
+        let payments = [
+            ("a".to_string(), Decimal::from(150_000_000)),
+            ("b".to_string(), Decimal::ONE),
+        ];
+        let results = wallet.send_batch_payment(&payments).await.unwrap();
+        assert!(results.iter().all(|(_, tx)| tx.tx_hash == "txid-1"));
+        assert_eq!(
+            sent.lock().unwrap().clone(),
+            vec![json!(["", {"a": "1.50000000", "b": "0.00000001"}])]
+        );
+
+        // Nothing is sent if any address or amount is invalid
+        let bad_address = [("bad".to_string(), Decimal::ONE)];
+        assert!(matches!(
+            wallet.send_batch_payment(&bad_address).await,
+            Err(WalletError::InvalidAddress(_))
+        ));
+        let fractional = [("a".to_string(), Decimal::new(15, 1))];
+        assert!(wallet.send_batch_payment(&fractional).await.is_err());
+        assert_eq!(sent.lock().unwrap().len(), 1);
+    }
+}
@@ -6,6 +6,7 @@
 //! - Transaction confirmation checking
 
 pub mod aleo;
+pub mod bitcoind;
 pub mod monero;
 pub mod tari;
 
@@ -34,8 +35,8 @@ pub enum WalletError {
     #[error("Transaction not found: {0}")]
     TransactionNotFound(String),
 
-    #[error("Wallet locked")]
-    #[allow(dead_code)]
+    /// The wallet RPC has no wallet open (or it is locked)
+    #[error("Wallet locked or not open")]
     WalletLocked,
 
     #[error("Configuration error: {0}")]
@@ -46,6 +47,25 @@ pub enum WalletError {
 /// Result type for wallet operations
 pub type WalletResult<T> = Result<T, WalletError>;
 
+/// Convert an amount to whole atomic units for a wallet RPC call
+///
+/// Balances are credited in whole units, so a fractional or negative amount
+/// here is a bug upstream and is refused rather than rounded.
+pub fn to_atomic(amount: Decimal) -> WalletResult<u64> {
+    use rust_decimal::prelude::ToPrimitive;
+
+    if !amount.fract().is_zero() {
+        return Err(WalletError::TransactionFailed(format!(
+            "Amount {} is not a whole number of atomic units",
+            amount
+        )));
+    }
+
+    amount
+        .to_u64()
+        .ok_or_else(|| WalletError::TransactionFailed(format!("Invalid amount {}", amount)))
+}
+
 /// Transaction status
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TxStatus {
@@ -61,6 +81,35 @@ pub enum TxStatus {
     NotFound,
 }
 
+/// Block reward received by the pool wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinbaseReward {
+    /// Amount received, in atomic units
+    pub amount: Decimal,
+    /// Coinbase transaction (or block) hash
+    pub tx_hash: String,
+}
+
+/// A payment transaction that was broadcast
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentTx {
+    pub tx_hash: String,
+    /// Secret a recipient can use to verify the payment (Monero tx key),
+    /// if the wallet returned one at send time
+    pub tx_key: Option<String>,
+}
+
+/// A signed transaction built but not yet broadcast
+#[derive(Debug, Clone)]
+pub struct PreparedTx {
+    pub tx_hash: String,
+    pub tx_key: Option<String>,
+    /// Wallet-specific blob needed to relay the transaction later
+    pub tx_metadata: String,
+    /// Network fee, in atomic units
+    pub fee: Decimal,
+}
+
 /// Common wallet operations trait
 #[async_trait]
 pub trait Wallet: Send + Sync {
@@ -70,19 +119,60 @@ pub trait Wallet: Send + Sync {
     /// Get the pool wallet's total balance (including locked)
     async fn get_total_balance(&self) -> WalletResult<Decimal>;
 
+    /// Get the pool wallet's own address
+    /// Returns None if the backend has no wallet to ask (e.g. a node RPC)
+    async fn get_address(&self) -> WalletResult<Option<String>>;
+
     /// Validate a wallet address
     async fn validate_address(&self, address: &str) -> WalletResult<bool>;
 
     /// Send a payment to a single address
-    /// Returns the transaction hash
-    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<String>;
+    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<SentTx>;
 
     /// Send payments to multiple addresses (batch payment)
-    /// Returns a list of (address, tx_hash) pairs
+    /// Returns a list of (address, transaction) pairs
     async fn send_batch_payment(
         &self,
         payments: &[(String, Decimal)],
-    ) -> WalletResult<Vec<(String, String)>>;
+    ) -> WalletResult<Vec<(String, SentTx)>>;
+
+    /// Look up the block reward the pool wallet received at a height
+    /// Returns None if the wallet has no coinbase for that block (yet)
+    async fn find_coinbase(&self, height: u64) -> WalletResult<Option<CoinbaseReward>>;
+
+    /// Build and sign a batch payment without broadcasting it
+    /// Returns None if the wallet can't hold a transaction for later relay
+    async fn prepare_batch_payment(
+        &self,
+        _payments: &[(String, Decimal)],
+    ) -> WalletResult<Option<PreparedTx>> {
+        Ok(None)
+    }
+
+    /// Broadcast a transaction built by `prepare_batch_payment`
+    /// Returns the transaction hash
+    async fn relay_prepared(&self, _tx_metadata: &str) -> WalletResult<String> {
+        Err(WalletError::TransactionFailed(
+            "wallet does not support relaying prepared transactions".to_string(),
+        ))
+    }
+
+    /// Look up the payment proof key of a sent transaction
+    /// Returns None if the wallet has none (or none yet, e.g. an unfinished Tari kernel)
+    async fn get_tx_key(&self, _tx_hash: &str) -> WalletResult<Option<String>> {
+        Ok(None)
+    }
+
+    /// Generate a signed proof that a transaction paid an address
+    /// Returns None if the wallet can't produce one
+    async fn get_tx_proof(
+        &self,
+        _tx_hash: &str,
+        _address: &str,
+        _message: Option<&str>,
+    ) -> WalletResult<Option<String>> {
+        Ok(None)
+    }
 
     /// Check the status of a transaction
     async fn get_tx_status(&self, tx_hash: &str) -> WalletResult<TxStatus>;
@@ -90,3 +180,126 @@ pub trait Wallet: Send + Sync {
     /// Get required confirmations for a transaction to be considered final
     fn required_confirmations(&self) -> u64;
 }
+
+/// Wallet that records what it sends and relays
+#[cfg(test)]
+#[derive(Default)]
+pub struct TestWallet {
+    pub unlocked: Decimal,
+    /// Total balance, if more than the unlocked one
+    pub total: Option<Decimal>,
+    /// Coinbase received for every block
+    pub coinbase: Option<Decimal>,
+    /// Height whose coinbase lookup fails
+    pub coinbase_error_at: Option<u64>,
+    pub fail_relay: bool,
+    /// Whether sent transactions have confirmed
+    pub confirmed: std::sync::atomic::AtomicBool,
+    pub sent: std::sync::Mutex<Vec<(String, Decimal)>>,
+    pub relayed: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl TestWallet {
+    pub fn with_balance(unlocked: i64) -> Self {
+        Self {
+            unlocked: Decimal::from(unlocked),
+            ..Default::default()
+        }
+    }
+
+    pub fn sent(&self) -> Vec<(String, Decimal)> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn relayed(&self) -> Vec<String> {
+        self.relayed.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Wallet for TestWallet {
+    async fn get_balance(&self) -> WalletResult<Decimal> {
+        Ok(self.unlocked)
+    }
+
+    async fn get_total_balance(&self) -> WalletResult<Decimal> {
+        Ok(self.total.unwrap_or(self.unlocked))
+    }
+
+    async fn get_address(&self) -> WalletResult<Option<String>> {
+        Ok(None)
+    }
+
+    async fn validate_address(&self, _address: &str) -> WalletResult<bool> {
+        Ok(true)
+    }
+
+    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<SentTx> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((to_address.to_string(), amount));
+        Ok(SentTx {
+            tx_hash: format!("tx-{}", to_address),
+            tx_key: None,
+        })
+    }
+
+    async fn send_batch_payment(
+        &self,
+        payments: &[(String, Decimal)],
+    ) -> WalletResult<Vec<(String, SentTx)>> {
+        let mut results = Vec::new();
+        for (address, amount) in payments {
+            results.push((address.clone(), self.send_payment(address, *amount).await?));
+        }
+        Ok(results)
+    }
+
+    async fn find_coinbase(&self, height: u64) -> WalletResult<Option<CoinbaseReward>> {
+        if self.coinbase_error_at == Some(height) {
+            return Err(WalletError::ConnectionFailed("timed out".to_string()));
+        }
+        Ok(self.coinbase.map(|amount| CoinbaseReward {
+            amount,
+            tx_hash: format!("coinbase-{}", height),
+        }))
+    }
+
+    async fn prepare_batch_payment(
+        &self,
+        payments: &[(String, Decimal)],
+    ) -> WalletResult<Option<PreparedTx>> {
+        Ok(Some(PreparedTx {
+            tx_hash: "prepared".to_string(),
+            tx_key: None,
+            tx_metadata: format!("blob-{}", payments[0].0),
+            fee: Decimal::ONE,
+        }))
+    }
+
+    async fn relay_prepared(&self, tx_metadata: &str) -> WalletResult<String> {
+        if self.fail_relay {
+            return Err(WalletError::TransactionFailed("double spend".to_string()));
+        }
+        self.relayed.lock().unwrap().push(tx_metadata.to_string());
+        Ok("prepared".to_string())
+    }
+
+    async fn get_tx_key(&self, tx_hash: &str) -> WalletResult<Option<String>> {
+        Ok(Some(format!("kernel-{}", tx_hash)))
+    }
+
+    async fn get_tx_status(&self, _tx_hash: &str) -> WalletResult<TxStatus> {
+        if self.confirmed.load(std::sync::atomic::Ordering::SeqCst) {
+            return Ok(TxStatus::Confirmed);
+        }
+        Ok(TxStatus::Pending)
+    }
+
+    fn required_confirmations(&self) -> u64 {
+        10
+    }
+}
@@ -5,11 +5,15 @@
 //! - Address validation
 //! - Transaction creation
 
-use super::{TxStatus, Wallet, WalletError, WalletResult};
+use super::{
+    to_atomic, CoinbaseReward, PreparedTx, SentTx, TxStatus, Wallet, WalletError, WalletResult,
+};
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+
+/// monero-wallet-rpc error code when no wallet file is open
+const RPC_ERROR_NOT_OPEN: i64 = -13;
 
 /// Monero wallet client
 pub struct MoneroWallet {
@@ -20,7 +24,12 @@ pub struct MoneroWallet {
 
 impl MoneroWallet {
     /// Create a new Monero wallet client
+    ///
+    /// Accepts the RPC base URL with or without the trailing `/json_rpc`.
     pub fn new(rpc_url: &str, mixin: u32) -> Self {
+        let rpc_url = rpc_url.trim_end_matches('/');
+        let rpc_url = rpc_url.strip_suffix("/json_rpc").unwrap_or(rpc_url);
+
         Self {
             rpc_url: rpc_url.to_string(),
             client: reqwest::Client::new(),
@@ -43,7 +52,7 @@ impl MoneroWallet {
 
         let response = self
             .client
-            .post(&format!("{}/json_rpc", self.rpc_url))
+            .post(format!("{}/json_rpc", self.rpc_url))
             .json(&request)
             .send()
             .await
@@ -55,6 +64,9 @@ impl MoneroWallet {
             .map_err(|e| WalletError::RpcError(e.to_string()))?;
 
         if let Some(error) = rpc_response.error {
+            if error.code == RPC_ERROR_NOT_OPEN {
+                return Err(WalletError::WalletLocked);
+            }
             return Err(WalletError::RpcError(error.message));
         }
 
@@ -64,6 +76,98 @@ impl MoneroWallet {
     }
 }
 
+impl MoneroWallet {
+    /// Open a wallet file from wallet-rpc's --wallet-dir, closing the current one
+    pub async fn open_wallet(&self, filename: &str, password: &str) -> WalletResult<()> {
+        let _: serde_json::Value = self
+            .rpc_call(
+                "open_wallet",
+                serde_json::json!({"filename": filename, "password": password}),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Scan the daemon for new blocks; returns the number of blocks fetched
+    pub async fn refresh(&self) -> WalletResult<u64> {
+        let result: RefreshResponse = self.rpc_call("refresh", serde_json::json!({})).await?;
+        Ok(result.blocks_fetched)
+    }
+
+    /// Height the wallet has synced to
+    pub async fn get_height(&self) -> WalletResult<u64> {
+        let result: GetHeightResponse = self.rpc_call("get_height", serde_json::json!({})).await?;
+        Ok(result.height)
+    }
+}
+
+/// Transactions created by a sweep
+#[derive(Debug, Clone, Default)]
+pub struct SweepResult {
+    pub tx_hashes: Vec<String>,
+    /// Total swept, in atomic units
+    pub amount: Decimal,
+    /// Total fees, in atomic units
+    pub fee: Decimal,
+}
+
+impl MoneroWallet {
+    /// Count unlocked, unspent outputs below `below_amount` (all if None)
+    pub async fn count_unlocked_outputs(&self, below_amount: Option<u64>) -> WalletResult<u32> {
+        let result: IncomingTransfersResponse = self
+            .rpc_call(
+                "incoming_transfers",
+                serde_json::json!({"transfer_type": "available", "account_index": 0}),
+            )
+            .await?;
+
+        let count = result
+            .transfers
+            .iter()
+            .filter(|t| !t.spent && t.unlocked)
+            .filter(|t| below_amount.is_none_or(|below| t.amount < below))
+            .count();
+
+        Ok(count as u32)
+    }
+
+    /// Sweep unlocked outputs below `below_amount` (all if None) back to the pool wallet
+    pub async fn sweep_to_self(&self, below_amount: Option<u64>) -> WalletResult<SweepResult> {
+        let address = self
+            .get_address()
+            .await?
+            .ok_or_else(|| WalletError::RpcError("Wallet has no address".to_string()))?;
+
+        let mut params = serde_json::json!({
+            "address": address,
+            "account_index": 0,
+            "subaddr_indices_all": true,
+            "priority": 1,
+            "ring_size": self.mixin + 1
+        });
+        if let Some(below) = below_amount {
+            params["below_amount"] = serde_json::json!(below);
+        }
+
+        let result: SweepResponse = self
+            .rpc_call("sweep_all", params)
+            .await
+            .map_err(|e| WalletError::TransactionFailed(e.to_string()))?;
+
+        Ok(result.into())
+    }
+
+    /// Sweep unmixable dust outputs back to the pool wallet
+    pub async fn sweep_dust(&self) -> WalletResult<SweepResult> {
+        let result: SweepResponse = self
+            .rpc_call("sweep_dust", serde_json::json!({}))
+            .await
+            .map_err(|e| WalletError::TransactionFailed(e.to_string()))?;
+
+        Ok(result.into())
+    }
+}
+
 #[async_trait]
 impl Wallet for MoneroWallet {
     async fn get_balance(&self) -> WalletResult<Decimal> {
@@ -86,6 +190,14 @@ impl Wallet for MoneroWallet {
         Ok(balance_atomic)
     }
 
+    async fn get_address(&self) -> WalletResult<Option<String>> {
+        let result: GetAddressResponse = self
+            .rpc_call("get_address", serde_json::json!({"account_index": 0}))
+            .await?;
+
+        Ok(Some(result.address))
+    }
+
     async fn validate_address(&self, address: &str) -> WalletResult<bool> {
         let result: ValidateAddressResponse = self
             .rpc_call(
@@ -97,17 +209,14 @@ impl Wallet for MoneroWallet {
         Ok(result.valid)
     }
 
-    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<String> {
+    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<SentTx> {
         // Validate address first
         if !self.validate_address(to_address).await? {
             return Err(WalletError::InvalidAddress(to_address.to_string()));
         }
 
         // Amount should be in atomic units (piconero)
-        let amount_atomic: u64 = amount
-            .to_string()
-            .parse()
-            .map_err(|_| WalletError::TransactionFailed("Invalid amount".to_string()))?;
+        let amount_atomic = to_atomic(amount)?;
 
         let params = serde_json::json!({
             "destinations": [{
@@ -121,13 +230,16 @@ impl Wallet for MoneroWallet {
 
         let result: TransferResponse = self.rpc_call("transfer", params).await?;
 
-        Ok(result.tx_hash)
+        Ok(SentTx {
+            tx_hash: result.tx_hash,
+            tx_key: result.tx_key.filter(|k| !k.is_empty()),
+        })
     }
 
     async fn send_batch_payment(
         &self,
         payments: &[(String, Decimal)],
-    ) -> WalletResult<Vec<(String, String)>> {
+    ) -> WalletResult<Vec<(String, SentTx)>> {
         if payments.is_empty() {
             return Ok(Vec::new());
         }
@@ -140,19 +252,15 @@ impl Wallet for MoneroWallet {
         }
 
         // Build destinations
-        let destinations: Vec<serde_json::Value> = payments
+        let destinations = payments
             .iter()
             .map(|(address, amount)| {
-                let amount_atomic: u64 = amount
-                    .to_string()
-                    .parse()
-                    .unwrap_or(0);
-                serde_json::json!({
+                Ok(serde_json::json!({
                     "address": address,
-                    "amount": amount_atomic
-                })
+                    "amount": to_atomic(*amount)?
+                }))
             })
-            .collect();
+            .collect::<WalletResult<Vec<serde_json::Value>>>()?;
 
         let params = serde_json::json!({
             "destinations": destinations,
@@ -163,15 +271,131 @@ impl Wallet for MoneroWallet {
 
         let result: TransferResponse = self.rpc_call("transfer", params).await?;
 
-        // All payments in a batch have the same tx_hash
-        let results: Vec<(String, String)> = payments
+        // All payments in a batch share the transaction and its key
+        let sent = SentTx {
+            tx_hash: result.tx_hash,
+            tx_key: result.tx_key.filter(|k| !k.is_empty()),
+        };
+        let results: Vec<(String, SentTx)> = payments
             .iter()
-            .map(|(addr, _)| (addr.clone(), result.tx_hash.clone()))
+            .map(|(addr, _)| (addr.clone(), sent.clone()))
             .collect();
 
         Ok(results)
     }
 
+    async fn find_coinbase(&self, height: u64) -> WalletResult<Option<CoinbaseReward>> {
+        // min_height is exclusive in wallet RPC
+        let params = serde_json::json!({
+            "in": true,
+            "pool": true,
+            "account_index": 0,
+            "filter_by_height": true,
+            "min_height": height.saturating_sub(1),
+            "max_height": height
+        });
+
+        let result: GetTransfersResponse = self.rpc_call("get_transfers", params).await?;
+
+        // Coinbase outputs show up as "block" transfers
+        let coinbase: Vec<&IncomingTransfer> = result
+            .incoming
+            .iter()
+            .chain(result.pool.iter())
+            .filter(|t| t.transfer_type == "block" && t.height == height)
+            .collect();
+
+        let Some(first) = coinbase.first() else {
+            return Ok(None);
+        };
+
+        Ok(Some(CoinbaseReward {
+            amount: coinbase.iter().map(|t| Decimal::from(t.amount)).sum(),
+            tx_hash: first.txid.clone(),
+        }))
+    }
+
+    async fn prepare_batch_payment(
+        &self,
+        payments: &[(String, Decimal)],
+    ) -> WalletResult<Option<PreparedTx>> {
+        for (address, _) in payments {
+            if !self.validate_address(address).await? {
+                return Err(WalletError::InvalidAddress(address.clone()));
+            }
+        }
+
+        let destinations = payments
+            .iter()
+            .map(|(address, amount)| {
+                Ok(serde_json::json!({
+                    "address": address,
+                    "amount": to_atomic(*amount)?
+                }))
+            })
+            .collect::<WalletResult<Vec<serde_json::Value>>>()?;
+
+        let params = serde_json::json!({
+            "destinations": destinations,
+            "priority": 1,
+            "ring_size": self.mixin + 1,
+            "get_tx_key": true,
+            "do_not_relay": true,
+            "get_tx_metadata": true
+        });
+
+        let result: TransferResponse = self
+            .rpc_call("transfer", params)
+            .await
+            .map_err(|e| WalletError::TransactionFailed(e.to_string()))?;
+
+        let tx_metadata = result
+            .tx_metadata
+            .filter(|m| !m.is_empty())
+            .ok_or_else(|| WalletError::TransactionFailed("Wallet returned no tx_metadata".to_string()))?;
+
+        Ok(Some(PreparedTx {
+            tx_hash: result.tx_hash,
+            tx_key: result.tx_key.filter(|k| !k.is_empty()),
+            tx_metadata,
+            fee: Decimal::from(result.fee.unwrap_or(0)),
+        }))
+    }
+
+    async fn relay_prepared(&self, tx_metadata: &str) -> WalletResult<String> {
+        let result: RelayTxResponse = self
+            .rpc_call("relay_tx", serde_json::json!({"hex": tx_metadata}))
+            .await
+            .map_err(|e| WalletError::TransactionFailed(e.to_string()))?;
+
+        Ok(result.tx_hash)
+    }
+
+    async fn get_tx_key(&self, tx_hash: &str) -> WalletResult<Option<String>> {
+        let result: GetTxKeyResponse = self
+            .rpc_call("get_tx_key", serde_json::json!({"txid": tx_hash}))
+            .await?;
+
+        Ok(Some(result.tx_key).filter(|k| !k.is_empty()))
+    }
+
+    async fn get_tx_proof(
+        &self,
+        tx_hash: &str,
+        address: &str,
+        message: Option<&str>,
+    ) -> WalletResult<Option<String>> {
+        let params = serde_json::json!({
+            "txid": tx_hash,
+            "address": address,
+            "message": message.unwrap_or_default()
+        });
+
+        let result: GetTxProofResponse = self.rpc_call("get_tx_proof", params).await?;
+
+        Ok(Some(result.signature))
+    }
+
     async fn get_tx_status(&self, tx_hash: &str) -> WalletResult<TxStatus> {
         let params = serde_json::json!({
             "txid": tx_hash
@@ -222,6 +446,8 @@ struct JsonRpcResponse<T> {
 
 #[derive(Deserialize)]
 struct RpcError {
+    #[serde(default)]
+    code: i64,
     message: String,
 }
 
@@ -231,6 +457,22 @@ struct GetBalanceResponse {
     unlocked_balance: u64,
 }
 
+#[derive(Deserialize)]
+struct GetAddressResponse {
+    address: String,
+}
+
+#[derive(Deserialize)]
+struct RefreshResponse {
+    #[serde(default)]
+    blocks_fetched: u64,
+}
+
+#[derive(Deserialize)]
+struct GetHeightResponse {
+    height: u64,
+}
+
 #[derive(Deserialize)]
 struct ValidateAddressResponse {
     valid: bool,
@@ -243,6 +485,23 @@ struct TransferResponse {
     tx_key: Option<String>,
     #[serde(default)]
     fee: Option<u64>,
+    #[serde(default)]
+    tx_metadata: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RelayTxResponse {
+    tx_hash: String,
+}
+
+#[derive(Deserialize)]
+struct GetTxKeyResponse {
+    tx_key: String,
+}
+
+#[derive(Deserialize)]
+struct GetTxProofResponse {
+    signature: String,
 }
 
 #[derive(Deserialize)]
@@ -255,5 +514,59 @@ struct TransferInfo {
     #[serde(default)]
     confirmations: Option<u64>,
     #[serde(default)]
+    #[allow(dead_code)]
     height: Option<u64>,
 }
+
+#[derive(Deserialize)]
+struct GetTransfersResponse {
+    #[serde(default, rename = "in")]
+    incoming: Vec<IncomingTransfer>,
+    #[serde(default)]
+    pool: Vec<IncomingTransfer>,
+}
+
+#[derive(Deserialize)]
+struct IncomingTransfer {
+    txid: String,
+    amount: u64,
+    #[serde(default)]
+    height: u64,
+    #[serde(rename = "type", default)]
+    transfer_type: String,
+}
+
+#[derive(Deserialize)]
+struct IncomingTransfersResponse {
+    #[serde(default)]
+    transfers: Vec<OwnedOutput>,
+}
+
+#[derive(Deserialize)]
+struct OwnedOutput {
+    amount: u64,
+    #[serde(default)]
+    spent: bool,
+    #[serde(default)]
+    unlocked: bool,
+}
+
+#[derive(Deserialize)]
+struct SweepResponse {
+    #[serde(default)]
+    tx_hash_list: Vec<String>,
+    #[serde(default)]
+    amount_list: Vec<u64>,
+    #[serde(default)]
+    fee_list: Vec<u64>,
+}
+
+impl From<SweepResponse> for SweepResult {
+    fn from(response: SweepResponse) -> Self {
+        Self {
+            tx_hashes: response.tx_hash_list,
+            amount: response.amount_list.into_iter().map(Decimal::from).sum(),
+            fee: response.fee_list.into_iter().map(Decimal::from).sum(),
+        }
+    }
+}
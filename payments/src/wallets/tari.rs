@@ -5,7 +5,7 @@
 //! - Address validation
 //! - Transaction creation
 
-use super::{TxStatus, Wallet, WalletError, WalletResult};
+use super::{to_atomic, CoinbaseReward, SentTx, TxStatus, Wallet, WalletError, WalletResult};
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -83,6 +83,14 @@ impl Wallet for TariWallet {
         Ok(Decimal::from(total))
     }
 
+    async fn get_address(&self) -> WalletResult<Option<String>> {
+        let result: GetAddressResponse = self
+            .rpc_call("get_address", serde_json::json!({}))
+            .await?;
+
+        Ok(Some(result.address))
+    }
+
     async fn validate_address(&self, address: &str) -> WalletResult<bool> {
         // Tari addresses are emoji-based or hex-encoded public keys
         // Basic validation: check format
@@ -95,12 +103,9 @@ impl Wallet for TariWallet {
         Ok(is_valid)
     }
 
-    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<String> {
+    async fn send_payment(&self, to_address: &str, amount: Decimal) -> WalletResult<SentTx> {
         // Amount in microTari
-        let amount_ut: u64 = amount
-            .to_string()
-            .parse()
-            .map_err(|_| WalletError::TransactionFailed("Invalid amount".to_string()))?;
+        let amount_ut = to_atomic(amount)?;
 
         let params = serde_json::json!({
             "destinations": [{
@@ -113,29 +118,33 @@ impl Wallet for TariWallet {
 
         let result: TransferResponse = self.rpc_call("transfer", params).await?;
 
-        Ok(result.transaction_id.to_string())
+        // The kernel signature that proves the payment only exists once the
+        // recipient has replied; it is looked up later via get_tx_key
+        Ok(SentTx {
+            tx_hash: result.transaction_id.to_string(),
+            tx_key: None,
+        })
     }
 
     async fn send_batch_payment(
         &self,
         payments: &[(String, Decimal)],
-    ) -> WalletResult<Vec<(String, String)>> {
+    ) -> WalletResult<Vec<(String, SentTx)>> {
         if payments.is_empty() {
             return Ok(Vec::new());
         }
 
         // Tari wallet RPC supports batch transfers
-        let destinations: Vec<serde_json::Value> = payments
+        let destinations = payments
             .iter()
             .map(|(address, amount)| {
-                let amount_ut: u64 = amount.to_string().parse().unwrap_or(0);
-                serde_json::json!({
+                Ok(serde_json::json!({
                     "address": address,
-                    "amount": amount_ut,
+                    "amount": to_atomic(*amount)?,
                     "fee_per_gram": 5
-                })
+                }))
             })
-            .collect();
+            .collect::<WalletResult<Vec<serde_json::Value>>>()?;
 
         let params = serde_json::json!({
             "destinations": destinations,
@@ -145,14 +154,55 @@ impl Wallet for TariWallet {
         let result: TransferResponse = self.rpc_call("transfer", params).await?;
 
         // All payments in a batch share the same transaction ID
-        let results: Vec<(String, String)> = payments
+        let results: Vec<(String, SentTx)> = payments
             .iter()
-            .map(|(addr, _)| (addr.clone(), result.transaction_id.to_string()))
+            .map(|(addr, _)| {
+                let sent = SentTx {
+                    tx_hash: result.transaction_id.to_string(),
+                    tx_key: None,
+                };
+                (addr.clone(), sent)
+            })
             .collect();
 
         Ok(results)
     }
 
+    async fn find_coinbase(&self, height: u64) -> WalletResult<Option<CoinbaseReward>> {
+        let result: GetCompletedTransactionsResponse = self
+            .rpc_call("get_completed_transactions", serde_json::json!({}))
+            .await?;
+
+        let coinbase: Vec<&CompletedTransaction> = result
+            .transactions
+            .iter()
+            .filter(|tx| tx.is_coinbase && tx.mined_in_block_height == Some(height))
+            .filter(|tx| !matches!(tx.status.as_str(), "Rejected" | "Cancelled"))
+            .collect();
+
+        let Some(first) = coinbase.first() else {
+            return Ok(None);
+        };
+
+        Ok(Some(CoinbaseReward {
+            amount: coinbase.iter().map(|tx| Decimal::from(tx.amount)).sum(),
+            tx_hash: first.tx_id.to_string(),
+        }))
+    }
+
+    async fn get_tx_key(&self, tx_hash: &str) -> WalletResult<Option<String>> {
+        let tx_id: u64 = tx_hash
+            .parse()
+            .map_err(|_| WalletError::TransactionNotFound(tx_hash.to_string()))?;
+
+        let result: GetTransactionResponse = self
+            .rpc_call("get_transaction_info", serde_json::json!({"transaction_id": tx_id}))
+            .await?;
+
+        // The kernel signature is what block explorers index Tari payments by
+        Ok(result.excess_sig.filter(|s| !s.is_empty()))
+    }
+
     async fn get_tx_status(&self, tx_hash: &str) -> WalletResult<TxStatus> {
         let tx_id: u64 = tx_hash
             .parse()
@@ -226,6 +276,11 @@ struct GetBalanceResponse {
     pending_outgoing_balance: u64,
 }
 
+#[derive(Deserialize)]
+struct GetAddressResponse {
+    address: String,
+}
+
 #[derive(Deserialize)]
 struct TransferResponse {
     transaction_id: u64,
@@ -241,4 +296,25 @@ struct GetTransactionResponse {
     confirmations: Option<u64>,
     #[serde(default)]
     message: Option<String>,
+    /// Kernel excess signature, set once the transaction is completed
+    #[serde(default)]
+    excess_sig: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GetCompletedTransactionsResponse {
+    #[serde(default)]
+    transactions: Vec<CompletedTransaction>,
+}
+
+#[derive(Deserialize)]
+struct CompletedTransaction {
+    tx_id: u64,
+    amount: u64,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    is_coinbase: bool,
+    #[serde(default)]
+    mined_in_block_height: Option<u64>,
 }
@@ -0,0 +1,513 @@
+//! Outbound webhooks
+//!
+//! Events from the bus are matched against each `[[webhooks]]` target,
+//! rendered into a request body and queued in `webhook_deliveries`. A
+//! delivery loop POSTs due deliveries and retries failures with exponential
+//! backoff until `max_attempts`, so deliveries survive restarts and
+//! unreachable targets.
+//!
+//! Bodies are the event JSON, or the target's template with `{{field}}`
+//! placeholders filled from the event (`{{summary}}` is a one-line
+//! description, `{{event}}` the whole event). With a `secret`, the body's
+//! HMAC-SHA256 is sent as `sha256=<hex>` in the signature header.
+
+use crate::config::WebhookConfig;
+use crate::db::{Coin, Database, WebhookDelivery};
+use crate::events::{Event, EventBus, EventKind};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// How long a target may take to answer
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before the first retry; doubles with every failed attempt
+const RETRY_BASE_SECS: i64 = 30;
+
+/// Longest delay between retries
+const RETRY_MAX_SECS: i64 = 6 * 3600;
+
+/// How often the queue is checked for due retries
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Most deliveries sent per pass
+const DELIVERY_BATCH: i64 = 50;
+
+/// How long finished deliveries are kept
+const DELIVERY_RETENTION_DAYS: i64 = 7;
+
+/// How often finished deliveries are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Queues and delivers events to the configured webhook targets
+#[derive(Clone)]
+pub struct Webhooks {
+    db: Database,
+    targets: Arc<HashMap<String, WebhookConfig>>,
+    client: reqwest::Client,
+    /// Wakes the delivery loop when deliveries are queued
+    wake: Arc<Notify>,
+}
+
+impl Webhooks {
+    /// Targets that are not enabled are ignored
+    pub fn new(db: Database, configs: &[WebhookConfig]) -> Self {
+        let targets = configs
+            .iter()
+            .filter(|w| w.enabled)
+            .map(|w| (w.name.clone(), w.clone()))
+            .collect();
+
+        Self {
+            db,
+            targets: Arc::new(targets),
+            client: reqwest::Client::new(),
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Spawn the queueing and delivery loops until shutdown
+    ///
+    /// Call before anything publishes: on the first start, events published
+    /// before this are not sent.
+    pub async fn start(
+        &self,
+        events: &EventBus,
+        shutdown: &broadcast::Sender<()>,
+    ) -> anyhow::Result<Vec<JoinHandle<()>>> {
+        for target in self.targets.values() {
+            let events = match target.events.is_empty() {
+                true => "all".to_string(),
+                false => target.events.join(","),
+            };
+            info!(webhook = %target.name, events = %events, signed = target.secret.is_some(), "Webhook enabled");
+        }
+
+        // The first start only sends new events, not the stored history
+        let cursor = match self.db.get_webhook_cursor().await? {
+            Some(cursor) => cursor,
+            None => self.db.latest_event_id().await?,
+        };
+
+        let (webhooks, events, queue_shutdown) = (self.clone(), events.clone(), shutdown.subscribe());
+        let queue = tokio::spawn(async move { webhooks.queue_events(events, cursor, queue_shutdown).await });
+
+        let (webhooks, delivery_shutdown) = (self.clone(), shutdown.subscribe());
+        let delivery = tokio::spawn(async move { webhooks.deliver_loop(delivery_shutdown).await });
+
+        Ok(vec![queue, delivery])
+    }
+
+    /// Queue deliveries for every event published after `cursor`
+    async fn queue_events(&self, events: EventBus, cursor: i64, mut shutdown: broadcast::Receiver<()>) {
+        let mut stream = Box::pin(events.subscribe(None, Some(cursor)));
+        loop {
+            let event = tokio::select! {
+                event = stream.next() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                _ = shutdown.recv() => break,
+            };
+
+            // Keep the event until it is queued; the cursor must not skip it
+            while let Err(e) = self.enqueue(&event).await {
+                warn!(event_id = event.id, error = %e, "Failed to queue webhook deliveries, retrying");
+                tokio::select! {
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                    _ = shutdown.recv() => return,
+                }
+            }
+        }
+    }
+
+    /// Render the event for every target that wants it and queue the deliveries
+    async fn enqueue(&self, event: &Event) -> anyhow::Result<()> {
+        let event_type = event.kind.name();
+        let mut deliveries = Vec::new();
+
+        for target in self.targets.values() {
+            if !target.events.is_empty() && !target.events.iter().any(|e| e == event_type) {
+                continue;
+            }
+            match render(target.template.as_deref(), event) {
+                Ok(body) => deliveries.push((target.name.clone(), body)),
+                Err(e) => warn!(webhook = %target.name, event_id = event.id, error = %e, "Failed to render webhook body"),
+            }
+        }
+
+        self.db
+            .enqueue_webhook_deliveries(event.id, event_type, &deliveries)
+            .await?;
+        if !deliveries.is_empty() {
+            debug!(event_id = event.id, event_type, count = deliveries.len(), "Webhook deliveries queued");
+            self.wake.notify_one();
+        }
+        Ok(())
+    }
+
+    /// Send due deliveries until shutdown
+    async fn deliver_loop(&self, mut shutdown: broadcast::Receiver<()>) {
+        let mut last_prune: Option<Instant> = None;
+
+        loop {
+            if last_prune.is_none_or(|t| t.elapsed() >= PRUNE_INTERVAL) {
+                let cutoff = Utc::now() - chrono::Duration::days(DELIVERY_RETENTION_DAYS);
+                if let Err(e) = self.db.prune_webhook_deliveries(cutoff).await {
+                    warn!(error = %e, "Failed to prune webhook deliveries");
+                }
+                last_prune = Some(Instant::now());
+            }
+
+            let full_batch = match self.deliver_due(Utc::now()).await {
+                Ok(sent) => sent as i64 >= DELIVERY_BATCH,
+                Err(e) => {
+                    warn!(error = %e, "Failed to read webhook queue");
+                    false
+                }
+            };
+            if full_batch {
+                continue;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                _ = self.wake.notified() => {}
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+
+    /// Attempt every delivery due by `now`; returns how many were attempted
+    async fn deliver_due(&self, now: DateTime<Utc>) -> anyhow::Result<usize> {
+        let due = self.db.get_due_webhook_deliveries(now, DELIVERY_BATCH).await?;
+
+        for delivery in &due {
+            let Some(target) = self.targets.get(&delivery.webhook) else {
+                self.db
+                    .record_webhook_attempt(delivery.id, Some("webhook is no longer configured"), None)
+                    .await?;
+                continue;
+            };
+
+            match self.deliver(target, delivery).await {
+                Ok(()) => {
+                    debug!(webhook = %target.name, delivery_id = delivery.id, "Webhook delivered");
+                    self.db.record_webhook_attempt(delivery.id, None, None).await?;
+                }
+                Err(e) => {
+                    let attempts = delivery.attempts + 1;
+                    let retry_at = (attempts < target.max_attempts).then(|| now + retry_delay(attempts));
+                    match retry_at {
+                        Some(retry_at) => warn!(
+                            webhook = %target.name,
+                            delivery_id = delivery.id,
+                            attempts,
+                            retry_at = %retry_at.to_rfc3339(),
+                            error = %e,
+                            "Webhook delivery failed, will retry"
+                        ),
+                        None => error!(
+                            webhook = %target.name,
+                            delivery_id = delivery.id,
+                            event_id = delivery.event_id,
+                            event_type = %delivery.event_type,
+                            attempts,
+                            error = %e,
+                            "Webhook delivery failed, giving up"
+                        ),
+                    }
+                    self.db
+                        .record_webhook_attempt(delivery.id, Some(&e), retry_at)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(due.len())
+    }
+
+    /// POST one delivery; Err is why it failed
+    async fn deliver(&self, target: &WebhookConfig, delivery: &WebhookDelivery) -> Result<(), String> {
+        let mut request = self
+            .client
+            .post(&target.url)
+            .timeout(DELIVERY_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Solopool-Event", &delivery.event_type)
+            .header("X-Solopool-Delivery", delivery.id.to_string());
+        if let Some(secret) = &target.secret {
+            request = request.header(
+                target.signature_header.as_str(),
+                format!("sha256={}", sign(secret, &delivery.body)),
+            );
+        }
+
+        match request.body(delivery.body.clone()).send().await {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => Err(format!("{} answered HTTP {}", target.url, response.status())),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Build a delivery body from a template, or the event JSON without one
+///
+/// String fields are JSON-escaped without quotes, so templates quote them
+/// (`"{{hash}}"`); placeholders the event doesn't have render empty.
+pub fn render(template: Option<&str>, event: &Event) -> anyhow::Result<String> {
+    let mut value = serde_json::to_value(event)?;
+    let Some(template) = template else {
+        return Ok(value.to_string());
+    };
+    value["summary"] = Value::String(summary(event));
+
+    let mut body = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        body.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| anyhow::anyhow!("has an unclosed placeholder"))?;
+
+        let key = after[..end].trim();
+        let field = if key == "event" { Some(&value) } else { value.get(key) };
+        match field {
+            Some(Value::String(s)) => {
+                let quoted = serde_json::to_string(s)?;
+                body.push_str(&quoted[1..quoted.len() - 1]);
+            }
+            Some(field) => body.push_str(&field.to_string()),
+            None => {}
+        }
+        rest = &after[end + 2..];
+    }
+    body.push_str(rest);
+
+    serde_json::from_str::<Value>(&body)
+        .map_err(|e| anyhow::anyhow!("does not render valid JSON ({})", e))?;
+    Ok(body)
+}
+
+/// Check a template renders valid JSON
+pub fn check_template(template: &str) -> Result<(), String> {
+    let sample = Event {
+        id: 1,
+        coin: Coin::Xmr,
+        created_at: Utc::now(),
+        kind: EventKind::BlockFound {
+            height: 3_100_000,
+            hash: "ab".repeat(32),
+            reward: Decimal::new(600_000_000_000, 0),
+            finder: "4Address".to_string(),
+        },
+    };
+    render(Some(template), &sample).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// One line describing the event, for chat webhooks
+fn summary(event: &Event) -> String {
+    let coin = event.coin.to_string().to_uppercase();
+    match &event.kind {
+        EventKind::SharesSynced { shares, .. } => format!("{}: {} new shares", coin, shares),
+        EventKind::BlockFound { height, reward, finder, .. } => {
+            format!("{}: block {} found by {} (reward {})", coin, height, finder, reward)
+        }
+        EventKind::BlockConfirmed { height, reward, .. } => {
+            format!("{}: block {} matured (reward {})", coin, height, reward)
+        }
+        EventKind::CoinbaseMissing { height, .. } => {
+            format!("{}: coinbase of block {} not found in pool wallet, reward held", coin, height)
+        }
+        EventKind::RewardsDistributed { height, amount, miners, .. } => {
+            format!("{}: {} from block {} credited to {} miners", coin, amount, height, miners)
+        }
+        EventKind::PaymentSent { address, amount, tx_hash, .. } => {
+            format!("{}: paid {} to {} ({})", coin, amount, address, tx_hash)
+        }
+        EventKind::PaymentConfirmed { address, amount, .. } => {
+            format!("{}: payment of {} to {} confirmed", coin, amount, address)
+        }
+        EventKind::PaymentFailed { address, amount, reason, .. } => {
+            format!("{}: payment of {} to {} failed: {}", coin, amount, address, reason)
+        }
+        EventKind::WalletBalanceLow { balance, threshold } => {
+            format!("{}: wallet balance {} is below {}", coin, balance, threshold)
+        }
+        EventKind::PoolOffline => format!("{}: pool API is offline", coin),
+    }
+}
+
+/// Hex HMAC-SHA256 of a body
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Delay after the given number of failed attempts
+fn retry_delay(attempts: u32) -> chrono::Duration {
+    let secs = RETRY_BASE_SECS.saturating_mul(1i64 << attempts.saturating_sub(1).min(20));
+    chrono::Duration::seconds(secs.min(RETRY_MAX_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TempDatabase;
+    use axum::{http::HeaderMap, http::StatusCode, routing::post, Router};
+    use std::sync::Mutex;
+
+    fn payment_failed() -> Event {
+        Event {
+            id: 42,
+            coin: Coin::Btc,
+            created_at: Utc::now(),
+            kind: EventKind::PaymentFailed {
+                payment_id: "p1".to_string(),
+                address: "bc1q\"quoted\"".to_string(),
+                amount: Decimal::new(150_000, 0),
+                reason: "insufficient funds".to_string(),
+            },
+        }
+    }
+
+    fn target(url: &str) -> WebhookConfig {
+        WebhookConfig {
+            name: "ops".to_string(),
+            url: url.to_string(),
+            enabled: true,
+            events: vec!["payment_failed".to_string()],
+            template: None,
+            secret: Some("key".to_string()),
+            signature_header: "X-Solopool-Signature".to_string(),
+            max_attempts: 3,
+        }
+    }
+
+    #[test]
+    fn renders_templates() {
+        let event = payment_failed();
+
+        let body = render(None, &event).unwrap();
+        let json: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["type"], "payment_failed");
+        assert_eq!(json["amount"], "150000");
+
+        let body = render(Some(r#"{"content": "{{summary}}", "id": {{id}}, "missing": "{{height}}"}"#), &event).unwrap();
+        let json: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            json["content"],
+            r#"BTC: payment of 150000 to bc1q"quoted" failed: insufficient funds"#
+        );
+        assert_eq!(json["id"], 42);
+        assert_eq!(json["missing"], "");
+
+        let body = render(Some(r#"{"wrapped": {{event}}}"#), &event).unwrap();
+        let json: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["wrapped"]["payment_id"], "p1");
+
+        assert!(check_template(r#"{"text": "{{summary}}"}"#).is_ok());
+        assert!(check_template(r#"{"text": {{summary}}}"#).is_err());
+        assert!(check_template(r#"{"text": "{{summary"}"#).is_err());
+    }
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(retry_delay(1).num_seconds(), 30);
+        assert_eq!(retry_delay(2).num_seconds(), 60);
+        assert_eq!(retry_delay(5).num_seconds(), 480);
+        assert_eq!(retry_delay(20).num_seconds(), RETRY_MAX_SECS);
+        assert_eq!(retry_delay(u32::MAX).num_seconds(), RETRY_MAX_SECS);
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_retried_with_backoff_and_signed() {
+        // Target that fails the first request
+        let received: Arc<Mutex<Vec<(HeaderMap, String)>>> = Arc::default();
+        let log = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| {
+                let log = log.clone();
+                async move {
+                    let mut log = log.lock().unwrap();
+                    log.push((headers, body));
+                    if log.len() == 1 {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::NO_CONTENT
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let db = TempDatabase::new().await;
+        let webhooks = Webhooks::new(db.clone(), &[target(&url)]);
+
+        // Not subscribed to block_found
+        let mut block = payment_failed();
+        block.id = 41;
+        block.kind = EventKind::PoolOffline;
+        webhooks.enqueue(&block).await.unwrap();
+        webhooks.enqueue(&payment_failed()).await.unwrap();
+        assert_eq!(db.get_webhook_cursor().await.unwrap(), Some(42));
+
+        let now = Utc::now();
+        assert_eq!(webhooks.deliver_due(now).await.unwrap(), 1);
+        // Backing off: nothing due until the first retry delay has passed
+        assert_eq!(webhooks.deliver_due(now + chrono::Duration::seconds(10)).await.unwrap(), 0);
+        assert_eq!(webhooks.deliver_due(now + chrono::Duration::seconds(31)).await.unwrap(), 1);
+        assert_eq!(webhooks.deliver_due(now + chrono::Duration::days(1)).await.unwrap(), 0);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (headers, body) = &received[1];
+        assert_eq!(headers["x-solopool-event"], "payment_failed");
+        assert_eq!(
+            headers["x-solopool-signature"].to_str().unwrap(),
+            format!("sha256={}", sign("key", body))
+        );
+        assert_eq!(serde_json::from_str::<Value>(body).unwrap()["payment_id"], "p1");
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let db = TempDatabase::new().await;
+        // Nothing listens on port 9 locally
+        let webhooks = Webhooks::new(db.clone(), &[target("http://127.0.0.1:9/hook")]);
+        webhooks.enqueue(&payment_failed()).await.unwrap();
+
+        let mut now = Utc::now();
+        for attempt in 1..=3 {
+            assert_eq!(webhooks.deliver_due(now).await.unwrap(), 1, "attempt {}", attempt);
+            now += retry_delay(attempt);
+        }
+        assert_eq!(webhooks.deliver_due(now + chrono::Duration::days(1)).await.unwrap(), 0);
+    }
+}
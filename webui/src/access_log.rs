@@ -1,5 +1,4 @@
 use axum::{
-    body::Body,
     extract::ConnectInfo,
     http::{Request, Response},
 };
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::DateTime;
 use serde::Deserialize;
 
 use crate::models::{AlgorithmStats, WorkerStats, format_hashrate};
@@ -8,6 +8,7 @@ pub struct AleoPoolClient;
 
 /// Response from ALEO pool server stats endpoint
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct AleoPoolStats {
     #[serde(default)]
     pool_hashrate: f64,
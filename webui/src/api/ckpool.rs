@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::DateTime;
 use serde::Deserialize;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
@@ -10,6 +10,7 @@ pub struct CkPoolClient;
 
 /// CKPool stats response from socket API
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct CkPoolStatsResponse {
     #[serde(default)]
     runtime: u64,
@@ -51,6 +52,7 @@ struct CkPoolStatsResponse {
 
 /// CKPool user/worker entry from socket API
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct CkPoolUserEntry {
     #[serde(default)]
     user: String,
@@ -3,23 +3,27 @@ mod aleo;
 mod monero_pool;
 mod tari;
 mod nodes;
+mod payments;
 
 use std::sync::Arc;
 use chrono::Utc;
 use tokio::time::{interval, Duration};
 
+use crate::metrics::StatsMetrics;
 use crate::models::{AppState, PoolStats, AlgorithmStats};
 
 pub use ckpool::CkPoolClient;
 pub use aleo::AleoPoolClient;
 pub use monero_pool::MoneroPoolClient;
 pub use tari::{TariMergeClient, TariMinerClient};
+pub use payments::PaymentsClient;
 
 /// Background task to periodically update pool statistics
 pub async fn stats_updater(state: Arc<AppState>) {
     let refresh_secs = state.config.server.refresh_interval_secs;
     let mut interval = interval(Duration::from_secs(refresh_secs));
     let server_ip = &state.server_ip;
+    let metrics = &state.metrics;
 
     tracing::info!("Stats updater started (refresh every {}s)", refresh_secs);
 
@@ -31,15 +35,15 @@ pub async fn stats_updater(state: Arc<AppState>) {
         // Fetch BTC stats (CKPool)
         if let Some(ref btc_config) = state.config.pools.btc {
             if btc_config.enabled {
-                new_stats.btc = fetch_ckpool_stats(btc_config, "BTC", server_ip).await;
+                new_stats.btc = fetch_ckpool_stats(btc_config, "BTC", server_ip, metrics).await;
                 // Fetch node sync status
                 if let Some(ref rpc_url) = btc_config.node_rpc_url {
                     let rpc_pass = btc_config.node_rpc_password.as_deref().unwrap_or("");
-                    new_stats.btc.sync_status = nodes::fetch_bitcoin_sync(
+                    new_stats.btc.sync_status = metrics.time_node_fetch("btc", nodes::fetch_bitcoin_sync(
                         rpc_url,
                         &btc_config.node_rpc_user,
                         rpc_pass,
-                    ).await;
+                    )).await;
                 }
             }
         }
@@ -47,15 +51,15 @@ pub async fn stats_updater(state: Arc<AppState>) {
         // Fetch BCH stats (CKPool)
         if let Some(ref bch_config) = state.config.pools.bch {
             if bch_config.enabled {
-                new_stats.bch = fetch_ckpool_stats(bch_config, "BCH", server_ip).await;
+                new_stats.bch = fetch_ckpool_stats(bch_config, "BCH", server_ip, metrics).await;
                 // Fetch node sync status
                 if let Some(ref rpc_url) = bch_config.node_rpc_url {
                     let rpc_pass = bch_config.node_rpc_password.as_deref().unwrap_or("");
-                    new_stats.bch.sync_status = nodes::fetch_bitcoin_sync(
+                    new_stats.bch.sync_status = metrics.time_node_fetch("bch", nodes::fetch_bitcoin_sync(
                         rpc_url,
                         &bch_config.node_rpc_user,
                         rpc_pass,
-                    ).await;
+                    )).await;
                 }
             }
         }
@@ -63,15 +67,15 @@ pub async fn stats_updater(state: Arc<AppState>) {
         // Fetch DGB stats (CKPool)
         if let Some(ref dgb_config) = state.config.pools.dgb {
             if dgb_config.enabled {
-                new_stats.dgb = fetch_ckpool_stats(dgb_config, "DGB", server_ip).await;
+                new_stats.dgb = fetch_ckpool_stats(dgb_config, "DGB", server_ip, metrics).await;
                 // Fetch node sync status
                 if let Some(ref rpc_url) = dgb_config.node_rpc_url {
                     let rpc_pass = dgb_config.node_rpc_password.as_deref().unwrap_or("");
-                    new_stats.dgb.sync_status = nodes::fetch_bitcoin_sync(
+                    new_stats.dgb.sync_status = metrics.time_node_fetch("dgb", nodes::fetch_bitcoin_sync(
                         rpc_url,
                         &dgb_config.node_rpc_user,
                         rpc_pass,
-                    ).await;
+                    )).await;
                 }
             }
         }
@@ -79,7 +83,7 @@ pub async fn stats_updater(state: Arc<AppState>) {
         // Fetch XMR stats (monero-pool - monero_only mode)
         if let Some(ref xmr_config) = state.config.pools.xmr {
             if xmr_config.enabled {
-                match MoneroPoolClient::fetch_stats(&xmr_config.api_url).await {
+                match metrics.time_pool_fetch("xmr", MoneroPoolClient::fetch_stats(&xmr_config.api_url)).await {
                     Ok(mut stats) => {
                         stats.name = xmr_config.name.clone();
                         stats.algorithm = xmr_config.algorithm.clone();
@@ -90,11 +94,11 @@ pub async fn stats_updater(state: Arc<AppState>) {
                         stats.password = xmr_config.password.clone();
                         stats.pool_wallet_address = xmr_config.pool_wallet_address.clone();
                         // Fetch node sync status
-                        stats.sync_status = nodes::fetch_monero_sync(
+                        stats.sync_status = metrics.time_node_fetch("xmr", nodes::fetch_monero_sync(
                             &xmr_config.node_rpc_url,
                             xmr_config.node_rpc_user.as_deref(),
                             xmr_config.node_rpc_password.as_deref(),
-                        ).await;
+                        )).await;
                         new_stats.xmr = stats;
                     }
                     Err(e) => {
@@ -109,11 +113,11 @@ pub async fn stats_updater(state: Arc<AppState>) {
                             username_format: xmr_config.username_format.clone(),
                             password: xmr_config.password.clone(),
                             pool_wallet_address: xmr_config.pool_wallet_address.clone(),
-                            sync_status: nodes::fetch_monero_sync(
+                            sync_status: metrics.time_node_fetch("xmr", nodes::fetch_monero_sync(
                                 &xmr_config.node_rpc_url,
                                 xmr_config.node_rpc_user.as_deref(),
                                 xmr_config.node_rpc_password.as_deref(),
-                            ).await,
+                            )).await,
                             ..Default::default()
                         };
                     }
@@ -124,7 +128,7 @@ pub async fn stats_updater(state: Arc<AppState>) {
         // Fetch XTM stats (minotari_miner - tari_only mode)
         if let Some(ref xtm_config) = state.config.pools.xtm {
             if xtm_config.enabled {
-                match TariMinerClient::fetch_stats(&xtm_config.api_url).await {
+                match metrics.time_pool_fetch("xtm", TariMinerClient::fetch_stats(&xtm_config.api_url)).await {
                     Ok(mut stats) => {
                         stats.name = xtm_config.name.clone();
                         stats.algorithm = xtm_config.algorithm.clone();
@@ -135,7 +139,7 @@ pub async fn stats_updater(state: Arc<AppState>) {
                         stats.password = xtm_config.password.clone();
                         stats.pool_wallet_address = xtm_config.pool_wallet_address.clone();
                         // Fetch node sync status
-                        stats.sync_status = nodes::fetch_tari_sync(xtm_config.node_grpc_port).await;
+                        stats.sync_status = metrics.time_node_fetch("xtm", nodes::fetch_tari_sync(xtm_config.node_grpc_port)).await;
                         new_stats.xtm = stats;
                     }
                     Err(e) => {
@@ -150,7 +154,7 @@ pub async fn stats_updater(state: Arc<AppState>) {
                             username_format: xtm_config.username_format.clone(),
                             password: xtm_config.password.clone(),
                             pool_wallet_address: xtm_config.pool_wallet_address.clone(),
-                            sync_status: nodes::fetch_tari_sync(xtm_config.node_grpc_port).await,
+                            sync_status: metrics.time_node_fetch("xtm", nodes::fetch_tari_sync(xtm_config.node_grpc_port)).await,
                             ..Default::default()
                         };
                     }
@@ -166,12 +170,16 @@ pub async fn stats_updater(state: Arc<AppState>) {
                     .unwrap_or_else(|| format!("http://127.0.0.1:{}", merge_config.stratum_port));
 
                 // Fetch sync status for both chains (use Monero as primary indicator)
-                let xmr_sync = nodes::fetch_monero_sync(
+                // Node fetches are labelled per chain; the standalone pools are off in merge mode
+                let xmr_sync = metrics.time_node_fetch("xmr", nodes::fetch_monero_sync(
                     &merge_config.xmr_node_rpc_url,
                     merge_config.xmr_node_rpc_user.as_deref(),
                     merge_config.xmr_node_rpc_password.as_deref(),
+                )).await;
+                let xtm_sync = metrics.time_node_fetch(
+                    "xtm",
+                    nodes::fetch_tari_sync(merge_config.xtm_node_grpc_port),
                 ).await;
-                let xtm_sync = nodes::fetch_tari_sync(merge_config.xtm_node_grpc_port).await;
 
                 // Combined sync status: show XMR sync progress (primary chain for merge mining)
                 let combined_sync = if xmr_sync.is_synced && xtm_sync.is_synced {
@@ -205,7 +213,7 @@ pub async fn stats_updater(state: Arc<AppState>) {
                     }
                 };
 
-                match TariMergeClient::fetch_stats(&api_url).await {
+                match metrics.time_pool_fetch("xmr_xtm_merge", TariMergeClient::fetch_stats(&api_url)).await {
                     Ok(mut stats) => {
                         stats.name = merge_config.name.clone();
                         stats.algorithm = merge_config.algorithm.clone();
@@ -244,14 +252,14 @@ pub async fn stats_updater(state: Arc<AppState>) {
         if let Some(ref aleo_config) = state.config.pools.aleo {
             if aleo_config.enabled {
                 // Fetch node sync status
-                let aleo_sync = nodes::fetch_aleo_sync(
+                let aleo_sync = metrics.time_node_fetch("aleo", nodes::fetch_aleo_sync(
                     &aleo_config.node_rest_url,
                     &aleo_config.network,
                     aleo_config.node_rpc_user.as_deref(),
                     aleo_config.node_rpc_password.as_deref(),
-                ).await;
+                )).await;
 
-                match AleoPoolClient::fetch_stats(&aleo_config.api_url).await {
+                match metrics.time_pool_fetch("aleo", AleoPoolClient::fetch_stats(&aleo_config.api_url)).await {
                     Ok(mut stats) => {
                         stats.name = aleo_config.name.clone();
                         stats.algorithm = aleo_config.algorithm.clone();
@@ -286,14 +294,24 @@ pub async fn stats_updater(state: Arc<AppState>) {
 
         new_stats.last_updated = Some(Utc::now());
 
-        // All pools are fee-free (0% pool fee)
-        new_stats.btc.pool_fee_percent = 0.0;
-        new_stats.bch.pool_fee_percent = 0.0;
-        new_stats.dgb.pool_fee_percent = 0.0;
-        new_stats.xmr.pool_fee_percent = 0.0;
-        new_stats.xtm.pool_fee_percent = 0.0;
-        new_stats.xmr_xtm_merge.pool_fee_percent = 0.0;
-        new_stats.aleo.pool_fee_percent = 0.0;
+        // Pool fees come from the payment processor; without it pools are fee-free
+        match PaymentsClient::fetch_pool_fees(
+            &state.config.server.payments_api_url,
+            &state.config.server.payments_api_token,
+        ).await {
+            Ok(fees) => {
+                let fee = |coin: &str| fees.get(coin).copied().unwrap_or(0.0);
+                new_stats.btc.pool_fee_percent = fee("btc");
+                new_stats.bch.pool_fee_percent = fee("bch");
+                new_stats.dgb.pool_fee_percent = fee("dgb");
+                new_stats.xmr.pool_fee_percent = fee("xmr");
+                new_stats.xtm.pool_fee_percent = fee("xtm");
+                // The merge card shows the XMR fee; XTM rewards use the xtm fee
+                new_stats.xmr_xtm_merge.pool_fee_percent = fee("xmr");
+                new_stats.aleo.pool_fee_percent = fee("aleo");
+            }
+            Err(e) => tracing::debug!("Failed to fetch pool fees from payment processor: {}", e),
+        }
 
         // Store worker stats to database for persistence
         store_workers_to_db(&state.db, &new_stats).await;
@@ -365,7 +383,12 @@ async fn store_workers_to_db(db: &Arc<crate::db::Database>, stats: &PoolStats) {
 
 /// Helper function to fetch CKPool stats with proper error handling
 /// Uses Unix socket API exclusively - each pool must have its own socket directory
-async fn fetch_ckpool_stats(config: &crate::config::CkPoolConfig, coin: &str, server_ip: &str) -> AlgorithmStats {
+async fn fetch_ckpool_stats(
+    config: &crate::config::CkPoolConfig,
+    coin: &str,
+    server_ip: &str,
+    metrics: &StatsMetrics,
+) -> AlgorithmStats {
     // Socket directory is required - each CKPool instance must have its own
     let Some(ref socket_dir) = config.socket_dir else {
         tracing::error!("{} pool missing socket_dir configuration", coin);
@@ -382,7 +405,8 @@ async fn fetch_ckpool_stats(config: &crate::config::CkPoolConfig, coin: &str, se
         };
     };
 
-    match CkPoolClient::fetch_stats(socket_dir).await {
+    let pool_id = coin.to_lowercase();
+    match metrics.time_pool_fetch(&pool_id, CkPoolClient::fetch_stats(socket_dir)).await {
         Ok(mut stats) => {
             stats.name = config.name.clone();
             stats.algorithm = config.algorithm.clone();
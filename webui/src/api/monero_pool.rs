@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::DateTime;
 use serde::Deserialize;
 
 use crate::models::{AlgorithmStats, WorkerStats, format_hashrate};
@@ -8,6 +8,7 @@ pub struct MoneroPoolClient;
 
 /// monero-pool stats response
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct MoneroPoolStats {
     #[serde(default)]
     pool_hashrate: u64,
@@ -45,8 +46,10 @@ struct MoneroPoolMiner {
 impl MoneroPoolClient {
     /// Fetch statistics from monero-pool API
     pub async fn fetch_stats(api_base: &str) -> anyhow::Result<AlgorithmStats> {
-        let mut stats = AlgorithmStats::default();
-        stats.hashrate_unit = "H/s".to_string();
+        let mut stats = AlgorithmStats {
+            hashrate_unit: "H/s".to_string(),
+            ..Default::default()
+        };
 
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(5))
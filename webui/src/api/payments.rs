@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// Client for the payment processor API
+pub struct PaymentsClient;
+
+/// Per-coin stats from the payment processor's /api/stats
+#[derive(Debug, Deserialize)]
+struct PaymentsStats {
+    #[serde(default)]
+    xmr: Option<PaymentsCoinStats>,
+    #[serde(default)]
+    xtm: Option<PaymentsCoinStats>,
+    #[serde(default)]
+    aleo: Option<PaymentsCoinStats>,
+    #[serde(default)]
+    btc: Option<PaymentsCoinStats>,
+    #[serde(default)]
+    bch: Option<PaymentsCoinStats>,
+    #[serde(default)]
+    dgb: Option<PaymentsCoinStats>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentsCoinStats {
+    /// Decimal string, e.g. "1.5"
+    #[serde(default)]
+    pool_fee_percent: Option<String>,
+}
+
+impl PaymentsClient {
+    /// Fetch the pool fee percentage of each coin ("xmr", "btc", ...)
+    pub async fn fetch_pool_fees(api_url: &str, api_token: &str) -> anyhow::Result<HashMap<&'static str, f64>> {
+        let url = format!("{}/api/stats", api_url.trim_end_matches('/'));
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()?;
+
+        let mut request = client.get(&url);
+        if !api_token.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", api_token));
+        }
+
+        let stats: PaymentsStats = request.send().await?.error_for_status()?.json().await?;
+
+        let coins = [
+            ("xmr", stats.xmr),
+            ("xtm", stats.xtm),
+            ("aleo", stats.aleo),
+            ("btc", stats.btc),
+            ("bch", stats.bch),
+            ("dgb", stats.dgb),
+        ];
+
+        Ok(coins
+            .into_iter()
+            .filter_map(|(coin, stats)| {
+                let percent = stats?.pool_fee_percent?.parse::<f64>().ok()?;
+                Some((coin, percent))
+            })
+            .collect())
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::DateTime;
 use serde::Deserialize;
 
 use crate::models::{AlgorithmStats, WorkerStats, format_hashrate};
@@ -8,6 +8,7 @@ pub struct TariMergeClient;
 
 /// Tari merge mining proxy stats (if available via API)
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct TariMergeStats {
     #[serde(default)]
     hashrate: f64,
@@ -37,8 +38,10 @@ impl TariMergeClient {
     /// Fetch statistics from Tari merge mining proxy
     /// Note: The actual API endpoints depend on the merge mining proxy implementation
     pub async fn fetch_stats(api_base: &str) -> anyhow::Result<AlgorithmStats> {
-        let mut stats = AlgorithmStats::default();
-        stats.hashrate_unit = "H/s".to_string();
+        let mut stats = AlgorithmStats {
+            hashrate_unit: "H/s".to_string(),
+            ..Default::default()
+        };
 
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(5))
@@ -124,8 +127,10 @@ pub struct TariMinerClient;
 impl TariMinerClient {
     /// Fetch statistics from minotari_miner
     pub async fn fetch_stats(api_base: &str) -> anyhow::Result<AlgorithmStats> {
-        let mut stats = AlgorithmStats::default();
-        stats.hashrate_unit = "H/s".to_string();
+        let mut stats = AlgorithmStats {
+            hashrate_unit: "H/s".to_string(),
+            ..Default::default()
+        };
 
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(5))
@@ -172,6 +172,7 @@ impl AuthState {
 }
 
 /// Middleware to require authentication
+#[allow(dead_code)]
 pub async fn require_auth(
     State(auth): State<Arc<AuthState>>,
     cookies: Cookies,
@@ -7,6 +7,20 @@ pub struct Config {
     /// Authentication configuration
     #[serde(default)]
     pub auth: AuthConfig,
+    /// Prometheus metrics endpoint configuration
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MetricsConfig {
+    /// Serve Prometheus metrics at /metrics (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Bearer token required to scrape /metrics
+    /// If empty, /metrics uses the normal WebUI session authentication
+    #[serde(default)]
+    pub token: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -404,6 +418,7 @@ impl Default for Config {
                 payments_api_token: String::new(),
             },
             auth: AuthConfig::default(),
+            metrics: MetricsConfig::default(),
             pools: PoolsConfig {
                 btc: Some(CkPoolConfig {
                     enabled: true,
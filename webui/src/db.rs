@@ -3,7 +3,7 @@
 //! Uses SQLite to store worker stats that persist across WebUI restarts.
 //! Stats are updated periodically from pool backends and stored for display.
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection};
 use std::path::Path;
@@ -131,6 +131,7 @@ impl Database {
     }
 
     /// Upsert (insert or update) worker stats
+    #[allow(clippy::too_many_arguments)]
     pub fn upsert_worker(
         &self,
         pool_id: &str,
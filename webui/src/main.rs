@@ -23,12 +23,14 @@ mod api;
 mod auth;
 mod config;
 mod db;
+mod metrics;
 mod models;
 
 use access_log::{AccessLogLayer, AccessLogWriter};
 use auth::AuthState;
 use config::Config;
 use db::Database;
+use metrics::StatsMetrics;
 use models::{detect_server_ip, AppState, PoolStats};
 
 /// Embedded static files (compiled into binary at build time)
@@ -121,8 +123,17 @@ async fn main() -> anyhow::Result<()> {
         stats: RwLock::new(PoolStats::default()),
         server_ip,
         db: Arc::new(db),
+        metrics: StatsMetrics::new(),
     });
 
+    if config.metrics.enabled {
+        if config.metrics.token.is_empty() {
+            tracing::info!("Prometheus metrics enabled at /metrics (session authentication)");
+        } else {
+            tracing::info!("Prometheus metrics enabled at /metrics (bearer token authentication)");
+        }
+    }
+
     // Spawn background task to fetch stats
     let state_clone = app_state.clone();
     tokio::spawn(async move {
@@ -170,6 +181,7 @@ async fn main() -> anyhow::Result<()> {
         .route("/api/stats/:pool", get(get_pool_stats))
         .route("/api/workers/:pool/:worker", delete(delete_worker))
         .route("/api/health", get(health_check))
+        .route("/metrics", get(get_metrics))
         // Payment processor proxy routes
         .route("/api/payments/stats", get(proxy_payments_stats))
         .route("/api/payments/stats/:coin", get(proxy_payments_coin_stats))
@@ -306,6 +318,8 @@ async fn require_auth(
         || path == "/css/style.css"
         || path == "/css/login.css"
         || path == "/api/health"
+        // Metrics endpoint performs its own authentication
+        || path == "/metrics"
     {
         return next.run(request).await;
     }
@@ -464,6 +478,54 @@ async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Prometheus metrics endpoint
+///
+/// Authenticated by the metrics bearer token when one is configured,
+/// otherwise by the normal session cookie (when auth is enabled).
+async fn get_metrics(
+    State((state, auth)): State<(Arc<AppState>, Arc<AuthState>)>,
+    cookies: Cookies,
+    request: Request<Body>,
+) -> Response {
+    let metrics_config = &state.config.metrics;
+    if !metrics_config.enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let authorized = if !metrics_config.token.is_empty() {
+        request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|token| token == metrics_config.token)
+    } else if auth.enabled {
+        match cookies.get(&auth.cookie_name) {
+            Some(session_cookie) => auth.sessions.validate(session_cookie.value()).await.is_some(),
+            None => false,
+        }
+    } else {
+        true
+    };
+
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, "Authentication required").into_response();
+    }
+
+    let stats = state.stats.read().await;
+    match state.metrics.render(&stats) {
+        Ok(body) => (
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            body,
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to render metrics: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 /// Delete a worker from the database
 async fn delete_worker(
     State((state, _)): State<(Arc<AppState>, Arc<AuthState>)>,
@@ -0,0 +1,390 @@
+//! Prometheus metrics for the stats collector
+//!
+//! Pool, worker and node sync series are rendered from the latest
+//! `PoolStats` snapshot on each scrape. Fetch latency and error counters
+//! are recorded by `api::stats_updater` as it polls each backend.
+
+use std::future::Future;
+use std::time::Instant;
+
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+use crate::models::{to_base_hashrate, AlgorithmStats, PoolStats};
+
+/// Backend source label for pool API / CKPool socket fetches
+pub const SOURCE_POOL: &str = "pool";
+/// Backend source label for node RPC sync checks
+pub const SOURCE_NODE: &str = "node";
+
+/// Metric registry for the WebUI
+///
+/// Only the updater series live here. Snapshot series are built into a fresh
+/// registry on each scrape, so concurrent scrapes never share gauges.
+#[derive(Clone)]
+pub struct StatsMetrics {
+    fetch_duration: HistogramVec,
+    fetch_errors: IntCounterVec,
+}
+
+/// Pool, worker and node series for one scrape
+struct Snapshot {
+    pool_online: GaugeVec,
+    pool_hashrate: GaugeVec,
+    pool_workers: GaugeVec,
+    pool_workers_online: GaugeVec,
+    pool_blocks_found: GaugeVec,
+    worker_hashrate: GaugeVec,
+    worker_shares_accepted: GaugeVec,
+    worker_shares_rejected: GaugeVec,
+    worker_online: GaugeVec,
+    node_online: GaugeVec,
+    node_synced: GaugeVec,
+    node_height: GaugeVec,
+    node_target_height: GaugeVec,
+    node_sync_percent: GaugeVec,
+    last_updated: GaugeVec,
+}
+
+fn gauge(name: &str, help: &str, labels: &[&str]) -> GaugeVec {
+    GaugeVec::new(Opts::new(name, help), labels).expect("valid metric")
+}
+
+impl Snapshot {
+    fn new() -> Self {
+        Self {
+            pool_online: gauge("solopool_pool_online", "Whether the pool backend is reachable", &["pool"]),
+            pool_hashrate: gauge("solopool_pool_hashrate_hps", "Pool hashrate in H/s", &["pool"]),
+            pool_workers: gauge("solopool_pool_workers", "Workers reported by the pool", &["pool"]),
+            pool_workers_online: gauge("solopool_pool_workers_online", "Workers currently online", &["pool"]),
+            pool_blocks_found: gauge("solopool_pool_blocks_found", "Blocks found as reported by the pool", &["pool"]),
+            worker_hashrate: gauge("solopool_worker_hashrate_hps", "Worker hashrate in H/s", &["pool", "worker"]),
+            worker_shares_accepted: gauge("solopool_worker_shares_accepted", "Accepted shares reported for a worker", &["pool", "worker"]),
+            worker_shares_rejected: gauge("solopool_worker_shares_rejected", "Rejected shares reported for a worker", &["pool", "worker"]),
+            worker_online: gauge("solopool_worker_online", "Whether the worker is online", &["pool", "worker"]),
+            node_online: gauge("solopool_node_online", "Whether the node RPC is reachable", &["pool"]),
+            node_synced: gauge("solopool_node_synced", "Whether the node is fully synced", &["pool"]),
+            node_height: gauge("solopool_node_height", "Current node block height", &["pool"]),
+            node_target_height: gauge("solopool_node_target_height", "Target block height reported by the node", &["pool"]),
+            node_sync_percent: gauge("solopool_node_sync_percent", "Node sync progress (0-100)", &["pool"]),
+            last_updated: gauge("solopool_stats_last_updated_timestamp_seconds", "Unix time of the last completed stats refresh", &[]),
+        }
+    }
+
+    fn gauges(&self) -> [&GaugeVec; 15] {
+        [
+            &self.pool_online,
+            &self.pool_hashrate,
+            &self.pool_workers,
+            &self.pool_workers_online,
+            &self.pool_blocks_found,
+            &self.worker_hashrate,
+            &self.worker_shares_accepted,
+            &self.worker_shares_rejected,
+            &self.worker_online,
+            &self.node_online,
+            &self.node_synced,
+            &self.node_height,
+            &self.node_target_height,
+            &self.node_sync_percent,
+            &self.last_updated,
+        ]
+    }
+
+    fn fill(&self, stats: &PoolStats) {
+        let pools: [(&str, &AlgorithmStats); 7] = [
+            ("btc", &stats.btc),
+            ("bch", &stats.bch),
+            ("dgb", &stats.dgb),
+            ("xmr", &stats.xmr),
+            ("xtm", &stats.xtm),
+            ("xmr_xtm_merge", &stats.xmr_xtm_merge),
+            ("aleo", &stats.aleo),
+        ];
+
+        for (pool_id, pool) in pools {
+            if !pool.enabled {
+                continue;
+            }
+            let labels = [pool_id];
+
+            self.pool_online
+                .with_label_values(&labels)
+                .set(bool_value(pool.online));
+            self.pool_hashrate
+                .with_label_values(&labels)
+                .set(to_base_hashrate(pool.total_hashrate, &pool.hashrate_unit));
+            self.pool_workers
+                .with_label_values(&labels)
+                .set(pool.worker_count as f64);
+            self.pool_workers_online
+                .with_label_values(&labels)
+                .set(pool.workers.iter().filter(|w| w.is_online).count() as f64);
+            self.pool_blocks_found
+                .with_label_values(&labels)
+                .set(pool.blocks_found as f64);
+
+            for worker in &pool.workers {
+                let labels = [pool_id, worker.name.as_str()];
+                self.worker_hashrate
+                    .with_label_values(&labels)
+                    .set(to_base_hashrate(worker.hashrate, &worker.hashrate_unit));
+                self.worker_shares_accepted
+                    .with_label_values(&labels)
+                    .set(worker.shares_accepted as f64);
+                self.worker_shares_rejected
+                    .with_label_values(&labels)
+                    .set(worker.shares_rejected as f64);
+                self.worker_online
+                    .with_label_values(&labels)
+                    .set(bool_value(worker.is_online));
+            }
+
+            let sync = &pool.sync_status;
+            self.node_online
+                .with_label_values(&labels)
+                .set(bool_value(sync.node_online));
+            self.node_synced
+                .with_label_values(&labels)
+                .set(bool_value(sync.is_synced));
+            self.node_height
+                .with_label_values(&labels)
+                .set(sync.current_height as f64);
+            if let Some(target) = sync.target_height {
+                self.node_target_height
+                    .with_label_values(&labels)
+                    .set(target as f64);
+            }
+            self.node_sync_percent
+                .with_label_values(&labels)
+                .set(sync.sync_percent);
+        }
+
+        if let Some(updated) = stats.last_updated {
+            self.last_updated
+                .with_label_values(&[])
+                .set(updated.timestamp() as f64);
+        }
+    }
+}
+
+impl StatsMetrics {
+    /// Create the updater metric families
+    pub fn new() -> Self {
+        let fetch_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "solopool_stats_fetch_duration_seconds",
+                "Time taken to fetch stats from a backend",
+            )
+            .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+            &["pool", "source"],
+        )
+        .expect("valid metric");
+
+        let fetch_errors = IntCounterVec::new(
+            Opts::new(
+                "solopool_stats_fetch_errors_total",
+                "Failed stats fetches per backend",
+            ),
+            &["pool", "source"],
+        )
+        .expect("valid metric");
+
+        Self {
+            fetch_duration,
+            fetch_errors,
+        }
+    }
+
+    /// Record the outcome of a single backend fetch
+    pub fn observe_fetch(&self, pool: &str, source: &str, started: Instant, ok: bool) {
+        self.fetch_duration
+            .with_label_values(&[pool, source])
+            .observe(started.elapsed().as_secs_f64());
+        if !ok {
+            self.fetch_errors.with_label_values(&[pool, source]).inc();
+        }
+    }
+
+    /// Time a pool backend fetch that returns a `Result`
+    pub async fn time_pool_fetch<T, E>(
+        &self,
+        pool: &str,
+        fetch: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let started = Instant::now();
+        let result = fetch.await;
+        self.observe_fetch(pool, SOURCE_POOL, started, result.is_ok());
+        result
+    }
+
+    /// Time a node sync check; an unreachable node counts as an error
+    pub async fn time_node_fetch(
+        &self,
+        pool: &str,
+        fetch: impl Future<Output = crate::models::SyncStatus>,
+    ) -> crate::models::SyncStatus {
+        let started = Instant::now();
+        let status = fetch.await;
+        self.observe_fetch(pool, SOURCE_NODE, started, status.node_online);
+        status
+    }
+
+    /// Render all series for the given snapshot
+    pub fn render(&self, stats: &PoolStats) -> anyhow::Result<String> {
+        let snapshot = Snapshot::new();
+        snapshot.fill(stats);
+
+        let registry = Registry::new();
+        for vec in snapshot.gauges() {
+            registry.register(Box::new(vec.clone()))?;
+        }
+        registry.register(Box::new(self.fetch_duration.clone()))?;
+        registry.register(Box::new(self.fetch_errors.clone()))?;
+
+        let encoder = TextEncoder::new();
+        let mut buffer = Vec::new();
+        encoder.encode(&registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+impl Default for StatsMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn bool_value(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SyncStatus, WorkerStats};
+
+    fn worker(name: &str, kilohashes: f64) -> WorkerStats {
+        WorkerStats {
+            name: name.to_string(),
+            wallet_address: String::new(),
+            hashrate: kilohashes,
+            hashrate_unit: "KH/s".to_string(),
+            shares_accepted: 10,
+            shares_rejected: 1,
+            blocks_found: 0,
+            best_share: 0.0,
+            last_share_time: None,
+            connected_since: None,
+            is_online: true,
+        }
+    }
+
+    fn pool(workers: Vec<WorkerStats>) -> AlgorithmStats {
+        AlgorithmStats {
+            enabled: true,
+            online: true,
+            total_hashrate: workers.iter().map(|w| w.hashrate).sum(),
+            hashrate_unit: "KH/s".to_string(),
+            worker_count: workers.len(),
+            workers,
+            sync_status: SyncStatus {
+                node_online: true,
+                is_synced: true,
+                current_height: 3_000_000,
+                target_height: Some(3_000_000),
+                sync_percent: 100.0,
+                status_message: String::new(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_enabled_pools_and_their_workers() {
+        let stats = PoolStats {
+            xmr: pool(vec![worker("rig1", 2.0)]),
+            ..Default::default()
+        };
+        let text = StatsMetrics::new().render(&stats).unwrap();
+
+        assert!(text.contains("solopool_pool_online{pool=\"xmr\"} 1"));
+        assert!(text.contains("solopool_pool_hashrate_hps{pool=\"xmr\"} 2000"));
+        assert!(text.contains("solopool_worker_hashrate_hps{pool=\"xmr\",worker=\"rig1\"} 2000"));
+        assert!(text.contains("solopool_worker_shares_rejected{pool=\"xmr\",worker=\"rig1\"} 1"));
+        assert!(text.contains("solopool_node_height{pool=\"xmr\"} 3000000"));
+        assert!(!text.contains("pool=\"btc\""));
+    }
+
+    #[test]
+    fn workers_that_disconnect_drop_out_of_the_next_scrape() {
+        let metrics = StatsMetrics::new();
+        let before = PoolStats {
+            xmr: pool(vec![worker("rig1", 2.0), worker("rig2", 1.0)]),
+            ..Default::default()
+        };
+        assert!(metrics.render(&before).unwrap().contains("worker=\"rig2\""));
+
+        let after = PoolStats {
+            xmr: pool(vec![worker("rig1", 2.0)]),
+            ..Default::default()
+        };
+        let text = metrics.render(&after).unwrap();
+        assert!(text.contains("worker=\"rig1\""));
+        assert!(!text.contains("worker=\"rig2\""));
+    }
+
+    #[test]
+    fn concurrent_scrapes_do_not_mix_snapshots() {
+        let metrics = StatsMetrics::new();
+        let xmr = PoolStats {
+            xmr: pool(vec![worker("xmr-rig", 2.0)]),
+            ..Default::default()
+        };
+        let aleo = PoolStats {
+            aleo: pool(vec![worker("aleo-rig", 2.0)]),
+            ..Default::default()
+        };
+
+        std::thread::scope(|scope| {
+            for (stats, own, other) in [
+                (&xmr, "xmr-rig", "aleo-rig"),
+                (&aleo, "aleo-rig", "xmr-rig"),
+            ] {
+                let metrics = metrics.clone();
+                scope.spawn(move || {
+                    for _ in 0..200 {
+                        let text = metrics.render(stats).unwrap();
+                        assert!(text.contains(own));
+                        assert!(!text.contains(other));
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn fetch_errors_and_durations_persist_across_scrapes() {
+        let metrics = StatsMetrics::new();
+        metrics.observe_fetch("xmr", SOURCE_NODE, Instant::now(), false);
+        metrics.observe_fetch("xmr", SOURCE_POOL, Instant::now(), true);
+
+        for _ in 0..2 {
+            let text = metrics.render(&PoolStats::default()).unwrap();
+            assert!(
+                text.contains("solopool_stats_fetch_errors_total{pool=\"xmr\",source=\"node\"} 1")
+            );
+            assert!(
+                !text.contains("solopool_stats_fetch_errors_total{pool=\"xmr\",source=\"pool\"}")
+            );
+            assert!(text.contains(
+                "solopool_stats_fetch_duration_seconds_count{pool=\"xmr\",source=\"pool\"} 1"
+            ));
+        }
+    }
+}
@@ -5,6 +5,7 @@ use tokio::sync::RwLock;
 
 use crate::config::Config;
 use crate::db::Database;
+use crate::metrics::StatsMetrics;
 
 /// Application state shared across handlers
 pub struct AppState {
@@ -14,6 +15,8 @@ pub struct AppState {
     pub server_ip: String,
     /// Database for persistent worker stats
     pub db: Arc<Database>,
+    /// Prometheus metrics for the stats collector
+    pub metrics: StatsMetrics,
 }
 
 /// Detect the server's public IP address
@@ -144,6 +147,7 @@ pub struct WorkerStats {
 }
 
 impl WorkerStats {
+    #[allow(dead_code)]
     pub fn new(name: String) -> Self {
         // Extract wallet address from name if format is "wallet.worker"
         let wallet_address = if name.contains('.') {
@@ -170,6 +174,7 @@ impl WorkerStats {
 
 /// CKPool log entry for parsing
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct CkPoolLogEntry {
     pub timestamp: DateTime<Utc>,
     pub worker: String,
@@ -196,3 +201,17 @@ pub fn format_hashrate(hashrate: f64) -> (f64, String) {
         (hashrate, "H/s".to_string())
     }
 }
+
+/// Convert a display-scaled hashrate (as produced by `format_hashrate`) back to H/s
+pub fn to_base_hashrate(value: f64, unit: &str) -> f64 {
+    let multiplier = match unit {
+        "EH/s" => 1e18,
+        "PH/s" => 1e15,
+        "TH/s" => 1e12,
+        "GH/s" => 1e9,
+        "MH/s" => 1e6,
+        "KH/s" | "kH/s" => 1e3,
+        _ => 1.0,
+    };
+    value * multiplier
+}
@@ -410,6 +410,10 @@ function createPoolCard(pool, stats) {
                 <span class="connection-label">Password:</span>
                 <span class="connection-value">${stats.password || 'x'}</span>
             </div>
+            <div class="connection-row">
+                <span class="connection-label">Pool Fee:</span>
+                <span class="connection-value">${stats.pool_fee_percent ? `${stats.pool_fee_percent}%` : 'None'}</span>
+            </div>
         </div>
     `;
 